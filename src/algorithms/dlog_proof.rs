@@ -1,8 +1,10 @@
+use crate::algorithms::random;
 use crate::algorithms::sha::HSha512Trunc256;
-use curv::arithmetic::traits::Samplable;
+use crate::algorithms::validation::{check_bigint, check_bits, Validate, ValidationError};
 use curv::arithmetic::traits::ZeroizeBN;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::BigInt;
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 
@@ -19,17 +21,18 @@ pub struct DlogProof {
 /// "Composite discrete logarithm and secure authentication" , D. Pointcheval , pp 3.2
 #[allow(clippy::many_single_char_names)]
 impl DlogProof {
-    pub fn create(
+    pub fn create<R: RngCore + CryptoRng + ?Sized>(
         N: &BigInt,
         g: &BigInt,
         V: &BigInt,
         s: &BigInt,
         max_secret_length: u32,
         security_param: u32,
+        rng: &mut R,
     ) -> Self {
         let log_r = max_secret_length + DIGEST_BIT_LENGTH + security_param;
         let R = BigInt::from(2).pow(log_r);
-        let mut r = BigInt::sample_below(&R);
+        let mut r = random::sample_below(rng, &R);
         let x = g.powm_sec(&r, N);
         let salt = BigInt::from(ING_TSS_DLOG.as_bytes());
         let c = HSha512Trunc256::create_hash(&[&salt, N, g, V, &x]);
@@ -48,10 +51,19 @@ impl DlogProof {
     }
 }
 
+/// Bounds the response by the size of the modulus and the challenge by the size of the digest
+impl Validate for DlogProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_bigint("dlog proof response", &self.y)?;
+        check_bits("dlog proof challenge", &self.c, DIGEST_BIT_LENGTH as usize)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::algorithms::dlog_proof::{DlogProof, DIGEST_BIT_LENGTH};
     use crate::algorithms::zkp::{ZkpSetup, DEFAULT_GROUP_ORDER_BIT_LENGTH};
+    use rand::rngs::OsRng;
 
     #[test]
     fn check_bitness() {
@@ -67,6 +79,7 @@ mod tests {
             setup.alpha(),
             max_secret_length,
             security_param,
+            &mut OsRng,
         );
 
         assert!(
@@ -90,6 +103,7 @@ mod tests {
                 setup.alpha(),
                 max_secret_length,
                 security_param,
+                &mut OsRng,
             );
             assert!(proof.verify(&setup.N_tilde, &setup.h1, &setup.h2))
         });
@@ -0,0 +1,186 @@
+//! Proof that a Paillier ciphertext encrypts the discrete log of a curve point
+//!
+//! Given Paillier key $` N `$, ciphertext $` c = (1+N)^{x} r^{N} \mod N^{2} `$ and point $` Y = g^{x} `$, the prover shows the knowledge of $` x, r `$
+//! with the sigma protocol made non-interactive by Fiat-Shamir transform:
+//!
+//! * commitments $` A = (1+N)^{\alpha} \beta^{N} \mod N^{2} , B = g^{\alpha} `$ for random $` \alpha < 2^{\ell} , \beta \in \mathbb{Z}_{N}^{\star} `$
+//! * challenge $` e = H(N, c, Y, A, B) `$
+//! * responses $` z_{1} = \alpha + e x `$ over integers and $` z_{2} = \beta r^{e} \mod N `$
+//!
+//! The verifier checks $` (1+N)^{z_{1}} z_{2}^{N} = A c^{e} \mod N^{2} `$ , $` g^{z_{1}} = B Y^{e} `$ and the bit length of $` z_{1} `$.
+//! The bound on $` z_{1} `$ guarantees that the encrypted value is much smaller than $` N `$, hence it is the discrete log of $` Y `$ up to the sign,
+//! while $` \ell `$ hides $` x `$ statistically.
+use crate::algorithms::random;
+use crate::algorithms::sha::HSha512Trunc256;
+use crate::algorithms::validation::{
+    check_bigint, check_bits, check_point, Validate, ValidationError,
+};
+use curv::arithmetic::traits::ZeroizeBN;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use paillier::EncryptionKey;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+
+pub const ING_TSS_ENC_DLOG: &str = "ING TS encrypted dlog proof sub-protocol v1.0";
+/// Bit length of the challenge
+pub const CHALLENGE_BIT_LENGTH: usize = HSha512Trunc256::DIGEST_BIT_LENGTH;
+/// Statistical security parameter of the masking of the secret
+pub const SECURITY_PARAMETER: usize = 128;
+/// Bit length $` \ell `$ of the mask $` \alpha `$
+pub const MASK_BIT_LENGTH: usize = 256 + CHALLENGE_BIT_LENGTH + SECURITY_PARAMETER;
+
+/// Non-interactive proof that a Paillier ciphertext encrypts the discrete log of a curve point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncDlogProof {
+    A: BigInt,
+    B: GE,
+    z1: BigInt,
+    z2: BigInt,
+}
+
+#[allow(clippy::many_single_char_names)]
+impl EncDlogProof {
+    /// Creates the proof that `c`, encrypted with `ek` and randomness `r`, encrypts `x`, where `Y` = $` g^{x} `$
+    pub fn create<R: RngCore + CryptoRng + ?Sized>(
+        ek: &EncryptionKey,
+        c: &BigInt,
+        Y: &GE,
+        x: &FE,
+        r: &BigInt,
+        rng: &mut R,
+    ) -> Self {
+        let N = &ek.n;
+        let NN = &ek.nn;
+        let mut alpha = random::sample_below(rng, &BigInt::from(2).pow(MASK_BIT_LENGTH as u32));
+        let mut beta = random::sample_unit(rng, N);
+        let A = ((alpha.borrow() * N + 1) * beta.powm_sec(N, NN)) % NN;
+        let g: GE = ECPoint::generator();
+        let alpha_mod_q: FE = ECScalar::from(&alpha);
+        let B = g * alpha_mod_q;
+        let e = Self::challenge(N, c, Y, &A, &B);
+
+        let z1 = &alpha + &(&e * &x.to_big_int());
+        let z2 = (&beta * &r.powm_sec(&e, N)) % N;
+        alpha.zeroize_bn();
+        beta.zeroize_bn();
+        Self { A, B, z1, z2 }
+    }
+
+    /// Verifies that `c` encrypted with `ek` is the ciphertext of the discrete log of `Y`
+    pub fn verify(&self, ek: &EncryptionKey, c: &BigInt, Y: &GE) -> bool {
+        let N = &ek.n;
+        let NN = &ek.nn;
+        if self.z1.bit_length() > MASK_BIT_LENGTH + 1 {
+            log::trace!("z1 is too large");
+            return false;
+        }
+        // the modulus has to exceed twice the bound of the response, so that the encrypted value does not wrap around
+        if N.bit_length() <= MASK_BIT_LENGTH + 2 {
+            log::trace!("Paillier modulus is too small");
+            return false;
+        }
+        let e = Self::challenge(N, c, Y, &self.A, &self.B);
+
+        let lhs = ((self.z1.borrow() * N + 1) * self.z2.powm_sec(N, NN)) % NN;
+        let rhs = (&self.A * &c.powm_sec(&e, NN)) % NN;
+        if lhs != rhs {
+            log::trace!("the ciphertext does not match the response");
+            return false;
+        }
+
+        let g: GE = ECPoint::generator();
+        let e: FE = ECScalar::from(&e);
+        let z1: FE = ECScalar::from(&self.z1);
+        if (g * z1).get_element() != (self.B + *Y * e).get_element() {
+            log::trace!("the point does not match the response");
+            return false;
+        }
+        true
+    }
+
+    fn challenge(N: &BigInt, c: &BigInt, Y: &GE, A: &BigInt, B: &GE) -> BigInt {
+        let salt = BigInt::from(ING_TSS_ENC_DLOG.as_bytes());
+        HSha512Trunc256::create_hash(&[
+            &salt,
+            N,
+            c,
+            &Y.bytes_compressed_to_big_int(),
+            A,
+            &B.bytes_compressed_to_big_int(),
+        ])
+    }
+}
+
+/// Bounds the response $` z_{1} `$ by the size of the mask and other values by the size of the modulus
+impl Validate for EncDlogProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_bigint("encrypted dlog proof commitment", &self.A)?;
+        check_point("encrypted dlog proof commitment", &self.B)?;
+        check_bits(
+            "encrypted dlog proof response",
+            &self.z1,
+            MASK_BIT_LENGTH + 1,
+        )?;
+        check_bigint("encrypted dlog proof response", &self.z2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EncDlogProof;
+    use crate::algorithms::random;
+    use crate::algorithms::validation::Validate;
+    use crate::ecdsa::PaillierKeys;
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+    use paillier::{EncryptWithChosenRandomness, Paillier, Randomness, RawPlaintext};
+    use rand::rngs::OsRng;
+
+    fn encrypt(keys: &PaillierKeys, x: &FE) -> (BigInt, BigInt) {
+        let r = random::sample_unit(&mut OsRng, &keys.ek.n);
+        let c = Paillier::encrypt_with_chosen_randomness(
+            &keys.ek,
+            RawPlaintext::from(x.to_big_int()),
+            &Randomness::from(&r),
+        )
+        .0
+        .into_owned();
+        (c, r)
+    }
+
+    #[test]
+    fn proof_verifies() {
+        let keys = PaillierKeys::random();
+        let x: FE = ECScalar::new_random();
+        let g: GE = ECPoint::generator();
+        let (c, r) = encrypt(&keys, &x);
+        let proof = EncDlogProof::create(&keys.ek, &c, &(g * x), &x, &r, &mut OsRng);
+        assert!(proof.validate().is_ok());
+        assert!(proof.verify(&keys.ek, &c, &(g * x)));
+    }
+
+    #[test]
+    fn proof_rejects_another_point() {
+        let keys = PaillierKeys::random();
+        let x: FE = ECScalar::new_random();
+        let g: GE = ECPoint::generator();
+        let (c, r) = encrypt(&keys, &x);
+        let proof = EncDlogProof::create(&keys.ek, &c, &(g * x), &x, &r, &mut OsRng);
+        let other: FE = ECScalar::new_random();
+        assert!(!proof.verify(&keys.ek, &c, &(g * other)));
+    }
+
+    #[test]
+    fn proof_rejects_ciphertext_of_another_value() {
+        let keys = PaillierKeys::random();
+        let x: FE = ECScalar::new_random();
+        let g: GE = ECPoint::generator();
+        let (c, r) = encrypt(&keys, &x);
+        let proof = EncDlogProof::create(&keys.ek, &c, &(g * x), &x, &r, &mut OsRng);
+        let (other_c, _) = encrypt(&keys, &ECScalar::new_random());
+        assert!(!proof.verify(&keys.ek, &other_c, &(g * x)));
+    }
+}
@@ -1,28 +1,33 @@
-//! Zero knowledge range proofs, safe prime generator and SHA512-256 wrapper
+//! Zero knowledge range proofs, proof of encrypted discrete log, safe prime generator, SHA512-256 wrapper, injectable randomness, Feldman's secret sharing and validation of received data
 //!
 #![allow(non_snake_case)]
 pub mod dlog_proof;
+pub mod enc_dlog_proof;
 pub mod nizk_rsa;
 pub mod primes;
+pub mod random;
 pub mod sha;
+pub mod validation;
+pub mod vss;
 pub mod zkp;
 
-use curv::arithmetic::traits::Samplable;
 use curv::BigInt;
+use rand::{CryptoRng, RngCore};
 use std::borrow::Borrow;
 
 /// Finds a generator of  a cyclic group of order n
 /// using known factorization of n.
 ///
 /// See "Handbook of applied cryptography", algorithm 4.80
-pub fn sample_generator_from_cyclic_group(
+pub fn sample_generator_from_cyclic_group<R: RngCore + CryptoRng + ?Sized>(
     modulo: &BigInt,
     order: &BigInt,
     order_factorization: &[&BigInt],
+    rng: &mut R,
 ) -> BigInt {
     let One = BigInt::one();
     loop {
-        let alpha = BigInt::sample_below(modulo);
+        let alpha = random::sample_below(rng, modulo);
         if order_factorization
             .iter()
             .find(|&&x| alpha.powm_sec(&(order / x), modulo.borrow()) == One)
@@ -52,7 +57,11 @@ pub fn crt_solver(reminders: &[&BigInt], moduli: &[&BigInt]) -> BigInt {
 /// Samples elements from two cyclic subgroups modulo prime p = (P-1)/2.
 /// Finds the generator using CRT
 ///
-pub fn sample_generator_of_rsa_group(safe_p: &BigInt, safe_q: &BigInt) -> BigInt {
+pub fn sample_generator_of_rsa_group<R: RngCore + CryptoRng + ?Sized>(
+    safe_p: &BigInt,
+    safe_q: &BigInt,
+    rng: &mut R,
+) -> BigInt {
     let One = &BigInt::one();
     let Two = &BigInt::from(2);
 
@@ -60,8 +69,8 @@ pub fn sample_generator_of_rsa_group(safe_p: &BigInt, safe_q: &BigInt) -> BigInt
     let q_prim = (safe_q - One) / Two;
 
     // find generators in prime order subgroups of groups modulo safe_p and safe_q
-    let g_p = sample_generator_of_cyclic_subgroup(safe_p, &p_prim);
-    let g_q = sample_generator_of_cyclic_subgroup(safe_q, &q_prim);
+    let g_p = sample_generator_of_cyclic_subgroup(safe_p, &p_prim, rng);
+    let g_q = sample_generator_of_cyclic_subgroup(safe_q, &q_prim, rng);
     crt_solver(&[&g_p, &g_q], &[safe_p, safe_q])
 }
 
@@ -71,13 +80,17 @@ pub fn sample_generator_of_rsa_group(safe_p: &BigInt, safe_q: &BigInt) -> BigInt
 /// where $` p,p' `$ are prime and $` p' | (p-1) `$. As the group is cyclic, the element is the generator.
 ///  
 /// See "Introduction to modern cryptography", 2nd ed , Algorithm 8.65
-pub fn sample_generator_of_cyclic_subgroup(p: &BigInt, p_prim: &BigInt) -> BigInt {
+pub fn sample_generator_of_cyclic_subgroup<R: RngCore + CryptoRng + ?Sized>(
+    p: &BigInt,
+    p_prim: &BigInt,
+    rng: &mut R,
+) -> BigInt {
     const MAX_ITERATIONS_IN_REJECTION_SAMPLING: usize = 256;
     let p_minus_one = p - &BigInt::one();
     if p_prim.divides(&p_minus_one) {
         let exp = &p_minus_one.div_floor(p_prim);
         for _ in 0..MAX_ITERATIONS_IN_REJECTION_SAMPLING {
-            let h = BigInt::sample_below(p);
+            let h = random::sample_below(rng, p);
             if h != BigInt::one() {
                 return h.powm_sec(&exp, p);
             }
@@ -1,8 +1,11 @@
 //!  Generate safe primes
 
 #![allow(non_snake_case)]
+use crate::algorithms::random;
 use curv::arithmetic::traits::{BitManipulation, NumberTests, Samplable};
 use curv::BigInt;
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use zeroize::Zeroize;
@@ -44,6 +47,16 @@ fn bn_mr_min_checks(bits: usize) -> usize {
 /// Find a prime p of given bit size such as $` p' = (p-1)/2 `$ is prime too.
 /// Return (p, p')
 pub fn random_safe_prime(bit_length: usize) -> (BigInt, BigInt) {
+    random_safe_prime_with_rng(bit_length, &mut OsRng)
+}
+
+/// generate safe prime p, drawing candidates from given generator
+///
+/// See [`random_safe_prime`](fn.random_safe_prime.html)
+pub fn random_safe_prime_with_rng<R: RngCore + CryptoRng + ?Sized>(
+    bit_length: usize,
+    rng: &mut R,
+) -> (BigInt, BigInt) {
     let One = BigInt::one();
     let Two = &BigInt::from(2);
 
@@ -54,7 +67,7 @@ pub fn random_safe_prime(bit_length: usize) -> (BigInt, BigInt) {
         log::warn!("bit length too large");
     }
     loop {
-        let mut p_prim = BigInt::sample(bit_length - 1);
+        let mut p_prim = random::sample(rng, bit_length - 1);
         // make it odd
         BigInt::set_bit(&mut p_prim, 0, true);
         // Set MSB to 1
@@ -74,6 +87,28 @@ pub fn random_safe_prime(bit_length: usize) -> (BigInt, BigInt) {
     }
 }
 
+/// generate prime p of given bit size, drawing candidates from given generator
+///
+/// Used to make Paillier keys of deterministic tests reproducible, production keys are generated by the `paillier` crate.
+pub fn random_prime_with_rng<R: RngCore + CryptoRng + ?Sized>(
+    bit_length: usize,
+    rng: &mut R,
+) -> BigInt {
+    if bit_length < 2 {
+        panic!("bit length cant be less than 2");
+    }
+    loop {
+        let mut p = random::sample(rng, bit_length);
+        // make it odd
+        BigInt::set_bit(&mut p, 0, true);
+        // Set MSB to 1
+        BigInt::set_bit(&mut p, bit_length - 1, true);
+        if is_prime(&p, bit_length) {
+            return p;
+        }
+    }
+}
+
 /// tests probabilistically whether given number is prime
 ///
 /// * first, check if the number is divisible by each of first 2048 known primes.
@@ -188,6 +223,19 @@ mod tests {
         println!("{} obtained in {} seconds", p, now.elapsed().as_secs_f32());
     }
 
+    #[test]
+    fn seeded_prime_is_reproducible() {
+        use crate::algorithms::primes::random_prime_with_rng;
+        use crate::algorithms::random::RandomnessSource;
+        const BIT_LENGTH: usize = 256;
+
+        let p = random_prime_with_rng(BIT_LENGTH, &mut RandomnessSource::seeded(1));
+        let q = random_prime_with_rng(BIT_LENGTH, &mut RandomnessSource::seeded(1));
+        assert_eq!(p, q);
+        assert_eq!(p.bit_length(), BIT_LENGTH);
+        assert!(is_prime(&p, BIT_LENGTH));
+    }
+
     #[test]
     #[ignore]
     fn print_product_of_first_primes() {
@@ -0,0 +1,176 @@
+//! Injectable source of randomness
+//!
+//! Functions of the crate which sample secrets or nonces accept a random generator implementing `RngCore + CryptoRng`.
+//! Protocol states keep a [`RandomnessSource`], a cloneable handle of such generator, which is passed from one state to another.
+//!
+//! Production code uses the generator of the operating system, which is the default.
+//! Tests and known-answer vectors can use a seeded generator to make protocol runs reproducible.
+//! Note that the sampling performed inside `curv` and `paillier` crates, like proofs of discrete logarithm, can't be controlled this way.
+//! Feldman's VSS is implemented by the [`vss`](../vss/index.html) module on top of the injected generator for that reason.
+//! Paillier keys are generated by the `paillier` crate from the generator of the operating system unless the source is seeded.
+//!
+//! [`RandomnessSource`]: struct.RandomnessSource.html
+//!
+use curv::elliptic::curves::traits::ECScalar;
+use curv::{BigInt, FE};
+use rand::rngs::{OsRng, StdRng};
+use rand::{CryptoRng, RngCore, SeedableRng};
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+/// Cryptographically secure random generator
+pub trait CryptoRngCore: RngCore + CryptoRng {}
+
+impl<T: RngCore + CryptoRng> CryptoRngCore for T {}
+
+/// Shared handle of a cryptographically secure random generator
+///
+/// Clones of the handle draw from the same generator, hence the sequence of sampled values depends only on the order of draws.
+#[derive(Clone)]
+pub struct RandomnessSource {
+    rng: Arc<Mutex<Box<dyn CryptoRngCore + Send>>>,
+    seeded: bool,
+}
+
+impl RandomnessSource {
+    pub fn new(rng: impl CryptoRngCore + Send + 'static) -> Self {
+        RandomnessSource {
+            rng: Arc::new(Mutex::new(Box::new(rng))),
+            seeded: false,
+        }
+    }
+
+    /// generator of the operating system
+    pub fn os() -> Self {
+        Self::new(OsRng)
+    }
+
+    /// deterministic generator, for tests only
+    pub fn seeded(seed: u64) -> Self {
+        RandomnessSource {
+            seeded: true,
+            ..Self::new(StdRng::seed_from_u64(seed))
+        }
+    }
+
+    /// tells whether the source is the deterministic generator created by `seeded`
+    pub fn is_seeded(&self) -> bool {
+        self.seeded
+    }
+
+    fn with_rng<T>(&self, f: impl FnOnce(&mut dyn CryptoRngCore) -> T) -> T {
+        let mut rng = self.rng.lock().expect("random generator lock is poisoned");
+        f(&mut **rng)
+    }
+}
+
+impl Default for RandomnessSource {
+    fn default() -> Self {
+        Self::os()
+    }
+}
+
+impl Debug for RandomnessSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RandomnessSource")
+    }
+}
+
+impl RngCore for RandomnessSource {
+    fn next_u32(&mut self) -> u32 {
+        self.with_rng(|rng| rng.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.with_rng(|rng| rng.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.with_rng(|rng| rng.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.with_rng(|rng| rng.try_fill_bytes(dest))
+    }
+}
+
+impl CryptoRng for RandomnessSource {}
+
+/// Samples uniformly a non-negative number of at most `bit_size` bits
+pub fn sample<R: RngCore + CryptoRng + ?Sized>(rng: &mut R, bit_size: usize) -> BigInt {
+    if bit_size == 0 {
+        return BigInt::zero();
+    }
+    let mut bytes = vec![0u8; (bit_size + 7) / 8];
+    rng.fill_bytes(&mut bytes);
+    let excess_bits = bytes.len() * 8 - bit_size;
+    bytes[0] &= 0xFF >> excess_bits;
+    BigInt::from(&bytes[..])
+}
+
+/// Samples uniformly from $` [0, upper) `$
+pub fn sample_below<R: RngCore + CryptoRng + ?Sized>(rng: &mut R, upper: &BigInt) -> BigInt {
+    assert!(*upper > BigInt::zero(), "upper bound has to be positive");
+    let bit_size = upper.bit_length();
+    loop {
+        let candidate = sample(rng, bit_size);
+        if candidate < *upper {
+            return candidate;
+        }
+    }
+}
+
+/// Samples uniformly from $` [lower, upper) `$
+pub fn sample_range<R: RngCore + CryptoRng + ?Sized>(
+    rng: &mut R,
+    lower: &BigInt,
+    upper: &BigInt,
+) -> BigInt {
+    lower + &sample_below(rng, &(upper - lower))
+}
+
+/// Samples uniformly from $` Z^{*}_{n} `$
+pub fn sample_unit<R: RngCore + CryptoRng + ?Sized>(rng: &mut R, n: &BigInt) -> BigInt {
+    loop {
+        let candidate = sample_below(rng, n);
+        if candidate != BigInt::zero() && candidate.invert(n).is_some() {
+            return candidate;
+        }
+    }
+}
+
+/// Samples non-zero scalar of the elliptic curve group
+pub fn sample_scalar<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> FE {
+    ECScalar::from(&sample_range(rng, &BigInt::one(), &FE::q()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sample, sample_below, sample_scalar, RandomnessSource};
+    use curv::elliptic::curves::traits::ECScalar;
+    use curv::{BigInt, FE};
+
+    #[test]
+    fn seeded_source_is_reproducible() {
+        let mut first = RandomnessSource::seeded(42);
+        let mut second = RandomnessSource::seeded(42);
+        let a: FE = sample_scalar(&mut first);
+        let b: FE = sample_scalar(&mut second);
+        assert_eq!(a.to_big_int(), b.to_big_int());
+
+        // clones share the generator, so the next draw differs
+        let c: FE = sample_scalar(&mut first.clone());
+        assert_ne!(a.to_big_int(), c.to_big_int());
+    }
+
+    #[test]
+    fn sampling_respects_bounds() {
+        let mut rng = RandomnessSource::seeded(7);
+        let upper = BigInt::from(1000);
+        for _ in 0..100 {
+            let x = sample_below(&mut rng, &upper);
+            assert!(x >= BigInt::zero() && x < upper);
+            assert!(sample(&mut rng, 13) < BigInt::from(1 << 13));
+        }
+    }
+}
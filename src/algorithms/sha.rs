@@ -3,7 +3,8 @@ use curv::arithmetic::traits::Converter;
 use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use curv::{BigInt, FE, GE};
-use rand::{thread_rng, Rng};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use sha2::{Digest, Sha512Trunc256};
 use std::convert::TryFrom;
 
@@ -19,9 +20,17 @@ impl HSha512Trunc256 {
     }
 
     pub fn create_hash_with_random_nonce(big_ints: &[&BigInt]) -> (BigInt, BigInt) {
+        Self::create_hash_with_rng(big_ints, &mut OsRng)
+    }
+
+    /// same as `create_hash_with_random_nonce` but draws the nonce from given generator
+    pub fn create_hash_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        big_ints: &[&BigInt],
+        rng: &mut R,
+    ) -> (BigInt, BigInt) {
         let mut nonce = [0u8; Self::NONCE_SIZE_BYTES];
         let mut hasher = Sha512Trunc256::new();
-        thread_rng().fill(&mut nonce[..]);
+        rng.fill_bytes(&mut nonce[..]);
 
         hasher.input(nonce);
         for value in big_ints {
@@ -59,8 +68,17 @@ impl HSha512Trunc256 {
     }
 
     pub fn create_hash_bounded_by_q(big_ints: &[&BigInt], q: &BigInt) -> (BigInt, BigInt) {
+        Self::create_hash_bounded_by_q_with_rng(big_ints, q, &mut OsRng)
+    }
+
+    /// same as `create_hash_bounded_by_q` but draws nonces from given generator
+    pub fn create_hash_bounded_by_q_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        big_ints: &[&BigInt],
+        q: &BigInt,
+        rng: &mut R,
+    ) -> (BigInt, BigInt) {
         for _ in 0..Self::MAX_ITERATIONS_IN_REJECTION_SAMPLING {
-            let (hash, nonce) = Self::create_hash_with_random_nonce(big_ints);
+            let (hash, nonce) = Self::create_hash_with_rng(big_ints, &mut *rng);
             let hash = hash.abs();
             if hash < *q {
                 return (hash, nonce);
@@ -0,0 +1,259 @@
+//! Validation of data received from other parties
+//!
+//! Messages are deserialized from untrusted input, so that a malicious party can send points which are not on the curve,
+//! or big integers of arbitrary size which turn each modular exponentiation into a denial of service.
+//! Types implementing [`Validate`] check curve membership of points, maximum bit lengths of big integers and sizes of collections,
+//! and the [`Validated`] wrapper can be created only through `TryFrom` which runs these checks.
+//! Phases of protocols validate every message they consume and blame the sender of an invalid one,
+//! so that messages which do not pass through the [`wire`](../../wire/index.html) decoder are checked as well.
+//!
+//! Checks which depend on the context, e.g. whether a ciphertext belongs to $` \mathbb{Z}_{N^2}^{\star} `$ for the Paillier modulus $`N`$ of its sender,
+//! are provided by [`check_ciphertext`] and performed by the protocol which knows the key.
+//!
+//! [`Validate`]: trait.Validate.html
+//! [`Validated`]: struct.Validated.html
+//! [`check_ciphertext`]: fn.check_ciphertext.html
+//!
+use crate::ecdsa::is_valid_curve_point;
+use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof;
+use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+use curv::elliptic::curves::traits::ECPoint;
+use curv::{BigInt, GE};
+use paillier::EncryptionKey;
+use std::ops::Deref;
+use thiserror::Error;
+
+/// Maximum bit length of Paillier modulus $`N`$ and of range proof modulus $` \tilde{N} `$
+pub const MAX_MODULUS_BIT_LENGTH: usize = 4096;
+/// Maximum bit length of any other big integer, large enough for elements of $` \mathbb{Z}_{N^2} `$ and for responses of range proofs
+pub const MAX_BIGINT_BIT_LENGTH: usize = 2 * MAX_MODULUS_BIT_LENGTH + 1024;
+/// Maximum number of commitments in Feldman's VSS
+pub const MAX_VSS_COMMITMENTS: usize = 1024;
+/// Maximum number of elements in a list of big integers, e.g. in a proof of correctness of Paillier key
+pub const MAX_LIST_LENGTH: usize = 256;
+/// Maximum number of epochs in the history of a key
+pub const MAX_KEY_EPOCHS: usize = 4096;
+
+/// Enumerates reasons of rejecting received data
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ValidationError {
+    #[error("validation: {field} is not a point on the curve")]
+    PointNotOnCurve { field: &'static str },
+    #[error("validation: {field} has {bits} bits, at most {max} allowed")]
+    TooLarge {
+        field: &'static str,
+        bits: usize,
+        max: usize,
+    },
+    #[error("validation: {field} is out of range")]
+    OutOfRange { field: &'static str },
+    #[error("validation: {field} has {count} elements, expected {expected}")]
+    InvalidLength {
+        field: &'static str,
+        count: usize,
+        expected: String,
+    },
+    #[error("validation: invalid Paillier key, {0}")]
+    InvalidPaillierKey(String),
+}
+
+/// Checks the data received from other parties
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+/// Data which passed [`Validate`](trait.Validate.html) checks
+///
+/// Created through `TryFrom` implementations of wrapped types.
+#[derive(Debug, Clone)]
+pub struct Validated<T>(T);
+
+impl<T> Validated<T> {
+    /// Unwraps validated value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Validated<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: Validate> Validated<T> {
+    /// Validates the value
+    ///
+    /// A generic `TryFrom<T>` is not possible because of the blanket implementation in `core`,
+    /// therefore types use this function in their own `TryFrom` implementations.
+    pub fn new(value: T) -> Result<Self, ValidationError> {
+        value.validate()?;
+        Ok(Validated(value))
+    }
+}
+
+/// Checks that the point lies on the curve
+pub fn check_point(field: &'static str, point: &GE) -> Result<(), ValidationError> {
+    if is_valid_curve_point(point.get_element()) {
+        Ok(())
+    } else {
+        Err(ValidationError::PointNotOnCurve { field })
+    }
+}
+
+/// Checks that the absolute value of the integer has at most `max` bits
+pub fn check_bits(field: &'static str, value: &BigInt, max: usize) -> Result<(), ValidationError> {
+    let bits = value.bit_length();
+    if bits <= max {
+        Ok(())
+    } else {
+        Err(ValidationError::TooLarge { field, bits, max })
+    }
+}
+
+/// Checks the bit length of a general purpose big integer against [`MAX_BIGINT_BIT_LENGTH`](constant.MAX_BIGINT_BIT_LENGTH.html)
+pub fn check_bigint(field: &'static str, value: &BigInt) -> Result<(), ValidationError> {
+    check_bits(field, value, MAX_BIGINT_BIT_LENGTH)
+}
+
+/// Checks that $` 0 \leq value < bound `$
+pub fn check_below(
+    field: &'static str,
+    value: &BigInt,
+    bound: &BigInt,
+) -> Result<(), ValidationError> {
+    if *value >= BigInt::zero() && value < bound {
+        Ok(())
+    } else {
+        Err(ValidationError::OutOfRange { field })
+    }
+}
+
+/// Checks the length of the list
+pub fn check_length<T>(
+    field: &'static str,
+    list: &[T],
+    min: usize,
+    max: usize,
+) -> Result<(), ValidationError> {
+    if list.len() >= min && list.len() <= max {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidLength {
+            field,
+            count: list.len(),
+            expected: format!("{}..={}", min, max),
+        })
+    }
+}
+
+/// Checks that the ciphertext belongs to $` \mathbb{Z}_{N^2}^{\star} `$ for the Paillier key of its sender
+pub fn check_ciphertext(
+    field: &'static str,
+    c: &BigInt,
+    ek: &EncryptionKey,
+) -> Result<(), ValidationError> {
+    check_below(field, c, &ek.nn)?;
+    if c.gcd(&ek.n) == BigInt::one() {
+        Ok(())
+    } else {
+        Err(ValidationError::OutOfRange { field })
+    }
+}
+
+impl Validate for EncryptionKey {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_bits("paillier modulus", &self.n, MAX_MODULUS_BIT_LENGTH)?;
+        if self.n <= BigInt::one() {
+            return Err(ValidationError::InvalidPaillierKey(
+                "modulus is too small".to_string(),
+            ));
+        }
+        if self.nn != &self.n * &self.n {
+            return Err(ValidationError::InvalidPaillierKey(
+                "nn is not the square of the modulus".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Validate for DLogProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_point("dlog proof public key", &self.pk)?;
+        check_point("dlog proof commitment", &self.pk_t_rand_commitment)
+    }
+}
+
+impl Validate for HomoELGamalProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_point("homomorphic ElGamal proof T", &self.T)?;
+        check_point("homomorphic ElGamal proof A3", &self.A3)
+    }
+}
+
+impl Validate for VerifiableSS {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_length("vss commitments", &self.commitments, 1, MAX_VSS_COMMITMENTS)?;
+        if self.parameters.threshold.checked_add(1) != Some(self.commitments.len()) {
+            return Err(ValidationError::InvalidLength {
+                field: "vss commitments",
+                count: self.commitments.len(),
+                expected: "threshold + 1".to_string(),
+            });
+        }
+        self.commitments
+            .iter()
+            .try_for_each(|c| check_point("vss commitment", c))
+    }
+}
+
+impl<T: Validate> Validate for Option<T> {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.as_ref().map_or(Ok(()), Validate::validate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_bits, check_ciphertext, check_point, Validate, ValidationError};
+    use curv::elliptic::curves::traits::ECPoint;
+    use curv::{BigInt, GE};
+    use paillier::{EncryptionKey, KeyGeneration, Paillier};
+
+    #[test]
+    fn paillier_key() {
+        let ek = Paillier::keypair_with_modulus_size(512).keys().0;
+        assert!(ek.validate().is_ok());
+        assert!(check_ciphertext("c", &BigInt::from(2), &ek).is_ok());
+        assert!(check_ciphertext("c", &ek.nn, &ek).is_err());
+        assert!(check_ciphertext("c", &ek.n, &ek).is_err());
+        assert!(check_ciphertext("c", &BigInt::from(-1), &ek).is_err());
+
+        let forged = EncryptionKey {
+            n: ek.n.clone(),
+            nn: BigInt::from(1) << 100_000,
+        };
+        assert!(matches!(
+            forged.validate(),
+            Err(ValidationError::InvalidPaillierKey(_))
+        ));
+    }
+
+    #[test]
+    fn limits() {
+        assert!(check_bits("x", &(BigInt::from(1) << 255), 256).is_ok());
+        assert_eq!(
+            check_bits("x", &(BigInt::from(1) << 256), 256),
+            Err(ValidationError::TooLarge {
+                field: "x",
+                bits: 257,
+                max: 256
+            })
+        );
+        assert!(check_point("g", &GE::generator()).is_ok());
+    }
+}
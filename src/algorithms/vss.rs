@@ -0,0 +1,95 @@
+//! Feldman's verifiable secret sharing with injectable randomness
+//!
+//! Produces the same [`VerifiableSS`] scheme and shares as `VerifiableSS::share` of `curv`, which samples coefficients of the polynomial
+//! from the generator of the operating system. Here coefficients are drawn from the given generator, so that protocols
+//! run with a seeded [`RandomnessSource`](../random/struct.RandomnessSource.html) share their secrets reproducibly.
+//!
+//! [`VerifiableSS`]: https://docs.rs/curv-kzen/0.2.8/curv/cryptographic_primitives/secret_sharing/feldman_vss/struct.VerifiableSS.html
+//!
+use crate::algorithms::random;
+use crate::secret::Secret;
+use curv::cryptographic_primitives::secret_sharing::feldman_vss::{
+    ShamirSecretSharing, VerifiableSS,
+};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use rand::{CryptoRng, RngCore};
+
+/// Shares `secret` by a random polynomial of degree `threshold`, whose coefficients are sampled from `rng`
+///
+/// Returns Feldman's commitments to the coefficients and shares of `share_count` parties, the share of the party with index `i`
+/// being the value of the polynomial at `i`, where indexes start from 1.
+pub fn share<R: RngCore + CryptoRng + ?Sized>(
+    threshold: usize,
+    share_count: usize,
+    secret: &FE,
+    rng: &mut R,
+) -> (VerifiableSS, Vec<FE>) {
+    let coefficients = Secret::new(
+        std::iter::once(*secret)
+            .chain((0..threshold).map(|_| random::sample_scalar(rng)))
+            .collect::<Vec<_>>(),
+    );
+    let shares = (1..=share_count)
+        .map(|i| evaluate(&coefficients, i))
+        .collect::<Vec<_>>();
+    let g: GE = ECPoint::generator();
+    let commitments = coefficients.iter().map(|a| g * a).collect::<Vec<_>>();
+    (
+        VerifiableSS {
+            parameters: ShamirSecretSharing {
+                threshold,
+                share_count,
+            },
+            commitments,
+        },
+        shares,
+    )
+    // coefficients are erased here
+}
+
+/// Value of the polynomial at `x`, given its coefficients starting from the free term
+#[allow(clippy::cast_possible_truncation)]
+fn evaluate(coefficients: &[FE], x: usize) -> FE {
+    let x: FE = ECScalar::from(&BigInt::from(x as u64));
+    // Horner's scheme
+    coefficients
+        .iter()
+        .rev()
+        .fold(FE::zero(), |acc, a| acc * x + *a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::share;
+    use crate::algorithms::random::{self, RandomnessSource};
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{FE, GE};
+
+    #[test]
+    fn shares_match_commitments() {
+        let mut rng = RandomnessSource::seeded(3);
+        let secret: FE = random::sample_scalar(&mut rng);
+        let (vss, shares) = share(2, 5, &secret, &mut rng);
+        assert_eq!(vss.commitments.len(), 3);
+        assert_eq!(shares.len(), 5);
+        let g: GE = ECPoint::generator();
+        assert_eq!(vss.commitments[0], g * secret);
+        for (i, share) in shares.iter().enumerate() {
+            assert!(vss.validate_share(share, i + 1).is_ok());
+        }
+        let indices = [0, 2, 4];
+        let reconstructed = vss.reconstruct(&indices, &[shares[0], shares[2], shares[4]]);
+        assert_eq!(reconstructed.to_big_int(), secret.to_big_int());
+    }
+
+    #[test]
+    fn seeded_sharing_is_reproducible() {
+        let secret: FE = ECScalar::new_random();
+        let (first, first_shares) = share(1, 3, &secret, &mut RandomnessSource::seeded(5));
+        let (second, second_shares) = share(1, 3, &secret, &mut RandomnessSource::seeded(5));
+        assert_eq!(first.commitments, second.commitments);
+        let to_big_ints = |shares: &[FE]| shares.iter().map(|s| s.to_big_int()).collect::<Vec<_>>();
+        assert_eq!(to_big_ints(&first_shares), to_big_ints(&second_shares));
+    }
+}
@@ -187,8 +187,16 @@ use zeroize::Zeroize;
 use crate::algorithms::dlog_proof::DlogProof;
 use crate::algorithms::nizk_rsa;
 use crate::algorithms::primes::PairOfSafePrimes;
+use crate::algorithms::random;
 use crate::algorithms::sha::HSha512Trunc256;
+use crate::algorithms::validation::{
+    check_below, check_bigint, check_bits, check_length, check_point, Validate, ValidationError,
+    MAX_MODULUS_BIT_LENGTH,
+};
+use crate::secret::Secret;
 use curv::cryptographic_primitives::proofs::sigma_dlog::{DLogProof, ProveDLog};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use trace::trace;
@@ -249,6 +257,26 @@ pub struct ZkpPublicSetup {
     pub n_tilde_proof: Vec<BigInt>,
 }
 
+/// Bounds $` \tilde{N} `$ by [`MAX_MODULUS_BIT_LENGTH`](../validation/constant.MAX_MODULUS_BIT_LENGTH.html) and all other values by $` \tilde{N} `$
+impl Validate for ZkpPublicSetup {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_bits("N_tilde", &self.N_tilde, MAX_MODULUS_BIT_LENGTH)?;
+        check_below("h1", &self.h1, &self.N_tilde)?;
+        check_below("h2", &self.h2, &self.N_tilde)?;
+        self.dlog_proof.validate()?;
+        self.inv_dlog_proof.validate()?;
+        check_length(
+            "N_tilde proof",
+            &self.n_tilde_proof,
+            nizk_rsa::M2,
+            nizk_rsa::M2,
+        )?;
+        self.n_tilde_proof
+            .iter()
+            .try_for_each(|sigma| check_below("N_tilde proof", sigma, &self.N_tilde))
+    }
+}
+
 /// The non-interactive proof of correctness of zero knowledge range proof setup.
 /// Uses Schnorr's proof of knowing the discrete logarithm.
 /// Needs to be shared with each party along with the setup itself
@@ -260,9 +288,12 @@ pub struct ZkpSetupProof {
 }
 
 #[cfg(not(test))]
-fn pair_of_safe_primes(bit_length: usize) -> PairOfSafePrimes {
-    let (p, p_prim) = super::primes::random_safe_prime(bit_length);
-    let (q, q_prim) = super::primes::random_safe_prime(bit_length);
+fn pair_of_safe_primes<R: RngCore + CryptoRng + ?Sized>(
+    bit_length: usize,
+    rng: &mut R,
+) -> PairOfSafePrimes {
+    let (p, p_prim) = super::primes::random_safe_prime_with_rng(bit_length, rng);
+    let (q, q_prim) = super::primes::random_safe_prime_with_rng(bit_length, rng);
     PairOfSafePrimes {
         p,
         p_prim,
@@ -272,7 +303,10 @@ fn pair_of_safe_primes(bit_length: usize) -> PairOfSafePrimes {
 }
 
 #[cfg(test)]
-fn pair_of_safe_primes(bit_length: usize) -> PairOfSafePrimes {
+fn pair_of_safe_primes<R: RngCore + CryptoRng + ?Sized>(
+    bit_length: usize,
+    rng: &mut R,
+) -> PairOfSafePrimes {
     use rand::Rng;
     use std::fs;
     use std::path::Path;
@@ -287,7 +321,6 @@ fn pair_of_safe_primes(bit_length: usize) -> PairOfSafePrimes {
         serde_json::from_str(&fs::read_to_string(path).expect("invalid safe-prime file"))
             .expect("invalid safe prime format");
     // output one prime set { p, p',q, q' } chosen randomly from the array
-    let mut rng = rand::thread_rng();
     let index = rng.gen_range(0, primes.len());
     primes[index].to_owned()
 }
@@ -297,14 +330,23 @@ impl ZkpSetup {
     /// Generates new zero knowledge range proof setup.
     /// Uses Fujisaki - Okamoto bit commitment scheme, "Statistical zero knowledge protocols to prove modular polynomial relations"
     pub fn random(group_order_bit_length: usize) -> Self {
+        Self::random_with_rng(group_order_bit_length, &mut OsRng)
+    }
+
+    /// Generates new zero knowledge range proof setup, drawing random values from given generator
+    #[trace(disable(rng))]
+    pub fn random_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        group_order_bit_length: usize,
+        rng: &mut R,
+    ) -> Self {
         use crate::algorithms::sample_generator_of_rsa_group;
         let bit_length = group_order_bit_length / 2;
 
         // Fujisaki-Okamoto commitment scheme setup
         let One = &BigInt::one();
-        let mut primes = pair_of_safe_primes(bit_length);
+        let mut primes = pair_of_safe_primes(bit_length, rng);
         let b0 = loop {
-            let b0 = sample_generator_of_rsa_group(&primes.p, &primes.q);
+            let b0 = sample_generator_of_rsa_group(&primes.p, &primes.q, rng);
             if b0 != *One {
                 break b0;
             }
@@ -313,7 +355,7 @@ impl ZkpSetup {
         let N_tilde = primes.p.borrow() * primes.q.borrow();
         let mut phi = (primes.p.borrow() - One) * (primes.q.borrow() - One);
         let alpha = loop {
-            let alpha = BigInt::strict_sample_range(&BigInt::from(1), &(phi.borrow() / 4));
+            let alpha = random::sample_range(rng, &BigInt::from(2), &(phi.borrow() / 4));
             if alpha.invert(&phi).is_some() {
                 break alpha;
             }
@@ -352,6 +394,15 @@ impl ZkpPublicSetup {
     ///  Creates new public setup and generates proof of knowledge of $` \alpha , \alpha^{-1} `$
     /// and proof of $` gcd(\tilde{N}, phi(\tilde{N} ) = 1 `$
     pub fn from_private_zkp_setup(setup: &ZkpSetup) -> Self {
+        Self::from_private_zkp_setup_with_rng(setup, &mut OsRng)
+    }
+
+    /// Creates new public setup from private one, drawing random values of proofs from given generator
+    #[trace(disable(rng))]
+    pub fn from_private_zkp_setup_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        setup: &ZkpSetup,
+        rng: &mut R,
+    ) -> Self {
         let One = &BigInt::one();
         let mut phi = (&setup.p - One) * (&setup.q - One);
         let inv_alpha = &setup.alpha.invert(&phi).expect("alpha must be invertible"); // already checked in the constructor
@@ -374,6 +425,7 @@ impl ZkpPublicSetup {
                 &setup.alpha,
                 max_secret_length,
                 Self::DLOG_PROOF_SECURITY_PARAMETER,
+                rng,
             ),
             inv_dlog_proof: DlogProof::create(
                 &setup.N_tilde,
@@ -382,6 +434,7 @@ impl ZkpPublicSetup {
                 &inv_alpha,
                 max_secret_length,
                 Self::DLOG_PROOF_SECURITY_PARAMETER,
+                rng,
             ),
             n_tilde_proof,
         }
@@ -482,25 +535,50 @@ pub struct MessageA {
     pub c: BigInt,
     pub range_proof: Option<AliceProof>,
 }
+
+impl Validate for MessageA {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_bigint("MessageA.c", &self.c)?;
+        self.range_proof.validate()
+    }
+}
 #[trace(pretty, prefix = "MessageA::")]
 impl MessageA {
     // a - Alice 's secret
+    #[trace(disable(a))]
     pub fn new(a: &FE, alice_pk: &EncryptionKey, bob_setup: Option<&ZkpPublicSetup>) -> MessageA {
-        let mut r = BigInt::from_paillier_key(&alice_pk);
+        Self::new_with_rng(a, alice_pk, bob_setup, &mut OsRng)
+    }
+
+    /// same as `new` but draws the encryption randomness and the randomness of the proof from given generator
+    #[trace(disable(a, rng))]
+    pub fn new_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        a: &FE,
+        alice_pk: &EncryptionKey,
+        bob_setup: Option<&ZkpPublicSetup>,
+        rng: &mut R,
+    ) -> MessageA {
+        let r = Secret::new(random::sample_unit(rng, &alice_pk.n));
         let cipher = Paillier::encrypt_with_chosen_randomness(
             alice_pk,
             RawPlaintext::from(a.to_big_int()),
-            &Randomness::from(&r),
+            &Randomness::from(&*r),
         )
         .0
         .into_owned();
 
         let proof = bob_setup.map(|zkp_setup| {
-            AliceProof::generate(&a.to_big_int(), &cipher, alice_pk, zkp_setup, &r, &FE::q())
+            AliceProof::generate(
+                &a.to_big_int(),
+                &cipher,
+                alice_pk,
+                zkp_setup,
+                &r,
+                &FE::q(),
+                rng,
+            )
         });
 
-        r.zeroize_bn();
-
         MessageA {
             c: cipher,
             range_proof: proof,
@@ -514,29 +592,10 @@ impl MessageA {
 struct AliceZkpInit {
     alice_pk: EncryptionKey,
     bob_setup: ZkpPublicSetup,
-    pub alpha: BigInt,
-    pub beta: BigInt,
-    pub gamma: BigInt,
-    pub ro: BigInt,
-}
-
-/// Zeroize Alice's ZKP
-impl Zeroize for AliceZkpInit {
-    fn zeroize(&mut self) {
-        self.alice_pk.n.zeroize_bn();
-        self.alice_pk.nn.zeroize_bn();
-        self.bob_setup.zeroize();
-        self.alpha.zeroize_bn();
-        self.beta.zeroize_bn();
-        self.gamma.zeroize_bn();
-        self.ro.zeroize_bn();
-    }
-}
-
-impl Drop for AliceZkpInit {
-    fn drop(&mut self) {
-        self.zeroize();
-    }
+    pub alpha: Secret<BigInt>,
+    pub beta: Secret<BigInt>,
+    pub gamma: Secret<BigInt>,
+    pub ro: Secret<BigInt>,
 }
 
 impl AliceZkpInit {
@@ -550,14 +609,21 @@ impl AliceZkpInit {
     ///
     ///  `q` - modulo of the elliptic group used in ECDSA
     ///
-    fn random(alice_pk: &EncryptionKey, bob_setup: &ZkpPublicSetup, q: &BigInt) -> Self {
+    ///  `rng` - source of randomness
+    ///
+    fn random<R: RngCore + CryptoRng + ?Sized>(
+        alice_pk: &EncryptionKey,
+        bob_setup: &ZkpPublicSetup,
+        q: &BigInt,
+        rng: &mut R,
+    ) -> Self {
         Self {
             alice_pk: alice_pk.clone(),
             bob_setup: bob_setup.clone(),
-            alpha: BigInt::sample_below(&q.pow(3)),
-            beta: BigInt::from_paillier_key(&alice_pk),
-            gamma: BigInt::sample_below(&(q.pow(3) * &bob_setup.N_tilde)),
-            ro: BigInt::sample_below(&(q * &bob_setup.N_tilde)),
+            alpha: Secret::new(random::sample_below(rng, &q.pow(3))),
+            beta: Secret::new(random::sample_unit(rng, &alice_pk.n)),
+            gamma: Secret::new(random::sample_below(rng, &(q.pow(3) * &bob_setup.N_tilde))),
+            ro: Secret::new(random::sample_below(rng, &(q * &bob_setup.N_tilde))),
         }
     }
     pub fn N(&self) -> &BigInt {
@@ -590,7 +656,7 @@ impl AliceZkpRound1 {
             z: (init.h1().powm_sec(&a, init.N_tilde())
                 * init.h2().powm_sec(&init.ro, init.N_tilde()))
                 % init.N_tilde(),
-            u: ((init.alpha.borrow() * init.N() + 1) * init.beta.powm_sec(init.N(), init.NN()))
+            u: ((&*init.alpha * init.N() + 1) * init.beta.powm_sec(init.N(), init.NN()))
                 % init.NN(),
             w: (init.h1().powm_sec(&init.alpha, init.N_tilde())
                 * init.h2().powm_sec(&init.gamma, init.N_tilde()))
@@ -609,9 +675,9 @@ struct AliceZkpRound2 {
 impl AliceZkpRound2 {
     pub fn from(init: &AliceZkpInit, e: &BigInt, a: &BigInt, r: &BigInt) -> Self {
         Self {
-            s: (r.powm_sec(&e, init.N()) * init.beta.borrow()) % init.N(),
-            s1: (e * a) + init.alpha.borrow(),
-            s2: (e * init.ro.borrow()) + init.gamma.borrow(),
+            s: (r.powm_sec(&e, init.N()) * &*init.beta) % init.N(),
+            s1: (e * a) + &*init.alpha,
+            s2: (e * &*init.ro) + &*init.gamma,
         }
     }
 }
@@ -629,6 +695,23 @@ pub struct AliceProof {
     s1: BigInt,
     s2: BigInt,
 }
+
+impl Validate for AliceProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        [
+            ("AliceProof.z", &self.z),
+            ("AliceProof.u", &self.u),
+            ("AliceProof.w", &self.w),
+            ("AliceProof.e", &self.e.0),
+            ("AliceProof.e", &self.e.1),
+            ("AliceProof.s", &self.s),
+            ("AliceProof.s1", &self.s1),
+            ("AliceProof.s2", &self.s2),
+        ]
+        .iter()
+        .try_for_each(|&(field, value)| check_bigint(field, value))
+    }
+}
 #[trace(pretty, prefix = "AliceProof::")]
 impl AliceProof {
     /// verify Alice's proof using the proof and public keys
@@ -695,21 +778,24 @@ impl AliceProof {
     /// create the proof using Alice's private keys of Paillier and private keys of ZKP setup
     /// requires randomness used for encrypting Alice's secret a
     /// requires the EC group order of the used curve
-    pub fn generate(
+    #[trace(disable(a, r, rng))]
+    pub fn generate<R: RngCore + CryptoRng + ?Sized>(
         a: &BigInt,
         cipher: &BigInt,
         alice_pk: &EncryptionKey,
         bob_zkp_setup: &ZkpPublicSetup,
         r: &BigInt,
         q: &BigInt,
+        rng: &mut R,
     ) -> Self {
-        let init = AliceZkpInit::random(alice_pk, bob_zkp_setup, q);
+        let init = AliceZkpInit::random(alice_pk, bob_zkp_setup, q, rng);
         let round1 = AliceZkpRound1::from(&init, a);
 
         let Gen = init.N() + 1;
-        let e = HSha512Trunc256::create_hash_bounded_by_q(
+        let e = HSha512Trunc256::create_hash_bounded_by_q_with_rng(
             &[init.N(), &Gen, cipher, &round1.z, &round1.u, &round1.w],
             q,
+            rng,
         );
 
         let round2 = AliceZkpRound2::from(&init, &e.0, a, r);
@@ -732,6 +818,13 @@ pub struct DLogProofs {
     pub beta_tag_proof: DLogProof,
 }
 
+impl Validate for DLogProofs {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.b_proof.validate()?;
+        self.beta_tag_proof.validate()
+    }
+}
+
 /// enumerates types of proofs Bob can use in the protocol
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BobProofType {
@@ -740,6 +833,16 @@ pub enum BobProofType {
     DLogProofs(DLogProofs),
 }
 
+impl Validate for BobProofType {
+    fn validate(&self) -> Result<(), ValidationError> {
+        match self {
+            BobProofType::RangeProofExt(proof) => proof.validate(),
+            BobProofType::RangeProof(proof) => proof.validate(),
+            BobProofType::DLogProofs(proofs) => proofs.validate(),
+        }
+    }
+}
+
 /// enumerates the subtype of Bob's proof
 #[derive(Debug)]
 pub enum MTAMode {
@@ -752,17 +855,47 @@ pub struct MessageB {
     pub c: BigInt,
     pub proof: BobProofType,
 }
+
+impl Validate for MessageB {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_bigint("MessageB.c", &self.c)?;
+        self.proof.validate()
+    }
+}
 #[trace(pretty, prefix = "MessageB::")]
 impl MessageB {
     /// b  is Bob's secret
     /// returns ( Message, new Bob's secret )
+    #[trace(disable(b))]
     pub fn new(
         b: &FE,
         alice_ek: &EncryptionKey,
         alice_zkp_setup: Option<&ZkpPublicSetup>,
         alice_msg: &MessageA,
         mta_mode: MTAMode,
-    ) -> (MessageB, FE) {
+    ) -> (MessageB, Secret<FE>) {
+        Self::new_with_rng(
+            b,
+            alice_ek,
+            alice_zkp_setup,
+            alice_msg,
+            mta_mode,
+            &mut OsRng,
+        )
+    }
+
+    /// same as `new` but draws Bob's additive share, the encryption randomness and the randomness of the range proof from given generator
+    ///
+    /// Discrete log proofs, used when Alice has no range proof setup, sample their randomness internally.
+    #[trace(disable(b, rng))]
+    pub fn new_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        b: &FE,
+        alice_ek: &EncryptionKey,
+        alice_zkp_setup: Option<&ZkpPublicSetup>,
+        alice_msg: &MessageA,
+        mta_mode: MTAMode,
+        rng: &mut R,
+    ) -> (MessageB, Secret<FE>) {
         let alice_c = &alice_msg.c;
 
         // E(a) * b
@@ -773,17 +906,17 @@ impl MessageB {
             RawPlaintext::from(&b_bn),
         );
 
-        let beta_prim = BigInt::sample_below(&alice_ek.n);
+        let beta_prim = Secret::new(random::sample_below(rng, &alice_ek.n));
 
         // E(beta_prim)
-        let r = Randomness::sample(&alice_ek);
+        let r = Randomness::from(&random::sample_unit(rng, &alice_ek.n));
         let enc_beta_prim =
-            Paillier::encrypt_with_chosen_randomness(alice_ek, RawPlaintext::from(&beta_prim), &r);
+            Paillier::encrypt_with_chosen_randomness(alice_ek, RawPlaintext::from(&*beta_prim), &r);
         //
         let mta_out = Paillier::add(alice_ek, b_times_enc_a, enc_beta_prim);
 
-        let beta_prim_fe: FE = ECScalar::from(&beta_prim);
-        let beta = FE::zero().sub(&beta_prim_fe.get_element());
+        let beta_prim_fe: Secret<FE> = Secret::new(ECScalar::from(&beta_prim));
+        let beta = Secret::new(FE::zero().sub(&beta_prim_fe.get_element()));
 
         let proof = match &alice_zkp_setup {
             Some(zkp_setup) => {
@@ -798,6 +931,7 @@ impl MessageB {
                         zkp_setup,
                         &r,
                         &FE::q(),
+                        rng,
                     )),
                     MTAMode::MtAwc => BobProofType::RangeProofExt(BobProofExt::generate(
                         &alice_c,
@@ -808,6 +942,7 @@ impl MessageB {
                         zkp_setup,
                         &r,
                         &FE::q(),
+                        rng,
                     )),
                 }
             }
@@ -835,48 +970,44 @@ impl MessageB {
 struct BobZkpInit {
     pub alice_ek: EncryptionKey,
     pub alice_setup: ZkpPublicSetup,
-    pub alpha: BigInt,
-    pub beta: BigInt,
-    pub gamma: BigInt,
-    pub ro: BigInt,
-    pub ro_prim: BigInt,
-    pub sigma: BigInt,
-    pub tau: BigInt,
-}
-
-impl Zeroize for BobZkpInit {
-    fn zeroize(&mut self) {
-        self.alice_ek.n.zeroize_bn();
-        self.alice_ek.nn.zeroize_bn();
-        self.alice_setup.zeroize();
-        self.alpha.zeroize_bn();
-        self.beta.zeroize_bn();
-        self.gamma.zeroize_bn();
-        self.ro.zeroize_bn();
-        self.ro_prim.zeroize_bn();
-        self.sigma.zeroize_bn();
-        self.tau.zeroize_bn();
-    }
-}
-
-impl Drop for BobZkpInit {
-    fn drop(&mut self) {
-        self.zeroize();
-    }
+    pub alpha: Secret<BigInt>,
+    pub beta: Secret<BigInt>,
+    pub gamma: Secret<BigInt>,
+    pub ro: Secret<BigInt>,
+    pub ro_prim: Secret<BigInt>,
+    pub sigma: Secret<BigInt>,
+    pub tau: Secret<BigInt>,
 }
 
 impl BobZkpInit {
-    fn random(alice_ek: &EncryptionKey, alice_setup: &ZkpPublicSetup, q: &BigInt) -> Self {
+    fn random<R: RngCore + CryptoRng + ?Sized>(
+        alice_ek: &EncryptionKey,
+        alice_setup: &ZkpPublicSetup,
+        q: &BigInt,
+        rng: &mut R,
+    ) -> Self {
         Self {
             alice_ek: alice_ek.clone(),
             alice_setup: alice_setup.clone(),
-            alpha: BigInt::sample_below(&q.pow(3)),
-            beta: BigInt::from_paillier_key(&alice_ek),
-            gamma: BigInt::sample_below(&(q.pow(2) * &alice_ek.n)),
-            ro: BigInt::sample_below(&(q * alice_setup.N_tilde.borrow())),
-            ro_prim: BigInt::sample_below(&(q.pow(3) * alice_setup.N_tilde.borrow())),
-            sigma: BigInt::sample_below(&(q * alice_setup.N_tilde.borrow())),
-            tau: BigInt::sample_below(&(q.pow(3) * alice_setup.N_tilde.borrow())),
+            alpha: Secret::new(random::sample_below(rng, &q.pow(3))),
+            beta: Secret::new(random::sample_unit(rng, &alice_ek.n)),
+            gamma: Secret::new(random::sample_below(rng, &(q.pow(2) * &alice_ek.n))),
+            ro: Secret::new(random::sample_below(
+                rng,
+                &(q * alice_setup.N_tilde.borrow()),
+            )),
+            ro_prim: Secret::new(random::sample_below(
+                rng,
+                &(q.pow(3) * alice_setup.N_tilde.borrow()),
+            )),
+            sigma: Secret::new(random::sample_below(
+                rng,
+                &(q * alice_setup.N_tilde.borrow()),
+            )),
+            tau: Secret::new(random::sample_below(
+                rng,
+                &(q.pow(3) * alice_setup.N_tilde.borrow()),
+            )),
         }
     }
     fn N(&self) -> &BigInt {
@@ -928,7 +1059,7 @@ impl BobZkpRound1 {
                 * init.h2().powm_sec(&init.tau, init.N_tilde()))
                 % init.N_tilde(),
             v: (a_encrypted.powm_sec(&init.alpha, init.NN())
-                * (init.gamma.borrow() * init.N() + 1)
+                * (&*init.gamma * init.N() + 1)
                 * init.beta.powm_sec(init.N(), init.NN()))
                 % init.NN(),
         }
@@ -952,11 +1083,11 @@ impl BobZkpRound2 {
     fn from(init: &BobZkpInit, e: &BigInt, b: &FE, beta_prim: &BigInt, r: &Randomness) -> Self {
         let b_bn = b.to_big_int();
         Self {
-            s: (r.0.borrow().powm_sec(e, init.N()) * init.beta.borrow()) % init.N(),
-            s1: (e * b_bn) + init.alpha.borrow(),
-            s2: (e * init.ro.borrow()) + init.ro_prim.borrow(),
-            t1: (e * beta_prim) + init.gamma.borrow(),
-            t2: (e * init.sigma.borrow()) + init.tau.borrow(),
+            s: (r.0.borrow().powm_sec(e, init.N()) * &*init.beta) % init.N(),
+            s1: (e * b_bn) + &*init.alpha,
+            s2: (e * &*init.ro) + &*init.ro_prim,
+            t1: (e * beta_prim) + &*init.gamma,
+            t2: (e * &*init.sigma) + &*init.tau,
         }
     }
 }
@@ -977,6 +1108,27 @@ pub struct BobProof {
     t2: BigInt,
 }
 
+impl Validate for BobProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        [
+            ("BobProof.t", &self.t),
+            ("BobProof.v", &self.v),
+            ("BobProof.w", &self.w),
+            ("BobProof.z", &self.z),
+            ("BobProof.z_prim", &self.z_prim),
+            ("BobProof.e", &self.e.0),
+            ("BobProof.e", &self.e.1),
+            ("BobProof.s", &self.s),
+            ("BobProof.s1", &self.s1),
+            ("BobProof.s2", &self.s2),
+            ("BobProof.t1", &self.t1),
+            ("BobProof.t2", &self.t2),
+        ]
+        .iter()
+        .try_for_each(|&(field, value)| check_bigint(field, value))
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 #[trace(pretty, prefix = "BobProof::")]
 impl BobProof {
@@ -1055,7 +1207,8 @@ impl BobProof {
         true
     }
 
-    pub fn generate(
+    #[trace(disable(b, beta_prim, r, rng))]
+    pub fn generate<R: RngCore + CryptoRng + ?Sized>(
         a_encrypted: &BigInt,
         mta_encrypted: &BigInt,
         b: &FE,
@@ -1064,21 +1217,25 @@ impl BobProof {
         alice_setup: &ZkpPublicSetup,
         r: &Randomness,
         q: &BigInt,
+        rng: &mut R,
     ) -> BobProof {
-        let init = BobZkpInit::random(alice_ek, &alice_setup, q);
+        let init = BobZkpInit::random(alice_ek, &alice_setup, q, rng);
         let round1 = BobZkpRound1::from(&init, b, beta_prim, a_encrypted);
 
-        let e = HSha512Trunc256::create_hash_with_random_nonce(&[
-            init.N(),
-            &init.Gen(),
-            a_encrypted,
-            mta_encrypted,
-            &round1.z,
-            &round1.z_prim,
-            &round1.t,
-            &round1.v,
-            &round1.w,
-        ]);
+        let e = HSha512Trunc256::create_hash_with_rng(
+            &[
+                init.N(),
+                &init.Gen(),
+                a_encrypted,
+                mta_encrypted,
+                &round1.z,
+                &round1.z_prim,
+                &round1.t,
+                &round1.v,
+                &round1.w,
+            ],
+            rng,
+        );
 
         let round2 = BobZkpRound2::from(&init, &e.0, b, beta_prim, r);
 
@@ -1106,6 +1263,14 @@ pub struct BobProofExt {
     X: GE,
 }
 
+impl Validate for BobProofExt {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.proof.validate()?;
+        check_point("BobProofExt.u", &self.u)?;
+        check_point("BobProofExt.X", &self.X)
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 #[trace(pretty, prefix = "BobProofExt::")]
 impl BobProofExt {
@@ -1160,7 +1325,8 @@ impl BobProofExt {
         true
     }
 
-    pub fn generate(
+    #[trace(disable(b, beta_prim, r, rng))]
+    pub fn generate<R: RngCore + CryptoRng + ?Sized>(
         a_encrypted: &BigInt,
         mta_encrypted: &BigInt,
         b: &FE,
@@ -1169,8 +1335,9 @@ impl BobProofExt {
         alice_setup: &ZkpPublicSetup,
         r: &Randomness,
         q: &BigInt,
+        rng: &mut R,
     ) -> BobProofExt {
-        let init = BobZkpInit::random(alice_ek, &alice_setup, q);
+        let init = BobZkpInit::random(alice_ek, &alice_setup, q, rng);
 
         let (X, u) = {
             let ec_gen: GE = ECPoint::generator();
@@ -1180,21 +1347,24 @@ impl BobProofExt {
 
         let round1 = BobZkpRound1::from(&init, b, beta_prim, a_encrypted);
 
-        let e = HSha512Trunc256::create_hash_with_random_nonce(&[
-            init.N(),
-            &init.Gen(),
-            &X.x_coor().unwrap(),
-            &X.y_coor().unwrap(),
-            a_encrypted,
-            mta_encrypted,
-            &u.x_coor().unwrap(),
-            &u.y_coor().unwrap(),
-            &round1.z,
-            &round1.z_prim,
-            &round1.t,
-            &round1.v,
-            &round1.w,
-        ]);
+        let e = HSha512Trunc256::create_hash_with_rng(
+            &[
+                init.N(),
+                &init.Gen(),
+                &X.x_coor().unwrap(),
+                &X.y_coor().unwrap(),
+                a_encrypted,
+                mta_encrypted,
+                &u.x_coor().unwrap(),
+                &u.y_coor().unwrap(),
+                &round1.z,
+                &round1.z_prim,
+                &round1.t,
+                &round1.v,
+                &round1.w,
+            ],
+            rng,
+        );
 
         let round2 = BobZkpRound2::from(&init, &e.0, b, beta_prim, r);
 
@@ -1293,6 +1463,35 @@ mod tests {
         assert!(proof.verify(&message.c, &public_paillier_key, &bob_setup));
     }
 
+    #[test]
+    fn seeded_alice_zkp_is_reproducible() {
+        use crate::algorithms::random::RandomnessSource;
+
+        let bob_setup = ZkpSetup::random(DEFAULT_GROUP_ORDER_BIT_LENGTH);
+        let bob_public_setup = ZkpPublicSetup::from_private_zkp_setup(&bob_setup);
+        let paillier_keys = PaillierKeys::random();
+        let a = FE::new_random();
+
+        let messages = (0..2)
+            .map(|_| {
+                MessageA::new_with_rng(
+                    &a,
+                    &paillier_keys.ek,
+                    Some(&bob_public_setup),
+                    &mut RandomnessSource::seeded(3),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(messages[0].c, messages[1].c);
+        assert_eq!(
+            serde_json::to_string(&messages[0].range_proof).unwrap(),
+            serde_json::to_string(&messages[1].range_proof).unwrap()
+        );
+
+        let proof = messages[0].range_proof.as_ref().unwrap();
+        assert!(proof.verify(&messages[0].c, &paillier_keys.ek, &bob_setup));
+    }
+
     #[derive(Debug)]
     pub struct AliceOrBob {
         pub paillier_keys: PaillierKeys,
@@ -1369,7 +1568,7 @@ mod tests {
                         let alice_share = alice_setup.paillier_keys.decrypt(msgb.c.clone());
                         let alice_share = alice_share.0.into_owned();
                         let alpha: FE = ECScalar::from(&alice_share);
-                        assert_eq!(a * b, alpha + beta);
+                        assert_eq!(a * b, alpha + *beta);
                         // verify range proof
                         if !proof.verify(&msga.c, &msgb.c, alice_public_key, &alice_setup.zkp_setup)
                         {
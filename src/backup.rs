@@ -0,0 +1,346 @@
+//! Verifiable backup of key shares to an escrow key
+//!
+//! [`ShareBackup`] encrypts the secret share of a party with the Paillier key of an escrow, e.g. of a disaster recovery service,
+//! and proves with [`EncDlogProof`] that the ciphertext encrypts the discrete log of the public share of the party.
+//! An auditor checks the backup with [`ShareBackup::verify`] knowing only the escrow's encryption key, and compares its public share
+//! with the public share of the party in the key info of any member of the committee.
+//!
+//! The rest of the key info, including the Paillier key and the range proof setup of the party, can't be verified this way.
+//! It is sealed with `XSalsa20-Poly1305` as in [`keystore`](../keystore/index.html), under a random key which is encrypted with the escrow key too.
+//! [`ShareBackup::restore`] decrypts both with the escrow's decryption key and rebuilds [`MultiPartyInfo`] of the party,
+//! which is checked against the verified public share and by [`MultiPartyInfo::validate`].
+//!
+//! ```ignore
+//! let backup = ShareBackup::create(&multi_party_info, &escrow_ek)?;
+//! backup.verify(&escrow_ek)?;
+//! let multi_party_info = backup.restore(&escrow_keys)?;
+//! ```
+//!
+//! [`ShareBackup`]: struct.ShareBackup.html
+//! [`ShareBackup::verify`]: struct.ShareBackup.html#method.verify
+//! [`ShareBackup::restore`]: struct.ShareBackup.html#method.restore
+//! [`EncDlogProof`]: ../algorithms/enc_dlog_proof/struct.EncDlogProof.html
+//! [`MultiPartyInfo`]: ../ecdsa/keygen/struct.MultiPartyInfo.html
+//! [`MultiPartyInfo::validate`]: ../ecdsa/keygen/struct.MultiPartyInfo.html#method.validate
+//!
+use crate::algorithms::enc_dlog_proof::{EncDlogProof, MASK_BIT_LENGTH};
+use crate::algorithms::random;
+use crate::algorithms::validation::{check_ciphertext, check_point, Validate, ValidationError};
+use crate::ecdsa::keygen::{IntegrityFinding, KeyId, MultiPartyInfo, MultiPartyPublicInfo};
+use crate::ecdsa::PaillierKeys;
+use crate::protocol::PartyIndex;
+use crate::secret::Secret;
+use curv::arithmetic::traits::Converter;
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use paillier::{EncryptWithChosenRandomness, EncryptionKey, Paillier, Randomness, RawPlaintext};
+use rand::rngs::OsRng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::secretbox;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Enumerates errors reported when the backup is created, verified or restored
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("backup: public shares of the key are unknown, the key has to be reshared first")]
+    PublicSharesMissing,
+    #[error("backup: invalid escrow key: {0}")]
+    InvalidEscrowKey(String),
+    #[error("backup: encrypted share does not match the public share of party {party}")]
+    InvalidProof { party: PartyIndex },
+    #[error("backup: the share does not match the public share")]
+    ShareMismatch,
+    #[error("backup: integrity check failed, the backup is tampered or the escrow key is wrong")]
+    IntegrityCheckFailed,
+    #[error("backup: sealed key info does not match the backup: {0}")]
+    InconsistentBackup(String),
+    #[error("backup: restored keys are inconsistent: {0:?}")]
+    InconsistentKeys(Vec<IntegrityFinding>),
+    #[error("backup: malformed backup: {0}")]
+    Malformed(String),
+    #[error("backup: {0}")]
+    Invalid(#[from] ValidationError),
+    #[error("backup: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("backup: {0}")]
+    Sealing(String),
+}
+
+/// Encrypted share of a party with the proof of its correctness and the sealed rest of its key info
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareBackup {
+    pub party: PartyIndex,
+    pub key_id: KeyId,
+    pub epoch: u64,
+    /// x-coordinate of the share
+    pub point: usize,
+    /// public share $` X_{i} = g^{x_{i}} `$ of the party, which the encrypted share is verified against
+    pub public_share: GE,
+    /// the secret share encrypted with the escrow key
+    pub encrypted_share: BigInt,
+    pub proof: EncDlogProof,
+    /// the key `sealed_info` is sealed with, encrypted with the escrow key
+    pub encrypted_sealing_key: BigInt,
+    pub nonce: String,
+    /// public info and Paillier keys of the party, sealed and hex-encoded
+    pub sealed_info: String,
+}
+
+/// Part of the key info which is sealed
+#[derive(Serialize, Deserialize)]
+struct SealedInfo {
+    info: MultiPartyPublicInfo,
+    paillier_keys: PaillierKeys,
+}
+
+impl ShareBackup {
+    /// Creates the backup of the key info of the party, encrypted with `escrow_ek`
+    pub fn create(info: &MultiPartyInfo, escrow_ek: &EncryptionKey) -> Result<Self, BackupError> {
+        Self::create_with_rng(info, escrow_ek, &mut OsRng)
+    }
+
+    /// same as `create`, but the randomness of encryptions and of the proof is drawn from `rng`
+    pub fn create_with_rng<R: RngCore + CryptoRng + ?Sized>(
+        info: &MultiPartyInfo,
+        escrow_ek: &EncryptionKey,
+        rng: &mut R,
+    ) -> Result<Self, BackupError> {
+        check_escrow_key(escrow_ek)?;
+        let public_share = *info
+            .public_shares
+            .get(&info.own_party_index)
+            .ok_or(BackupError::PublicSharesMissing)?;
+        let share = Secret::new(info.own_share());
+        let g: GE = ECPoint::generator();
+        if (g * *share).get_element() != public_share.get_element() {
+            return Err(BackupError::ShareMismatch);
+        }
+        let (encrypted_share, r) = encrypt(escrow_ek, &share.to_big_int(), rng);
+        let proof =
+            EncDlogProof::create(escrow_ek, &encrypted_share, &public_share, &share, &r, rng);
+
+        sodiumoxide::init()
+            .map_err(|_| BackupError::Sealing("sodiumoxide can't be initialized".to_string()))?;
+        let key = secretbox::gen_key();
+        let nonce = secretbox::gen_nonce();
+        let plaintext = Zeroizing::new(serde_json::to_vec(&SealedInfo {
+            info: info.public_info(),
+            paillier_keys: info.own_he_keys.clone(),
+        })?);
+        let sealed_info = secretbox::seal(&plaintext, &nonce, &key);
+        let (encrypted_sealing_key, _) = encrypt(escrow_ek, &BigInt::from(&key.0[..]), rng);
+
+        Ok(ShareBackup {
+            party: info.own_party_index,
+            key_id: info.metadata.key_id,
+            epoch: info.metadata.epoch,
+            point: info.own_point(),
+            public_share,
+            encrypted_share,
+            proof,
+            encrypted_sealing_key,
+            nonce: hex::encode(nonce.0),
+            sealed_info: hex::encode(sealed_info),
+        })
+    }
+
+    /// Checks that the backup is encrypted with `escrow_ek` and that the encrypted share matches the public share, without decrypting it
+    pub fn verify(&self, escrow_ek: &EncryptionKey) -> Result<(), BackupError> {
+        check_escrow_key(escrow_ek)?;
+        check_point("public share", &self.public_share)?;
+        check_ciphertext("encrypted share", &self.encrypted_share, escrow_ek)?;
+        check_ciphertext(
+            "encrypted sealing key",
+            &self.encrypted_sealing_key,
+            escrow_ek,
+        )?;
+        self.proof.validate()?;
+        if self
+            .proof
+            .verify(escrow_ek, &self.encrypted_share, &self.public_share)
+        {
+            Ok(())
+        } else {
+            Err(BackupError::InvalidProof { party: self.party })
+        }
+    }
+
+    /// Verifies the backup, decrypts it with `escrow_keys` and rebuilds the key info of the party
+    pub fn restore(&self, escrow_keys: &PaillierKeys) -> Result<MultiPartyInfo, BackupError> {
+        self.verify(&escrow_keys.ek)?;
+        let sealed = self.open(escrow_keys)?;
+        let info = &sealed.info;
+        if info.own_party_index != self.party
+            || info.own_point != self.point
+            || info.metadata.key_id != self.key_id
+            || info.metadata.epoch != self.epoch
+        {
+            return Err(BackupError::InconsistentBackup(
+                "party, share or key differs".to_string(),
+            ));
+        }
+        match info.public_shares.get(&self.party) {
+            Some(public_share) if public_share.get_element() == self.public_share.get_element() => {
+            }
+            _ => {
+                return Err(BackupError::InconsistentBackup(
+                    "public share differs".to_string(),
+                ))
+            }
+        }
+
+        let share = self.decrypt_share(escrow_keys)?;
+        let restored = sealed
+            .info
+            .into_multi_party_info((self.point, *share), sealed.paillier_keys);
+        let findings = restored.validate();
+        if findings.is_empty() {
+            Ok(restored)
+        } else {
+            Err(BackupError::InconsistentKeys(findings))
+        }
+    }
+
+    /// decrypts the sealing key and opens the sealed info
+    fn open(&self, escrow_keys: &PaillierKeys) -> Result<SealedInfo, BackupError> {
+        let key_bytes = Zeroizing::new(BigInt::to_vec(
+            &escrow_keys
+                .decrypt(self.encrypted_sealing_key.clone())
+                .0
+                .into_owned(),
+        ));
+        if key_bytes.len() > secretbox::KEYBYTES {
+            return Err(BackupError::IntegrityCheckFailed);
+        }
+        // leading zeros of the key are lost in the encryption
+        let mut padded = Zeroizing::new(vec![0u8; secretbox::KEYBYTES - key_bytes.len()]);
+        padded.extend_from_slice(&key_bytes);
+        let key = secretbox::Key::from_slice(&padded).ok_or(BackupError::IntegrityCheckFailed)?;
+        let nonce = secretbox::Nonce::from_slice(&decode_hex("nonce", &self.nonce)?)
+            .ok_or_else(|| BackupError::Malformed("invalid nonce length".to_string()))?;
+        let ciphertext = decode_hex("sealed info", &self.sealed_info)?;
+        sodiumoxide::init()
+            .map_err(|_| BackupError::Sealing("sodiumoxide can't be initialized".to_string()))?;
+        let plaintext = Zeroizing::new(
+            secretbox::open(&ciphertext, &nonce, &key)
+                .map_err(|_| BackupError::IntegrityCheckFailed)?,
+        );
+        Ok(serde_json::from_slice(&plaintext)?)
+    }
+
+    /// decrypts the share and checks it against the public share
+    ///
+    /// The proof bounds the encrypted value only up to the sign, and a negative value is decrypted as its residue modulo $` N `$.
+    fn decrypt_share(&self, escrow_keys: &PaillierKeys) -> Result<Secret<FE>, BackupError> {
+        let plaintext = Secret::new(
+            escrow_keys
+                .decrypt(self.encrypted_share.clone())
+                .0
+                .into_owned(),
+        );
+        let negative = Secret::new(&*plaintext - &escrow_keys.ek.n);
+        let g: GE = ECPoint::generator();
+        [&*plaintext, &*negative]
+            .iter()
+            .map(|value| Secret::new(ECScalar::from(&value.mod_floor(&FE::q()))))
+            .find(|share: &Secret<FE>| {
+                (g * **share).get_element() == self.public_share.get_element()
+            })
+            .ok_or(BackupError::ShareMismatch)
+    }
+}
+
+/// The escrow key has to be large enough for the proof
+fn check_escrow_key(escrow_ek: &EncryptionKey) -> Result<(), BackupError> {
+    escrow_ek.validate()?;
+    if escrow_ek.n.bit_length() <= MASK_BIT_LENGTH + 2 {
+        return Err(BackupError::InvalidEscrowKey(format!(
+            "modulus of {} bits is too small",
+            escrow_ek.n.bit_length()
+        )));
+    }
+    Ok(())
+}
+
+/// Encrypts `value` with `ek` and returns the ciphertext along with its randomness
+fn encrypt<R: RngCore + CryptoRng + ?Sized>(
+    ek: &EncryptionKey,
+    value: &BigInt,
+    rng: &mut R,
+) -> (BigInt, Secret<BigInt>) {
+    let r = Secret::new(random::sample_unit(rng, &ek.n));
+    let c = Paillier::encrypt_with_chosen_randomness(
+        ek,
+        RawPlaintext::from(value),
+        &Randomness::from(&*r),
+    )
+    .0
+    .into_owned();
+    (c, r)
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, BackupError> {
+    hex::decode(value).map_err(|e| BackupError::Malformed(format!("{}: {}", field, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BackupError, ShareBackup};
+    use crate::testkit::fixtures::load_keys;
+
+    #[test]
+    fn backup_restores_key_info() -> anyhow::Result<()> {
+        let keys = load_keys("keys")?;
+        let escrow = &keys[1].own_he_keys;
+        let backup = ShareBackup::create(&keys[0], &escrow.ek)?;
+        let backup: ShareBackup = serde_json::from_str(&serde_json::to_string(&backup)?)?;
+        backup.verify(&escrow.ek)?;
+
+        let restored = backup.restore(escrow)?;
+        assert_eq!(restored.secret_share, keys[0].secret_share);
+        assert_eq!(restored.own_he_keys, keys[0].own_he_keys);
+        assert_eq!(restored.public_shares, keys[0].public_shares);
+        assert_eq!(restored.metadata, keys[0].metadata);
+        Ok(())
+    }
+
+    #[test]
+    fn backup_is_bound_to_escrow_key() -> anyhow::Result<()> {
+        let keys = load_keys("keys")?;
+        let backup = ShareBackup::create(&keys[0], &keys[1].own_he_keys.ek)?;
+        let other_escrow = &keys[2].own_he_keys;
+        assert!(matches!(
+            backup.verify(&other_escrow.ek),
+            Err(BackupError::InvalidProof { party }) if party == keys[0].own_party_index
+        ));
+        assert!(backup.restore(other_escrow).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn backup_detects_share_of_another_party() -> anyhow::Result<()> {
+        let keys = load_keys("keys")?;
+        let escrow = &keys[2].own_he_keys;
+        let backup = ShareBackup::create(&keys[0], &escrow.ek)?;
+        let other = ShareBackup::create(&keys[1], &escrow.ek)?;
+
+        let mut replaced = backup.clone();
+        replaced.encrypted_share = other.encrypted_share.clone();
+        assert!(matches!(
+            replaced.verify(&escrow.ek),
+            Err(BackupError::InvalidProof { .. })
+        ));
+
+        // the share of another party with its proof verifies, but does not match the sealed key info
+        replaced.public_share = other.public_share;
+        replaced.proof = other.proof;
+        replaced.verify(&escrow.ek)?;
+        assert!(matches!(
+            replaced.restore(escrow),
+            Err(BackupError::InconsistentBackup(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,759 @@
+//! Command line tool which runs the protocols of the crate
+//!
+//! Every party runs as a separate process which talks to other parties over TCP or Unix sockets listed in the peer file:
+//!
+//! ```json
+//! { "peers": [
+//!     { "index": 0, "address": "127.0.0.1:7000" },
+//!     { "index": 1, "address": "unix:/tmp/mpc-ts.1.sock" }
+//! ] }
+//! ```
+//!
+//! Nodes of the resharing protocol additionally carry `"committee": "old"` or `"committee": "new"`,
+//! so that the same party can be listed once per committee.
+//!
+//! Key shares produced by `keygen` and `reshare` are sealed by the `keystore` with a passphrase, which is read from the file given by `--passphrase-file`
+//! or from `MPC_TS_PASSPHRASE` environment variable. Commands which read key shares open them the same way.
+//! `--insecure-plaintext` reads and writes JSON encoded `MultiPartyInfo` instead, which exposes the secret share and the Paillier key of the party.
+//! The integrity of the key share is checked before connecting to other parties, `inspect` prints public information about the share.
+//!
+//! The `simulate` subcommand runs all parties in the current process and reports how much time every phase takes.
+mod network;
+mod simulate;
+
+use crate::network::{accept_all, Committee, Peer, Peers};
+use crate::simulate::PhaseTimings;
+use anyhow::{anyhow, bail, Context};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use ecdsa_mpc::algorithms::random::{self, RandomnessSource};
+use ecdsa_mpc::algorithms::zkp::{ZkpSetup, DEFAULT_GROUP_ORDER_BIT_LENGTH};
+use ecdsa_mpc::ecdsa::keygen::{
+    self, KeyGeneratorTraits, MultiPartyInfo, SecretKeyLoader, SecretKeyLoaderError,
+};
+use ecdsa_mpc::ecdsa::resharing::{new_member, old_member};
+use ecdsa_mpc::ecdsa::signature::{self, SignedMessage, SigningTraits};
+use ecdsa_mpc::ecdsa::{InitialKeys, InitialPublicKeys, MessageHashType};
+use ecdsa_mpc::keystore::{KeyStore, SealingKey};
+use ecdsa_mpc::protocol::PartyIndex;
+use ecdsa_mpc::state_machine::BoxedState;
+use ecdsa_mpc::{Parameters, Signature};
+use paillier::DecryptionKey;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+use zeroize::Zeroizing;
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "mpc-ts", about = "Threshold ECDSA in secure multi-party setup")]
+enum Command {
+    /// Generates a share of new key together with other parties
+    Keygen {
+        #[structopt(flatten)]
+        node: NodeOptions,
+        /// minimal number of parties required to sign
+        #[structopt(long)]
+        min_signers: usize,
+        /// number of parties holding a share of the key
+        #[structopt(long)]
+        share_count: usize,
+        #[structopt(flatten)]
+        range_proofs: RangeProofOptions,
+        /// file to write the key share to
+        #[structopt(long, parse(from_os_str))]
+        output: PathBuf,
+        #[structopt(flatten)]
+        key_file: KeyFileOptions,
+    },
+    /// Signs a message together with other parties listed in the peer file
+    Sign {
+        #[structopt(flatten)]
+        node: NodeOptions,
+        /// file containing the key share
+        #[structopt(long, parse(from_os_str))]
+        key: PathBuf,
+        #[structopt(flatten)]
+        key_file: KeyFileOptions,
+        #[structopt(flatten)]
+        message: MessageOptions,
+        /// file to write the signature to, printed to stdout if omitted
+        #[structopt(long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Reshares the key from the old committee to the new one
+    Reshare {
+        #[structopt(flatten)]
+        node: NodeOptions,
+        /// committee of this node: `old` or `new`
+        #[structopt(long, parse(try_from_str = parse_committee))]
+        committee: Committee,
+        /// key share of the member of old committee
+        #[structopt(long, parse(from_os_str), required_if("committee", "old"))]
+        key: Option<PathBuf>,
+        /// minimal number of signers of the key held by the old committee, required by members of new committee
+        #[structopt(long, required_if("committee", "new"))]
+        old_min_signers: Option<usize>,
+        /// share count of the key held by the old committee, required by members of new committee
+        #[structopt(long, required_if("committee", "new"))]
+        old_share_count: Option<usize>,
+        /// minimal number of signers of the reshared key
+        #[structopt(long)]
+        min_signers: usize,
+        /// share count of the reshared key
+        #[structopt(long)]
+        share_count: usize,
+        /// range proof setup, used by members of new committee
+        #[structopt(flatten)]
+        range_proofs: RangeProofOptions,
+        /// file to write the new key share to, required by members of new committee
+        #[structopt(long, parse(from_os_str), required_if("committee", "new"))]
+        output: Option<PathBuf>,
+        #[structopt(flatten)]
+        key_file: KeyFileOptions,
+    },
+    /// Verifies the signature
+    Verify {
+        /// file containing a key share whose public key is used
+        #[structopt(long, parse(from_os_str), required_unless("public-key"))]
+        key: Option<PathBuf>,
+        /// public key, hex encoded compressed point
+        #[structopt(long, conflicts_with("key"))]
+        public_key: Option<String>,
+        #[structopt(flatten)]
+        key_file: KeyFileOptions,
+        /// file containing the signature produced by `sign`
+        #[structopt(long, parse(from_os_str))]
+        signature: PathBuf,
+        #[structopt(flatten)]
+        message: MessageOptions,
+    },
+    /// Prints public information about the key share
+    Inspect {
+        /// file containing the key share
+        #[structopt(long, parse(from_os_str))]
+        key: PathBuf,
+        #[structopt(flatten)]
+        key_file: KeyFileOptions,
+    },
+    /// Runs key generation and signing by all parties in this process and reports time taken by each phase
+    Simulate {
+        /// minimal number of parties required to sign
+        #[structopt(long)]
+        min_signers: usize,
+        /// number of parties holding a share of the key
+        #[structopt(long)]
+        share_count: usize,
+        /// file containing a list of range proof setups, one per party, see `examples/zkp-setup.rs`
+        #[structopt(long, parse(from_os_str))]
+        range_proof_setups: Option<PathBuf>,
+        /// seed of the random generator, makes the run reproducible
+        #[structopt(long)]
+        seed: Option<u64>,
+    },
+}
+
+/// Options of a node taking part in a protocol
+#[derive(Debug, StructOpt)]
+struct NodeOptions {
+    /// file listing all nodes of the protocol run
+    #[structopt(long, parse(from_os_str))]
+    peers: PathBuf,
+    /// index of the party this node acts for
+    #[structopt(long)]
+    party: usize,
+    /// timeout of a protocol phase in seconds
+    #[structopt(long, default_value = "120")]
+    timeout: u64,
+    /// time in seconds to wait for other nodes to start listening
+    #[structopt(long, default_value = "60")]
+    connect_timeout: u64,
+}
+
+impl NodeOptions {
+    fn timeout(&self) -> Option<Duration> {
+        Some(Duration::from_secs(self.timeout))
+    }
+
+    fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout)
+    }
+}
+
+/// Environment variable holding the passphrase key shares are sealed with
+const PASSPHRASE_VARIABLE: &str = "MPC_TS_PASSPHRASE";
+
+/// How key shares are read and written
+#[derive(Debug, StructOpt)]
+struct KeyFileOptions {
+    /// file containing the passphrase which seals key shares, `MPC_TS_PASSPHRASE` environment variable is used if omitted
+    #[structopt(long, parse(from_os_str))]
+    passphrase_file: Option<PathBuf>,
+    /// reads and writes key shares as plain JSON, which exposes the secret share
+    #[structopt(long, conflicts_with("passphrase-file"))]
+    insecure_plaintext: bool,
+}
+
+impl KeyFileOptions {
+    fn sealing_key(&self) -> anyhow::Result<SealingKey> {
+        let passphrase = match &self.passphrase_file {
+            Some(path) => Zeroizing::new(
+                fs::read_to_string(path)
+                    .with_context(|| format!("can't read {}", path.display()))?,
+            ),
+            None => Zeroizing::new(std::env::var(PASSPHRASE_VARIABLE).map_err(|_| {
+                anyhow!(
+                    "passphrase is required, use --passphrase-file or {}",
+                    PASSPHRASE_VARIABLE
+                )
+            })?),
+        };
+        let passphrase = passphrase.trim_end_matches(&['\r', '\n'][..]);
+        if passphrase.is_empty() {
+            bail!("passphrase is empty");
+        }
+        Ok(SealingKey::Passphrase(passphrase.to_string()))
+    }
+
+    /// Fails before a protocol is run if the key share it produces can't be sealed
+    fn check(&self) -> anyhow::Result<()> {
+        if !self.insecure_plaintext {
+            self.sealing_key()?;
+        }
+        Ok(())
+    }
+
+    /// Reads the key share and checks its integrity
+    fn read_key(&self, path: &Path) -> anyhow::Result<MultiPartyInfo> {
+        if self.insecure_plaintext {
+            return read_plaintext_key(path);
+        }
+        KeyStore::new()
+            .load(path, &self.sealing_key()?)
+            .with_context(|| format!("can't open key share {}", path.display()))
+    }
+
+    fn write_key(&self, path: &Path, info: &MultiPartyInfo) -> anyhow::Result<()> {
+        if self.insecure_plaintext {
+            log::warn!("key share {} is written unencrypted", path.display());
+            return write_json(path, info);
+        }
+        KeyStore::new()
+            .save(path, info, &self.sealing_key()?)
+            .with_context(|| format!("can't write {}", path.display()))
+    }
+}
+
+#[derive(Debug, StructOpt)]
+struct RangeProofOptions {
+    /// file containing JSON encoded range proof setup of this party
+    #[structopt(long, parse(from_os_str))]
+    range_proof_setup: Option<PathBuf>,
+    /// generates new range proof setup, which takes several minutes
+    #[structopt(long, conflicts_with("range-proof-setup"))]
+    generate_range_proof_setup: bool,
+}
+
+impl RangeProofOptions {
+    fn load(&self, rng: &mut RandomnessSource) -> anyhow::Result<Option<ZkpSetup>> {
+        if let Some(path) = &self.range_proof_setup {
+            return Ok(Some(read_json(path)?));
+        }
+        if self.generate_range_proof_setup {
+            log::info!("generating range proof setup");
+            return Ok(Some(ZkpSetup::random_with_rng(
+                DEFAULT_GROUP_ORDER_BIT_LENGTH,
+                rng,
+            )));
+        }
+        log::warn!("signing without range proofs is insecure");
+        Ok(None)
+    }
+}
+
+/// Message to sign or to verify, either the text which is hashed with SHA-256 or the hash itself
+#[derive(Debug, StructOpt)]
+struct MessageOptions {
+    /// text of the message
+    #[structopt(long, required_unless("hash"))]
+    message: Option<String>,
+    /// hex encoded hash of the message
+    #[structopt(long, conflicts_with("message"))]
+    hash: Option<String>,
+}
+
+impl MessageOptions {
+    fn hash(&self) -> anyhow::Result<MessageHashType> {
+        let hash = match (&self.message, &self.hash) {
+            (Some(message), _) => BigInt::from(Sha256::digest(message.as_bytes()).as_slice()),
+            (None, Some(hash)) => BigInt::from_str_radix(hash, 16)
+                .map_err(|e| anyhow!("invalid hash {}: {:?}", hash, e))?,
+            (None, None) => bail!("either message or its hash is required"),
+        };
+        Ok(ECScalar::from(&hash.mod_floor(&FE::q())))
+    }
+}
+
+/// Secret keys sampled before key generation
+#[derive(Debug)]
+struct InitialKeysLoader(InitialKeys);
+
+impl SecretKeyLoader for InitialKeysLoader {
+    fn get_initial_secret(&self) -> Result<Box<FE>, SecretKeyLoaderError> {
+        Ok(Box::new(self.0.u_i))
+    }
+
+    fn get_paillier_secret(&self) -> Result<Box<DecryptionKey>, SecretKeyLoaderError> {
+        Ok(Box::new(self.0.paillier_keys.dk.clone()))
+    }
+}
+
+/// Public information about a key share printed by `inspect`
+#[derive(Debug, Serialize)]
+struct KeyInfo {
+    party: PartyIndex,
+    key_id: String,
+    epoch: u64,
+    min_signers: usize,
+    share_count: usize,
+    public_key: String,
+    point: usize,
+    parties: Vec<(PartyIndex, usize)>,
+    range_proofs: bool,
+    /// inconsistencies found by the integrity check
+    findings: Vec<String>,
+}
+
+impl From<&MultiPartyInfo> for KeyInfo {
+    fn from(info: &MultiPartyInfo) -> Self {
+        let mut parties = info
+            .party_to_point_map
+            .points
+            .iter()
+            .map(|(party, point)| (*party, *point))
+            .collect::<Vec<_>>();
+        parties.sort_by_key(|(_, point)| *point);
+        KeyInfo {
+            party: info.own_party_index,
+            key_id: info.metadata.key_id.to_string(),
+            epoch: info.metadata.epoch,
+            min_signers: info.key_params.threshold() + 1,
+            share_count: info.key_params.share_count(),
+            public_key: public_key_to_hex(&info.public_key),
+            point: info.own_point(),
+            parties,
+            range_proofs: info.range_proof_setups.is_some(),
+            findings: info.validate().iter().map(|f| f.to_string()).collect(),
+        }
+    }
+}
+
+fn parse_committee(s: &str) -> anyhow::Result<Committee> {
+    match s {
+        "old" => Ok(Committee::Old),
+        "new" => Ok(Committee::New),
+        _ => bail!("committee is either `old` or `new`, got {}", s),
+    }
+}
+
+fn public_key_to_hex(public_key: &GE) -> String {
+    public_key.bytes_compressed_to_big_int().to_str_radix(16)
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("can't read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("can't parse {}", path.display()))
+}
+
+/// Reads the key share stored as plain JSON and checks its integrity
+fn read_plaintext_key(path: &Path) -> anyhow::Result<MultiPartyInfo> {
+    let info: MultiPartyInfo = read_json(path)?;
+    let findings = info.validate();
+    if !findings.is_empty() {
+        bail!(
+            "key share {} is inconsistent: {}",
+            path.display(),
+            findings
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+                .join("; ")
+        );
+    }
+    Ok(info)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    fs::write(path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("can't write {}", path.display()))
+}
+
+fn keygen(
+    node: &NodeOptions,
+    params: Parameters,
+    range_proofs: &RangeProofOptions,
+    output: &Path,
+    key_file: &KeyFileOptions,
+) -> anyhow::Result<()> {
+    key_file.check()?;
+    let peers = Peers::load(&node.peers)?;
+    let position = peers.position(node.party, None)?;
+    let mut rng = RandomnessSource::os();
+    let range_proof_setup = range_proofs.load(&mut rng)?;
+
+    let init_keys = InitialKeys::random_with_rng(&mut rng);
+    let init_pub_keys = InitialPublicKeys::from(&init_keys);
+    let start_state: BoxedState<KeyGeneratorTraits> = Box::new(keygen::Phase1::new_with_rng(
+        &params,
+        init_pub_keys,
+        range_proof_setup,
+        &peers.parties(None),
+        PartyIndex::from(node.party),
+        Arc::new(Box::new(InitialKeysLoader(init_keys))),
+        node.timeout(),
+        rng,
+    )?);
+
+    let final_state = network::run(
+        start_state,
+        &peers,
+        position,
+        accept_all,
+        node.connect_timeout(),
+    )?;
+    key_file.write_key(output, &final_state.multiparty_shared_info)?;
+    log::info!(
+        "key {} is written to {}",
+        public_key_to_hex(&final_state.multiparty_shared_info.public_key),
+        output.display()
+    );
+    Ok(())
+}
+
+fn sign(
+    node: &NodeOptions,
+    key: &Path,
+    key_file: &KeyFileOptions,
+    message: &MessageOptions,
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    let peers = Peers::load(&node.peers)?;
+    let position = peers.position(node.party, None)?;
+    let multi_party_info = key_file.read_key(key)?;
+    if multi_party_info.own_party_index != PartyIndex::from(node.party) {
+        bail!(
+            "key share {} belongs to party {}",
+            key.display(),
+            multi_party_info.own_party_index
+        );
+    }
+
+    let start_state: BoxedState<SigningTraits> = Box::new(signature::Phase1::new(
+        message.hash()?,
+        multi_party_info,
+        &peers.parties(None),
+        node.timeout(),
+    )?);
+    let signed_message = network::run(
+        start_state,
+        &peers,
+        position,
+        accept_all,
+        node.connect_timeout(),
+    )?;
+
+    match output {
+        Some(path) => write_json(path, &signed_message),
+        None => {
+            println!("{}", serde_json::to_string_pretty(&signed_message)?);
+            Ok(())
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn reshare(
+    node: &NodeOptions,
+    committee: Committee,
+    key: Option<&Path>,
+    old_params: Option<Parameters>,
+    new_params: Parameters,
+    range_proofs: &RangeProofOptions,
+    output: Option<&Path>,
+    key_file: &KeyFileOptions,
+) -> anyhow::Result<()> {
+    let peers = Peers::load(&node.peers)?;
+    let position = peers.position(node.party, Some(committee))?;
+    let old_committee = peers.parties(Some(Committee::Old));
+    let new_committee = peers.parties(Some(Committee::New));
+
+    match committee {
+        Committee::Old => {
+            let key = key.ok_or_else(|| anyhow!("key share is required"))?;
+            let multi_party_info = key_file.read_key(key)?;
+            let start_state: BoxedState<old_member::KeyResharingTraits> =
+                Box::new(old_member::Phase1::new(
+                    &multi_party_info,
+                    &new_params,
+                    &old_committee,
+                    &new_committee,
+                    node.timeout(),
+                )?);
+            network::run(
+                start_state,
+                &peers,
+                position,
+                Peer::accepts_resharing_message,
+                node.connect_timeout(),
+            )?;
+            log::info!("key share {} is handed over", key.display());
+        }
+        Committee::New => {
+            let old_params =
+                old_params.ok_or_else(|| anyhow!("parameters of old key are required"))?;
+            let output = output.ok_or_else(|| anyhow!("output file is required"))?;
+            key_file.check()?;
+            let mut rng = RandomnessSource::os();
+            let range_proof_setup = range_proofs.load(&mut rng)?;
+            let start_state: BoxedState<new_member::KeyResharingTraits> =
+                Box::new(new_member::Phase1::new_with_rng(
+                    &old_params,
+                    &new_params,
+                    &old_committee,
+                    &new_committee,
+                    PartyIndex::from(node.party),
+                    range_proof_setup,
+                    node.timeout(),
+                    rng,
+                )?);
+            let final_state = network::run(
+                start_state,
+                &peers,
+                position,
+                Peer::accepts_resharing_message,
+                node.connect_timeout(),
+            )?;
+            key_file.write_key(output, &final_state.info)?;
+            log::info!("new key share is written to {}", output.display());
+        }
+    }
+    Ok(())
+}
+
+fn verify(
+    key: Option<&Path>,
+    key_file: &KeyFileOptions,
+    public_key: Option<&str>,
+    signature: &Path,
+    message: &MessageOptions,
+) -> anyhow::Result<()> {
+    let public_key: GE = match (key, public_key) {
+        (Some(path), _) => key_file.read_key(path)?.public_key,
+        (None, Some(hex_key)) => ECPoint::from_bytes(&hex::decode(hex_key)?)
+            .map_err(|e| anyhow!("invalid public key {}: {:?}", hex_key, e))?,
+        (None, None) => bail!("either key share or public key is required"),
+    };
+    let signed_message: SignedMessage = read_json(signature)?;
+    let hash = message.hash()?;
+    if hash != signed_message.hash {
+        bail!("signature is made for another message");
+    }
+    let signature = Signature {
+        r: signed_message.r,
+        s: signed_message.s,
+    };
+    if !signature.verify(&public_key, &hash) {
+        bail!("signature is invalid");
+    }
+    println!("signature is valid");
+    Ok(())
+}
+
+fn simulate(
+    params: Parameters,
+    range_proof_setups: Option<&Path>,
+    seed: Option<u64>,
+) -> anyhow::Result<()> {
+    let rng = match seed {
+        Some(seed) => RandomnessSource::seeded(seed),
+        None => RandomnessSource::os(),
+    };
+    let share_count = params.share_count();
+    let parties = (0..share_count).map(PartyIndex::from).collect::<Vec<_>>();
+    let mut range_proof_setups = match range_proof_setups {
+        Some(path) => {
+            let setups: Vec<ZkpSetup> = read_json(path)?;
+            if setups.len() < share_count {
+                bail!(
+                    "{} range proof setups are required, {} found in {}",
+                    share_count,
+                    setups.len(),
+                    path.display()
+                );
+            }
+            setups.into_iter().map(Some).collect()
+        }
+        None => {
+            log::warn!("simulating without range proofs");
+            vec![None; share_count]
+        }
+    };
+
+    let started = Instant::now();
+    let mut start_states = Vec::new();
+    for (party, range_proof_setup) in parties.iter().zip(range_proof_setups.drain(..)) {
+        let init_keys = InitialKeys::random_with_rng(&mut rng.clone());
+        let init_pub_keys = InitialPublicKeys::from(&init_keys);
+        let start_state: BoxedState<KeyGeneratorTraits> = Box::new(keygen::Phase1::new_with_rng(
+            &params,
+            init_pub_keys,
+            range_proof_setup,
+            &parties,
+            *party,
+            Arc::new(Box::new(InitialKeysLoader(init_keys))),
+            None,
+            rng.clone(),
+        )?);
+        start_states.push((*party, start_state));
+    }
+    println!(
+        "initial keys of {} parties: {:?}",
+        share_count,
+        started.elapsed()
+    );
+
+    let (final_states, timings) = simulate::run(start_states)?;
+    report("keygen", &timings);
+
+    let public_key = final_states
+        .first()
+        .ok_or_else(|| anyhow!("no key shares produced"))?
+        .multiparty_shared_info
+        .public_key;
+    let signers = &parties[..=params.threshold()];
+    let message_hash: MessageHashType = random::sample_scalar(&mut rng.clone());
+    let mut start_states = Vec::new();
+    for final_state in final_states.into_iter().take(signers.len()) {
+        let info = final_state.multiparty_shared_info;
+        let party = info.own_party_index;
+        let start_state: BoxedState<SigningTraits> = Box::new(signature::Phase1::new_with_rng(
+            message_hash,
+            info,
+            signers,
+            None,
+            rng.clone(),
+        )?);
+        start_states.push((party, start_state));
+    }
+    let (signed_messages, timings) = simulate::run(start_states)?;
+    report("signing", &timings);
+
+    for signed_message in signed_messages {
+        let signature = Signature {
+            r: signed_message.r,
+            s: signed_message.s,
+        };
+        if !signature.verify(&public_key, &message_hash) {
+            bail!("signing has produced invalid signature");
+        }
+    }
+    Ok(())
+}
+
+fn report(protocol: &str, timings: &PhaseTimings) {
+    println!("{}:", protocol);
+    for (phase, elapsed) in &timings.phases {
+        println!("  {:<12} {:?}", phase, elapsed);
+    }
+    println!("  {:<12} {:?}", "total", timings.total());
+}
+
+fn main() -> anyhow::Result<()> {
+    let _ = env_logger::builder().try_init();
+
+    match Command::from_args() {
+        Command::Keygen {
+            node,
+            min_signers,
+            share_count,
+            range_proofs,
+            output,
+            key_file,
+        } => keygen(
+            &node,
+            Parameters::new(min_signers, share_count)?,
+            &range_proofs,
+            &output,
+            &key_file,
+        ),
+        Command::Sign {
+            node,
+            key,
+            key_file,
+            message,
+            output,
+        } => sign(&node, &key, &key_file, &message, output.as_deref()),
+        Command::Reshare {
+            node,
+            committee,
+            key,
+            old_min_signers,
+            old_share_count,
+            min_signers,
+            share_count,
+            range_proofs,
+            output,
+            key_file,
+        } => {
+            let old_params = match (old_min_signers, old_share_count) {
+                (Some(min_signers), Some(share_count)) => {
+                    Some(Parameters::new(min_signers, share_count)?)
+                }
+                _ => None,
+            };
+            reshare(
+                &node,
+                committee,
+                key.as_deref(),
+                old_params,
+                Parameters::new(min_signers, share_count)?,
+                &range_proofs,
+                output.as_deref(),
+                &key_file,
+            )
+        }
+        Command::Verify {
+            key,
+            public_key,
+            key_file,
+            signature,
+            message,
+        } => verify(
+            key.as_deref(),
+            &key_file,
+            public_key.as_deref(),
+            &signature,
+            &message,
+        ),
+        Command::Inspect { key, key_file } => {
+            let info = key_file.read_key(&key)?;
+            println!("{}", serde_json::to_string_pretty(&KeyInfo::from(&info))?);
+            Ok(())
+        }
+        Command::Simulate {
+            min_signers,
+            share_count,
+            range_proof_setups,
+            seed,
+        } => simulate(
+            Parameters::new(min_signers, share_count)?,
+            range_proof_setups.as_deref(),
+            seed,
+        ),
+    }
+}
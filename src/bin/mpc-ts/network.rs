@@ -0,0 +1,442 @@
+//! Point-to-point links between processes of a protocol run
+//!
+//! Every node listens on its own address and opens one outgoing connection to every other node.
+//! A connection starts with the handshake carrying the position of the connecting node in the peer list,
+//! which is followed by a sequence of frames, each being a message encoded in the crate's wire format and prefixed with its length as big endian `u32`.
+//!
+//! Messages are routed the same way the in-process simulator does:
+//!  * `Peer(p)` is delivered to every other node acting as the party `p`
+//!  * `Broadcast` is delivered to every node acting as a party different from the sender
+//!
+//! Nodes of the resharing protocol additionally filter messages by the committee they belong to.
+use anyhow::{anyhow, bail, Context};
+use crossbeam_channel::{Receiver, Sender};
+use ecdsa_mpc::ecdsa::resharing::{new_member, old_member, Message as ResharingMessage};
+use ecdsa_mpc::protocol::{Address, InputMessage, OutputMessage, PartyIndex};
+use ecdsa_mpc::state_machine::sync_channels::StateMachine;
+use ecdsa_mpc::state_machine::{BoxedState, StateMachineTraits};
+use ecdsa_mpc::wire::{self, WireMessage};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{fs, thread};
+
+/// Upper bound of a frame size, protects the reader from allocating arbitrary amounts of memory
+const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Committee of the resharing protocol a node belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Committee {
+    Old,
+    New,
+}
+
+/// Entry of the peer list
+///
+/// `address` is either `host:port` of a TCP socket or `unix:<path>` of a Unix domain socket.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Peer {
+    pub index: usize,
+    pub address: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub committee: Option<Committee>,
+}
+
+impl Peer {
+    pub fn party(&self) -> PartyIndex {
+        PartyIndex::from(self.index)
+    }
+
+    /// Returns true if the node accepts given resharing message
+    pub fn accepts_resharing_message(&self, msg: &ResharingMessage) -> bool {
+        match self.committee {
+            Some(Committee::Old) => old_member::is_message_to_committee(msg),
+            Some(Committee::New) => new_member::is_message_to_committee(msg),
+            None => true,
+        }
+    }
+}
+
+/// List of all nodes taking part in the protocol run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Peers {
+    pub peers: Vec<Peer>,
+}
+
+impl Peers {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let peers: Peers = serde_json::from_str(
+            &fs::read_to_string(path)
+                .with_context(|| format!("can't read peer list {}", path.display()))?,
+        )
+        .with_context(|| format!("can't parse peer list {}", path.display()))?;
+        Ok(peers)
+    }
+
+    /// Finds position of the node acting as `index` in `committee`
+    pub fn position(&self, index: usize, committee: Option<Committee>) -> anyhow::Result<usize> {
+        let mut found = self
+            .peers
+            .iter()
+            .enumerate()
+            .filter(|(_, peer)| peer.index == index && peer.committee == committee);
+        match (found.next(), found.next()) {
+            (Some((position, _)), None) => Ok(position),
+            (None, _) => bail!(
+                "party {} {:?} is not found in the peer list",
+                index,
+                committee
+            ),
+            (Some(_), Some(_)) => bail!(
+                "party {} {:?} is listed more than once in the peer list",
+                index,
+                committee
+            ),
+        }
+    }
+
+    /// Parties of the nodes which belong to `committee`, in the order of the list
+    pub fn parties(&self, committee: Option<Committee>) -> Vec<PartyIndex> {
+        self.peers
+            .iter()
+            .filter(|peer| peer.committee == committee)
+            .map(Peer::party)
+            .collect()
+    }
+}
+
+type Reader = Box<dyn Read + Send>;
+type Writer = Box<dyn Write + Send>;
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    fn bind(address: &str) -> anyhow::Result<Self> {
+        match address.strip_prefix("unix:") {
+            #[cfg(unix)]
+            Some(path) => {
+                // the socket file left by previous run prevents binding
+                let _ = fs::remove_file(path);
+                Ok(Listener::Unix(UnixListener::bind(path)?))
+            }
+            #[cfg(not(unix))]
+            Some(_) => bail!("unix sockets are not supported on this platform"),
+            None => Ok(Listener::Tcp(TcpListener::bind(address)?)),
+        }
+    }
+
+    fn accept(&self) -> std::io::Result<Reader> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept()?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+fn connect(address: &str) -> std::io::Result<Writer> {
+    match address.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => Ok(Box::new(UnixStream::connect(path)?)),
+        #[cfg(not(unix))]
+        Some(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::Other,
+            "unix sockets are not supported on this platform",
+        )),
+        None => {
+            let stream = TcpStream::connect(address)?;
+            stream.set_nodelay(true)?;
+            Ok(Box::new(stream))
+        }
+    }
+}
+
+/// Connects to the peer, retrying until it starts listening or `deadline` passes
+fn connect_with_retry(address: &str, deadline: Instant) -> anyhow::Result<Writer> {
+    loop {
+        match connect(address) {
+            Ok(writer) => return Ok(writer),
+            Err(e) if Instant::now() < deadline => {
+                log::debug!("connection to {} failed: {}, retrying", address, e);
+                thread::sleep(Duration::from_millis(200));
+            }
+            Err(e) => return Err(anyhow!("can't connect to {}: {}", address, e)),
+        }
+    }
+}
+
+fn write_frame(writer: &mut dyn Write, payload: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&(payload.len() as u32).to_be_bytes())?;
+    writer.write_all(payload)?;
+    writer.flush()
+}
+
+fn read_frame(reader: &mut dyn Read) -> anyhow::Result<Vec<u8>> {
+    let mut length = [0u8; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_be_bytes(length) as usize;
+    if length > MAX_FRAME_LENGTH {
+        bail!("frame of {} bytes exceeds the limit", length);
+    }
+    let mut payload = vec![0u8; length];
+    reader.read_exact(&mut payload)?;
+    Ok(payload)
+}
+
+/// Reads messages of the connected peer and forwards them to the machine
+fn receive<B: WireMessage>(
+    mut reader: Reader,
+    peers: &Peers,
+    own_position: usize,
+    accepts: fn(&Peer, &B) -> bool,
+    ingress: &Sender<InputMessage<B>>,
+) -> anyhow::Result<()> {
+    let handshake = read_frame(&mut reader)?;
+    let source: usize = serde_json::from_slice(&handshake)?;
+    let sender = peers
+        .peers
+        .get(source)
+        .ok_or_else(|| anyhow!("handshake from unknown peer {}", source))?
+        .party();
+    loop {
+        let payload = match read_frame(&mut reader) {
+            Ok(payload) => payload,
+            // the peer has closed the connection
+            Err(_) => return Ok(()),
+        };
+        let body: B = match wire::decode(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("message from {} is rejected: {}", sender, e);
+                continue;
+            }
+        };
+        if !accepts(&peers.peers[own_position], &body) {
+            continue;
+        }
+        if ingress.send(InputMessage { sender, body }).is_err() {
+            // the machine has finished
+            return Ok(());
+        }
+    }
+}
+
+/// Positions of the nodes which should receive the message sent by the node at `own_position`
+fn recipients<B>(
+    peers: &Peers,
+    own_position: usize,
+    message: &OutputMessage<B>,
+    accepts: fn(&Peer, &B) -> bool,
+) -> Vec<usize> {
+    let own_party = peers.peers[own_position].party();
+    peers
+        .peers
+        .iter()
+        .enumerate()
+        .filter(|(position, peer)| {
+            *position != own_position
+                && match &message.recipient {
+                    Address::Peer(party) => peer.party() == *party,
+                    Address::Broadcast => peer.party() != own_party,
+                }
+                && accepts(peer, &message.body)
+        })
+        .map(|(position, _)| position)
+        .collect()
+}
+
+/// Executes the protocol on behalf of the node at `own_position` of the peer list
+///
+/// Blocks until the machine finishes. Peers which are not listening yet are retried until `connect_timeout` expires.
+pub fn run<T, B>(
+    start_state: BoxedState<T>,
+    peers: &Peers,
+    own_position: usize,
+    accepts: fn(&Peer, &B) -> bool,
+    connect_timeout: Duration,
+) -> anyhow::Result<T::FinalState>
+where
+    T: StateMachineTraits<InMsg = InputMessage<B>, OutMsg = OutputMessage<B>>,
+    T::ErrorState: Debug,
+    B: WireMessage + Send + 'static,
+{
+    let own_address = peers.peers[own_position].address.clone();
+    let listener =
+        Listener::bind(&own_address).with_context(|| format!("can't listen on {}", own_address))?;
+    log::info!("listening on {}", own_address);
+
+    let (ingress, rx) = crossbeam_channel::unbounded::<InputMessage<B>>();
+    let (tx, egress) = crossbeam_channel::unbounded::<OutputMessage<B>>();
+
+    {
+        let peers = peers.clone();
+        thread::spawn(move || loop {
+            match listener.accept() {
+                Ok(reader) => {
+                    let peers = peers.clone();
+                    let ingress = ingress.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = receive(reader, &peers, own_position, accepts, &ingress) {
+                            log::warn!("incoming connection failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => log::warn!("accept failed: {}", e),
+            }
+        });
+    }
+
+    let router = {
+        let peers = peers.clone();
+        thread::spawn(move || route(egress, &peers, own_position, accepts, connect_timeout))
+    };
+
+    let outcome = StateMachine::<T>::new(start_state, &rx, &tx).execute();
+    // closing the queue lets the router deliver the remaining messages and quit
+    drop(tx);
+    router
+        .join()
+        .map_err(|_| anyhow!("message router panicked"))??;
+
+    match outcome {
+        Some(Ok(final_state)) => Ok(final_state),
+        Some(Err(error_state)) => bail!("protocol failed: {:?}", error_state),
+        None => bail!("state machine has stopped unexpectedly"),
+    }
+}
+
+/// Sends output of the machine to its recipients until the machine closes the queue
+fn route<B: WireMessage>(
+    egress: Receiver<OutputMessage<B>>,
+    peers: &Peers,
+    own_position: usize,
+    accepts: fn(&Peer, &B) -> bool,
+    connect_timeout: Duration,
+) -> anyhow::Result<()> {
+    let deadline = Instant::now() + connect_timeout;
+    let handshake = serde_json::to_vec(&own_position)?;
+    let mut connections: HashMap<usize, Writer> = HashMap::new();
+
+    for message in egress.iter() {
+        let payload = wire::encode(&message.body)?;
+        for position in recipients(peers, own_position, &message, accepts) {
+            if !connections.contains_key(&position) {
+                let mut writer = connect_with_retry(&peers.peers[position].address, deadline)?;
+                write_frame(&mut writer, &handshake)?;
+                connections.insert(position, writer);
+            }
+            let writer = connections
+                .get_mut(&position)
+                .expect("connection is established above");
+            write_frame(writer, &payload).with_context(|| {
+                format!("can't send message to {}", peers.peers[position].address)
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Accepts every message, used by protocols where each party runs exactly one node
+pub fn accept_all<B>(_peer: &Peer, _msg: &B) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{accept_all, read_frame, recipients, write_frame, Committee, Peer, Peers};
+    use ecdsa_mpc::ecdsa::resharing::Message;
+    use ecdsa_mpc::protocol::{Address, OutputMessage, PartyIndex};
+
+    fn peer(index: usize, committee: Option<Committee>) -> Peer {
+        Peer {
+            index,
+            address: format!("127.0.0.1:{}", 7000 + index),
+            committee,
+        }
+    }
+
+    #[test]
+    fn frames_roundtrip() -> anyhow::Result<()> {
+        let mut buffer = Vec::new();
+        write_frame(&mut buffer, b"first")?;
+        write_frame(&mut buffer, b"")?;
+        let mut reader = &buffer[..];
+        assert_eq!(read_frame(&mut reader)?, b"first".to_vec());
+        assert_eq!(read_frame(&mut reader)?, Vec::<u8>::new());
+        assert!(read_frame(&mut reader).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn routing() {
+        let peers = Peers {
+            peers: (0..3).map(|i| peer(i, None)).collect(),
+        };
+        let broadcast = OutputMessage {
+            recipient: Address::Broadcast,
+            body: (),
+        };
+        assert_eq!(recipients(&peers, 1, &broadcast, accept_all), vec![0, 2]);
+        let direct = OutputMessage {
+            recipient: Address::Peer(PartyIndex::from(2)),
+            body: (),
+        };
+        assert_eq!(recipients(&peers, 1, &direct, accept_all), vec![2]);
+    }
+
+    #[test]
+    fn resharing_routing_respects_committees() {
+        let peers = Peers {
+            peers: vec![
+                peer(0, Some(Committee::Old)),
+                peer(1, Some(Committee::Old)),
+                peer(0, Some(Committee::New)),
+                peer(1, Some(Committee::New)),
+            ],
+        };
+        let ack = OutputMessage {
+            recipient: Address::Peer(PartyIndex::from(0)),
+            body: Message::Ack,
+        };
+        // the ACK of the new member 1 is delivered to the old member 0 only
+        assert_eq!(
+            recipients(&peers, 3, &ack, Peer::accepts_resharing_message),
+            vec![0]
+        );
+        let commit = OutputMessage {
+            recipient: Address::Peer(PartyIndex::from(1)),
+            body: Message::Commit,
+        };
+        // the commit of the old member 0 is delivered to the new member 1 only
+        assert_eq!(
+            recipients(&peers, 0, &commit, Peer::accepts_resharing_message),
+            vec![3]
+        );
+        let final_ack = OutputMessage {
+            recipient: Address::Peer(PartyIndex::from(0)),
+            body: Message::FinalAck,
+        };
+        // the final ACK is accepted by both committees
+        assert_eq!(
+            recipients(&peers, 3, &final_ack, Peer::accepts_resharing_message),
+            vec![0, 2]
+        );
+    }
+}
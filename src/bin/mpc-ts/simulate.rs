@@ -0,0 +1,94 @@
+//! Runs all parties of a protocol in the current process and measures the time spent in every phase
+//!
+//! Unlike the network simulator of the `testkit`, which measures virtual time, the driver measures the wall clock time
+//! each phase takes to process its messages, summed over all parties.
+use anyhow::bail;
+use ecdsa_mpc::protocol::{Address, InputMessage, OutputMessage, PartyIndex};
+use ecdsa_mpc::state_machine::stepper::Stepper;
+use ecdsa_mpc::state_machine::{BoxedState, StateMachineTraits};
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+
+/// Accumulated processing time of phases, in the order the phases were entered
+#[derive(Debug, Default)]
+pub struct PhaseTimings {
+    pub phases: Vec<(String, Duration)>,
+}
+
+impl PhaseTimings {
+    fn add(&mut self, phase: String, elapsed: Duration) {
+        match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+            Some((_, total)) => *total += elapsed,
+            None => self.phases.push((phase, elapsed)),
+        }
+    }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|(_, elapsed)| *elapsed).sum()
+    }
+}
+
+/// Executes the protocol by all `parties`, delivering messages in the order they were sent
+///
+/// Time spent by a party in handling a message is attributed to the phase which has received the message.
+pub fn run<T, B>(
+    parties: Vec<(PartyIndex, BoxedState<T>)>,
+) -> anyhow::Result<(Vec<T::FinalState>, PhaseTimings)>
+where
+    T: StateMachineTraits<InMsg = InputMessage<B>, OutMsg = OutputMessage<B>>,
+    T::ErrorState: Debug,
+    B: Clone,
+{
+    let mut timings = PhaseTimings::default();
+    let mut queue = VecDeque::new();
+    let mut nodes = Vec::new();
+    let mut outcomes = Vec::new();
+
+    for (party, start_state) in parties {
+        let mut stepper = Stepper::new(start_state);
+        let started = Instant::now();
+        let output = stepper.start();
+        timings.add(stepper.status().phase, started.elapsed());
+        queue.extend(output.into_iter().map(|msg| (party, msg)));
+        nodes.push((party, stepper));
+        outcomes.push(None);
+    }
+
+    while let Some((sender, msg)) = queue.pop_front() {
+        for (index, (party, stepper)) in nodes.iter_mut().enumerate() {
+            let party = *party;
+            let addressed = match &msg.recipient {
+                Address::Peer(recipient) => *recipient == party,
+                Address::Broadcast => party != sender,
+            };
+            if !addressed || outcomes[index].is_some() {
+                continue;
+            }
+            let phase = stepper.status().phase;
+            let started = Instant::now();
+            let outcome = stepper.deliver(InputMessage {
+                sender,
+                body: msg.body.clone(),
+            });
+            timings.add(phase, started.elapsed());
+            queue.extend(stepper.take_output().into_iter().map(|msg| (party, msg)));
+            match outcome {
+                Some(Ok(final_state)) => outcomes[index] = Some(final_state),
+                Some(Err(error_state)) => bail!("party {} failed: {:?}", party, error_state),
+                None => {}
+            }
+        }
+    }
+
+    let stalled = nodes
+        .iter()
+        .zip(outcomes.iter())
+        .filter(|(_, outcome)| outcome.is_none())
+        .map(|((party, stepper), _)| format!("{} in {}", party, stepper.status().phase))
+        .collect::<Vec<_>>();
+    if !stalled.is_empty() {
+        bail!("parties have stalled: {:?}", stalled);
+    }
+    Ok((outcomes.into_iter().flatten().collect(), timings))
+}
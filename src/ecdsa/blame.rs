@@ -0,0 +1,145 @@
+//! Blame reports
+//!
+//! When a protocol fails because of misbehaviour of some party, each honest party produces a list of [`Blame`] records.
+//! A record names the accused party, the phase where the misbehaviour was detected, the kind of the violation and
+//! the raw messages received from the accused party in that phase.
+//!
+//! The records are serializable, so that a coordinator can collect them from all parties and re-run the failed check
+//! against the attached evidence independently of the accusing party.
+//!
+//! [`Blame`]: struct.Blame.html
+
+use crate::protocol::{InputMessage, PartyIndex};
+use serde::{Deserialize, Serialize};
+
+/// Kind of protocol violation attributed to a party
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display)]
+pub enum BlameKind {
+    /// decommitment does not match the commitment broadcast earlier
+    InvalidCommitment,
+    /// secret share is not consistent with Feldman's VSS commitments
+    InvalidSecretSharing,
+    /// public key or its share is not a valid curve point
+    InvalidPublicKey,
+    /// zero knowledge proof of knowledge of discrete log failed
+    InvalidDlogProof,
+    /// zero knowledge proof of Paillier key's correctness failed
+    InvalidCorrectKeyProof,
+    /// range proof setup was expected but not provided
+    RangeProofSetupMissing,
+    /// range proof setup was provided but not expected
+    RangeProofSetupUnexpected,
+    /// range proof setup failed its verification
+    InvalidRangeProofSetup,
+    /// message of unexpected type was received
+    UnexpectedMessage,
+    /// Alice's range proof in MtA failed
+    InvalidAliceProof,
+    /// Bob's proof in MtA failed
+    InvalidBobProof,
+    /// Homomorphic ElGamal proof failed
+    InvalidElGamalProof,
+    /// party did not send expected message before the phase timed out
+    Unresponsive,
+    /// party was configured with protocol inputs different from ours
+    InputMismatch,
+    /// message failed validation, e.g. a ciphertext is out of range of the Paillier key
+    InvalidMessage,
+    /// party holds the share of an older epoch of the key
+    StaleKeyShare,
+}
+
+/// Accusation of a party, with evidence attached
+///
+/// `evidence` contains raw messages received from the accused party in the phase where the violation was detected.
+/// Some checks depend on messages from earlier phases, for example decommitments are verified against commitments,
+/// in which case the messages from earlier phases are attached too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blame<M> {
+    pub party: PartyIndex,
+    pub phase: String,
+    pub kind: BlameKind,
+    pub evidence: Vec<M>,
+}
+
+/// Errors which can attribute a violation to a party
+pub trait Accusation {
+    /// returns the accused party and the kind of violation, or `None` if the error does not blame anyone
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)>;
+
+    /// returns all accused parties, for errors which can blame more than one party at once
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        self.accusation().into_iter().collect()
+    }
+}
+
+/// Builds blame records for errors which accuse some party, attaching messages of accused party from `evidence`
+pub(crate) fn assign_blame<E, M>(
+    errors: &[E],
+    phase: &str,
+    evidence: &[InputMessage<M>],
+) -> Vec<Blame<M>>
+where
+    E: Accusation,
+    M: Clone,
+{
+    errors
+        .iter()
+        .flat_map(|e| e.accusations())
+        .map(|(party, kind)| Blame {
+            party,
+            phase: phase.to_string(),
+            kind,
+            evidence: evidence
+                .iter()
+                .filter(|m| m.sender == party)
+                .map(|m| m.body.clone())
+                .collect(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assign_blame, Accusation, BlameKind};
+    use crate::protocol::{InputMessage, PartyIndex};
+
+    enum TestError {
+        Bad(PartyIndex),
+        Other,
+    }
+
+    impl Accusation for TestError {
+        fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+            match self {
+                TestError::Bad(p) => Some((*p, BlameKind::InvalidDlogProof)),
+                TestError::Other => None,
+            }
+        }
+    }
+
+    #[test]
+    fn blame_attaches_evidence_of_accused_party() {
+        let evidence = vec![
+            InputMessage {
+                sender: PartyIndex::from(1),
+                body: "good",
+            },
+            InputMessage {
+                sender: PartyIndex::from(2),
+                body: "bad",
+            },
+        ];
+        let errors = vec![TestError::Bad(PartyIndex::from(2)), TestError::Other];
+        let blame = assign_blame(&errors, "phase4", &evidence);
+
+        assert_eq!(blame.len(), 1);
+        assert_eq!(blame[0].party, PartyIndex::from(2));
+        assert_eq!(blame[0].phase, "phase4");
+        assert_eq!(blame[0].kind, BlameKind::InvalidDlogProof);
+        assert_eq!(blame[0].evidence, vec!["bad"]);
+
+        let serialized = serde_json::to_string(&blame).expect("serializable blame");
+        assert!(serialized.contains("InvalidDlogProof"));
+    }
+}
@@ -0,0 +1,1407 @@
+//!  Controlled export of the key
+//!
+//! Reconstructs the secret key at a designated recipient, e.g. to migrate the key out of the threshold setup or to recover funds in an emergency.
+//! The ceremony is the opposite of the [key import](../import/index.html) and defeats the purpose of the threshold scheme, hence it is guarded by
+//! an explicit authorization round and leaves the audit trail at every participant.
+//!
+//! Any `t+1` holders of the key take part, along with the recipient which does not hold a share. All of them are configured with the same reference
+//! to the authorization of the export, the ticket, and with the Paillier key of the recipient, which is generated by the recipient in advance
+//! and authorized together with the ticket. Both are included into the digest of inputs together with the public key and the lists of holders and the recipient.
+//! The recipient sends its Paillier key with the proof of its correctness to holders, and a holder aborts if the key differs from the authorized one,
+//! so that a party which impersonates the recipient can't obtain shares encrypted with a key of its own.
+//! Every holder `i` computes its Lagrange-weighted share $` w_{i} = \lambda_{i} x_{i} `$ over the set of holders and broadcasts its authorization
+//! to other holders and to the recipient, which carries the metadata of the key and $` W_{i} = X_{i}^{\lambda_{i}} `$.
+//! Holders check weighted public shares of each other against public shares of the key, and that they sum up to the public key.
+//! A holder records the audit event and releases $` w_{i} `$ encrypted with the authorized Paillier key only when all checks have passed.
+//!
+//! The recipient decrypts weighted shares, checks each against $` W_{i} `$, sums them into the secret key $` x = \sum w_{i} `$
+//! and verifies it against the public key. It records its own audit event before it confirms the export to holders and returns the key.
+//! Audit events are recorded through [`ExportAuditLog`] provided by the application, and a failure to record the event aborts the export.
+//!
+//! Holders keep their shares, and the key remains usable by the committee after the export.
+//!
+//! [`ExportAuditLog`]: trait.ExportAuditLog.html
+use crate::algorithms::validation::ValidationError;
+use crate::ecdsa::blame::{Accusation, BlameKind};
+use crate::ecdsa::keygen::KeyId;
+use crate::ecdsa::{
+    is_broadcast_input_complete, to_hash_map_gen, InputDigest, Parameters, PhaseTimeout,
+    ProtocolError,
+};
+use crate::protocol::PartyIndex;
+use curv::GE;
+use paillier::EncryptionKey;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::Debug;
+use std::iter::FromIterator;
+use std::sync::Arc;
+use thiserror::Error;
+
+pub use super::messages::export::{InMsg, Message, OutMsg};
+
+/// Enumerates errors which can be reported by key export protocol
+#[derive(Debug, Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum ExportError {
+    #[error("export: {0}")]
+    Timeout(#[from] PhaseTimeout),
+    #[error("unexpected message {message_type:?}, party {party}")]
+    UnknownMessageType {
+        message_type: Message,
+        party: PartyIndex,
+    },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("public shares of the key are unknown, the key has to be reshared first")]
+    PublicSharesMissing,
+    #[error("invalid correct key proof {proof}, party {party}")]
+    InvalidCorrectKeyProof { proof: String, party: PartyIndex },
+    #[error("party {party} has sent a Paillier key which is not authorized for the export")]
+    UnauthorizedRecipientKey { party: PartyIndex },
+    #[error("holders describe the key differently")]
+    InconsistentAuthorization,
+    #[error("party {party} has sent a weighted share which does not match its public share")]
+    InvalidWeightedShare { party: PartyIndex },
+    #[error("party {party} has sent an invalid ciphertext")]
+    InvalidCiphertext { party: PartyIndex },
+    #[error("weighted shares do not sum up to the public key")]
+    PublicKeyMismatch,
+    #[error("audit event can't be recorded: {0}")]
+    AuditFailed(String),
+    #[error("protocol setup error: {0}")]
+    ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
+    #[error("{0}")]
+    GeneralError(String),
+}
+
+impl Accusation for ExportError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            ExportError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            ExportError::InvalidMessage { party, .. } => Some((*party, BlameKind::InvalidMessage)),
+            ExportError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            ExportError::InvalidCorrectKeyProof { party, .. } => {
+                Some((*party, BlameKind::InvalidCorrectKeyProof))
+            }
+            ExportError::UnauthorizedRecipientKey { party } => {
+                Some((*party, BlameKind::InputMismatch))
+            }
+            ExportError::InvalidWeightedShare { party } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            ExportError::InvalidCiphertext { party } => Some((*party, BlameKind::InvalidMessage)),
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            ExportError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
+/// Contains a vector of possible export errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<ExportError, Message>;
+
+/// Step of the export recorded in the audit log
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ExportAction {
+    /// a holder is about to send its weighted share to the recipient
+    ShareReleased,
+    /// the recipient has reconstructed the key and verified it against the public key
+    KeyReconstructed,
+}
+
+/// Audit record of the export, produced by every participant
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportAuditEvent {
+    pub action: ExportAction,
+    /// the party which records the event
+    pub party: PartyIndex,
+    pub key_id: KeyId,
+    pub epoch: u64,
+    pub public_key: GE,
+    pub holders: BTreeSet<PartyIndex>,
+    pub recipient: PartyIndex,
+    /// reference to the authorization of the export
+    pub ticket: String,
+}
+
+/// Interface for recording audit events of key export in the log of the application
+///
+/// A holder records its event before it releases its share, and the recipient records its event before it returns the key.
+/// The export fails if the event can't be recorded.
+pub trait ExportAuditLog: Debug {
+    fn record(&self, event: &ExportAuditEvent) -> Result<(), ExportAuditError>;
+}
+
+#[derive(Debug)]
+pub struct ExportAuditError(pub String);
+
+/// the type of the reference to [`ExportAuditLog`] used by key export protocol
+///
+/// [`ExportAuditLog`]: trait.ExportAuditLog.html
+pub type AExportAuditLog = Arc<Box<dyn ExportAuditLog + Send + Sync>>;
+
+impl ProtocolError<Message> for ExportError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        ExportError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        ExportError::InvalidMessage { party, error }
+    }
+}
+
+/// Digest of export inputs which holders and the recipient have to agree on
+fn input_digest(
+    params: &Parameters,
+    public_key: &GE,
+    holders: &[PartyIndex],
+    recipient: PartyIndex,
+    recipient_ek: &EncryptionKey,
+    ticket: &str,
+) -> InputDigest {
+    InputDigest::new()
+        .with_parameters("parameters", params)
+        .with_point("public_key", public_key)
+        .with_parties("holders", holders)
+        .with_parties("recipient", &[recipient])
+        .with_encryption_key("recipient_key", recipient_ek)
+        .with_text("ticket", ticket)
+}
+
+/// Checks the sizes of the set of holders and returns it as a set
+fn check_holders(
+    params: &Parameters,
+    holders: &[PartyIndex],
+    recipient: PartyIndex,
+) -> Result<BTreeSet<PartyIndex>, ExportError> {
+    if holders.len() <= params.threshold() {
+        return Err(ExportError::ProtocolSetupError(
+            "too few holders".to_string(),
+        ));
+    }
+    if holders.len() > params.share_count() {
+        return Err(ExportError::ProtocolSetupError(
+            "too many holders".to_string(),
+        ));
+    }
+    let holders_as_set = BTreeSet::from_iter(holders.iter().cloned());
+    if holders_as_set.len() != holders.len() {
+        return Err(ExportError::ProtocolSetupError(
+            "duplicate entries in holders' list".to_string(),
+        ));
+    }
+    if holders_as_set.contains(&recipient) {
+        return Err(ExportError::ProtocolSetupError(
+            "recipient can't be a holder".to_string(),
+        ));
+    }
+    Ok(holders_as_set)
+}
+
+/// Records `event` in the audit log
+fn record(audit_log: &AExportAuditLog, event: &ExportAuditEvent) -> Result<(), ExportError> {
+    audit_log
+        .record(event)
+        .map_err(|e| ExportError::AuditFailed(e.0))
+}
+
+/// Contains the protocol part performed by a holder of the share of the key
+pub mod holder {
+    use super::{
+        check_holders, input_digest, is_broadcast_input_complete, record, to_hash_map_gen,
+        AExportAuditLog, ErrorState, ExportAction, ExportAuditEvent, ExportError, InMsg, Message,
+        OutMsg,
+    };
+    use crate::algorithms::nizk_rsa;
+    use crate::algorithms::random::{self, RandomnessSource};
+    use crate::ecdsa::keygen::{
+        ASecretShareLoader, MultiPartyInfo, MultiPartyPublicInfo, SecretShareLoader,
+    };
+    use crate::ecdsa::messages::export::{Authorization, EncryptedShare, ExportRequest};
+    use crate::ecdsa::refresh::to_fe;
+    use crate::ecdsa::InputDigest;
+    use crate::protocol::{Address, PartyIndex};
+    use crate::secret::Secret;
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+    use paillier::{
+        EncryptWithChosenRandomness, EncryptionKey, Paillier, Randomness, RawPlaintext,
+    };
+    use std::collections::{BTreeSet, HashMap};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of key export protocol for a holder
+    ///
+    /// Contains the audit event recorded by the holder. The holder keeps its share of the key.
+    #[derive(Clone, Debug)]
+    pub struct FinalState {
+        pub event: ExportAuditEvent,
+    }
+
+    type OutMsgVec = Vec<OutMsg>;
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct ExportTraits;
+
+    impl StateMachineTraits for ExportTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// Initial phase of the protocol
+    ///
+    /// * broadcasts the authorization with the weighted public share to other holders and to the recipient
+    /// * collects authorizations of other holders and the request of the recipient, and verifies them
+    /// * records the audit event and encrypts the weighted share with the authorized Paillier key of the recipient
+    #[derive(Debug)]
+    pub struct Phase1 {
+        info: MultiPartyPublicInfo,
+        holders: BTreeSet<PartyIndex>,
+        other_holders: BTreeSet<PartyIndex>,
+        recipient: PartyIndex,
+        recipient_ek: EncryptionKey,
+        weighted_share: Secret<FE>,
+        weighted_public_shares: HashMap<PartyIndex, GE>,
+        ticket: String,
+        audit_log: AExportAuditLog,
+        inputs: InputDigest,
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        /// Initializes the export of the key to `recipient`, authorized by `ticket`
+        ///
+        /// `recipient_ek` is the Paillier key of the recipient authorized together with the ticket, the share is released only encrypted with it.
+        pub fn new(
+            multi_party_info: &MultiPartyInfo,
+            holders: &[PartyIndex],
+            recipient: PartyIndex,
+            recipient_ek: &EncryptionKey,
+            ticket: &str,
+            audit_log: AExportAuditLog,
+            timeout: Option<Duration>,
+        ) -> Result<Self, ExportError> {
+            Self::new_with_rng(
+                multi_party_info,
+                holders,
+                recipient,
+                recipient_ek,
+                ticket,
+                audit_log,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but the randomness of the encryption of the weighted share is sampled from `rng`
+        #[allow(clippy::too_many_arguments)]
+        pub fn new_with_rng(
+            multi_party_info: &MultiPartyInfo,
+            holders: &[PartyIndex],
+            recipient: PartyIndex,
+            recipient_ek: &EncryptionKey,
+            ticket: &str,
+            audit_log: AExportAuditLog,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ExportError> {
+            Self::init(
+                &multi_party_info.public_info(),
+                multi_party_info,
+                holders,
+                recipient,
+                recipient_ek,
+                ticket,
+                audit_log,
+                timeout,
+                rng,
+            )
+        }
+
+        /// Initializes the protocol which loads the secret share through `secret_loader` and samples the randomness of the encryption from `rng`
+        #[allow(clippy::too_many_arguments)]
+        pub fn new_with_loader(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: ASecretShareLoader,
+            holders: &[PartyIndex],
+            recipient: PartyIndex,
+            recipient_ek: &EncryptionKey,
+            ticket: &str,
+            audit_log: AExportAuditLog,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ExportError> {
+            Self::init(
+                multi_party_info,
+                &**secret_loader,
+                holders,
+                recipient,
+                recipient_ek,
+                ticket,
+                audit_log,
+                timeout,
+                rng,
+            )
+        }
+
+        #[allow(clippy::too_many_arguments)]
+        #[trace(disable(secret_loader, rng))]
+        fn init(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: &dyn SecretShareLoader,
+            holders: &[PartyIndex],
+            recipient: PartyIndex,
+            recipient_ek: &EncryptionKey,
+            ticket: &str,
+            audit_log: AExportAuditLog,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ExportError> {
+            let params = &multi_party_info.key_params;
+            let holders_as_set = check_holders(params, holders, recipient)?;
+            let own_party_index = multi_party_info.own_party_index;
+            if !holders_as_set.contains(&own_party_index) {
+                return Err(ExportError::ProtocolSetupError(
+                    "own party index not in holders' list".to_string(),
+                ));
+            }
+            let points = &multi_party_info.party_to_point_map;
+            if holders.iter().any(|p| !points.points.contains_key(p)) {
+                return Err(ExportError::ProtocolSetupError(
+                    "x-coordinate of some holder is unknown".to_string(),
+                ));
+            }
+            if holders
+                .iter()
+                .any(|p| !multi_party_info.public_shares.contains_key(p))
+            {
+                return Err(ExportError::PublicSharesMissing);
+            }
+
+            let weighted_public_shares = holders
+                .iter()
+                .map(|p| {
+                    let multiplier =
+                        points.calculate_lagrange_multiplier(holders, to_fe(points.points[p]));
+                    (*p, multi_party_info.public_shares[p] * &multiplier)
+                })
+                .collect::<HashMap<_, _>>();
+            let multiplier =
+                points.calculate_lagrange_multiplier(holders, to_fe(multi_party_info.own_point));
+            let weighted_share = {
+                let x_i = secret_loader
+                    .get_secret_share()
+                    .map(Secret::from_box)
+                    .map_err(|e| ExportError::ProtocolSetupError(e.0))?;
+                Secret::new(*x_i * multiplier)
+                // x_i is erased here
+            };
+
+            let mut other_holders = holders_as_set.clone();
+            other_holders.remove(&own_party_index);
+
+            Ok(Phase1 {
+                info: multi_party_info.clone(),
+                holders: holders_as_set,
+                other_holders,
+                recipient,
+                recipient_ek: recipient_ek.clone(),
+                weighted_share,
+                weighted_public_shares,
+                ticket: ticket.to_string(),
+                audit_log,
+                inputs: input_digest(
+                    params,
+                    &multi_party_info.public_key,
+                    holders,
+                    recipient,
+                    recipient_ek,
+                    ticket,
+                ),
+                timeout,
+                rng,
+            })
+        }
+
+        /// parties which send messages in the first round
+        fn senders(&self) -> BTreeSet<PartyIndex> {
+            let mut senders = self.other_holders.clone();
+            senders.insert(self.recipient);
+            senders
+        }
+
+        /// checks authorizations of other holders against their weighted public shares
+        #[trace(disable(input))]
+        fn verify_authorizations(
+            &self,
+            input: &HashMap<PartyIndex, Authorization>,
+        ) -> Vec<ExportError> {
+            let mut errors = self
+                .inputs
+                .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+                .into_iter()
+                .map(|(party, field)| ExportError::InputMismatch { party, field })
+                .collect::<Vec<_>>();
+            for (party, msg) in input.iter() {
+                let party = *party;
+                if msg.metadata != self.info.metadata {
+                    errors.push(ExportError::InputMismatch {
+                        party,
+                        field: "metadata".to_string(),
+                    });
+                }
+                if msg.weighted_public_share.get_element()
+                    != self.weighted_public_shares[&party].get_element()
+                {
+                    errors.push(ExportError::InvalidWeightedShare { party });
+                }
+            }
+            errors
+        }
+
+        /// checks inputs of the recipient, that its Paillier key is the authorized one and the proof of correctness of the key
+        #[trace(disable(request))]
+        fn verify_request(&self, request: &ExportRequest) -> Vec<ExportError> {
+            let mut errors = self
+                .inputs
+                .mismatched_fields(&request.inputs)
+                .into_iter()
+                .map(|field| ExportError::InputMismatch {
+                    party: self.recipient,
+                    field,
+                })
+                .collect::<Vec<_>>();
+            if request.ek.n != self.recipient_ek.n {
+                errors.push(ExportError::UnauthorizedRecipientKey {
+                    party: self.recipient,
+                });
+            } else if nizk_rsa::verify(&request.ek, &request.correct_key_proof.0).is_err() {
+                errors.push(ExportError::InvalidCorrectKeyProof {
+                    proof: format!("{:?}", request.correct_key_proof),
+                    party: self.recipient,
+                });
+            }
+            errors
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<ExportTraits> for Phase1 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::info!("Phase1 (holder) starts");
+            let authorization = Authorization {
+                inputs: self.inputs.clone(),
+                metadata: self.info.metadata.clone(),
+                weighted_public_share: self.weighted_public_shares[&self.info.own_party_index],
+            };
+            Some(
+                self.senders()
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R2(authorization.clone()),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            let expected = match msg.body {
+                Message::R1(_) => msg.sender == self.recipient,
+                Message::R2(_) => self.other_holders.contains(&msg.sender),
+                _ => false,
+            };
+            expected && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.senders())
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ExportTraits> {
+            let fail = |errors: Vec<ExportError>| {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Holder.phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                Transition::FinalState(Err(error_state))
+            };
+            let (recipient_msgs, holder_msgs): (Vec<_>, Vec<_>) = current_msg_set
+                .iter()
+                .cloned()
+                .partition(|m| m.sender == self.recipient);
+            let input = match (
+                to_hash_map_gen::<Authorization, _, _>(&holder_msgs),
+                to_hash_map_gen::<ExportRequest, _, _>(&recipient_msgs),
+            ) {
+                (Ok(authorizations), Ok(mut requests)) => match requests.remove(&self.recipient) {
+                    Some(request) => Ok((authorizations, request)),
+                    None => Err(vec![ExportError::GeneralError(
+                        "message of the recipient is missing".to_string(),
+                    )]),
+                },
+                (authorizations, requests) => Err(authorizations
+                    .err()
+                    .into_iter()
+                    .chain(requests.err())
+                    .flatten()
+                    .collect()),
+            };
+            let (authorizations, request) = match input {
+                Ok(input) => input,
+                Err(errors) => return fail(errors),
+            };
+
+            let mut errors = self.verify_authorizations(&authorizations);
+            errors.extend(self.verify_request(&request));
+            if !errors.is_empty() {
+                return fail(errors);
+            }
+            let sum = self
+                .weighted_public_shares
+                .values()
+                .fold(None, |acc: Option<GE>, w| {
+                    Some(acc.map_or(*w, |acc| acc + *w))
+                });
+            if sum.map(|sum| sum.get_element()) != Some(self.info.public_key.get_element()) {
+                return fail(vec![ExportError::PublicKeyMismatch]);
+            }
+
+            let event = ExportAuditEvent {
+                action: ExportAction::ShareReleased,
+                party: self.info.own_party_index,
+                key_id: self.info.metadata.key_id,
+                epoch: self.info.metadata.epoch,
+                public_key: self.info.public_key,
+                holders: self.holders.clone(),
+                recipient: self.recipient,
+                ticket: self.ticket.clone(),
+            };
+            // the share is not released unless the event is recorded
+            if let Err(e) = record(&self.audit_log, &event) {
+                return fail(vec![e]);
+            }
+
+            let ek = &self.recipient_ek;
+            let ciphertext = Paillier::encrypt_with_chosen_randomness(
+                ek,
+                RawPlaintext::from(self.weighted_share.to_big_int()),
+                &Randomness::from(&random::sample_unit(&mut self.rng.clone(), &ek.n)),
+            )
+            .0
+            .into_owned();
+            log::info!("Phase1 (holder) succeeded, the share is released");
+
+            Transition::NewState(Box::new(Phase2 {
+                recipient: self.recipient,
+                ciphertext,
+                event,
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Holder.phase1", &self.senders(), current_msg_set)
+        }
+    }
+
+    /// Last phase of the protocol
+    ///
+    /// * sends the encrypted weighted share to the recipient
+    /// * waits for the recipient to confirm the export
+    #[derive(Debug)]
+    struct Phase2 {
+        recipient: PartyIndex,
+        ciphertext: BigInt,
+        event: ExportAuditEvent,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase2::")]
+    impl State<ExportTraits> for Phase2 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase2 (holder) starts");
+            Some(vec![OutMsg {
+                recipient: Address::Peer(self.recipient),
+                body: Message::R3(EncryptedShare {
+                    ciphertext: self.ciphertext.clone(),
+                }),
+            }])
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::Confirmed)
+                && msg.sender == self.recipient
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            current_msg_set.iter().any(|m| m.sender == self.recipient)
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<ExportTraits> {
+            log::info!("Phase2 (holder) succeeded, the export is confirmed");
+            Transition::FinalState(Ok(FinalState {
+                event: self.event.clone(),
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            let recipient = std::iter::once(self.recipient).collect::<BTreeSet<_>>();
+            PhaseStatus::new("Holder.phase2", &recipient, current_msg_set)
+        }
+    }
+}
+
+/// Contains the protocol part performed by the recipient of the key
+pub mod recipient {
+    use super::{
+        check_holders, input_digest, is_broadcast_input_complete, record, to_hash_map_gen,
+        AExportAuditLog, ErrorState, ExportAction, ExportAuditEvent, ExportError, InMsg, Message,
+        OutMsg,
+    };
+    use crate::algorithms::nizk_rsa;
+    use crate::algorithms::validation::check_ciphertext;
+    use crate::ecdsa::all_mapped_equal;
+    use crate::ecdsa::keygen::{CorrectKeyProof, KeyMetadata};
+    use crate::ecdsa::messages::export::{Authorization, EncryptedShare, ExportRequest};
+    use crate::ecdsa::{InputDigest, PaillierKeys, Parameters};
+    use crate::protocol::{Address, PartyIndex};
+    use crate::secret::Secret;
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{FE, GE};
+    use std::cell::RefCell;
+    use std::collections::{BTreeSet, HashMap};
+    use std::fmt::{Debug, Error, Formatter};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of key export protocol for the recipient
+    #[derive(Debug)]
+    pub struct FinalState {
+        pub secret_key: Secret<FE>,
+        pub public_key: GE,
+        /// the audit event recorded by the recipient
+        pub event: ExportAuditEvent,
+    }
+
+    type OutMsgVec = Vec<OutMsg>;
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct ExportTraits;
+
+    impl StateMachineTraits for ExportTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// Initial phase of the protocol
+    ///
+    /// * sends its authorized Paillier key and ZK proof of its correctness to holders
+    /// * collects authorizations of holders and checks that their weighted public shares sum up to the public key
+    #[derive(Debug)]
+    pub struct Phase1 {
+        public_key: GE,
+        holders: BTreeSet<PartyIndex>,
+        own_party_index: PartyIndex,
+        paillier_keys: PaillierKeys,
+        correct_key_proof: CorrectKeyProof,
+        ticket: String,
+        audit_log: AExportAuditLog,
+        inputs: InputDigest,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        /// Initializes the export of the key with `public_key` from `holders`, authorized by `ticket`
+        ///
+        /// `paillier_keys` are generated in advance, their public part being authorized together with the ticket and configured at holders.
+        #[allow(clippy::too_many_arguments)]
+        pub fn new(
+            params: &Parameters,
+            public_key: &GE,
+            holders: &[PartyIndex],
+            own_party_index: PartyIndex,
+            paillier_keys: PaillierKeys,
+            ticket: &str,
+            audit_log: AExportAuditLog,
+            timeout: Option<Duration>,
+        ) -> Result<Self, ExportError> {
+            let holders_as_set = check_holders(params, holders, own_party_index)?;
+            Ok(Phase1 {
+                public_key: *public_key,
+                holders: holders_as_set,
+                own_party_index,
+                correct_key_proof: CorrectKeyProof(nizk_rsa::gen_proof(&paillier_keys.dk)),
+                inputs: input_digest(
+                    params,
+                    public_key,
+                    holders,
+                    own_party_index,
+                    &paillier_keys.ek,
+                    ticket,
+                ),
+                paillier_keys,
+                ticket: ticket.to_string(),
+                audit_log,
+                timeout,
+            })
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<ExportTraits> for Phase1 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::info!("Phase1 (recipient) starts");
+            let request = ExportRequest {
+                inputs: self.inputs.clone(),
+                ek: self.paillier_keys.ek.clone(),
+                correct_key_proof: self.correct_key_proof.clone(),
+            };
+            Some(
+                self.holders
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R1(request.clone()),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R2(_))
+                && self.holders.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.holders)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ExportTraits> {
+            let fail = |errors: Vec<ExportError>| {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Recipient.phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                Transition::FinalState(Err(error_state))
+            };
+            let input = match to_hash_map_gen::<Authorization, _, _>(&current_msg_set) {
+                Ok(input) => input,
+                Err(errors) => return fail(errors),
+            };
+            let errors = self
+                .inputs
+                .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+                .into_iter()
+                .map(|(party, field)| ExportError::InputMismatch { party, field })
+                .collect::<Vec<_>>();
+            if !errors.is_empty() {
+                return fail(errors);
+            }
+            if !all_mapped_equal(input.values(), |msg| msg.metadata.clone()) {
+                return fail(vec![ExportError::InconsistentAuthorization]);
+            }
+            let sum = input
+                .values()
+                .map(|msg| msg.weighted_public_share)
+                .fold(None, |acc: Option<GE>, w| {
+                    Some(acc.map_or(w, |acc| acc + w))
+                });
+            if sum.map(|sum| sum.get_element()) != Some(self.public_key.get_element()) {
+                return fail(vec![ExportError::PublicKeyMismatch]);
+            }
+            let metadata = match input.values().next() {
+                Some(msg) => msg.metadata.clone(),
+                None => {
+                    return fail(vec![ExportError::GeneralError(
+                        "authorizations are missing".to_string(),
+                    )])
+                }
+            };
+            log::info!("Phase1 (recipient) succeeded, the export is authorized");
+
+            Transition::NewState(Box::new(Phase2 {
+                public_key: self.public_key,
+                holders: self.holders.clone(),
+                own_party_index: self.own_party_index,
+                paillier_keys: self.paillier_keys.clone(),
+                metadata,
+                weighted_public_shares: input
+                    .iter()
+                    .map(|(p, msg)| (*p, msg.weighted_public_share))
+                    .collect(),
+                authorizations: current_msg_set.clone(),
+                ticket: self.ticket.clone(),
+                audit_log: self.audit_log.clone(),
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Recipient.phase1", &self.holders, current_msg_set)
+        }
+    }
+
+    /// Second phase of the protocol
+    ///
+    /// * collects encrypted weighted shares from holders, decrypts them and checks them against weighted public shares
+    /// * sums up weighted shares into the secret key, verifies it against the public key and records the audit event
+    #[derive(Debug)]
+    struct Phase2 {
+        public_key: GE,
+        holders: BTreeSet<PartyIndex>,
+        own_party_index: PartyIndex,
+        paillier_keys: PaillierKeys,
+        metadata: KeyMetadata,
+        weighted_public_shares: HashMap<PartyIndex, GE>,
+        /// messages of the first phase, attached as evidence against holders
+        authorizations: Vec<InMsg>,
+        ticket: String,
+        audit_log: AExportAuditLog,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase2::")]
+    impl Phase2 {
+        /// decrypts the weighted share of `party` and checks it against its weighted public share
+        #[trace(disable(msg))]
+        fn decrypt_share(
+            &self,
+            party: PartyIndex,
+            msg: &EncryptedShare,
+        ) -> Result<Secret<FE>, ExportError> {
+            check_ciphertext("ciphertext", &msg.ciphertext, &self.paillier_keys.ek)
+                .map_err(|_| ExportError::InvalidCiphertext { party })?;
+            let plaintext = Secret::new(
+                self.paillier_keys
+                    .decrypt(msg.ciphertext.clone())
+                    .0
+                    .into_owned(),
+            );
+            let share: Secret<FE> = Secret::new(ECScalar::from(&plaintext));
+            let g: GE = ECPoint::generator();
+            if (g * *share).get_element() == self.weighted_public_shares[&party].get_element() {
+                Ok(share)
+            } else {
+                Err(ExportError::InvalidWeightedShare { party })
+            }
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase2::")]
+    impl State<ExportTraits> for Phase2 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase2 (recipient) starts");
+            None
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R3(_))
+                && self.holders.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.holders)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ExportTraits> {
+            let fail = |errors: Vec<ExportError>| {
+                let evidence =
+                    [self.authorizations.as_slice(), current_msg_set.as_slice()].concat();
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Recipient.phase2", &evidence);
+                log::error!("Phase2 returns {:?}", error_state);
+                Transition::FinalState(Err(error_state))
+            };
+            let input = match to_hash_map_gen::<EncryptedShare, _, _>(&current_msg_set) {
+                Ok(input) => input,
+                Err(errors) => return fail(errors),
+            };
+            let mut errors = Vec::new();
+            let mut secret_key = Secret::new(FE::zero());
+            for (party, msg) in input.iter() {
+                match self.decrypt_share(*party, msg) {
+                    Ok(share) => secret_key = Secret::new(*secret_key + *share),
+                    Err(e) => errors.push(e),
+                }
+            }
+            if !errors.is_empty() {
+                return fail(errors);
+            }
+            let g: GE = ECPoint::generator();
+            if (g * *secret_key).get_element() != self.public_key.get_element() {
+                return fail(vec![ExportError::PublicKeyMismatch]);
+            }
+
+            let event = ExportAuditEvent {
+                action: ExportAction::KeyReconstructed,
+                party: self.own_party_index,
+                key_id: self.metadata.key_id,
+                epoch: self.metadata.epoch,
+                public_key: self.public_key,
+                holders: self.holders.clone(),
+                recipient: self.own_party_index,
+                ticket: self.ticket.clone(),
+            };
+            if let Err(e) = record(&self.audit_log, &event) {
+                return fail(vec![e]);
+            }
+            log::info!("Phase2 (recipient) succeeded, the key is reconstructed");
+
+            Transition::NewState(Box::new(Confirm {
+                output: Some(
+                    self.holders
+                        .iter()
+                        .map(|p| OutMsg {
+                            recipient: Address::Peer(*p),
+                            body: Message::Confirmed,
+                        })
+                        .collect(),
+                ),
+                outcome: RefCell::new(Some(Ok(FinalState {
+                    secret_key,
+                    public_key: self.public_key,
+                    event,
+                }))),
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Recipient.phase2", &self.holders, current_msg_set)
+        }
+    }
+
+    /// Last phase of the protocol, which confirms the export to holders and exits immediately
+    ///
+    /// The machine can't send messages on its final transition, hence the phase exits by zero timeout.
+    struct Confirm {
+        output: Option<Vec<OutMsg>>,
+        outcome: RefCell<Option<MachineResult>>,
+    }
+
+    impl Debug for Confirm {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+            write!(f, "recipient::Confirm")
+        }
+    }
+
+    #[trace(pretty, prefix = "Confirm::")]
+    impl State<ExportTraits> for Confirm {
+        fn start(&mut self) -> Option<Vec<OutMsg>> {
+            self.output.take()
+        }
+
+        fn is_message_expected(&self, _msg: &InMsg, _current_msg_set: &[InMsg]) -> bool {
+            false
+        }
+
+        fn is_input_complete(&self, _current_msg_set: &[InMsg]) -> bool {
+            false
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<ExportTraits> {
+            Transition::FinalState(
+                self.outcome
+                    .borrow_mut()
+                    .take()
+                    .unwrap_or_else(|| Err(consumed_error())),
+            )
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_secs(0))
+        }
+
+        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
+            self.outcome
+                .borrow_mut()
+                .take()
+                .unwrap_or_else(|| Err(consumed_error()))
+        }
+    }
+
+    fn consumed_error() -> ErrorState {
+        ErrorState::new(vec![ExportError::GeneralError(
+            "recipient: the phase is consumed already".to_string(),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        holder, recipient, AExportAuditLog, ExportAction, ExportAuditError, ExportAuditEvent,
+        ExportAuditLog, ExportError,
+    };
+    use crate::algorithms::random::RandomnessSource;
+    use crate::ecdsa::blame::BlameKind;
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::ecdsa::{PaillierKeys, PhaseTimeout};
+    use crate::protocol::PartyIndex;
+    use crate::state_machine::BoxedState;
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::{load_keys, Run};
+    use crate::testkit::{Action, Adversary};
+    use anyhow::bail;
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+    use paillier::EncryptionKey;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    enum Outcome {
+        Holder(holder::MachineResult),
+        Recipient(recipient::MachineResult),
+    }
+
+    /// keeps recorded events in memory
+    #[derive(Debug, Default)]
+    struct MemoryAuditLog {
+        events: Mutex<Vec<ExportAuditEvent>>,
+    }
+
+    impl ExportAuditLog for MemoryAuditLog {
+        fn record(&self, event: &ExportAuditEvent) -> Result<(), ExportAuditError> {
+            self.events
+                .lock()
+                .map_err(|e| ExportAuditError(e.to_string()))?
+                .push(event.clone());
+            Ok(())
+        }
+    }
+
+    /// shares the same log between all participants, so that the test can inspect it
+    #[derive(Debug)]
+    struct SharedAuditLog(Arc<MemoryAuditLog>);
+
+    impl ExportAuditLog for SharedAuditLog {
+        fn record(&self, event: &ExportAuditEvent) -> Result<(), ExportAuditError> {
+            self.0.record(event)
+        }
+    }
+
+    /// Paillier keys of the recipient, authorized for the export
+    fn recipient_keys() -> PaillierKeys {
+        PaillierKeys::random_from(&RandomnessSource::seeded(11))
+    }
+
+    /// Runs export, returns outcomes of holders followed by the outcome of the recipient, `None` for parties which have not finished
+    ///
+    /// Holders release shares under `authorized_ek`, while the recipient runs with `paillier_keys`.
+    /// The last holder is malicious, if `adversary` is given.
+    fn run_export(
+        keys: &[MultiPartyInfo],
+        holders: &[PartyIndex],
+        authorized_ek: &EncryptionKey,
+        paillier_keys: PaillierKeys,
+        log: &Arc<MemoryAuditLog>,
+        adversary: Option<Adversary<super::Message>>,
+    ) -> anyhow::Result<Vec<Option<Outcome>>> {
+        let ticket = "change request 42";
+        let recipient = PartyIndex::from(100);
+        let audit_log = || -> AExportAuditLog { Arc::new(Box::new(SharedAuditLog(log.clone()))) };
+        let mut simulation =
+            Run::new(0x6578706f).with_adversary(holders[holders.len() - 1], adversary);
+        for key in keys
+            .iter()
+            .filter(|key| holders.contains(&key.own_party_index))
+        {
+            let party = key.own_party_index;
+            let start_state: BoxedState<holder::ExportTraits> =
+                Box::new(holder::Phase1::new_with_rng(
+                    key,
+                    holders,
+                    recipient,
+                    authorized_ek,
+                    ticket,
+                    audit_log(),
+                    // holders give up on the recipient which has aborted
+                    Some(Duration::from_secs(10)),
+                    RandomnessSource::seeded(0x6578706f + key.own_point() as u64),
+                )?);
+            simulation.add_node(
+                format!("holder {}", party),
+                party,
+                start_state,
+                Outcome::Holder,
+            );
+        }
+        let start_state: BoxedState<recipient::ExportTraits> = Box::new(recipient::Phase1::new(
+            &keys[0].key_params,
+            &keys[0].public_key,
+            holders,
+            recipient,
+            paillier_keys,
+            ticket,
+            audit_log(),
+            None,
+        )?);
+        simulation.add_node("recipient", recipient, start_state, Outcome::Recipient);
+
+        Ok(simulation
+            .run()
+            .outcomes
+            .into_iter()
+            .map(|node| node.outcome)
+            .collect())
+    }
+
+    #[test]
+    fn export_reconstructs_key_and_records_events() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let holders = vec![PartyIndex::from(0), PartyIndex::from(1)];
+        let log = Arc::new(MemoryAuditLog::default());
+        let paillier_keys = recipient_keys();
+        let outcomes = run_export(
+            &keys,
+            &holders,
+            &paillier_keys.ek.clone(),
+            paillier_keys,
+            &log,
+            None,
+        )?;
+        assert_eq!(outcomes.len(), 3);
+
+        let (points, shares): (Vec<FE>, Vec<FE>) = keys[..2]
+            .iter()
+            .map(|key| {
+                let x: FE = ECScalar::from(&BigInt::from(key.own_point() as u64));
+                (x, key.own_share())
+            })
+            .unzip();
+        let expected = VerifiableSS::lagrange_interpolation_at_zero(&points, &shares);
+        for outcome in outcomes {
+            match outcome {
+                Some(Outcome::Holder(Ok(state))) => {
+                    assert_eq!(state.event.action, ExportAction::ShareReleased)
+                }
+                Some(Outcome::Recipient(Ok(state))) => {
+                    assert_eq!(state.secret_key.to_big_int(), expected.to_big_int());
+                    let g: GE = ECPoint::generator();
+                    assert_eq!(
+                        (g * *state.secret_key).get_element(),
+                        keys[0].public_key.get_element()
+                    );
+                    assert_eq!(state.event.action, ExportAction::KeyReconstructed);
+                }
+                outcome => bail!("success expected, got {:?}", outcome),
+            }
+        }
+
+        let events = log.events.lock().unwrap();
+        assert_eq!(events.len(), 3);
+        for event in events.iter() {
+            assert_eq!(event.key_id, keys[0].metadata.key_id);
+            assert_eq!(event.ticket, "change request 42");
+            assert_eq!(event.holders.len(), 2);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn export_requires_more_than_threshold_holders() -> anyhow::Result<()> {
+        let keys = load_keys("keys")?;
+        let log: AExportAuditLog = Arc::new(Box::new(MemoryAuditLog::default()));
+        let holders = vec![keys[0].own_party_index];
+        let result = holder::Phase1::new(
+            &keys[0],
+            &holders,
+            PartyIndex::from(100),
+            &recipient_keys().ek,
+            "change request 42",
+            log,
+            None,
+        );
+        assert!(matches!(result, Err(ExportError::ProtocolSetupError(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn export_detects_corrupted_share() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let holders = vec![PartyIndex::from(0), PartyIndex::from(1)];
+        let recipient = PartyIndex::from(100);
+        let attacker = holders[1];
+        let adversary = Adversary::new(attacker, &[holders[0], recipient]).in_phase(
+            "Holder.phase2",
+            Action::Corrupt(|msg: &mut super::Message| {
+                if let super::Message::R3(encrypted_share) = msg {
+                    scenarios::shift_by_one(&mut encrypted_share.ciphertext)
+                }
+            }),
+        );
+        let log = Arc::new(MemoryAuditLog::default());
+        let paillier_keys = recipient_keys();
+        let outcomes = run_export(
+            &keys,
+            &holders,
+            &paillier_keys.ek.clone(),
+            paillier_keys,
+            &log,
+            Some(adversary),
+        )?;
+
+        assert_eq!(outcomes.len(), 3);
+        // holders time out waiting for the confirmation which never comes
+        for outcome in &outcomes[..2] {
+            match outcome {
+                Some(Outcome::Holder(Err(error_state))) => match error_state.errors.as_slice() {
+                    [ExportError::Timeout(PhaseTimeout { missing, .. })] => {
+                        assert_eq!(missing.iter().collect::<Vec<_>>(), vec![&recipient])
+                    }
+                    errors => bail!("unexpected errors {:?}", errors),
+                },
+                outcome => bail!("error of the holder expected, got {:?}", outcome),
+            }
+        }
+        match &outcomes[2] {
+            Some(Outcome::Recipient(Err(error_state))) => {
+                match error_state.errors.as_slice() {
+                    [ExportError::InvalidWeightedShare { party }] => assert_eq!(*party, attacker),
+                    errors => bail!("unexpected errors {:?}", errors),
+                }
+                assert_eq!(error_state.blame.len(), 1);
+                assert_eq!(error_state.blame[0].party, attacker);
+                assert_eq!(error_state.blame[0].evidence.len(), 2);
+            }
+            outcome => bail!("error of the recipient expected, got {:?}", outcome),
+        }
+        let events = log.events.lock().unwrap();
+        assert!(events
+            .iter()
+            .all(|event| event.action == ExportAction::ShareReleased));
+        Ok(())
+    }
+
+    #[test]
+    fn export_rejects_recipient_impersonation() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let holders = vec![PartyIndex::from(0), PartyIndex::from(1)];
+        let recipient = PartyIndex::from(100);
+        let log = Arc::new(MemoryAuditLog::default());
+        // the impostor takes the place of the recipient, but can't produce the authorized key
+        let impostor_keys = PaillierKeys::random_from(&RandomnessSource::seeded(12));
+        let outcomes = run_export(
+            &keys,
+            &holders,
+            &recipient_keys().ek,
+            impostor_keys,
+            &log,
+            None,
+        )?;
+
+        assert_eq!(outcomes.len(), 3);
+        for outcome in &outcomes[..2] {
+            match outcome {
+                Some(Outcome::Holder(Err(error_state))) => {
+                    assert!(error_state.errors.iter().any(|e| matches!(
+                        e,
+                        ExportError::UnauthorizedRecipientKey { party } if *party == recipient
+                    )));
+                    assert!(!error_state.blame.is_empty());
+                    for blame in &error_state.blame {
+                        assert_eq!(blame.party, recipient);
+                        assert_eq!(blame.kind, BlameKind::InputMismatch);
+                    }
+                }
+                outcome => bail!("error of the holder expected, got {:?}", outcome),
+            }
+        }
+        assert!(!matches!(outcomes[2], Some(Outcome::Recipient(Ok(_)))));
+        // no share has been released
+        assert!(log.events.lock().unwrap().is_empty());
+        Ok(())
+    }
+}
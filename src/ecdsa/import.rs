@@ -0,0 +1,972 @@
+//!  Import of an existing key by a trusted dealer
+//!
+//! Moves a single-signer secp256k1 key under the threshold control of a committee, so that its public key and addresses do not change.
+//! Unlike [key generation](../keygen/index.html), the secret key is known to a trusted dealer, which splits it with Feldman's VSS
+//! into shares of a random polynomial of degree `t` whose free term is the key, sends each party its share privately and erases the key and the shares.
+//! The dealer does not hold a share, hence it has to be a party distinct from the members of the committee.
+//!
+//! Parties generate or attach their Paillier keys and range proof setups and exchange them as in key generation.
+//! Each party checks its share against Feldman's commitments and the free term of the sharing against the public key of the imported key,
+//! which parties know beforehand, and computes public shares of all parties from the commitments.
+//! Parties confirm their shares along with the digest of the imported public info to each other and to the dealer.
+//! The dealer finishes when all parties have confirmed the same info, after which the application can delete the original key.
+//!
+//! The imported key starts at epoch zero with the committee of parties, and its id is derived from the public key and public shares like in key generation.
+//! The protocol trusts the dealer with the secret key, and does not protect the key against the dealer which keeps a copy of it.
+use crate::algorithms::validation::ValidationError;
+use crate::ecdsa::blame::{Accusation, BlameKind};
+use crate::ecdsa::{
+    is_broadcast_input_complete, to_hash_map_gen, InputDigest, Parameters, PhaseTimeout,
+    ProtocolError,
+};
+use crate::protocol::PartyIndex;
+use curv::GE;
+use std::collections::{BTreeSet, HashMap};
+use std::iter::FromIterator;
+use thiserror::Error;
+
+pub use super::messages::import::{InMsg, Message, OutMsg};
+
+/// Enumerates errors which can be reported by key import protocol
+#[derive(Debug, Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum ImportError {
+    #[error("import: {0}")]
+    Timeout(#[from] PhaseTimeout),
+    #[error("unexpected message {message_type:?}, party {party}")]
+    UnknownMessageType {
+        message_type: Message,
+        party: PartyIndex,
+    },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("share dealt by party {party} does not match its commitments or the public key")]
+    InvalidDealtShare { party: PartyIndex },
+    #[error("invalid correct key proof {proof}, party {party}")]
+    InvalidCorrectKeyProof { proof: String, party: PartyIndex },
+    #[error("missing range proof from {party}")]
+    RangeProofSetupMissing { party: PartyIndex },
+    #[error("unexpected range proof from {party}, proof {proof:?} ")]
+    RangeProofSetupUnexpected { proof: String, party: PartyIndex },
+    #[error("range proof setup: dlog proof failed , party {party}, proof {proof} ")]
+    RangeProofSetupDlogProofFailed { proof: String, party: PartyIndex },
+    #[error("party {party} has imported the key differently")]
+    InconsistentImport { party: PartyIndex },
+    #[error("parties have imported the key differently")]
+    ImportNotConfirmed,
+    #[error("protocol setup error: {0}")]
+    ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
+    #[error("{0}")]
+    GeneralError(String),
+}
+
+impl Accusation for ImportError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            ImportError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            ImportError::InvalidMessage { party, .. } => Some((*party, BlameKind::InvalidMessage)),
+            ImportError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            ImportError::InvalidDealtShare { party } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            ImportError::InvalidCorrectKeyProof { party, .. } => {
+                Some((*party, BlameKind::InvalidCorrectKeyProof))
+            }
+            ImportError::RangeProofSetupMissing { party } => {
+                Some((*party, BlameKind::RangeProofSetupMissing))
+            }
+            ImportError::RangeProofSetupUnexpected { party, .. } => {
+                Some((*party, BlameKind::RangeProofSetupUnexpected))
+            }
+            ImportError::RangeProofSetupDlogProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidRangeProofSetup))
+            }
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            ImportError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
+/// Contains a vector of possible import errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<ImportError, Message>;
+
+impl ProtocolError<Message> for ImportError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        ImportError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        ImportError::InvalidMessage { party, error }
+    }
+}
+
+/// Digest of import inputs which the dealer and parties have to agree on
+fn input_digest(
+    params: &Parameters,
+    public_key: &GE,
+    dealer: PartyIndex,
+    parties: &[PartyIndex],
+) -> InputDigest {
+    InputDigest::new()
+        .with_parameters("parameters", params)
+        .with_point("public_key", public_key)
+        .with_parties("dealer", &[dealer])
+        .with_parties("parties", parties)
+}
+
+/// Checks the list of parties and returns it as a set
+fn check_parties(
+    params: &Parameters,
+    dealer: PartyIndex,
+    parties: &[PartyIndex],
+) -> Result<BTreeSet<PartyIndex>, ImportError> {
+    if parties.len() != params.share_count() {
+        return Err(ImportError::ProtocolSetupError(format!(
+            "{} parties can't hold {} shares",
+            parties.len(),
+            params.share_count()
+        )));
+    }
+    let parties_as_set = BTreeSet::from_iter(parties.iter().cloned());
+    if parties_as_set.len() != parties.len() {
+        return Err(ImportError::ProtocolSetupError(
+            "duplicate entries in parties' list".to_string(),
+        ));
+    }
+    if parties_as_set.contains(&dealer) {
+        return Err(ImportError::ProtocolSetupError(
+            "dealer can't be a party".to_string(),
+        ));
+    }
+    Ok(parties_as_set)
+}
+
+/// x-coordinates of shares of parties, assigned in ascending order of party indexes starting from one
+fn party_points(parties: &BTreeSet<PartyIndex>) -> HashMap<PartyIndex, usize> {
+    parties.iter().zip(1..).map(|(p, x)| (*p, x)).collect()
+}
+
+/// Contains the protocol part performed by the dealer holding the key
+pub mod dealer {
+    use super::{
+        check_parties, input_digest, is_broadcast_input_complete, party_points, to_hash_map_gen,
+        ErrorState, ImportError, InMsg, Message, OutMsg,
+    };
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::vss;
+    use crate::ecdsa::all_mapped_equal;
+    use crate::ecdsa::keygen::{compute_public_shares, KeyId};
+    use crate::ecdsa::messages::import::{DealtShare, ShareVerified};
+    use crate::ecdsa::messages::{FeldmanVSS, SecretShare};
+    use crate::ecdsa::{InputDigest, Parameters};
+    use crate::protocol::{Address, PartyIndex};
+    use crate::secret::Secret;
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::traits::ECPoint;
+    use curv::{FE, GE};
+    use std::collections::{BTreeSet, HashMap};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of key import protocol for the dealer
+    ///
+    /// All parties have confirmed their shares of the key, hence the application can delete the original key.
+    #[derive(Clone, Debug)]
+    pub struct FinalState {
+        pub public_key: GE,
+        pub key_id: KeyId,
+    }
+
+    type OutMsgVec = Vec<OutMsg>;
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct ImportTraits;
+
+    impl StateMachineTraits for ImportTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// The only phase of the dealer
+    ///
+    /// * sends shares of the key with Feldman's commitments to parties and erases them
+    /// * collects confirmations of parties and checks that they have imported the same key info
+    #[derive(Debug)]
+    pub struct Phase1 {
+        parties: BTreeSet<PartyIndex>,
+        public_key: GE,
+        key_id: KeyId,
+        vss: VerifiableSS,
+        shares: Secret<HashMap<PartyIndex, SecretShare>>,
+        inputs: InputDigest,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        /// Initializes the import of `secret_key` to `parties` with given parameters
+        ///
+        /// The key is erased as soon as it is shared, and the shares are erased as soon as they are sent.
+        #[trace(disable(secret_key))]
+        pub fn new(
+            params: &Parameters,
+            secret_key: Secret<FE>,
+            own_party_index: PartyIndex,
+            parties: &[PartyIndex],
+            timeout: Option<Duration>,
+        ) -> Result<Self, ImportError> {
+            Self::new_with_rng(
+                params,
+                secret_key,
+                own_party_index,
+                parties,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but the coefficients of Feldman's VSS are sampled from `rng`
+        #[trace(disable(secret_key, rng))]
+        pub fn new_with_rng(
+            params: &Parameters,
+            secret_key: Secret<FE>,
+            own_party_index: PartyIndex,
+            parties: &[PartyIndex],
+            timeout: Option<Duration>,
+            mut rng: RandomnessSource,
+        ) -> Result<Self, ImportError> {
+            let parties_as_set = check_parties(params, own_party_index, parties)?;
+            let g: GE = ECPoint::generator();
+            let public_key = g * *secret_key;
+            let (vss, shares) = vss::share(
+                params.threshold(),
+                params.share_count(),
+                &secret_key,
+                &mut rng,
+            );
+            // secret_key is erased here
+            drop(secret_key);
+            let shares = Secret::new(shares);
+
+            let points = party_points(&parties_as_set);
+            let public_shares = compute_public_shares(&[&vss], &points).ok_or_else(|| {
+                ImportError::ProtocolSetupError("no shares of the key".to_string())
+            })?;
+            let shares = Secret::new(
+                points
+                    .iter()
+                    .map(|(p, x)| (*p, (*x, shares[*x - 1])))
+                    .collect::<HashMap<_, _>>(),
+            );
+
+            Ok(Phase1 {
+                public_key,
+                key_id: KeyId::derive(&public_key, &public_shares),
+                vss,
+                shares,
+                inputs: input_digest(params, &public_key, own_party_index, parties),
+                parties: parties_as_set,
+                timeout,
+            })
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<ImportTraits> for Phase1 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::info!("Phase1 (dealer) starts");
+            let output = self
+                .shares
+                .iter()
+                .map(|(p, share)| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R2(DealtShare {
+                        inputs: self.inputs.clone(),
+                        vss: FeldmanVSS {
+                            vss: self.vss.clone(),
+                            share: *share,
+                        },
+                    }),
+                })
+                .collect();
+            // shares are erased here
+            self.shares = Secret::new(HashMap::new());
+            Some(output)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R3(_))
+                && self.parties.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.parties)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ImportTraits> {
+            let errors = match to_hash_map_gen::<ShareVerified, _, _>(&current_msg_set) {
+                Ok(input) if all_mapped_equal(input.values(), |msg| msg.digest.clone()) => {
+                    vec![]
+                }
+                Ok(_) => vec![ImportError::ImportNotConfirmed],
+                Err(errors) => errors,
+            };
+            if !errors.is_empty() {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Dealer.phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                return Transition::FinalState(Err(error_state));
+            }
+
+            log::info!("Phase1 (dealer) succeeded, the key is imported");
+            Transition::FinalState(Ok(FinalState {
+                public_key: self.public_key,
+                key_id: self.key_id,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Dealer.phase1", &self.parties, current_msg_set)
+        }
+    }
+}
+
+/// Contains the protocol part performed by a party receiving the share of the key
+pub mod party {
+    use super::{
+        check_parties, input_digest, is_broadcast_input_complete, party_points, to_hash_map_gen,
+        ErrorState, ImportError, InMsg, Message, OutMsg,
+    };
+    use crate::algorithms::nizk_rsa;
+    use crate::algorithms::zkp::{ZkpPublicSetup, ZkpSetup};
+    use crate::ecdsa::keygen::{
+        compute_public_shares, CorrectKeyProof, KeyId, KeyMetadata, MultiPartyInfo, Party2PointMap,
+        RangeProofSetups,
+    };
+    use crate::ecdsa::messages::import::{DealtShare, PartyBroadcast, ShareVerified};
+    use crate::ecdsa::refresh::public_digest;
+    use crate::ecdsa::{InputDigest, PaillierKeys, Parameters};
+    use crate::protocol::{Address, PartyIndex};
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use curv::elliptic::curves::traits::ECPoint;
+    use curv::{BigInt, GE};
+    use paillier::EncryptionKey;
+    use std::collections::{BTreeSet, HashMap};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of key import protocol for a party
+    #[derive(Clone, Debug)]
+    pub struct FinalState {
+        pub info: MultiPartyInfo,
+    }
+
+    type OutMsgVec = Vec<OutMsg>;
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct ImportTraits;
+
+    impl StateMachineTraits for ImportTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// Initial phase of the protocol
+    ///
+    /// * broadcasts own Paillier key, ZK proof of its correctness and range proof setup to other parties
+    /// * collects same items from other parties and the share of the key from the dealer, and verifies them
+    /// * computes public shares of all parties
+    #[derive(Debug)]
+    pub struct Phase1 {
+        params: Parameters,
+        public_key: GE,
+        dealer: PartyIndex,
+        own_party_index: PartyIndex,
+        other_parties: BTreeSet<PartyIndex>,
+        points: HashMap<PartyIndex, usize>,
+        paillier_keys: PaillierKeys,
+        correct_key_proof: CorrectKeyProof,
+        range_proof_setup: Option<ZkpSetup>,
+        inputs: InputDigest,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        /// Initializes the import of the key with `public_key`, dealt by `dealer` to `parties`
+        ///
+        /// `paillier_keys` are either generated by [`PaillierKeys::random`](../../struct.PaillierKeys.html#method.random) or attached by the application.
+        /// The range proof setup is required, as in key generation.
+        #[allow(clippy::too_many_arguments)]
+        #[trace(disable(paillier_keys))]
+        pub fn new(
+            params: &Parameters,
+            public_key: &GE,
+            dealer: PartyIndex,
+            parties: &[PartyIndex],
+            own_party_index: PartyIndex,
+            paillier_keys: PaillierKeys,
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+        ) -> Result<Self, ImportError> {
+            let mut other_parties = check_parties(params, dealer, parties)?;
+            if !other_parties.remove(&own_party_index) {
+                return Err(ImportError::ProtocolSetupError(
+                    "own party index not in parties' list".to_string(),
+                ));
+            }
+            if !PaillierKeys::is_valid(&paillier_keys.ek, &paillier_keys.dk) {
+                return Err(ImportError::ProtocolSetupError(
+                    "invalid own Paillier key".to_string(),
+                ));
+            }
+            match &range_proof_setup {
+                Some(setup) => ZkpPublicSetup::from_private_zkp_setup(setup)
+                    .verify()
+                    .map_err(|e| ImportError::ProtocolSetupError(format!("{:?}", e)))?,
+                None => {
+                    return Err(ImportError::ProtocolSetupError(
+                        "Using this signature scheme without range proofs is insecure".to_string(),
+                    ))
+                }
+            }
+            let mut all_parties = other_parties.clone();
+            all_parties.insert(own_party_index);
+
+            Ok(Phase1 {
+                params: *params,
+                public_key: *public_key,
+                dealer,
+                own_party_index,
+                other_parties,
+                points: party_points(&all_parties),
+                correct_key_proof: CorrectKeyProof(nizk_rsa::gen_proof(&paillier_keys.dk)),
+                paillier_keys,
+                range_proof_setup,
+                inputs: input_digest(params, public_key, dealer, parties),
+                timeout,
+            })
+        }
+
+        /// parties which send messages in the first round
+        fn senders(&self) -> BTreeSet<PartyIndex> {
+            let mut senders = self.other_parties.clone();
+            senders.insert(self.dealer);
+            senders
+        }
+
+        /// checks inputs, Paillier keys and range proof setups of other parties
+        #[trace(disable(input))]
+        fn verify_parties(&self, input: &HashMap<PartyIndex, PartyBroadcast>) -> Vec<ImportError> {
+            let mut errors = self
+                .inputs
+                .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+                .into_iter()
+                .map(|(party, field)| ImportError::InputMismatch { party, field })
+                .collect::<Vec<_>>();
+            for (party, msg) in input.iter() {
+                let party = *party;
+                if nizk_rsa::verify(&msg.ek, &msg.correct_key_proof.0).is_err() {
+                    errors.push(ImportError::InvalidCorrectKeyProof {
+                        proof: format!("{:?}", msg.correct_key_proof),
+                        party,
+                    });
+                }
+                match (&self.range_proof_setup, &msg.range_proof_setup) {
+                    (Some(_), None) => errors.push(ImportError::RangeProofSetupMissing { party }),
+                    (None, Some(setup)) => errors.push(ImportError::RangeProofSetupUnexpected {
+                        proof: format!("{:?}", setup.dlog_proof),
+                        party,
+                    }),
+                    (Some(_), Some(setup)) => {
+                        if setup.verify().is_err() {
+                            errors.push(ImportError::RangeProofSetupDlogProofFailed {
+                                proof: format!("{:?}", setup.dlog_proof),
+                                party,
+                            })
+                        }
+                    }
+                    (None, None) => {}
+                }
+            }
+            errors
+        }
+
+        /// checks the inputs of the dealer and the share against its commitments and the public key
+        #[trace(disable(dealt_share))]
+        fn verify_dealer(&self, dealt_share: &DealtShare) -> Vec<ImportError> {
+            let mut errors = self
+                .inputs
+                .mismatched_fields(&dealt_share.inputs)
+                .into_iter()
+                .map(|field| ImportError::InputMismatch {
+                    party: self.dealer,
+                    field,
+                })
+                .collect::<Vec<_>>();
+            let vss = &dealt_share.vss;
+            let parameters_match = vss.vss.parameters.threshold == self.params.threshold()
+                && vss.vss.parameters.share_count == self.params.share_count();
+            if !parameters_match
+                || vss.share.0 != self.points[&self.own_party_index]
+                || !vss.verify(&self.public_key)
+            {
+                errors.push(ImportError::InvalidDealtShare { party: self.dealer });
+            }
+            errors
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<ImportTraits> for Phase1 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::info!("Phase1 (party) starts");
+            let broadcast = PartyBroadcast {
+                inputs: self.inputs.clone(),
+                ek: self.paillier_keys.ek.clone(),
+                correct_key_proof: self.correct_key_proof.clone(),
+                range_proof_setup: self
+                    .range_proof_setup
+                    .as_ref()
+                    .map(ZkpPublicSetup::from_private_zkp_setup),
+            };
+            Some(
+                self.other_parties
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R1(broadcast.clone()),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            let expected = match msg.body {
+                Message::R1(_) => self.other_parties.contains(&msg.sender),
+                Message::R2(_) => msg.sender == self.dealer,
+                _ => false,
+            };
+            expected && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.senders())
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ImportTraits> {
+            let fail = |errors: Vec<ImportError>| {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Party.phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                Transition::FinalState(Err(error_state))
+            };
+            let (dealer_msgs, party_msgs): (Vec<_>, Vec<_>) = current_msg_set
+                .iter()
+                .cloned()
+                .partition(|m| m.sender == self.dealer);
+            let input = match (
+                to_hash_map_gen::<PartyBroadcast, _, _>(&party_msgs),
+                to_hash_map_gen::<DealtShare, _, _>(&dealer_msgs),
+            ) {
+                (Ok(parties), Ok(mut dealer)) => match dealer.remove(&self.dealer) {
+                    Some(dealt_share) => Ok((parties, dealt_share)),
+                    None => Err(vec![ImportError::GeneralError(
+                        "message of the dealer is missing".to_string(),
+                    )]),
+                },
+                (parties, dealer) => Err(parties
+                    .err()
+                    .into_iter()
+                    .chain(dealer.err())
+                    .flatten()
+                    .collect()),
+            };
+            let (parties, dealt_share) = match input {
+                Ok(input) => input,
+                Err(errors) => return fail(errors),
+            };
+
+            let mut errors = self.verify_parties(&parties);
+            errors.extend(self.verify_dealer(&dealt_share));
+            if !errors.is_empty() {
+                return fail(errors);
+            }
+
+            let public_shares = match compute_public_shares(&[&dealt_share.vss.vss], &self.points) {
+                Some(public_shares) => public_shares,
+                None => {
+                    return fail(vec![ImportError::GeneralError(
+                        "public shares can't be computed".to_string(),
+                    )])
+                }
+            };
+            let committee = self.points.keys().copied().collect();
+            let metadata =
+                KeyMetadata::new(KeyId::derive(&self.public_key, &public_shares), committee);
+            let mut party_he_keys = HashMap::new();
+            let mut party_setups = HashMap::new();
+            for (party, msg) in parties.into_iter() {
+                party_he_keys.insert(party, msg.ek);
+                if let Some(setup) = msg.range_proof_setup {
+                    party_setups.insert(party, setup);
+                }
+            }
+            let info = MultiPartyInfo {
+                key_params: self.params,
+                own_party_index: self.own_party_index,
+                secret_share: dealt_share.vss.share,
+                public_key: self.public_key,
+                own_he_keys: self.paillier_keys.clone(),
+                party_he_keys,
+                party_to_point_map: Party2PointMap {
+                    points: self.points.clone(),
+                },
+                public_shares,
+                range_proof_setups: self.range_proof_setup.clone().map(|my_setup| {
+                    RangeProofSetups {
+                        my_setup,
+                        party_setups,
+                    }
+                }),
+                metadata,
+            };
+            log::info!("Phase1 (party) succeeded, the share is verified");
+
+            Transition::NewState(Box::new(Phase2 {
+                digest: public_digest(&info.public_info()),
+                info,
+                dealer: self.dealer,
+                other_parties: self.other_parties.clone(),
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Party.phase1", &self.senders(), current_msg_set)
+        }
+    }
+
+    /// Last phase of the protocol
+    ///
+    /// * sends the digest of the imported public info to other parties and to the dealer, confirming that the share is verified
+    /// * collects confirmations of other parties and compares their digests with ours
+    struct Phase2 {
+        info: MultiPartyInfo,
+        dealer: PartyIndex,
+        other_parties: BTreeSet<PartyIndex>,
+        digest: BigInt,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase2::")]
+    impl State<ImportTraits> for Phase2 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase2 (party) starts");
+            Some(
+                self.other_parties
+                    .iter()
+                    .chain(std::iter::once(&self.dealer))
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R3(ShareVerified {
+                            digest: self.digest.clone(),
+                        }),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R3(_))
+                && self.other_parties.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.other_parties)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ImportTraits> {
+            let errors = match to_hash_map_gen::<ShareVerified, _, _>(&current_msg_set) {
+                Ok(input) => input
+                    .into_iter()
+                    .filter(|(_, msg)| msg.digest != self.digest)
+                    .map(|(party, _)| ImportError::InconsistentImport { party })
+                    .collect::<Vec<_>>(),
+                Err(errors) => errors,
+            };
+            if !errors.is_empty() {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Party.phase2", &current_msg_set);
+                log::error!("Phase2 returns {:?}", error_state);
+                return Transition::FinalState(Err(error_state));
+            }
+
+            log::info!("Phase2 (party) succeeded");
+            Transition::FinalState(Ok(FinalState {
+                info: self.info.clone(),
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Party.phase2", &self.other_parties, current_msg_set)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dealer, party, ImportError};
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::ecdsa::{PaillierKeys, Parameters};
+    use crate::protocol::PartyIndex;
+    use crate::secret::Secret;
+    use crate::state_machine::BoxedState;
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::{load_range_proof_setups, Run};
+    use crate::testkit::{Action, Adversary};
+    use anyhow::bail;
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    enum Outcome {
+        Dealer(dealer::MachineResult),
+        Party(party::MachineResult),
+    }
+
+    /// Runs import, returns the outcome of the dealer followed by outcomes of parties, `None` for parties which have not finished
+    ///
+    /// The dealer is malicious, if `adversary` is given.
+    fn run_import(
+        secret_key: FE,
+        public_key: &GE,
+        adversary: Option<Adversary<super::Message>>,
+    ) -> anyhow::Result<Vec<Option<Outcome>>> {
+        let params = Parameters::new(2, 3)?;
+        let dealer_index = PartyIndex::from(100);
+        let parties = (0..3).map(PartyIndex::from).collect::<Vec<_>>();
+        let zkp_setups = load_range_proof_setups()?;
+
+        let mut run = Run::new(0x696d706f).with_adversary(dealer_index, adversary);
+        let dealer_state: BoxedState<dealer::ImportTraits> = Box::new(dealer::Phase1::new(
+            &params,
+            Secret::new(secret_key),
+            dealer_index,
+            &parties,
+            // the dealer gives up on parties which have aborted
+            Some(Duration::from_secs(10)),
+        )?);
+        run.add_node("dealer", dealer_index, dealer_state, Outcome::Dealer);
+        for (party, setup) in parties.iter().zip(zkp_setups.into_iter()) {
+            let start_state: BoxedState<party::ImportTraits> = Box::new(party::Phase1::new(
+                &params,
+                public_key,
+                dealer_index,
+                &parties,
+                *party,
+                PaillierKeys::random(),
+                Some(setup),
+                None,
+            )?);
+            run.add_node(
+                format!("party {}", party),
+                *party,
+                start_state,
+                Outcome::Party,
+            );
+        }
+        Ok(run
+            .run()
+            .outcomes
+            .into_iter()
+            .map(|node| node.outcome)
+            .collect())
+    }
+
+    fn imported_keys(outcomes: Vec<Option<Outcome>>) -> anyhow::Result<Vec<MultiPartyInfo>> {
+        outcomes
+            .into_iter()
+            .filter_map(|outcome| match outcome {
+                Some(Outcome::Dealer(Ok(_))) => None,
+                Some(Outcome::Party(Ok(final_state))) => Some(Ok(final_state.info)),
+                outcome => Some(Err(anyhow::anyhow!("success expected, got {:?}", outcome))),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn import_keeps_public_key() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let secret_key: FE = ECScalar::new_random();
+        let g: GE = ECPoint::generator();
+        let public_key = g * secret_key;
+        let outcomes = run_import(secret_key, &public_key, None)?;
+        let key_id = match &outcomes[0] {
+            Some(Outcome::Dealer(Ok(final_state))) => final_state.key_id,
+            outcome => bail!("success of the dealer expected, got {:?}", outcome),
+        };
+
+        let keys = imported_keys(outcomes)?;
+        assert_eq!(keys.len(), 3);
+        let (points, shares): (Vec<FE>, Vec<FE>) = keys[..2]
+            .iter()
+            .map(|key| {
+                let x: FE = ECScalar::from(&BigInt::from(key.own_point() as u64));
+                (x, key.own_share())
+            })
+            .unzip();
+        assert_eq!(
+            VerifiableSS::lagrange_interpolation_at_zero(&points, &shares).to_big_int(),
+            secret_key.to_big_int()
+        );
+        for key in keys.iter() {
+            assert_eq!(key.public_key.get_element(), public_key.get_element());
+            assert_eq!(key.metadata.key_id, key_id);
+            assert_eq!(key.metadata.epoch, 0);
+            assert!(key.validate().is_empty(), "{:?}", key.validate());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn import_rejects_share_of_another_key() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let g: GE = ECPoint::generator();
+        let public_key = g * ECScalar::new_random();
+        let outcomes = run_import(ECScalar::new_random(), &public_key, None)?;
+        let dealer_index = PartyIndex::from(100);
+        assert_eq!(outcomes.len(), 4);
+        // the dealer times out waiting for confirmations of parties
+        assert!(
+            matches!(&outcomes[0], Some(Outcome::Dealer(Err(_)))),
+            "error of the dealer expected, got {:?}",
+            outcomes[0]
+        );
+        for outcome in outcomes[1..].iter() {
+            match outcome {
+                Some(Outcome::Party(Err(error_state))) => {
+                    assert!(matches!(
+                        error_state.errors.as_slice(),
+                        [ImportError::InputMismatch { party, .. }, ..] if *party == dealer_index
+                    ));
+                }
+                outcome => bail!("error of a party expected, got {:?}", outcome),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn import_detects_corrupted_share() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let secret_key: FE = ECScalar::new_random();
+        let g: GE = ECPoint::generator();
+        let public_key = g * secret_key;
+        let dealer_index = PartyIndex::from(100);
+        let parties = (0..3).map(PartyIndex::from).collect::<Vec<_>>();
+        let adversary = Adversary::new(dealer_index, &parties).in_phase(
+            "Dealer.phase1",
+            Action::Corrupt(|msg: &mut super::Message| {
+                if let super::Message::R2(dealt_share) = msg {
+                    scenarios::shift_by_one(&mut dealt_share.vss.share.1)
+                }
+            }),
+        );
+        let outcomes = run_import(secret_key, &public_key, Some(adversary))?;
+
+        assert_eq!(outcomes.len(), 4);
+        assert!(
+            matches!(&outcomes[0], Some(Outcome::Dealer(Err(_)))),
+            "error of the dealer expected, got {:?}",
+            outcomes[0]
+        );
+        for outcome in outcomes[1..].iter() {
+            match outcome {
+                Some(Outcome::Party(Err(error_state))) => {
+                    match error_state.errors.as_slice() {
+                        [ImportError::InvalidDealtShare { party }] => {
+                            assert_eq!(*party, dealer_index)
+                        }
+                        errors => bail!("unexpected errors {:?}", errors),
+                    }
+                    assert_eq!(error_state.blame.len(), 1);
+                    assert_eq!(error_state.blame[0].party, dealer_index);
+                }
+                outcome => bail!("error of a party expected, got {:?}", outcome),
+            }
+        }
+        Ok(())
+    }
+}
@@ -12,6 +12,8 @@
 //! * Create [`Phase1`]
 //! * Create the [`StateMachine`] and set the instance of [`Phase1`] as its argument.
 //!
+//! [`Phase1::new_with_rng`] accepts the source of randomness used by the party, so that tests can run the protocol with seeded generators.
+//!
 //! # Example
 //!
 //! * creates the stream `protocol_sink` for incoming messages
@@ -101,8 +103,9 @@
 //!
 //! [`StateMachine`]: ../../state_machine/async_channels/struct.StateMachine.html
 //! [`Phase1`]: struct.Phase1.html
+//! [`Phase1::new_with_rng`]: struct.Phase1.html#method.new_with_rng
 
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
 use curv::cryptographic_primitives::proofs::sigma_dlog::ProveDLog;
@@ -113,11 +116,20 @@ use curv::{BigInt, FE, GE};
 use crate::ecdsa::messages::{FeldmanVSS, SecretShare};
 
 use crate::algorithms::nizk_rsa;
+use crate::algorithms::random::{self, RandomnessSource};
+use crate::algorithms::validation::{
+    check_bits, check_length, Validate, ValidationError, MAX_KEY_EPOCHS, MAX_LIST_LENGTH,
+    MAX_MODULUS_BIT_LENGTH,
+};
+use crate::algorithms::vss;
+use crate::ecdsa::blame::{Accusation, BlameKind};
 use crate::ecdsa::{
-    from_secp256k1_pk, is_valid_curve_point, CommitmentScheme, InitialPublicKeys,
-    ManagedPaillierDecryptionKey, ManagedSecretKey, PaillierKeys, Parameters,
+    from_secp256k1_pk, is_broadcast_input_complete, is_valid_curve_point, to_hash_map_gen,
+    CommitmentScheme, InitialPublicKeys, InputDigest, PaillierKeys, Parameters, PhaseTimeout,
+    ProtocolError,
 };
 use crate::protocol::{Address, PartyIndex};
+use crate::secret::{Erase, Secret};
 pub use paillier::DecryptionKey;
 use paillier::EncryptionKey;
 use thiserror::Error;
@@ -125,10 +137,15 @@ use thiserror::Error;
 #[doc(inline)]
 pub use super::messages::keygen::{DecommitPublicKey, InMsg, Message, OutMsg, Phase1Broadcast};
 
-use crate::state_machine::{State, StateMachineTraits, Transition};
-use serde::{Deserialize, Serialize};
+use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+use curv::arithmetic::traits::Converter;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use sha2::{Digest, Sha256};
+use std::fmt;
 
 use crate::algorithms::zkp::{ZkpPublicSetup, ZkpSetup, ZkpSetupVerificationError};
+use futures::future::BoxFuture;
+use std::convert::TryFrom;
 use std::iter::FromIterator;
 use std::sync::Arc;
 use std::time::Duration;
@@ -155,18 +172,108 @@ pub struct SecretKeyLoaderError(pub String);
 /// [`Phase1::new`]: struct.Phase1#method.new
 pub type ASecretKeyLoader = Arc<Box<dyn SecretKeyLoader + Send + Sync>>;
 
+/// Interface for loading the secret share and the Paillier decryption key produced by key generation, used by signing and resharing
+pub trait SecretShareLoader: std::fmt::Debug {
+    fn get_secret_share(&self) -> Result<Box<FE>, SecretKeyLoaderError>;
+    fn get_paillier_secret(&self) -> Result<Box<DecryptionKey>, SecretKeyLoaderError>;
+}
+
+/// the type of the reference to [`SecretShareLoader`] used by signing and resharing protocols
+///
+/// [`SecretShareLoader`]: trait.SecretShareLoader.html
+pub type ASecretShareLoader = Arc<Box<dyn SecretShareLoader + Send + Sync>>;
+
+/// Interface for deleting the secret share from the storage of the application, used by members of old committee of resharing
+///
+/// Resharing deletes the old share only after all members of new committee have committed to their new shares, see [resharing](../resharing/index.html).
+pub trait SecretShareDeleter: std::fmt::Debug {
+    fn delete_secret_share(&self) -> Result<(), SecretKeyLoaderError>;
+}
+
+/// the type of the reference to [`SecretShareDeleter`] used by resharing protocol
+///
+/// [`SecretShareDeleter`]: trait.SecretShareDeleter.html
+pub type ASecretShareDeleter = Arc<Box<dyn SecretShareDeleter + Send + Sync>>;
+
+/// Asynchronous variant of [`SecretShareLoader`], for vaults which are accessed over the network
+///
+/// Phases of protocols are synchronous, hence the loader is used through [`BlockingSecretShareLoader`].
+///
+/// [`SecretShareLoader`]: trait.SecretShareLoader.html
+/// [`BlockingSecretShareLoader`]: struct.BlockingSecretShareLoader.html
+pub trait AsyncSecretShareLoader: std::fmt::Debug + Send + Sync {
+    fn get_secret_share(&self) -> BoxFuture<'_, Result<Box<FE>, SecretKeyLoaderError>>;
+    fn get_paillier_secret(
+        &self,
+    ) -> BoxFuture<'_, Result<Box<DecryptionKey>, SecretKeyLoaderError>>;
+}
+
+/// Adapts [`AsyncSecretShareLoader`] to [`SecretShareLoader`]
+///
+/// Within a tokio runtime the future is awaited on the calling thread through `tokio::task::block_in_place`,
+/// which hands other tasks of the worker over to other workers, and resources of the future are driven by the runtime.
+/// Outside of any runtime a current-thread runtime is built for each load.
+///
+/// `block_in_place` panics on the thread running a current-thread runtime. There, phases have to run on a blocking thread,
+/// e.g. spawned by `tokio::task::spawn_blocking`, or secrets have to be fetched before the protocol starts and passed to it directly.
+///
+/// [`AsyncSecretShareLoader`]: trait.AsyncSecretShareLoader.html
+/// [`SecretShareLoader`]: trait.SecretShareLoader.html
+#[derive(Debug)]
+pub struct BlockingSecretShareLoader<L>(L);
+
+impl<L: AsyncSecretShareLoader> BlockingSecretShareLoader<L> {
+    pub fn new(loader: L) -> Self {
+        BlockingSecretShareLoader(loader)
+    }
+
+    fn wait<T>(
+        &self,
+        load: for<'a> fn(&'a L) -> BoxFuture<'a, Result<T, SecretKeyLoaderError>>,
+    ) -> Result<T, SecretKeyLoaderError> {
+        if tokio::runtime::Handle::try_current().is_ok() {
+            tokio::task::block_in_place(|| futures::executor::block_on(load(&self.0)))
+        } else {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| SecretKeyLoaderError(e.to_string()))?
+                .block_on(load(&self.0))
+        }
+    }
+}
+
+impl<L: AsyncSecretShareLoader> SecretShareLoader for BlockingSecretShareLoader<L> {
+    fn get_secret_share(&self) -> Result<Box<FE>, SecretKeyLoaderError> {
+        self.wait(L::get_secret_share)
+    }
+
+    fn get_paillier_secret(&self) -> Result<Box<DecryptionKey>, SecretKeyLoaderError> {
+        self.wait(L::get_paillier_secret)
+    }
+}
+
 /// Zero knowledge proof of Paillier key's correctness
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CorrectKeyProof(pub Vec<BigInt>);
 
+impl Validate for CorrectKeyProof {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_length("correct key proof", &self.0, nizk_rsa::M2, nizk_rsa::M2)?;
+        self.0
+            .iter()
+            .try_for_each(|sigma| check_bits("correct key proof", sigma, MAX_MODULUS_BIT_LENGTH))
+    }
+}
+
 /// Enumerates error type which can be raised by key generation protocol
 #[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
 pub enum KeygenError {
     #[error("Key generation cannot be started: {0}")]
     IncorrectParameters(String),
-    #[error("keygen: timeout in {phase}")]
-    Timeout { phase: String },
+    #[error("keygen: {0}")]
+    Timeout(#[from] PhaseTimeout),
     #[error("invalid decommitment {decomm}, commitment {comm}, party {party}")]
     InvalidComm {
         comm: String,
@@ -201,12 +308,71 @@ pub enum KeygenError {
     RangeProofSetupUnexpected { proof: String, party: PartyIndex },
     #[error("range proof setup: dlog proof failed , party {party}, proof {proof} ")]
     RangeProofSetupDlogProofFailed { proof: String, party: PartyIndex },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("dlog proof of party {party} does not match its public share {public_share}")]
+    PublicShareMismatch {
+        party: PartyIndex,
+        public_share: String,
+    },
+    #[error("own secret share does not match own public share computed from VSS commitments")]
+    OwnPublicShareMismatch,
     #[error("protocol setup error: {0}")]
     ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
     #[error("{0}")]
     GeneralError(String),
 }
 
+impl Accusation for KeygenError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            KeygenError::InvalidComm { party, .. } => Some((*party, BlameKind::InvalidCommitment)),
+            KeygenError::InvalidVSS { party, .. } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            KeygenError::InvalidPublicKey { party, .. } => {
+                Some((*party, BlameKind::InvalidPublicKey))
+            }
+            KeygenError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            KeygenError::InvalidMessage { party, .. } => Some((*party, BlameKind::InvalidMessage)),
+            KeygenError::InvalidDlogProof { party, .. } => {
+                Some((*party, BlameKind::InvalidDlogProof))
+            }
+            KeygenError::InvalidCorrectKeyProof { party, .. } => {
+                Some((*party, BlameKind::InvalidCorrectKeyProof))
+            }
+            KeygenError::RangeProofSetupMissing { party } => {
+                Some((*party, BlameKind::RangeProofSetupMissing))
+            }
+            KeygenError::RangeProofSetupUnexpected { party, .. } => {
+                Some((*party, BlameKind::RangeProofSetupUnexpected))
+            }
+            KeygenError::RangeProofSetupDlogProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidRangeProofSetup))
+            }
+            KeygenError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            KeygenError::PublicShareMismatch { party, .. } => {
+                Some((*party, BlameKind::InvalidDlogProof))
+            }
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            KeygenError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
 impl super::InitialKeys {
     /// samples from randomness
     pub fn random() -> Self {
@@ -220,12 +386,32 @@ impl super::InitialKeys {
             paillier_keys: PaillierKeys::random(),
         }
     }
+
+    /// samples from given source
+    pub fn random_with_rng(rng: &mut RandomnessSource) -> Self {
+        let u: FE = random::sample_scalar(rng);
+
+        #[allow(clippy::op_ref)]
+        let y = &ECPoint::generator() * &u;
+        super::InitialKeys {
+            u_i: u,
+            y_i: y,
+            paillier_keys: PaillierKeys::random_from(rng),
+        }
+    }
 }
 
 /// Comprises various outputs of key generation protocol
 ///
 /// The output value of key generation protocol and input parameter for signing protocol. Has to be saved to wallet/vault.   
+///
+/// Keys saved before public shares and metadata were introduced are still deserialized. Their public shares are unknown,
+/// and their metadata is the one of epoch zero: the key id is derived from the public key only, see [`KeyId::from_public_key`],
+/// and the committee consists of the parties whose x-coordinates are known. All parties holding shares of the same key derive the same metadata.
+///
+/// [`KeyId::from_public_key`]: struct.KeyId.html#method.from_public_key
 #[derive(Clone, Serialize, Deserialize, Debug)]
+#[serde(from = "StoredMultiPartyInfo")]
 pub struct MultiPartyInfo {
     pub key_params: Parameters,
     pub own_party_index: PartyIndex,
@@ -234,7 +420,52 @@ pub struct MultiPartyInfo {
     pub own_he_keys: PaillierKeys,
     pub party_he_keys: HashMap<PartyIndex, EncryptionKey>,
     pub party_to_point_map: Party2PointMap,
+    /// public shares $` X_{j} = g^{x_{j}} `$ of all parties, including own one, computed from VSS commitments
+    ///
+    /// Empty for keys generated before public shares were introduced, as shares of other parties can't be derived from the keys of one party.
+    pub public_shares: HashMap<PartyIndex, GE>,
     pub range_proof_setups: Option<RangeProofSetups>,
+    pub metadata: KeyMetadata,
+}
+
+/// `MultiPartyInfo` as saved by any version of the crate
+#[derive(Deserialize)]
+struct StoredMultiPartyInfo {
+    key_params: Parameters,
+    own_party_index: PartyIndex,
+    secret_share: SecretShare,
+    public_key: GE,
+    own_he_keys: PaillierKeys,
+    party_he_keys: HashMap<PartyIndex, EncryptionKey>,
+    party_to_point_map: Party2PointMap,
+    #[serde(default)]
+    public_shares: HashMap<PartyIndex, GE>,
+    range_proof_setups: Option<RangeProofSetups>,
+    metadata: Option<KeyMetadata>,
+}
+
+impl From<StoredMultiPartyInfo> for MultiPartyInfo {
+    fn from(stored: StoredMultiPartyInfo) -> Self {
+        let metadata = match stored.metadata {
+            Some(metadata) => metadata,
+            None => KeyMetadata::new(
+                KeyId::from_public_key(&stored.public_key),
+                stored.party_to_point_map.points.keys().copied().collect(),
+            ),
+        };
+        MultiPartyInfo {
+            key_params: stored.key_params,
+            own_party_index: stored.own_party_index,
+            secret_share: stored.secret_share,
+            public_key: stored.public_key,
+            own_he_keys: stored.own_he_keys,
+            party_he_keys: stored.party_he_keys,
+            party_to_point_map: stored.party_to_point_map,
+            public_shares: stored.public_shares,
+            range_proof_setups: stored.range_proof_setups,
+            metadata,
+        }
+    }
 }
 
 impl MultiPartyInfo {
@@ -244,6 +475,381 @@ impl MultiPartyInfo {
     pub fn own_share(&self) -> FE {
         self.secret_share.1
     }
+
+    /// Checks integrity of the info, e.g. of the one loaded from a file, and returns findings, which are empty if the info is consistent
+    ///
+    /// Checks that the public key is on the curve, that x-coordinates of shares are distinct and consistent with own share,
+    /// that public shares of parties are consistent with own share and with the public key, that Paillier keys are distinct and large enough, that range proof setups of other parties verify,
+    /// and that parameters of the key and its metadata agree with the set of parties. Verification of range proof setups takes a while.
+    pub fn validate(&self) -> Vec<IntegrityFinding> {
+        let mut findings = Vec::new();
+
+        if !is_valid_curve_point(self.public_key.get_element()) {
+            findings.push(IntegrityFinding::InvalidPublicKey);
+        }
+
+        let points = &self.party_to_point_map.points;
+        let parties = points.keys().copied().collect::<BTreeSet<_>>();
+        let other_parties = parties
+            .iter()
+            .filter(|p| **p != self.own_party_index)
+            .copied()
+            .collect::<Vec<_>>();
+
+        if points.get(&self.own_party_index) != Some(&self.own_point()) {
+            findings.push(IntegrityFinding::OwnPointMismatch {
+                share: self.own_point(),
+                mapped: points.get(&self.own_party_index).copied(),
+            });
+        }
+        let findings_before_points = findings.len();
+        let mut parties_by_point = BTreeMap::<usize, Vec<PartyIndex>>::new();
+        for party in parties.iter() {
+            parties_by_point
+                .entry(points[party])
+                .or_default()
+                .push(*party);
+        }
+        for (point, parties) in parties_by_point {
+            if point == 0 {
+                findings.extend(
+                    parties
+                        .iter()
+                        .map(|party| IntegrityFinding::ZeroPoint { party: *party }),
+                );
+            }
+            if parties.len() > 1 {
+                findings.push(IntegrityFinding::DuplicatePoint { point, parties });
+            }
+        }
+        let points_consistent = findings.len() == findings_before_points;
+
+        // keys generated before public shares were introduced have none
+        if !self.public_shares.is_empty() {
+            let findings_before_shares = findings.len();
+            for party in parties.iter() {
+                match self.public_shares.get(party) {
+                    None => findings.push(IntegrityFinding::MissingPublicShare { party: *party }),
+                    Some(share) if !is_valid_curve_point(share.get_element()) => {
+                        findings.push(IntegrityFinding::InvalidPublicShare { party: *party })
+                    }
+                    _ => {}
+                }
+            }
+            let mut unknown = self
+                .public_shares
+                .keys()
+                .filter(|p| !parties.contains(p))
+                .copied()
+                .collect::<Vec<_>>();
+            unknown.sort();
+            findings.extend(
+                unknown
+                    .into_iter()
+                    .map(|party| IntegrityFinding::UnknownPublicShare { party }),
+            );
+
+            let g: GE = ECPoint::generator();
+            if self.public_shares.get(&self.own_party_index) != Some(&(g * &self.own_share())) {
+                findings.push(IntegrityFinding::OwnPublicShareMismatch);
+            }
+
+            // any t+1 public shares interpolate to the public key
+            let quorum = self.key_params.threshold + 1;
+            if points_consistent
+                && findings.len() == findings_before_shares
+                && quorum <= parties.len()
+            {
+                let signers = parties.iter().take(quorum).copied().collect::<Vec<_>>();
+                let interpolated = signers
+                    .iter()
+                    .map(|party| {
+                        let x: FE = ECScalar::from(&BigInt::from(points[party] as u64));
+                        let multiplier = self
+                            .party_to_point_map
+                            .calculate_lagrange_multiplier(&signers, x);
+                        self.public_shares[party] * &multiplier
+                    })
+                    .fold(None, |acc: Option<GE>, point| match acc {
+                        None => Some(point),
+                        Some(sum) => Some(sum + point),
+                    });
+                if interpolated != Some(self.public_key) {
+                    findings.push(IntegrityFinding::PublicSharesMismatch);
+                }
+            }
+        }
+
+        if !PaillierKeys::is_valid(&self.own_he_keys.ek, &self.own_he_keys.dk) {
+            findings.push(IntegrityFinding::InvalidOwnPaillierKey);
+        }
+        findings.extend(
+            other_parties
+                .iter()
+                .filter(|p| !self.party_he_keys.contains_key(p))
+                .map(|party| IntegrityFinding::MissingPaillierKey { party: *party }),
+        );
+        let mut encryption_keys = self
+            .party_he_keys
+            .iter()
+            .map(|(party, ek)| (*party, ek))
+            .chain(std::iter::once((
+                self.own_party_index,
+                &self.own_he_keys.ek,
+            )))
+            .collect::<Vec<_>>();
+        encryption_keys.sort_by_key(|(party, _)| *party);
+        for (i, (party, ek)) in encryption_keys.iter().enumerate() {
+            if !parties.contains(party) {
+                findings.push(IntegrityFinding::UnknownParty { party: *party });
+            }
+            let bits = ek.n.bit_length();
+            if bits < nizk_rsa::N_MIN_SIZE {
+                findings.push(IntegrityFinding::PaillierKeyTooSmall {
+                    party: *party,
+                    bits,
+                    min: nizk_rsa::N_MIN_SIZE,
+                });
+            } else if let Err(e) = ek.validate() {
+                findings.push(IntegrityFinding::InvalidPaillierKey {
+                    party: *party,
+                    reason: e.to_string(),
+                });
+            }
+            if let Some((other, _)) = encryption_keys[..i].iter().find(|(_, k)| k.n == ek.n) {
+                findings.push(IntegrityFinding::DuplicatePaillierKey {
+                    party: *party,
+                    other: *other,
+                });
+            }
+        }
+
+        if let Some(setups) = &self.range_proof_setups {
+            findings.extend(
+                other_parties
+                    .iter()
+                    .filter(|p| !setups.party_setups.contains_key(p))
+                    .map(|party| IntegrityFinding::MissingRangeProofSetup { party: *party }),
+            );
+            let mut party_setups = setups.party_setups.iter().collect::<Vec<_>>();
+            party_setups.sort_by_key(|(party, _)| **party);
+            for (party, setup) in party_setups {
+                let verified = setup
+                    .validate()
+                    .map_err(|e| e.to_string())
+                    .and_then(|_| setup.verify().map_err(|e| e.to_string()));
+                if let Err(reason) = verified {
+                    findings.push(IntegrityFinding::InvalidRangeProofSetup {
+                        party: *party,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        let params = self.key_params;
+        if params.threshold == 0
+            || params.threshold >= params.share_count
+            || params.share_count != parties.len()
+        {
+            findings.push(IntegrityFinding::ParametersMismatch {
+                params,
+                parties: parties.len(),
+            });
+        }
+
+        if let Err(e) = self.metadata.validate() {
+            findings.push(IntegrityFinding::InvalidMetadata(e.to_string()));
+        } else if self.metadata.committee() != Some(&parties) {
+            findings.push(IntegrityFinding::CommitteeMismatch {
+                epoch: self.metadata.epoch,
+            });
+        }
+
+        findings
+    }
+
+    /// Returns the copy of the info with public fields replaced by `info`, e.g. by the one updated by [`repair`](../repair/index.html)
+    ///
+    /// The secret share and the Paillier decryption key are kept, hence `info` has to describe the same party and the same Paillier key.
+    pub fn with_public_info(&self, info: MultiPartyPublicInfo) -> MultiPartyInfo {
+        debug_assert_eq!(info.own_party_index, self.own_party_index);
+        debug_assert_eq!(info.own_point, self.own_point());
+        debug_assert_eq!(info.own_ek.n, self.own_he_keys.ek.n);
+        MultiPartyInfo {
+            key_params: info.key_params,
+            own_party_index: info.own_party_index,
+            secret_share: self.secret_share,
+            public_key: info.public_key,
+            own_he_keys: self.own_he_keys.clone(),
+            party_he_keys: info.party_he_keys,
+            party_to_point_map: info.party_to_point_map,
+            public_shares: info.public_shares,
+            range_proof_setups: info.range_proof_setups,
+            metadata: info.metadata,
+        }
+    }
+
+    /// Returns the copy of the info without the secret share and the Paillier decryption key
+    pub fn public_info(&self) -> MultiPartyPublicInfo {
+        MultiPartyPublicInfo {
+            key_params: self.key_params.clone(),
+            own_party_index: self.own_party_index,
+            own_point: self.own_point(),
+            public_key: self.public_key,
+            own_ek: self.own_he_keys.ek.clone(),
+            party_he_keys: self.party_he_keys.clone(),
+            party_to_point_map: self.party_to_point_map.clone(),
+            public_shares: self.public_shares.clone(),
+            range_proof_setups: self.range_proof_setups.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+}
+
+/// Inconsistency found by [`MultiPartyInfo::validate`](struct.MultiPartyInfo.html#method.validate)
+#[derive(Debug, Clone, Error)]
+pub enum IntegrityFinding {
+    #[error("public key is not a point on the curve")]
+    InvalidPublicKey,
+    #[error(
+        "x-coordinate {share} of own share differs from {mapped:?} in the map of parties to points"
+    )]
+    OwnPointMismatch { share: usize, mapped: Option<usize> },
+    #[error("x-coordinate {point} is assigned to several parties {parties:?}")]
+    DuplicatePoint {
+        point: usize,
+        parties: Vec<PartyIndex>,
+    },
+    #[error("x-coordinate of party {party} is zero, its share is the private key")]
+    ZeroPoint { party: PartyIndex },
+    #[error("public share of party {party} is missing")]
+    MissingPublicShare { party: PartyIndex },
+    #[error("public share of party {party} is not a point on the curve")]
+    InvalidPublicShare { party: PartyIndex },
+    #[error("public share of party {party} is stored but the party has no x-coordinate")]
+    UnknownPublicShare { party: PartyIndex },
+    #[error("own public share does not match own secret share")]
+    OwnPublicShareMismatch,
+    #[error("public shares do not interpolate to the public key")]
+    PublicSharesMismatch,
+    #[error("own Paillier decryption key does not match the encryption key")]
+    InvalidOwnPaillierKey,
+    #[error("Paillier key of party {party} is missing")]
+    MissingPaillierKey { party: PartyIndex },
+    #[error("party {party} has a Paillier key but no x-coordinate")]
+    UnknownParty { party: PartyIndex },
+    #[error("Paillier key of party {party} has {bits} bits, at least {min} required")]
+    PaillierKeyTooSmall {
+        party: PartyIndex,
+        bits: usize,
+        min: usize,
+    },
+    #[error("Paillier key of party {party} is invalid: {reason}")]
+    InvalidPaillierKey { party: PartyIndex, reason: String },
+    #[error("Paillier key of party {party} is the same as of party {other}")]
+    DuplicatePaillierKey {
+        party: PartyIndex,
+        other: PartyIndex,
+    },
+    #[error("range proof setup of party {party} is missing")]
+    MissingRangeProofSetup { party: PartyIndex },
+    #[error("range proof setup of party {party} is invalid: {reason}")]
+    InvalidRangeProofSetup { party: PartyIndex, reason: String },
+    #[error("key parameters {params} do not match {parties} parties")]
+    ParametersMismatch { params: Parameters, parties: usize },
+    #[error("invalid key metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("committee of epoch {epoch} differs from the parties holding shares")]
+    CommitteeMismatch { epoch: u64 },
+}
+
+/// Serves secrets kept in memory, so that protocols can be started from `MultiPartyInfo` loaded by the application as a whole
+impl SecretShareLoader for MultiPartyInfo {
+    fn get_secret_share(&self) -> Result<Box<FE>, SecretKeyLoaderError> {
+        Ok(Box::new(self.own_share()))
+    }
+
+    fn get_paillier_secret(&self) -> Result<Box<DecryptionKey>, SecretKeyLoaderError> {
+        Ok(Box::new(self.own_he_keys.dk.clone()))
+    }
+}
+
+/// Holds the secret share and the Paillier decryption key taken out of [`MultiPartyInfo`], and erases them when dropped
+///
+/// Protocols started from `MultiPartyInfo` keep the secrets only in this loader, which is dropped by the phase which uses them last.
+///
+/// [`MultiPartyInfo`]: struct.MultiPartyInfo.html
+#[derive(Debug)]
+pub struct SecretShareHolder {
+    secret_share: Secret<FE>,
+    dk: Secret<DecryptionKey>,
+}
+
+impl SecretShareHolder {
+    /// Splits the info into its public part and the holder of its secrets, erasing the secrets left in the info
+    pub fn split(mut info: MultiPartyInfo) -> (MultiPartyPublicInfo, Self) {
+        let holder = SecretShareHolder {
+            secret_share: Secret::new(info.own_share()),
+            dk: Secret::new(info.own_he_keys.dk.clone()),
+        };
+        info.secret_share.erase();
+        (info.public_info(), holder)
+    }
+}
+
+impl SecretShareLoader for SecretShareHolder {
+    fn get_secret_share(&self) -> Result<Box<FE>, SecretKeyLoaderError> {
+        Ok(Box::new(*self.secret_share))
+    }
+
+    fn get_paillier_secret(&self) -> Result<Box<DecryptionKey>, SecretKeyLoaderError> {
+        Ok(Box::new((*self.dk).clone()))
+    }
+}
+
+/// Public part of [`MultiPartyInfo`](struct.MultiPartyInfo.html)
+///
+/// Signing and resharing hold this part during the whole run, while the secret share and the Paillier decryption key are loaded through [`SecretShareLoader`](trait.SecretShareLoader.html)
+/// in the phase which uses them and erased right afterwards.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct MultiPartyPublicInfo {
+    pub key_params: Parameters,
+    pub own_party_index: PartyIndex,
+    /// x-coordinate of own secret share
+    pub own_point: usize,
+    pub public_key: GE,
+    pub own_ek: EncryptionKey,
+    pub party_he_keys: HashMap<PartyIndex, EncryptionKey>,
+    pub party_to_point_map: Party2PointMap,
+    /// public shares $` X_{j} = g^{x_{j}} `$ of all parties, including own one
+    pub public_shares: HashMap<PartyIndex, GE>,
+    pub range_proof_setups: Option<RangeProofSetups>,
+    pub metadata: KeyMetadata,
+}
+
+impl MultiPartyPublicInfo {
+    /// Completes the info with the secret share and the Paillier keys, e.g. at the end of a protocol which produces a new share
+    pub fn into_multi_party_info(
+        self,
+        secret_share: SecretShare,
+        own_he_keys: PaillierKeys,
+    ) -> MultiPartyInfo {
+        debug_assert_eq!(secret_share.0, self.own_point);
+        debug_assert_eq!(own_he_keys.ek.n, self.own_ek.n);
+        MultiPartyInfo {
+            key_params: self.key_params,
+            own_party_index: self.own_party_index,
+            secret_share,
+            public_key: self.public_key,
+            own_he_keys,
+            party_he_keys: self.party_he_keys,
+            party_to_point_map: self.party_to_point_map,
+            public_shares: self.public_shares,
+            range_proof_setups: self.range_proof_setups,
+            metadata: self.metadata,
+        }
+    }
 }
 
 /// Range proof private setup of ours and public versions of setups shared by other parties  
@@ -253,45 +859,161 @@ pub struct RangeProofSetups {
     pub party_setups: HashMap<PartyIndex, ZkpPublicSetup>,
 }
 
-/// Checks whether all expected messages have been received so far from other parties  
-fn is_broadcast_input_complete(
-    current_msg_set: &[InMsg],
-    other_parties: &BTreeSet<PartyIndex>,
-) -> bool {
-    let senders = current_msg_set.iter().map(|m| m.sender).collect::<Vec<_>>();
-    other_parties.iter().all(|p| senders.contains(p))
+/// Identifier of the key which does not change when the key is reshared
+///
+/// Hash of the public key and of public shares $` y_{i} `$ of the parties which generated the key, hence all parties derive the same id
+/// at the end of key generation, while two keys generated by the same parties get different ids. Serialized as a hex string.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyId(pub [u8; 32]);
+
+impl KeyId {
+    const DOMAIN: &'static [u8] = b"ecdsa-mpc key id";
+
+    /// derives the id from the public key and public shares of key generation
+    pub fn derive(public_key: &GE, public_shares: &HashMap<PartyIndex, GE>) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.input(Self::DOMAIN);
+        hasher.input(BigInt::to_vec(&public_key.bytes_compressed_to_big_int()));
+        let mut public_shares = public_shares.iter().collect::<Vec<_>>();
+        public_shares.sort_by_key(|(party, _)| **party);
+        for (party, y_i) in public_shares {
+            hasher.input(&party.0[..]);
+            hasher.input(BigInt::to_vec(&y_i.bytes_compressed_to_big_int()));
+        }
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&hasher.result());
+        KeyId(id)
+    }
+
+    /// derives the id of the key whose public shares are not known, e.g. the key generated before ids were introduced
+    pub fn from_public_key(public_key: &GE) -> Self {
+        Self::derive(public_key, &HashMap::new())
+    }
 }
 
-/// Extracts payloads form enum variants of input message into the hash map
-#[trace(disable(current_msg_set), res = "{:?}")]
-fn to_hash_map_gen<K, V>(current_msg_set: Vec<InMsg>) -> Result<HashMap<K, V>, Vec<KeygenError>>
-where
-    K: std::cmp::Eq + std::hash::Hash + std::convert::From<PartyIndex> + std::fmt::Debug,
-    V: std::fmt::Debug,
-    Option<V>: std::convert::From<Message>,
-{
-    let (converted_messages, errors) =
-        current_msg_set
-            .iter()
-            .fold((vec![], vec![]), |(mut values, mut errors), m| {
-                let body: Option<V> = m.body.clone().into();
-                match body {
-                    Some(b) => values.push((m.sender, b)),
-                    None => errors.push(KeygenError::UnknownMessageType {
-                        message_type: m.body.clone(),
-                        party: m.sender,
-                    }),
-                };
-                (values, errors)
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl fmt::Debug for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeyId({})", self)
+    }
+}
+
+impl Serialize for KeyId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bytes = hex::decode(String::deserialize(deserializer)?).map_err(de::Error::custom)?;
+        if bytes.len() != 32 {
+            return Err(de::Error::invalid_length(bytes.len(), &"32 bytes"));
+        }
+        let mut id = [0u8; 32];
+        id.copy_from_slice(&bytes);
+        Ok(KeyId(id))
+    }
+}
+
+/// Identity and history of the key
+///
+/// Key generation starts the history at epoch zero, and every resharing increments the epoch and records the new committee.
+/// Shares of different epochs do not combine into the key, therefore signing parties check that they hold shares of the same key and epoch
+/// before any secret material is exchanged.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyMetadata {
+    pub key_id: KeyId,
+    /// number of resharings, refreshes and threshold changes the key went through
+    pub epoch: u64,
+    /// committees of all epochs, the committee of epoch `e` is stored at index `e`
+    pub committees: Vec<BTreeSet<PartyIndex>>,
+}
+
+impl KeyMetadata {
+    /// metadata of the key generated by `committee`
+    pub fn new(key_id: KeyId, committee: BTreeSet<PartyIndex>) -> Self {
+        KeyMetadata {
+            key_id,
+            epoch: 0,
+            committees: vec![committee],
+        }
+    }
+
+    /// metadata of the key reshared with `committee`
+    pub fn next_epoch(&self, committee: BTreeSet<PartyIndex>) -> Self {
+        let mut committees = self.committees.clone();
+        committees.push(committee);
+        KeyMetadata {
+            key_id: self.key_id,
+            epoch: self.epoch + 1,
+            committees,
+        }
+    }
+
+    /// metadata of the key after `party` joined the committee of the current epoch, see [`repair`](../repair/index.html)
+    ///
+    /// The epoch does not change, as shares of other members remain valid.
+    pub fn with_member(&self, party: PartyIndex) -> Self {
+        let mut metadata = self.clone();
+        if let Some(committee) = metadata.committees.last_mut() {
+            committee.insert(party);
+        }
+        metadata
+    }
+
+    /// committee of given epoch
+    pub fn committee_of(&self, epoch: u64) -> Option<&BTreeSet<PartyIndex>> {
+        usize::try_from(epoch)
+            .ok()
+            .and_then(|epoch| self.committees.get(epoch))
+    }
+
+    /// committee of the current epoch
+    pub fn committee(&self) -> Option<&BTreeSet<PartyIndex>> {
+        self.committee_of(self.epoch)
+    }
+}
+
+impl Validate for KeyMetadata {
+    fn validate(&self) -> Result<(), ValidationError> {
+        check_length("committees", &self.committees, 1, MAX_KEY_EPOCHS)?;
+        if self.epoch != (self.committees.len() - 1) as u64 {
+            return Err(ValidationError::InvalidLength {
+                field: "committees",
+                count: self.committees.len(),
+                expected: format!("epoch {} + 1", self.epoch),
             });
+        }
+        self.committees.iter().try_for_each(|committee| {
+            if committee.is_empty() || committee.len() > MAX_LIST_LENGTH {
+                Err(ValidationError::InvalidLength {
+                    field: "committee",
+                    count: committee.len(),
+                    expected: format!("1..={}", MAX_LIST_LENGTH),
+                })
+            } else {
+                Ok(())
+            }
+        })
+    }
+}
 
-    if errors.is_empty() {
-        Ok(converted_messages
-            .into_iter()
-            .map(|(party, body)| (party.into(), body))
-            .collect::<HashMap<K, V>>())
-    } else {
-        Err(errors)
+impl ProtocolError<Message> for KeygenError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        KeygenError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        KeygenError::InvalidMessage { party, error }
     }
 }
 
@@ -329,7 +1051,9 @@ pub struct Phase1 {
     secret_key_loader: ASecretKeyLoader,
     paillier_key_proof: CorrectKeyProof,
     range_proof_setup: Option<ZkpSetup>,
+    inputs: InputDigest,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 #[doc(hidden)]
@@ -349,20 +1073,44 @@ impl Phase1 {
         own_party_index: PartyIndex,
         secret_key_loader: ASecretKeyLoader,
         timeout: Option<Duration>,
+    ) -> Result<Self, KeygenError> {
+        Self::new_with_rng(
+            params,
+            init_keys,
+            range_proof_setup,
+            parties,
+            own_party_index,
+            secret_key_loader,
+            timeout,
+            RandomnessSource::os(),
+        )
+    }
+
+    /// initializes the protocol, which draws the blinding of the commitment, the randomness of range proof setup proofs and the coefficients of Feldman's VSS from `rng`
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_rng(
+        params: &Parameters,
+        init_keys: InitialPublicKeys,
+        range_proof_setup: Option<ZkpSetup>,
+        parties: &[PartyIndex],
+        own_party_index: PartyIndex,
+        secret_key_loader: ASecretKeyLoader,
+        timeout: Option<Duration>,
+        mut rng: RandomnessSource,
     ) -> Result<Self, KeygenError> {
         let proof = {
             let dk = secret_key_loader
                 .get_paillier_secret()
-                .map(|dk| ManagedPaillierDecryptionKey(dk))
+                .map(Secret::from_box)
                 .map_err(|e| KeygenError::ProtocolSetupError(e.0))?;
-            if !PaillierKeys::is_valid(&init_keys.paillier_encryption_key, &dk.0) {
+            if !PaillierKeys::is_valid(&init_keys.paillier_encryption_key, &dk) {
                 return Err(KeygenError::ProtocolSetupError(
                     "invalid own Paillier key".to_string(),
                 ));
             }
-            nizk_rsa::gen_proof(&dk.0)
+            nizk_rsa::gen_proof(&dk)
         };
-        let scheme = CommitmentScheme::from_GE(&init_keys.y_i);
+        let scheme = CommitmentScheme::from_GE(&init_keys.y_i, &mut rng);
 
         let acting_parties = BTreeSet::from_iter(parties.iter().cloned());
         if acting_parties.len() != parties.len() {
@@ -387,6 +1135,10 @@ impl Phase1 {
                 "Using this signature scheme without range proofs is insecure".to_string(),
             ));
         }
+        let inputs = InputDigest::new()
+            .with_parameters("parameters", params)
+            .with_parties("parties", parties);
+
         Ok(Phase1 {
             params: *params,
             keys: init_keys,
@@ -396,7 +1148,9 @@ impl Phase1 {
             secret_key_loader,
             paillier_key_proof: CorrectKeyProof(proof),
             range_proof_setup,
+            inputs,
             timeout,
+            rng,
         })
     }
 }
@@ -405,10 +1159,11 @@ impl Phase1 {
 impl State<KeyGeneratorTraits> for Phase1 {
     fn start(&mut self) -> Option<OutMsgVec> {
         log::info!("Phase1 starts");
+        let rng = &mut self.rng;
         let zkp_public_setup = self
             .range_proof_setup
             .as_ref()
-            .map(|s| ZkpPublicSetup::from_private_zkp_setup(&s));
+            .map(|s| ZkpPublicSetup::from_private_zkp_setup_with_rng(&s, rng));
 
         let output = vec![OutMsg {
             recipient: Address::Broadcast,
@@ -417,6 +1172,7 @@ impl State<KeyGeneratorTraits> for Phase1 {
                 e: self.keys.paillier_encryption_key.clone(),
                 correct_key_proof: self.paillier_key_proof.clone(),
                 range_proof_setup: zkp_public_setup,
+                inputs: self.inputs.clone(),
             }),
         }];
         Some(output)
@@ -432,8 +1188,21 @@ impl State<KeyGeneratorTraits> for Phase1 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyGeneratorTraits> {
-        match to_hash_map_gen::<PartyIndex, Phase1Broadcast>(current_msg_set) {
+        match to_hash_map_gen::<Phase1Broadcast, _, _>(&current_msg_set) {
             Ok(comms) => {
+                let mismatches = self
+                    .inputs
+                    .mismatches(comms.iter().map(|(p, m)| (p, &m.inputs)))
+                    .into_iter()
+                    .map(|(party, field)| KeygenError::InputMismatch { party, field })
+                    .collect::<Vec<_>>();
+                if !mismatches.is_empty() {
+                    let error_state =
+                        ErrorState::new(mismatches).with_evidence("phase1", &current_msg_set);
+                    log::error!("Phase1 returns {:?}", error_state);
+                    return Transition::FinalState(Err(error_state));
+                }
+
                 let errors = comms
                     .iter()
                     .filter_map(
@@ -486,24 +1255,32 @@ impl State<KeyGeneratorTraits> for Phase1 {
                         secret_key_loader: self.secret_key_loader.clone(),
                         range_proof_setups,
                         timeout: self.timeout,
+                        rng: self.rng.clone(),
                     }))
                 } else {
-                    let error_state = ErrorState::new(errors);
+                    let error_state =
+                        ErrorState::new(errors).with_evidence("phase1", &current_msg_set);
                     log::error!("Phase1 returns {:?}", error_state);
                     Transition::FinalState(Err(error_state))
                 }
             }
             Err(e) => {
-                let error_state = ErrorState::new(e);
+                let error_state = ErrorState::new(e).with_evidence("phase1", &current_msg_set);
                 log::error!("Phase1 returns {:?}", error_state);
                 Transition::FinalState(Err(error_state))
             }
         }
     }
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![KeygenError::Timeout {
-            phase: "phase1".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase1", &self.other_parties, current_msg_set)
     }
     fn timeout(&self) -> Option<Duration> {
         self.timeout
@@ -521,6 +1298,7 @@ struct Phase2 {
     secret_key_loader: ASecretKeyLoader,
     range_proof_setups: Option<RangeProofSetups>,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 impl Phase2 {
@@ -528,21 +1306,32 @@ impl Phase2 {
     fn map_parties_to_shares(
         &self,
         party_list: Vec<PartyIndex>,
-        mut outgoing_shares: Vec<FE>,
-    ) -> HashMap<PartyIndex, SecretShare> {
+        outgoing_shares: Secret<Vec<FE>>,
+    ) -> Secret<HashMap<PartyIndex, SecretShare>> {
         let party_indexes_sorted = party_list.into_iter().collect::<BTreeSet<_>>();
         let number_of_parties = party_indexes_sorted.len();
-        let result = party_indexes_sorted
-            .into_iter()
-            .zip(1..=number_of_parties)
-            .zip(outgoing_shares.iter().cloned())
-            .map(|((party, index), share)| (party, (index, share)))
-            .collect::<HashMap<_, _>>();
-
-        // Simultaneously remove and zeroize elements.
-        outgoing_shares.drain(..).for_each(|mut s| s.zeroize());
+        Secret::new(
+            party_indexes_sorted
+                .into_iter()
+                .zip(1..=number_of_parties)
+                .zip(outgoing_shares.iter().cloned())
+                .map(|((party, index), share)| (party, (index, share)))
+                .collect::<HashMap<_, _>>(),
+        )
+        // outgoing_shares are erased here
+    }
 
-        result
+    /// Commitments are verified against broadcasts of Phase1, so these are included into the evidence
+    #[trace(disable(current_msg_set))]
+    fn evidence(&self, current_msg_set: &[InMsg]) -> Vec<InMsg> {
+        self.commitments
+            .iter()
+            .map(|(party, comm)| InMsg {
+                sender: *party,
+                body: Message::R1(comm.clone()),
+            })
+            .chain(current_msg_set.iter().cloned())
+            .collect()
     }
 }
 
@@ -570,9 +1359,13 @@ impl State<KeyGeneratorTraits> for Phase2 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyGeneratorTraits> {
-        let decomms = match to_hash_map_gen::<PartyIndex, DecommitPublicKey>(current_msg_set) {
+        let decomms = match to_hash_map_gen::<DecommitPublicKey, _, _>(&current_msg_set) {
             Ok(map) => map,
-            Err(e) => return Transition::FinalState(Err(ErrorState::new(e))),
+            Err(e) => {
+                return Transition::FinalState(Err(
+                    ErrorState::new(e).with_evidence("phase2", &current_msg_set)
+                ))
+            }
         };
 
         let mut errors = Vec::new();
@@ -651,33 +1444,33 @@ impl State<KeyGeneratorTraits> for Phase2 {
         let sk_loader_result = self
             .secret_key_loader
             .get_initial_secret()
-            .map(|s| ManagedSecretKey(s));
+            .map(Secret::from_box);
         if let Err(e) = &sk_loader_result {
             errors.push(KeygenError::GeneralError(e.0.clone()));
         }
 
         if !errors.is_empty() {
             log::error!("Phase2 returns errors {:?}", errors);
-            return Transition::FinalState(Err(ErrorState::new(errors)));
+            return Transition::FinalState(Err(
+                ErrorState::new(errors).with_evidence("phase2", &self.evidence(&current_msg_set))
+            ));
             //sk_loader_result is dropped here
         }
 
         let (vss_scheme, outgoing_shares) = {
             let sk = sk_loader_result.unwrap();
-            let vss_sharing =
-                VerifiableSS::share(self.params.threshold, self.params.share_count, &sk.0);
-            vss_sharing
+            let (vss_scheme, outgoing_shares) = vss::share(
+                self.params.threshold,
+                self.params.share_count,
+                &sk,
+                &mut self.rng.clone(),
+            );
+            (vss_scheme, Secret::new(outgoing_shares))
         }; // sk is dropped here
 
-        let mapped_shares = self.map_parties_to_shares(party_list, outgoing_shares);
-        let (parties_points, own_point): (Vec<(_, _)>, Vec<(_, _)>) = mapped_shares
-            .into_iter()
-            .partition(|(party, _)| *party != self.own_party_index);
-        let own_point = own_point[0].1;
-        let other_points = parties_points
-            .into_iter()
-            .map(|(party, share_xy)| (party, share_xy))
-            .collect::<HashMap<_, _>>();
+        let mut other_points = self.map_parties_to_shares(party_list, outgoing_shares);
+        // the own party is always present in the list of parties
+        let own_point = Secret::new(other_points.remove(&self.own_party_index).unwrap());
 
         Transition::NewState(Box::new(Phase3 {
             params: self.params,
@@ -698,10 +1491,16 @@ impl State<KeyGeneratorTraits> for Phase2 {
     fn timeout(&self) -> Option<Duration> {
         self.timeout
     }
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![KeygenError::Timeout {
-            phase: "phase2".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase2", &self.other_parties, current_msg_set)
     }
 }
 /// Computes the sum of points on the curve and validates every point
@@ -742,6 +1541,29 @@ fn try_computing_public_key(
     }
 }
 
+/// Computes public shares $` X_{j} = g^{x_{j}} `$ of all parties from Feldman's VSS commitments of the dealers
+///
+/// The public share of a party is the sum of dealers' commitment polynomials evaluated at the x-coordinate of the party.
+/// Returns `None` if the list of VSS schemes is empty
+pub(crate) fn compute_public_shares(
+    vss_schemes: &[&VerifiableSS],
+    points: &HashMap<PartyIndex, usize>,
+) -> Option<HashMap<PartyIndex, GE>> {
+    points
+        .iter()
+        .map(|(party, x)| {
+            vss_schemes
+                .iter()
+                .map(|vss| vss.get_point_commitment(*x))
+                .fold(None, |acc: Option<GE>, point| match acc {
+                    None => Some(point),
+                    Some(sum) => Some(sum + point),
+                })
+                .map(|public_share| (*party, public_share))
+        })
+        .collect()
+}
+
 /// Third phase of the protocol: broadcasts Shamir's shares with Feldman's proofs and verifies them
 struct Phase3 {
     params: Parameters,
@@ -750,8 +1572,8 @@ struct Phase3 {
     other_parties: BTreeSet<PartyIndex>,
     paillier_keys: HashMap<PartyIndex, EncryptionKey>,
     pubkey_map: HashMap<PartyIndex, GE>,
-    own_point: SecretShare,
-    other_points: HashMap<PartyIndex, SecretShare>,
+    own_point: Secret<SecretShare>,
+    other_points: Secret<HashMap<PartyIndex, SecretShare>>,
     vss_scheme: VerifiableSS,
     secret_key_loader: ASecretKeyLoader,
     range_proof_setups: Option<RangeProofSetups>,
@@ -788,9 +1610,13 @@ impl State<KeyGeneratorTraits> for Phase3 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyGeneratorTraits> {
-        let mut shares = match to_hash_map_gen::<PartyIndex, FeldmanVSS>(current_msg_set) {
+        let mut shares = match to_hash_map_gen::<FeldmanVSS, _, _>(&current_msg_set) {
             Ok(map) => map,
-            Err(e) => return Transition::FinalState(Err(ErrorState::new(e))),
+            Err(e) => {
+                return Transition::FinalState(Err(
+                    ErrorState::new(e).with_evidence("phase3", &current_msg_set)
+                ))
+            }
         };
 
         let mut errors = shares
@@ -830,9 +1656,32 @@ impl State<KeyGeneratorTraits> for Phase3 {
             errors.push(KeygenError::WrongXCoordinate { x_coord });
         }
 
-        let private_share = shares
+        let private_share = Secret::new(
+            shares
+                .iter()
+                .fold(self.own_point.1, |acc, (_party, fvss)| acc + fvss.share.1),
+        );
+
+        // public shares have to be computed before VSS messages are erased
+        let party_points = self
+            .other_points
             .iter()
-            .fold(self.own_point.1, |acc, (_party, fvss)| acc + fvss.share.1);
+            .map(|(p, share_xy)| (*p, share_xy.0))
+            .chain(std::iter::once((self.own_party_index, self.own_point.0)))
+            .collect::<HashMap<_, _>>();
+        let vss_schemes = shares
+            .values()
+            .map(|fvss| &fvss.vss)
+            .chain(std::iter::once(&self.vss_scheme))
+            .collect::<Vec<_>>();
+        // panic() is unreachable as the list contains own VSS scheme
+        let public_shares =
+            compute_public_shares(&vss_schemes, &party_points).expect("VSS schemes are missing");
+
+        let g: GE = ECPoint::generator();
+        if public_shares.get(&self.own_party_index) != Some(&(g * &*private_share)) {
+            errors.push(KeygenError::OwnPublicShareMismatch);
+        }
 
         shares.values_mut().for_each(|x| x.zeroize());
 
@@ -847,7 +1696,7 @@ impl State<KeyGeneratorTraits> for Phase3 {
         let dk_loader_result = self
             .secret_key_loader
             .get_paillier_secret()
-            .map(|dk| ManagedPaillierDecryptionKey(dk));
+            .map(Secret::from_box);
 
         if let Err(e) = &dk_loader_result {
             errors.push(KeygenError::GeneralError(e.0.clone()));
@@ -855,7 +1704,9 @@ impl State<KeyGeneratorTraits> for Phase3 {
 
         if !errors.is_empty() {
             log::error!("Phase3 returns errors {:?}", errors);
-            return Transition::FinalState(Err(ErrorState::new(errors)));
+            return Transition::FinalState(Err(
+                ErrorState::new(errors).with_evidence("phase3", &current_msg_set)
+            ));
         }
 
         // panic() on dk_loader_result.unwrap() is unreachable as dk_loader_result.is_err() is checked above
@@ -867,6 +1718,10 @@ impl State<KeyGeneratorTraits> for Phase3 {
             .iter()
             .map(|(p, share_xy)| (*p, share_xy.0))
             .collect();
+        let metadata = KeyMetadata::new(
+            KeyId::derive(&public_key, &self.pubkey_map),
+            self.pubkey_map.keys().copied().collect(),
+        );
 
         let new_state = Transition::NewState(Box::new(Phase4 {
             own_party_index: self.own_party_index,
@@ -874,25 +1729,33 @@ impl State<KeyGeneratorTraits> for Phase3 {
             multiparty_shared: MultiPartyInfo {
                 key_params: self.params,
                 own_party_index: self.own_party_index,
-                secret_share: (self.own_point.0, private_share),
+                secret_share: (self.own_point.0, *private_share),
                 public_key,
                 own_he_keys: PaillierKeys {
                     ek: self.keys.paillier_encryption_key.clone(),
-                    dk: (*dk.0).clone(),
+                    dk: (*dk).clone(),
                 },
                 party_he_keys: self.paillier_keys.clone(),
                 party_to_point_map: Party2PointMap { points },
+                public_shares,
                 range_proof_setups: self.range_proof_setups.clone(),
+                metadata,
             },
             timeout: self.timeout,
         }));
         new_state
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![KeygenError::Timeout {
-            phase: "phase3".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase3", &self.other_parties, current_msg_set)
     }
     fn timeout(&self) -> Option<Duration> {
         self.timeout
@@ -928,10 +1791,10 @@ impl State<KeyGeneratorTraits> for Phase4 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyGeneratorTraits> {
-        let proofs = match to_hash_map_gen::<PartyIndex, DLogProof>(current_msg_set) {
+        let proofs = match to_hash_map_gen::<DLogProof, _, _>(&current_msg_set) {
             Ok(p) => p,
             Err(e) => {
-                let err_state = ErrorState::new(e);
+                let err_state = ErrorState::new(e).with_evidence("phase4", &current_msg_set);
                 log::error!("Phase4 returns {:?}", err_state);
                 return Transition::FinalState(Err(err_state));
             }
@@ -940,13 +1803,19 @@ impl State<KeyGeneratorTraits> for Phase4 {
         let verification_error_vec = proofs
             .iter()
             .filter_map(|(party, msg)| {
-                if DLogProof::verify(&msg).is_ok() {
-                    None
-                } else {
+                if DLogProof::verify(&msg).is_err() {
                     Some(KeygenError::InvalidDlogProof {
                         proof: format!("{:?}", msg),
                         party: *party,
                     })
+                } else {
+                    match self.multiparty_shared.public_shares.get(party) {
+                        Some(public_share) if *public_share == msg.pk => None,
+                        public_share => Some(KeygenError::PublicShareMismatch {
+                            party: *party,
+                            public_share: format!("{:?}", public_share),
+                        }),
+                    }
                 }
             })
             .collect::<Vec<_>>();
@@ -974,17 +1843,25 @@ impl State<KeyGeneratorTraits> for Phase4 {
             }))
         } else {
             log::error!("Phase4 returns error vector {:?}", verification_error_vec);
-            Transition::FinalState(Err(ErrorState::new(verification_error_vec)))
+            Transition::FinalState(Err(
+                ErrorState::new(verification_error_vec).with_evidence("phase4", &current_msg_set)
+            ))
         }
     }
 
     fn timeout(&self) -> Option<Duration> {
         self.timeout
     }
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![KeygenError::Timeout {
-            phase: "phase4".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase4", &self.other_parties, current_msg_set)
     }
 }
 
@@ -1049,29 +1926,28 @@ pub struct FinalState {
     pub multiparty_shared_info: MultiPartyInfo,
 }
 
-/// Container of `KeygenError` type
-#[derive(Debug)]
-pub struct ErrorState {
-    errors: Vec<KeygenError>,
-}
-
-impl ErrorState {
-    pub fn new(errors: Vec<KeygenError>) -> Self {
-        ErrorState { errors }
-    }
-}
+/// Contains a vector of possible key generation errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<KeygenError, Message>;
 
 #[cfg(test)]
 mod tests {
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::validation::ValidationError;
     use crate::algorithms::zkp::ZkpSetup;
+    use crate::ecdsa::blame::{Accusation, BlameKind};
     use crate::ecdsa::keygen::{
-        FinalState, InMsg, KeyGeneratorTraits, OutMsg, Phase1, SecretKeyLoader,
-        SecretKeyLoaderError,
+        ErrorState, FinalState, InMsg, IntegrityFinding, KeyGeneratorTraits, KeyId, KeygenError,
+        MultiPartyInfo, OutMsg, Phase1, SecretKeyLoader, SecretKeyLoaderError,
     };
+    use crate::ecdsa::messages::keygen::Message;
     use crate::ecdsa::messages::SecretShare;
-    use crate::ecdsa::{InitialKeys, InitialPublicKeys, PaillierKeys, Parameters};
+    use crate::ecdsa::{InitialKeys, InitialPublicKeys, PaillierKeys, Parameters, PhaseTimeout};
     use crate::protocol::{Address, InputMessage, PartyIndex};
     use crate::state_machine::sync_channels::StateMachine;
+    use crate::state_machine::{BoxedState, State};
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::load_keys;
+    use crate::testkit::{Action, Adversary, NetworkConditions, Simulation, SimulationReport};
     use anyhow::bail;
     use crossbeam_channel::{Receiver, Sender};
     use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
@@ -1084,6 +1960,7 @@ mod tests {
     use std::io::Write;
     use std::path::Path;
     use std::sync::{Arc, Mutex};
+    use std::time::Duration;
     use std::{fs, thread};
 
     struct Node {
@@ -1170,6 +2047,503 @@ mod tests {
         keygen_helper(true)
     }
 
+    #[test]
+    fn keygen_timeout_reports_missing_parties() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let params = Parameters {
+            share_count: 3,
+            threshold: 1,
+        };
+        let parties = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+
+        let path = Path::new("tests/data/rp-setups.json");
+        let zkp_setups: Vec<ZkpSetup> = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        let init_keys = InitialKeys::random();
+        let init_pub_keys = InitialPublicKeys::from(&init_keys);
+        let mut records = HashMap::new();
+        records.insert(0, init_keys);
+        let wallet = Arc::new(Mutex::new(Wallet::new(records)));
+
+        let start_state = Box::new(Phase1::new(
+            &params,
+            init_pub_keys,
+            Some(zkp_setups[0].clone()),
+            parties.as_slice(),
+            parties[0],
+            Arc::new(Box::new(SecretKeyLoaderImpl::new(&wallet, 0))),
+            Some(Duration::from_millis(100)),
+        )?);
+
+        let (_ingress, rx) = crossbeam_channel::unbounded();
+        let (tx, _egress) = crossbeam_channel::unbounded();
+        let mut machine = StateMachine::<KeyGeneratorTraits>::new(start_state, &rx, &tx);
+        let error_state = match machine.execute() {
+            Some(Err(e)) => e,
+            _ => bail!("timeout expected"),
+        };
+
+        match error_state.errors() {
+            [KeygenError::Timeout(PhaseTimeout {
+                phase,
+                missing,
+                responded,
+            })] => {
+                assert_eq!(phase, "phase1");
+                assert_eq!(*missing, parties[1..].iter().copied().collect());
+                assert!(responded.is_empty());
+            }
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        assert_eq!(error_state.blame().len(), 2);
+        assert!(error_state
+            .blame()
+            .iter()
+            .all(|b| b.kind == BlameKind::Unresponsive && b.evidence.is_empty()));
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_phase1_is_reproducible() -> anyhow::Result<()> {
+        let keygen = SimulatedKeygen::new()?;
+        let broadcast = |seed| -> anyhow::Result<_> {
+            let mut phase = Phase1::new_with_rng(
+                &keygen.params,
+                keygen.public_keys[0].clone(),
+                Some(keygen.zkp_setups[0].clone()),
+                keygen.parties.as_slice(),
+                keygen.parties[0],
+                Arc::new(Box::new(SecretKeyLoaderImpl::new(&keygen.wallet, 0))),
+                None,
+                RandomnessSource::seeded(seed),
+            )?;
+            match phase.start().unwrap_or_default().pop().map(|m| m.body) {
+                Some(Message::R1(broadcast)) => Ok(broadcast),
+                _ => bail!("phase1 broadcast expected"),
+            }
+        };
+
+        let (first, second, other) = (broadcast(11)?, broadcast(11)?, broadcast(12)?);
+        assert_eq!(first.com, second.com);
+        assert_eq!(
+            serde_json::to_string(&first.range_proof_setup)?,
+            serde_json::to_string(&second.range_proof_setup)?
+        );
+        assert_ne!(first.com, other.com);
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_keygen_is_reproducible() -> anyhow::Result<()> {
+        let keygen = SimulatedKeygen::new()?;
+        let run = |seed: u64| -> anyhow::Result<Vec<serde_json::Value>> {
+            let mut simulation = Simulation::new(seed, NetworkConditions::default());
+            for (i, party) in keygen.parties.iter().enumerate() {
+                let start_state: BoxedState<KeyGeneratorTraits> = Box::new(Phase1::new_with_rng(
+                    &keygen.params,
+                    keygen.public_keys[i].clone(),
+                    Some(keygen.zkp_setups[i].clone()),
+                    keygen.parties.as_slice(),
+                    *party,
+                    Arc::new(Box::new(SecretKeyLoaderImpl::new(&keygen.wallet, i))),
+                    None,
+                    RandomnessSource::seeded(seed + i as u64),
+                )?);
+                simulation.add_node(format!("party {}", i), *party, start_state, |outcome| {
+                    outcome
+                });
+            }
+            simulation
+                .run()
+                .into_outcomes()?
+                .into_iter()
+                .map(|outcome| match outcome {
+                    // values of JSON objects are ordered by keys, unlike hash maps of the key info
+                    Ok(final_state) => {
+                        Ok(serde_json::to_value(&final_state.multiparty_shared_info)?)
+                    }
+                    Err(error_state) => bail!("keygen failed: {:?}", error_state),
+                })
+                .collect()
+        };
+
+        let (first, second) = (run(21)?, run(21)?);
+        assert_eq!(first.len(), keygen.parties.len());
+        assert_eq!(first, second);
+        Ok(())
+    }
+
+    type Outcome = Result<FinalState, ErrorState>;
+
+    /// keys and setups of three parties, shared by simulated runs of the protocol
+    struct SimulatedKeygen {
+        params: Parameters,
+        parties: Vec<PartyIndex>,
+        zkp_setups: Vec<ZkpSetup>,
+        public_keys: Vec<InitialPublicKeys>,
+        wallet: Arc<Mutex<Wallet>>,
+    }
+
+    impl SimulatedKeygen {
+        fn new() -> anyhow::Result<Self> {
+            let parties = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+            let path = Path::new("tests/data/rp-setups.json");
+            let zkp_setups: Vec<ZkpSetup> = serde_json::from_str(&fs::read_to_string(path)?)?;
+            let mut records = HashMap::new();
+            let mut public_keys = Vec::new();
+            for i in 0..parties.len() {
+                let init_keys = InitialKeys::random();
+                public_keys.push(InitialPublicKeys::from(&init_keys));
+                records.insert(i, init_keys);
+            }
+            Ok(SimulatedKeygen {
+                params: Parameters {
+                    share_count: 3,
+                    threshold: 1,
+                },
+                parties,
+                zkp_setups,
+                public_keys,
+                wallet: Arc::new(Mutex::new(Wallet::new(records))),
+            })
+        }
+
+        fn start_state(&self, i: usize, timeout: Option<Duration>) -> anyhow::Result<Box<Phase1>> {
+            Ok(Box::new(Phase1::new(
+                &self.params,
+                self.public_keys[i].clone(),
+                Some(self.zkp_setups[i].clone()),
+                self.parties.as_slice(),
+                self.parties[i],
+                Arc::new(Box::new(SecretKeyLoaderImpl::new(&self.wallet, i))),
+                timeout,
+            )?))
+        }
+
+        /// runs the protocol, the last party is driven by `adversary` if given
+        fn run(
+            &self,
+            seed: u64,
+            conditions: NetworkConditions,
+            timeout: Option<Duration>,
+            adversary: Option<Adversary<Message>>,
+        ) -> anyhow::Result<SimulationReport<Outcome>> {
+            let mut simulation = Simulation::new(seed, conditions);
+            for (i, party) in self.parties.iter().enumerate() {
+                let start_state: BoxedState<KeyGeneratorTraits> = self.start_state(i, timeout)?;
+                let start_state = match &adversary {
+                    Some(adversary) if i == self.parties.len() - 1 => adversary.wrap(start_state),
+                    _ => start_state,
+                };
+                simulation.add_node(format!("party {}", i), *party, start_state, |outcome| {
+                    outcome
+                });
+            }
+            Ok(simulation.run())
+        }
+
+        fn adversary(&self) -> Adversary<Message> {
+            Adversary::new(self.parties[2], &self.parties)
+        }
+    }
+
+    fn errors_of(outcome: &Option<Outcome>) -> anyhow::Result<&[KeygenError]> {
+        match outcome {
+            Some(Err(error_state)) => Ok(error_state.errors()),
+            _ => bail!("error expected, got {:?}", outcome),
+        }
+    }
+
+    /// checks that both honest parties blame the adversary for the single violation
+    fn assert_adversary_blamed(
+        report: &SimulationReport<Outcome>,
+        expected: impl Fn(&KeygenError) -> bool,
+    ) -> anyhow::Result<()> {
+        let adversary = report.outcomes[2].party;
+        for node in &report.outcomes[..2] {
+            let errors = errors_of(&node.outcome)?;
+            assert_eq!(errors.len(), 1, "{}: {:?}", node.name, errors);
+            assert!(expected(&errors[0]), "{}: {:?}", node.name, errors);
+            assert_eq!(errors[0].accusations().len(), 1);
+            assert_eq!(errors[0].accusations()[0].0, adversary);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn key_files_pass_integrity_check() -> anyhow::Result<()> {
+        for path in &["tests/data/keys.0.json", "tests/data/zkrp-keys.1.json"] {
+            let info: MultiPartyInfo = serde_json::from_str(&fs::read_to_string(path)?)?;
+            assert!(
+                info.validate().is_empty(),
+                "{}: {:?}",
+                path,
+                info.validate()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn baseline_key_files_load() -> anyhow::Result<()> {
+        for prefix in &["keys", "zkrp-keys"] {
+            let infos = (0..3)
+                .map(|i| {
+                    let path = format!("tests/data/{}.{}.json", prefix, i);
+                    Ok(serde_json::from_str::<MultiPartyInfo>(
+                        &fs::read_to_string(path)?,
+                    )?)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            for info in infos.iter() {
+                assert!(info.public_shares.is_empty());
+                assert_eq!(info.metadata, infos[0].metadata);
+            }
+            let metadata = &infos[0].metadata;
+            assert_eq!(
+                metadata.key_id,
+                KeyId::from_public_key(&infos[0].public_key)
+            );
+            assert_eq!(metadata.epoch, 0);
+            assert_eq!(
+                metadata.committee().map(|c| c.len()),
+                Some(infos[0].party_to_point_map.points.len())
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn integrity_check_reports_findings() -> anyhow::Result<()> {
+        let mut info: MultiPartyInfo =
+            serde_json::from_str(&fs::read_to_string("tests/data/keys.0.json")?)?;
+        let own_point = info.own_point();
+        let other = *info
+            .party_he_keys
+            .keys()
+            .next()
+            .expect("keys of other parties");
+        info.party_to_point_map.points.insert(other, own_point);
+        info.party_he_keys.get_mut(&other).unwrap().n = BigInt::from(15);
+        info.key_params.share_count = 4;
+
+        let findings = info.validate();
+        assert!(findings.iter().any(|f| matches!(
+            f,
+            IntegrityFinding::DuplicatePoint { point, parties } if *point == own_point && parties.contains(&other)
+        )));
+        assert!(findings.iter().any(|f| matches!(
+            f,
+            IntegrityFinding::PaillierKeyTooSmall { party, .. } if *party == other
+        )));
+        assert!(findings
+            .iter()
+            .any(|f| matches!(f, IntegrityFinding::ParametersMismatch { parties: 3, .. })));
+        assert_eq!(findings.len(), 3, "{:?}", findings);
+        Ok(())
+    }
+
+    #[test]
+    fn integrity_check_reports_inconsistent_public_shares() -> anyhow::Result<()> {
+        let info = load_keys("keys")?.remove(0);
+        assert!(info.validate().is_empty(), "{:?}", info.validate());
+        let other = *info
+            .party_he_keys
+            .keys()
+            .next()
+            .expect("keys of other parties");
+        let g: GE = ECPoint::generator();
+
+        let mut tampered = info.clone();
+        tampered.public_shares.insert(other, g);
+        let findings = tampered.validate();
+        assert!(
+            matches!(
+                findings.as_slice(),
+                [IntegrityFinding::PublicSharesMismatch]
+            ),
+            "{:?}",
+            findings
+        );
+
+        let mut tampered = info.clone();
+        tampered.public_shares.remove(&other);
+        tampered.public_shares.insert(tampered.own_party_index, g);
+        let findings = tampered.validate();
+        assert!(findings.iter().any(
+            |f| matches!(f, IntegrityFinding::MissingPublicShare { party } if *party == other)
+        ));
+        assert!(findings
+            .iter()
+            .any(|f| matches!(f, IntegrityFinding::OwnPublicShareMismatch)));
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+
+        // keys generated before public shares were introduced have none
+        let legacy: MultiPartyInfo =
+            serde_json::from_str(&fs::read_to_string("tests/data/keys.0.json")?)?;
+        assert!(legacy.public_shares.is_empty());
+        assert!(legacy.validate().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn keygen_in_simulated_network() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let conditions = NetworkConditions {
+            duplicate_probability: 0.2,
+            ..NetworkConditions::jittery(Duration::from_millis(20))
+        };
+        let report = SimulatedKeygen::new()?.run(0x6b657967656e, conditions, None, None)?;
+        assert!(report.duplicated > 0);
+        let final_states = report
+            .into_outcomes()?
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!("{:?}", e))?;
+        let public_keys = final_states
+            .iter()
+            .map(|fs| fs.multiparty_shared_info.public_key.get_element())
+            .collect::<HashSet<_>>();
+        assert_eq!(public_keys.len(), 1, "public keys are not same");
+        let metadata = &final_states[0].multiparty_shared_info.metadata;
+        assert!(final_states
+            .iter()
+            .all(|fs| fs.multiparty_shared_info.metadata == *metadata));
+        assert_eq!(metadata.epoch, 0);
+        assert!(final_states
+            .iter()
+            .all(|fs| fs.multiparty_shared_info.validate().is_empty()));
+        let public_shares = &final_states[0].multiparty_shared_info.public_shares;
+        assert_eq!(public_shares.len(), final_states.len());
+        assert!(final_states
+            .iter()
+            .all(|fs| fs.multiparty_shared_info.public_shares == *public_shares));
+        assert_eq!(
+            metadata.committee().map(|c| c.len()),
+            Some(final_states.len())
+        );
+        assert_ne!(
+            metadata.key_id,
+            KeyId::from_public_key(&final_states[0].multiparty_shared_info.public_key)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn keygen_detects_corrupted_vss() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keygen = SimulatedKeygen::new()?;
+        let adversary = keygen.adversary().in_phase(
+            "phase3",
+            Action::Corrupt(|msg: &mut Message| {
+                if let Message::R3(fvss) = msg {
+                    scenarios::shift_by_one(&mut fvss.share.1)
+                }
+            }),
+        );
+        let report = keygen.run(1, NetworkConditions::default(), None, Some(adversary))?;
+        assert_adversary_blamed(&report, |e| matches!(e, KeygenError::InvalidVSS { .. }))
+    }
+
+    #[test]
+    fn keygen_detects_bad_decommitment() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keygen = SimulatedKeygen::new()?;
+        let adversary = keygen.adversary().in_phase(
+            "phase2",
+            Action::Corrupt(|msg: &mut Message| {
+                if let Message::R2(decomm) = msg {
+                    scenarios::shift_by_one(&mut decomm.blind_factor)
+                }
+            }),
+        );
+        let report = keygen.run(2, NetworkConditions::default(), None, Some(adversary))?;
+        assert_adversary_blamed(&report, |e| matches!(e, KeygenError::InvalidComm { .. }))
+    }
+
+    #[test]
+    fn keygen_blames_invalid_message() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keygen = SimulatedKeygen::new()?;
+        // the message is handed over to the machine as is, without passing the wire decoder
+        let adversary = keygen.adversary().in_phase(
+            "phase2",
+            Action::Corrupt(|msg: &mut Message| {
+                if let Message::R2(decomm) = msg {
+                    scenarios::oversize(&mut decomm.blind_factor)
+                }
+            }),
+        );
+        let report = keygen.run(5, NetworkConditions::default(), None, Some(adversary))?;
+        assert_adversary_blamed(&report, |e| {
+            matches!(
+                e,
+                KeygenError::InvalidMessage {
+                    error: ValidationError::TooLarge { .. },
+                    ..
+                }
+            )
+        })
+    }
+
+    #[test]
+    fn keygen_detects_replayed_commitment() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keygen = SimulatedKeygen::new()?;
+        // commitment of the same party from an earlier session
+        let stale = keygen.start_state(2, None)?.start().unwrap_or_default();
+        let adversary = keygen.adversary().in_phase("phase1", Action::Replay(stale));
+        let report = keygen.run(3, NetworkConditions::default(), None, Some(adversary))?;
+        assert_adversary_blamed(&report, |e| matches!(e, KeygenError::InvalidComm { .. }))
+    }
+
+    #[test]
+    fn keygen_equivocation_aborts_victim() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keygen = SimulatedKeygen::new()?;
+        let victims = std::iter::once(keygen.parties[1]).collect();
+        let adversary = keygen.adversary().in_phase(
+            "phase2",
+            Action::Equivocate {
+                victims,
+                mutate: |msg: &mut Message| {
+                    if let Message::R2(decomm) = msg {
+                        scenarios::shift_by_one(&mut decomm.blind_factor)
+                    }
+                },
+            },
+        );
+        let timeout = Some(Duration::from_secs(10));
+        let report = keygen.run(4, NetworkConditions::default(), timeout, Some(adversary))?;
+
+        match errors_of(&report.outcomes[1].outcome)? {
+            [KeygenError::InvalidComm { party, .. }] => assert_eq!(*party, keygen.parties[2]),
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        // the other honest party has seen consistent messages, and waits for the victim in vain
+        match errors_of(&report.outcomes[0].outcome)? {
+            [KeygenError::Timeout(PhaseTimeout { phase, missing, .. })] => {
+                assert_eq!(phase, "phase3");
+                assert_eq!(*missing, std::iter::once(keygen.parties[1]).collect());
+            }
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn keygen_withheld_vss_times_out() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keygen = SimulatedKeygen::new()?;
+        let adversary = keygen.adversary().in_phase("phase3", Action::Withhold);
+        let timeout = Some(Duration::from_secs(10));
+        let report = keygen.run(5, NetworkConditions::default(), timeout, Some(adversary))?;
+        assert_adversary_blamed(
+            &report,
+            |e| matches!(e, KeygenError::Timeout(PhaseTimeout { phase, .. }) if phase == "phase3"),
+        )
+    }
+
     fn keygen_helper(enable_range_proofs: bool) -> anyhow::Result<()> {
         let _ = env_logger::builder().is_test(true).try_init();
         let params = Parameters {
@@ -2,21 +2,30 @@
 //!
 #![allow(non_snake_case)]
 #![allow(clippy::large_enum_variant)]
+use crate::algorithms::validation::{Validate, ValidationError};
 use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
 use curv::elliptic::curves::traits::ECPoint;
 use curv::{BigInt, FE, GE};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroize;
 
+/// Bit length of hash commitments and of their blind factors
+const COMMITMENT_BIT_LENGTH: usize = 256;
+
 /// key generation related message data types
 pub mod keygen {
-    use super::{BigInt, GE};
+    use super::{BigInt, COMMITMENT_BIT_LENGTH, GE};
+    use crate::algorithms::validation::{
+        check_bits, check_point, Validate, Validated, ValidationError,
+    };
     use crate::algorithms::zkp::ZkpPublicSetup;
     use crate::ecdsa::keygen::CorrectKeyProof;
     use crate::ecdsa::messages::FeldmanVSS;
+    use crate::ecdsa::InputDigest;
     use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
     use paillier::EncryptionKey;
     use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
 
     /// Enumerates messages used by key generation algorithm
     #[derive(Debug, Clone, Deserialize, Serialize, Display)]
@@ -80,12 +89,14 @@ pub mod keygen {
     /// * commitment to partial public EC schema key
     /// * proof for Paillier key
     /// * optional public range proof setup   
+    /// * digest of protocol inputs
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Phase1Broadcast {
         pub e: EncryptionKey,
         pub com: BigInt,
         pub correct_key_proof: CorrectKeyProof,
         pub range_proof_setup: Option<ZkpPublicSetup>,
+        pub inputs: InputDigest,
     }
 
     /// Decommitment of partial public EC schema key
@@ -94,26 +105,70 @@ pub mod keygen {
         pub blind_factor: BigInt,
         pub y_i: GE,
     }
+
+    impl Validate for Phase1Broadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.e.validate()?;
+            check_bits("commitment", &self.com, COMMITMENT_BIT_LENGTH)?;
+            self.correct_key_proof.validate()?;
+            self.range_proof_setup.validate()?;
+            self.inputs.validate()
+        }
+    }
+
+    impl Validate for DecommitPublicKey {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bits("blind factor", &self.blind_factor, COMMITMENT_BIT_LENGTH)?;
+            check_point("y_i", &self.y_i)
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(msg) => msg.validate(),
+                Message::R3(msg) => msg.validate(),
+                Message::R4(msg) => msg.validate(),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
 }
 
 /// Message data types used by the signing algorithm
 pub mod signing {
-    use super::{BigInt, FE, GE};
+    use super::{BigInt, COMMITMENT_BIT_LENGTH, FE, GE};
+    use crate::algorithms::validation::{
+        check_bits, check_point, Validate, Validated, ValidationError,
+    };
     use crate::algorithms::zkp::{MessageA, MessageB};
+    use crate::ecdsa::InputDigest;
     use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::HomoELGamalProof;
     use curv::cryptographic_primitives::proofs::sigma_dlog::DLogProof;
     use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
 
     pub type InMsg = crate::protocol::InputMessage<Message>;
     pub type OutMsg = crate::protocol::OutputMessage<Message>;
 
     /// Initial broadcast of the signing protocol
     ///
-    /// Contains commitment to $` g^{\gamma_{i}} `$ and the first message ( `MessageA` )  of `MtA` protocol
+    /// Contains commitment to $` g^{\gamma_{i}} `$, the first message ( `MessageA` )  of `MtA` protocol, the digest of protocol inputs
+    /// and the epoch of the key share of the sender
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct SignBroadcastPhase1 {
         pub com: BigInt,
         pub mta_a: MessageA,
+        pub inputs: InputDigest,
+        pub epoch: u64,
     }
 
     /// Decommitment of $` g^{\gamma_{i}} `$ and ZKP of knowing $` \gamma_{i} `$.
@@ -157,12 +212,14 @@ pub mod signing {
     /// the broadcast of $` \delta_{i} `$, see `Phase3` in the paper
     #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct Phase3data {
+        #[serde(with = "crate::wire::scalar")]
         pub delta_i: FE,
     }
 
     /// the final broadcast of the signing protocol, partial signature $` \s_{i} `$, see `Phase5E`
     #[derive(Debug, Clone, Deserialize, Serialize)]
     pub struct Phase5Edata {
+        #[serde(with = "crate::wire::scalar")]
         pub s_i: FE,
     }
 
@@ -268,21 +325,88 @@ pub mod signing {
             }
         }
     }
+
+    impl Validate for SignBroadcastPhase1 {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bits("commitment", &self.com, COMMITMENT_BIT_LENGTH)?;
+            self.mta_a.validate()?;
+            self.inputs.validate()
+        }
+    }
+
+    impl Validate for SignDecommitPhase4 {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bits("blind factor", &self.blind_factor, COMMITMENT_BIT_LENGTH)?;
+            check_point("g_gamma_i", &self.g_gamma_i)?;
+            self.gamma_proof.validate()
+        }
+    }
+
+    impl Validate for Phase5Decom1 {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_point("V_i", &self.V_i)?;
+            check_point("A_i", &self.A_i)?;
+            check_point("B_i", &self.B_i)?;
+            check_bits("blind factor", &self.blind_factor, COMMITMENT_BIT_LENGTH)?;
+            self.proof.validate()
+        }
+    }
+
+    impl Validate for Phase5Decom2 {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_point("U_i", &self.U_i)?;
+            check_point("T_i", &self.T_i)?;
+            check_bits("blind factor", &self.blind_factor, COMMITMENT_BIT_LENGTH)
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(msg) | Message::R2b(msg) => msg.validate(),
+                Message::R4(msg) => msg.validate(),
+                Message::R5(Phase5Com1 { com }) | Message::R7(Phase5Com2 { com }) => {
+                    check_bits("commitment", com, COMMITMENT_BIT_LENGTH)
+                }
+                Message::R6(msg) => msg.validate(),
+                Message::R8(msg) => msg.validate(),
+                Message::R3(_) | Message::R9(_) => Ok(()),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
 }
 /// key resharing related message data types
 pub mod resharing {
+    use super::COMMITMENT_BIT_LENGTH;
+    use crate::algorithms::validation::{
+        check_bits, check_point, Validate, Validated, ValidationError,
+    };
     use crate::algorithms::zkp::ZkpPublicSetup;
-    use crate::ecdsa::keygen::CorrectKeyProof;
+    use crate::ecdsa::keygen::{CorrectKeyProof, KeyMetadata};
     use crate::ecdsa::messages::SecretShare;
+    use crate::ecdsa::InputDigest;
     use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
     use curv::{BigInt, GE};
     use paillier::EncryptionKey;
     use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct Phase1Broadcast {
         pub y: GE,
         pub vss_commitment: BigInt,
+        pub inputs: InputDigest,
+        /// metadata of the key being reshared, which the new committee continues with the next epoch
+        pub metadata: KeyMetadata,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -290,10 +414,12 @@ pub mod resharing {
         pub ek: EncryptionKey,
         pub correct_key_proof: CorrectKeyProof,
         pub range_proof_setup: Option<ZkpPublicSetup>,
+        pub inputs: InputDigest,
     }
 
     #[derive(Debug, Clone, Serialize, Deserialize)]
     pub struct VSS {
+        #[serde(with = "crate::wire::scalar::share")]
         pub share: SecretShare,
         pub vss: VerifiableSS,
     }
@@ -305,6 +431,11 @@ pub mod resharing {
         R2(Phase2Broadcast),
         R3(VSS),
         Ack,
+        /// sent by a member of new committee to old members when its new share is verified
+        Prepared,
+        /// sent by a member of old committee to new members when all of them are prepared
+        Commit,
+        /// sent by a member of new committee when it has committed to its new share
         FinalAck,
     }
 
@@ -344,6 +475,738 @@ pub mod resharing {
             }
         }
     }
+
+    impl Validate for Phase1Broadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_point("y", &self.y)?;
+            check_bits(
+                "vss commitment",
+                &self.vss_commitment,
+                COMMITMENT_BIT_LENGTH,
+            )?;
+            self.inputs.validate()?;
+            self.metadata.validate()
+        }
+    }
+
+    impl Validate for Phase2Broadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.ek.validate()?;
+            self.correct_key_proof.validate()?;
+            self.range_proof_setup.validate()?;
+            self.inputs.validate()
+        }
+    }
+
+    impl Validate for VSS {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.vss.validate()
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(msg) => msg.validate(),
+                Message::R3(msg) => msg.validate(),
+                Message::Ack | Message::Prepared | Message::Commit | Message::FinalAck => Ok(()),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
+}
+
+/// share repair related message data types
+pub mod repair {
+    use crate::algorithms::validation::{
+        check_length, check_point, Validate, Validated, ValidationError, MAX_LIST_LENGTH,
+    };
+    use crate::algorithms::zkp::ZkpPublicSetup;
+    use crate::ecdsa::keygen::{CorrectKeyProof, KeyMetadata};
+    use crate::ecdsa::{InputDigest, Parameters};
+    use crate::protocol::PartyIndex;
+    use curv::{FE, GE};
+    use paillier::EncryptionKey;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+    use std::convert::TryFrom;
+
+    /// Public part of the key as seen by a helper, from which the recipient builds its own key info
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct KeyView {
+        pub key_params: Parameters,
+        pub public_key: GE,
+        pub metadata: KeyMetadata,
+        /// x-coordinates of shares of all parties
+        pub points: HashMap<PartyIndex, usize>,
+        /// public shares of all parties
+        pub public_shares: HashMap<PartyIndex, GE>,
+        /// Paillier keys of all parties, including the helper
+        pub encryption_keys: HashMap<PartyIndex, EncryptionKey>,
+        /// range proof setups of all parties except the helper, `None` if the key does not use range proofs
+        pub range_proof_setups: Option<HashMap<PartyIndex, ZkpPublicSetup>>,
+    }
+
+    /// The first message of a helper, sent to other helpers and to the recipient
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct HelperBroadcast {
+        pub inputs: InputDigest,
+        pub view: KeyView,
+        /// commitments $` g^{\delta_{ij}} `$ to the blinded pieces of the helper's contribution, one per helper
+        pub piece_commitments: HashMap<PartyIndex, GE>,
+    }
+
+    /// The first message of the recipient, sent to helpers
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct RecipientBroadcast {
+        pub inputs: InputDigest,
+        pub ek: EncryptionKey,
+        pub correct_key_proof: CorrectKeyProof,
+        pub range_proof_setup: Option<ZkpPublicSetup>,
+    }
+
+    /// Blinded piece of a helper's contribution, sent privately to another helper
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct BlindedPiece {
+        #[serde(with = "crate::wire::scalar")]
+        pub piece: FE,
+    }
+
+    /// Sum of the pieces received by a helper, sent privately to the recipient
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct PartialShare {
+        #[serde(with = "crate::wire::scalar")]
+        pub sum: FE,
+    }
+
+    /// Messages used by share repair algorithm
+    #[derive(Debug, Clone, Deserialize, Serialize, Display)]
+    pub enum Message {
+        R1(HelperBroadcast),
+        R2(RecipientBroadcast),
+        R3(BlindedPiece),
+        R4(PartialShare),
+        ShareVerified,
+        FinalAck,
+    }
+
+    pub type InMsg = crate::protocol::InputMessage<Message>;
+    pub type OutMsg = crate::protocol::OutputMessage<Message>;
+
+    impl InMsg {
+        pub fn is_duplicate(&self, current_msg_set: &[InMsg]) -> bool {
+            current_msg_set.iter().any(|m| m.sender == self.sender)
+        }
+    }
+
+    // Conversion helpers : unwrap MessageType variant to one of its inner structs
+    impl From<Message> for Option<HelperBroadcast> {
+        fn from(x: Message) -> Option<HelperBroadcast> {
+            match x {
+                Message::R1(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<RecipientBroadcast> {
+        fn from(x: Message) -> Option<RecipientBroadcast> {
+            match x {
+                Message::R2(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<BlindedPiece> {
+        fn from(x: Message) -> Option<BlindedPiece> {
+            match x {
+                Message::R3(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<PartialShare> {
+        fn from(x: Message) -> Option<PartialShare> {
+            match x {
+                Message::R4(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl Validate for KeyView {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_point("public key", &self.public_key)?;
+            self.metadata.validate()?;
+            let points = self.points.values().collect::<Vec<_>>();
+            check_length("points", &points, 1, MAX_LIST_LENGTH)?;
+            let public_shares = self.public_shares.values().collect::<Vec<_>>();
+            check_length("public shares", &public_shares, 0, MAX_LIST_LENGTH)?;
+            public_shares
+                .into_iter()
+                .try_for_each(|share| check_point("public share", share))?;
+            let encryption_keys = self.encryption_keys.values().collect::<Vec<_>>();
+            check_length("encryption keys", &encryption_keys, 1, MAX_LIST_LENGTH)?;
+            encryption_keys
+                .into_iter()
+                .try_for_each(|ek| ek.validate())?;
+            if let Some(setups) = &self.range_proof_setups {
+                let setups = setups.values().collect::<Vec<_>>();
+                check_length("range proof setups", &setups, 0, MAX_LIST_LENGTH)?;
+                setups.into_iter().try_for_each(|setup| setup.validate())?;
+            }
+            Ok(())
+        }
+    }
+
+    impl Validate for HelperBroadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            self.view.validate()?;
+            let commitments = self.piece_commitments.values().collect::<Vec<_>>();
+            check_length("piece commitments", &commitments, 1, MAX_LIST_LENGTH)?;
+            commitments
+                .into_iter()
+                .try_for_each(|c| check_point("piece commitment", c))
+        }
+    }
+
+    impl Validate for RecipientBroadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            self.ek.validate()?;
+            self.correct_key_proof.validate()?;
+            self.range_proof_setup.validate()
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(msg) => msg.validate(),
+                Message::R3(_) | Message::R4(_) | Message::ShareVerified | Message::FinalAck => {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
+}
+
+/// key refresh related message data types
+pub mod refresh {
+    use crate::algorithms::validation::{
+        check_bits, check_length, check_point, Validate, Validated, ValidationError,
+        MAX_LIST_LENGTH,
+    };
+    use crate::algorithms::zkp::ZkpPublicSetup;
+    use crate::ecdsa::keygen::{CorrectKeyProof, KeyMetadata};
+    use crate::ecdsa::InputDigest;
+    use curv::{BigInt, FE, GE};
+    use paillier::EncryptionKey;
+    use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
+
+    /// Bit length of the digest of the refreshed public key info
+    const DIGEST_BIT_LENGTH: usize = 256;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Phase1Broadcast {
+        pub inputs: InputDigest,
+        pub public_key: GE,
+        pub metadata: KeyMetadata,
+        /// commitments $` g^{a_{ik}} `$ to coefficients of the zero sharing polynomial, starting from the linear one
+        pub zero_commitments: Vec<GE>,
+        pub ek: EncryptionKey,
+        pub correct_key_proof: CorrectKeyProof,
+        pub range_proof_setup: Option<ZkpPublicSetup>,
+    }
+
+    /// Value of the sender's zero sharing polynomial at the recipient's x-coordinate, sent privately
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ZeroShare {
+        #[serde(with = "crate::wire::scalar")]
+        pub share: FE,
+    }
+
+    /// Confirms that the sender has verified its new share, carrying the digest of the refreshed public key info
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ShareVerified {
+        pub digest: BigInt,
+    }
+
+    /// Messages used by key refresh algorithm
+    #[derive(Debug, Clone, Deserialize, Serialize, Display)]
+    pub enum Message {
+        R1(Phase1Broadcast),
+        R2(ZeroShare),
+        R3(ShareVerified),
+    }
+
+    pub type InMsg = crate::protocol::InputMessage<Message>;
+    pub type OutMsg = crate::protocol::OutputMessage<Message>;
+
+    impl InMsg {
+        pub fn is_duplicate(&self, current_msg_set: &[InMsg]) -> bool {
+            current_msg_set.iter().any(|m| m.sender == self.sender)
+        }
+    }
+
+    // Conversion helpers : unwrap MessageType variant to one of its inner structs
+    impl From<Message> for Option<Phase1Broadcast> {
+        fn from(x: Message) -> Option<Phase1Broadcast> {
+            match x {
+                Message::R1(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<ZeroShare> {
+        fn from(x: Message) -> Option<ZeroShare> {
+            match x {
+                Message::R2(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<ShareVerified> {
+        fn from(x: Message) -> Option<ShareVerified> {
+            match x {
+                Message::R3(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl Validate for Phase1Broadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            check_point("public key", &self.public_key)?;
+            self.metadata.validate()?;
+            check_length(
+                "zero commitments",
+                &self.zero_commitments,
+                1,
+                MAX_LIST_LENGTH,
+            )?;
+            self.zero_commitments
+                .iter()
+                .try_for_each(|c| check_point("zero commitment", c))?;
+            self.ek.validate()?;
+            self.correct_key_proof.validate()?;
+            self.range_proof_setup.validate()
+        }
+    }
+
+    impl Validate for ShareVerified {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bits("digest", &self.digest, DIGEST_BIT_LENGTH)
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(_) => Ok(()),
+                Message::R3(msg) => msg.validate(),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
+}
+
+/// threshold change related message data types
+pub mod threshold_change {
+    use crate::algorithms::validation::{
+        check_bits, check_length, check_point, Validate, Validated, ValidationError,
+        MAX_LIST_LENGTH,
+    };
+    use crate::ecdsa::keygen::KeyMetadata;
+    use crate::ecdsa::InputDigest;
+    use curv::{BigInt, FE, GE};
+    use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
+
+    /// Bit length of the digest of the public key info with the new threshold
+    const DIGEST_BIT_LENGTH: usize = 256;
+
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Phase1Broadcast {
+        pub inputs: InputDigest,
+        pub public_key: GE,
+        pub metadata: KeyMetadata,
+        /// Feldman's commitments $` g^{a_{ik}} `$ to coefficients of the polynomial sharing the Lagrange-weighted share, starting from the free term
+        pub vss_commitments: Vec<GE>,
+    }
+
+    /// Value of the sender's sharing polynomial at the recipient's x-coordinate, sent privately
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct DealtShare {
+        #[serde(with = "crate::wire::scalar")]
+        pub share: FE,
+    }
+
+    /// Confirms that the sender has verified its new share, carrying the digest of the public key info with the new threshold
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ShareVerified {
+        pub digest: BigInt,
+    }
+
+    /// Messages used by threshold change algorithm
+    #[derive(Debug, Clone, Deserialize, Serialize, Display)]
+    pub enum Message {
+        R1(Phase1Broadcast),
+        R2(DealtShare),
+        R3(ShareVerified),
+    }
+
+    pub type InMsg = crate::protocol::InputMessage<Message>;
+    pub type OutMsg = crate::protocol::OutputMessage<Message>;
+
+    impl InMsg {
+        pub fn is_duplicate(&self, current_msg_set: &[InMsg]) -> bool {
+            current_msg_set.iter().any(|m| m.sender == self.sender)
+        }
+    }
+
+    // Conversion helpers : unwrap MessageType variant to one of its inner structs
+    impl From<Message> for Option<Phase1Broadcast> {
+        fn from(x: Message) -> Option<Phase1Broadcast> {
+            match x {
+                Message::R1(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<DealtShare> {
+        fn from(x: Message) -> Option<DealtShare> {
+            match x {
+                Message::R2(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<ShareVerified> {
+        fn from(x: Message) -> Option<ShareVerified> {
+            match x {
+                Message::R3(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl Validate for Phase1Broadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            check_point("public key", &self.public_key)?;
+            self.metadata.validate()?;
+            check_length("vss commitments", &self.vss_commitments, 2, MAX_LIST_LENGTH)?;
+            self.vss_commitments
+                .iter()
+                .try_for_each(|c| check_point("vss commitment", c))
+        }
+    }
+
+    impl Validate for ShareVerified {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bits("digest", &self.digest, DIGEST_BIT_LENGTH)
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(_) => Ok(()),
+                Message::R3(msg) => msg.validate(),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
+}
+
+/// key import related message data types
+pub mod import {
+    use crate::algorithms::validation::{check_bits, Validate, Validated, ValidationError};
+    use crate::algorithms::zkp::ZkpPublicSetup;
+    use crate::ecdsa::keygen::CorrectKeyProof;
+    use crate::ecdsa::messages::FeldmanVSS;
+    use crate::ecdsa::InputDigest;
+    use curv::BigInt;
+    use paillier::EncryptionKey;
+    use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
+
+    /// Bit length of the digest of the imported public key info
+    const DIGEST_BIT_LENGTH: usize = 256;
+
+    /// Paillier key and range proof setup of a party, broadcast to other parties
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct PartyBroadcast {
+        pub inputs: InputDigest,
+        pub ek: EncryptionKey,
+        pub correct_key_proof: CorrectKeyProof,
+        pub range_proof_setup: Option<ZkpPublicSetup>,
+    }
+
+    /// Share of the imported key with Feldman's commitments, sent privately by the dealer to each party
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct DealtShare {
+        pub inputs: InputDigest,
+        pub vss: FeldmanVSS,
+    }
+
+    /// Confirms that the sender has verified its share, carrying the digest of the imported public key info
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ShareVerified {
+        pub digest: BigInt,
+    }
+
+    /// Messages used by key import algorithm
+    #[derive(Debug, Clone, Deserialize, Serialize, Display)]
+    pub enum Message {
+        R1(PartyBroadcast),
+        R2(DealtShare),
+        R3(ShareVerified),
+    }
+
+    pub type InMsg = crate::protocol::InputMessage<Message>;
+    pub type OutMsg = crate::protocol::OutputMessage<Message>;
+
+    impl InMsg {
+        pub fn is_duplicate(&self, current_msg_set: &[InMsg]) -> bool {
+            current_msg_set.iter().any(|m| m.sender == self.sender)
+        }
+    }
+
+    // Conversion helpers : unwrap MessageType variant to one of its inner structs
+    impl From<Message> for Option<PartyBroadcast> {
+        fn from(x: Message) -> Option<PartyBroadcast> {
+            match x {
+                Message::R1(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<DealtShare> {
+        fn from(x: Message) -> Option<DealtShare> {
+            match x {
+                Message::R2(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<ShareVerified> {
+        fn from(x: Message) -> Option<ShareVerified> {
+            match x {
+                Message::R3(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl Validate for PartyBroadcast {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            self.ek.validate()?;
+            self.correct_key_proof.validate()?;
+            self.range_proof_setup.validate()
+        }
+    }
+
+    impl Validate for DealtShare {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            self.vss.validate()
+        }
+    }
+
+    impl Validate for ShareVerified {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bits("digest", &self.digest, DIGEST_BIT_LENGTH)
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(msg) => msg.validate(),
+                Message::R3(msg) => msg.validate(),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
+}
+
+/// key export related message data types
+pub mod export {
+    use crate::algorithms::validation::{
+        check_bigint, check_point, Validate, Validated, ValidationError,
+    };
+    use crate::ecdsa::keygen::{CorrectKeyProof, KeyMetadata};
+    use crate::ecdsa::InputDigest;
+    use curv::{BigInt, GE};
+    use paillier::EncryptionKey;
+    use serde::{Deserialize, Serialize};
+    use std::convert::TryFrom;
+
+    /// Paillier key of the recipient with the proof of its correctness, sent to holders
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct ExportRequest {
+        pub inputs: InputDigest,
+        pub ek: EncryptionKey,
+        pub correct_key_proof: CorrectKeyProof,
+    }
+
+    /// Consent of a holder to release its share, sent to other holders and to the recipient
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct Authorization {
+        pub inputs: InputDigest,
+        pub metadata: KeyMetadata,
+        /// $` X_{i}^{\lambda_{i}} `$, the public counterpart of the Lagrange-weighted share of the sender
+        pub weighted_public_share: GE,
+    }
+
+    /// Lagrange-weighted share of the sender encrypted with the Paillier key of the recipient
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub struct EncryptedShare {
+        pub ciphertext: BigInt,
+    }
+
+    /// Messages used by key export algorithm
+    #[derive(Debug, Clone, Deserialize, Serialize, Display)]
+    pub enum Message {
+        R1(ExportRequest),
+        R2(Authorization),
+        R3(EncryptedShare),
+        /// sent by the recipient to holders when the reconstructed key matches the public key
+        Confirmed,
+    }
+
+    pub type InMsg = crate::protocol::InputMessage<Message>;
+    pub type OutMsg = crate::protocol::OutputMessage<Message>;
+
+    impl InMsg {
+        pub fn is_duplicate(&self, current_msg_set: &[InMsg]) -> bool {
+            current_msg_set.iter().any(|m| m.sender == self.sender)
+        }
+    }
+
+    // Conversion helpers : unwrap MessageType variant to one of its inner structs
+    impl From<Message> for Option<ExportRequest> {
+        fn from(x: Message) -> Option<ExportRequest> {
+            match x {
+                Message::R1(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<Authorization> {
+        fn from(x: Message) -> Option<Authorization> {
+            match x {
+                Message::R2(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl From<Message> for Option<EncryptedShare> {
+        fn from(x: Message) -> Option<EncryptedShare> {
+            match x {
+                Message::R3(msg) => Some(msg),
+                _ => None,
+            }
+        }
+    }
+
+    impl Validate for ExportRequest {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            self.ek.validate()?;
+            self.correct_key_proof.validate()
+        }
+    }
+
+    impl Validate for Authorization {
+        fn validate(&self) -> Result<(), ValidationError> {
+            self.inputs.validate()?;
+            self.metadata.validate()?;
+            check_point("weighted public share", &self.weighted_public_share)
+        }
+    }
+
+    impl Validate for EncryptedShare {
+        fn validate(&self) -> Result<(), ValidationError> {
+            check_bigint("ciphertext", &self.ciphertext)
+        }
+    }
+
+    impl Validate for Message {
+        fn validate(&self) -> Result<(), ValidationError> {
+            match self {
+                Message::R1(msg) => msg.validate(),
+                Message::R2(msg) => msg.validate(),
+                Message::R3(msg) => msg.validate(),
+                Message::Confirmed => Ok(()),
+            }
+        }
+    }
+
+    impl TryFrom<Message> for Validated<Message> {
+        type Error = ValidationError;
+
+        fn try_from(msg: Message) -> Result<Self, Self::Error> {
+            Validated::new(msg)
+        }
+    }
 }
 
 /// Shamir's secret share
@@ -355,6 +1218,7 @@ pub type SecretShare = (usize, FE);
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FeldmanVSS {
     pub vss: VerifiableSS,
+    #[serde(with = "crate::wire::scalar::share")]
     pub share: SecretShare,
 }
 
@@ -375,6 +1239,12 @@ impl Drop for FeldmanVSS {
     }
 }
 
+impl Validate for FeldmanVSS {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.vss.validate()
+    }
+}
+
 impl FeldmanVSS {
     pub fn verify(&self, pubkey: &GE) -> bool {
         let valid = self.vss.validate_share(&self.share.1, self.share.0).is_ok();
@@ -4,33 +4,53 @@
 //!
 //!  The module implements following algorithms:
 //! * Key generation
+//! * import of an existing key by a trusted dealer
 //! * Signing
 //! * key refresh or re-sharing
+//! * repair of a lost share or enrollment of a new party
+//! * periodic key refresh which rotates Paillier keys and range proof setups
+//! * change of the threshold by the same committee
+//! * authorized export of the key to a designated recipient
 //!
-use crate::ecdsa::keygen::KeygenError;
-use crate::protocol::PartyIndex;
-use curv::arithmetic::traits::{Samplable, ZeroizeBN};
+use crate::algorithms::primes::random_prime_with_rng;
+use crate::algorithms::random;
+use crate::algorithms::random::RandomnessSource;
+use crate::algorithms::validation::{check_bits, Validate, ValidationError, MAX_LIST_LENGTH};
+use crate::ecdsa::blame::{assign_blame, Accusation, Blame, BlameKind};
+use crate::ecdsa::keygen::{KeyId, KeygenError};
+use crate::protocol::{InputMessage, PartyIndex};
+use crate::state_machine::PhaseStatus;
+use curv::arithmetic::traits::ZeroizeBN;
 use curv::cryptographic_primitives::commitments::hash_commitment::HashCommitment;
 use curv::cryptographic_primitives::commitments::traits::Commitment;
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
 use curv::elliptic::curves::traits::{ECPoint, ECScalar};
 use curv::{BigInt, FE, GE};
 use paillier::{
     is_prime, Decrypt, DecryptionKey, EncryptionKey, KeyGeneration, Paillier, RawCiphertext,
     RawPlaintext,
 };
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::fmt::{Debug, Display, Formatter};
-use std::sync::atomic;
+use thiserror::Error;
 use trace::trace;
 use zeroize::Zeroize;
 
+pub mod blame;
+pub mod export;
+pub mod import;
 pub mod keygen;
 pub mod messages;
+pub mod refresh;
+pub mod repair;
 pub mod resharing;
 pub mod signature;
+pub mod threshold_change;
 
 /// Parameters associated with shared key in threshold schema
 ///
@@ -126,6 +146,262 @@ impl SigningParameters {
     }
 }
 
+/// Digest of protocol inputs configured locally by a party
+///
+/// The first broadcast of each protocol carries the digest, so that parties detect inconsistent configuration
+/// (parameters, party lists, message hash etc.) before any secret material is exchanged.
+/// Each input is hashed separately under its own field name, hence the mismatch can be reported per field.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InputDigest(BTreeMap<String, BigInt>);
+
+impl InputDigest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds the digest of key parameters
+    pub fn with_parameters(self, field: &str, params: &Parameters) -> Self {
+        self.with_hash(
+            field,
+            HSha256::create_hash(&[
+                &BigInt::from(params.threshold as u64),
+                &BigInt::from(params.share_count as u64),
+            ]),
+        )
+    }
+
+    /// adds the digest of the set of parties, the order of parties does not matter
+    pub fn with_parties<'a, I>(self, field: &str, parties: I) -> Self
+    where
+        I: IntoIterator<Item = &'a PartyIndex>,
+    {
+        let parties = parties
+            .into_iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|p| BigInt::from(&p.0[..]))
+            .collect::<Vec<_>>();
+        self.with_hash(
+            field,
+            HSha256::create_hash(&parties.iter().collect::<Vec<_>>()),
+        )
+    }
+
+    /// adds the digest of a scalar, for example of the hash of the message to sign
+    pub fn with_scalar(self, field: &str, scalar: &FE) -> Self {
+        self.with_hash(field, HSha256::create_hash(&[&scalar.to_big_int()]))
+    }
+
+    /// adds the digest of a curve point, for example of the public key
+    pub fn with_point(self, field: &str, point: &GE) -> Self {
+        self.with_hash(
+            field,
+            HSha256::create_hash(&[&point.bytes_compressed_to_big_int()]),
+        )
+    }
+
+    /// adds the digest of the key id
+    pub fn with_key_id(self, field: &str, key_id: &KeyId) -> Self {
+        self.with_hash(field, HSha256::create_hash(&[&BigInt::from(&key_id.0[..])]))
+    }
+
+    /// adds the digest of the public Paillier key
+    pub fn with_encryption_key(self, field: &str, ek: &EncryptionKey) -> Self {
+        self.with_hash(field, HSha256::create_hash(&[&ek.n]))
+    }
+
+    /// adds the digest of a text, for example of the reference to an authorization
+    pub fn with_text(self, field: &str, text: &str) -> Self {
+        self.with_hash(
+            field,
+            HSha256::create_hash(&[&BigInt::from(text.as_bytes())]),
+        )
+    }
+
+    fn with_hash(mut self, field: &str, hash: BigInt) -> Self {
+        self.0.insert(field.to_owned(), hash);
+        self
+    }
+
+    /// returns names of fields which differ from `other`, including fields missing in either of digests
+    pub fn mismatched_fields(&self, other: &InputDigest) -> Vec<String> {
+        self.0
+            .keys()
+            .chain(other.0.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|field| self.0.get(*field) != other.0.get(*field))
+            .cloned()
+            .collect()
+    }
+
+    /// compares the digest with the digests received from other parties, returns pairs of the party and mismatched field
+    pub fn mismatches<'a, I>(&self, received: I) -> Vec<(PartyIndex, String)>
+    where
+        I: IntoIterator<Item = (&'a PartyIndex, &'a InputDigest)>,
+    {
+        received
+            .into_iter()
+            .flat_map(|(party, digest)| {
+                self.mismatched_fields(digest)
+                    .into_iter()
+                    .map(move |field| (*party, field))
+            })
+            .collect()
+    }
+}
+
+/// Bounds the number of fields and the size of digests
+impl Validate for InputDigest {
+    fn validate(&self) -> Result<(), ValidationError> {
+        if self.0.len() > MAX_LIST_LENGTH {
+            return Err(ValidationError::InvalidLength {
+                field: "input digest",
+                count: self.0.len(),
+                expected: format!("at most {}", MAX_LIST_LENGTH),
+            });
+        }
+        self.0
+            .values()
+            .try_for_each(|hash| check_bits("input digest", hash, 256))
+    }
+}
+
+/// Parties which did not respond before a phase of the protocol timed out
+#[derive(Debug, Clone, Error)]
+#[error("timeout in {phase}, missing messages from {missing:?}")]
+pub struct PhaseTimeout {
+    pub phase: String,
+    pub missing: BTreeSet<PartyIndex>,
+    pub responded: BTreeSet<PartyIndex>,
+}
+
+impl From<PhaseStatus> for PhaseTimeout {
+    fn from(status: PhaseStatus) -> Self {
+        PhaseTimeout {
+            missing: status.missing_senders(),
+            phase: status.phase,
+            responded: status.received_senders,
+        }
+    }
+}
+
+/// Blames each missing party as unresponsive
+impl Accusation for PhaseTimeout {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        None
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        self.missing
+            .iter()
+            .map(|party| (*party, BlameKind::Unresponsive))
+            .collect()
+    }
+}
+
+/// Errors which every protocol reports, whatever messages it exchanges
+///
+/// `M` is the message type of the protocol.
+pub trait ProtocolError<M>: Accusation + From<PhaseTimeout> {
+    /// message of unexpected type received from `party`
+    fn unknown_message_type(party: PartyIndex, message_type: M) -> Self;
+    /// message received from `party` failed its validation
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self;
+}
+
+/// Container of protocol errors and of blame records derived from these errors
+#[derive(Debug)]
+pub struct ErrorState<E, M> {
+    pub(crate) errors: Vec<E>,
+    pub(crate) blame: Vec<Blame<M>>,
+}
+
+impl<E, M> ErrorState<E, M> {
+    pub fn new(errors: Vec<E>) -> Self {
+        ErrorState {
+            errors,
+            blame: Vec::new(),
+        }
+    }
+
+    /// Merges errors and blame records of both states
+    pub fn append(mut self, rhs: Self) -> Self {
+        self.errors.extend(rhs.errors);
+        self.blame.extend(rhs.blame);
+        self
+    }
+
+    pub fn errors(&self) -> &[E] {
+        &self.errors
+    }
+
+    pub fn blame(&self) -> &[Blame<M>] {
+        &self.blame
+    }
+}
+
+impl<E: Accusation, M: Clone> ErrorState<E, M> {
+    /// Assigns blame for the errors which accuse other parties, attaching their messages from `evidence`
+    pub fn with_evidence(mut self, phase: &str, evidence: &[InputMessage<M>]) -> Self {
+        self.blame = assign_blame(&self.errors, phase, evidence);
+        self
+    }
+}
+
+impl<E: Accusation + From<PhaseTimeout>, M: Clone> ErrorState<E, M> {
+    /// Reports the parties which did not respond in the phase described by `status`,
+    /// attaching messages received in the phase from other parties
+    pub fn timeout(status: PhaseStatus, evidence: &[InputMessage<M>]) -> Self {
+        let phase = status.phase.clone();
+        ErrorState::new(vec![E::from(PhaseTimeout::from(status))]).with_evidence(&phase, evidence)
+    }
+}
+
+/// Checks whether all expected messages have been received so far from other parties
+pub(crate) fn is_broadcast_input_complete<M>(
+    current_msg_set: &[InputMessage<M>],
+    other_parties: &BTreeSet<PartyIndex>,
+) -> bool {
+    let senders = current_msg_set.iter().map(|m| m.sender).collect::<Vec<_>>();
+    other_parties.iter().all(|p| senders.contains(p))
+}
+
+/// Extracts payloads from enum variants of input messages into the hash map
+///
+/// Reports every message of unexpected type and every message failing its validation.
+#[trace(disable(current_msg_set), res = "{:?}")]
+pub(crate) fn to_hash_map_gen<V, M, E>(
+    current_msg_set: &[InputMessage<M>],
+) -> Result<HashMap<PartyIndex, V>, Vec<E>>
+where
+    V: Debug,
+    M: Clone + Validate,
+    Option<V>: From<M>,
+    E: ProtocolError<M> + Debug,
+{
+    let (converted_messages, errors) =
+        current_msg_set
+            .iter()
+            .fold((vec![], vec![]), |(mut values, mut errors), m| {
+                let body: Option<V> = m.body.clone().into();
+                match body {
+                    Some(b) => match m.body.validate() {
+                        Ok(()) => values.push((m.sender, b)),
+                        Err(error) => errors.push(E::invalid_message(m.sender, error)),
+                    },
+                    None => errors.push(E::unknown_message_type(m.sender, m.body.clone())),
+                };
+                (values, errors)
+            });
+
+    if errors.is_empty() {
+        Ok(converted_messages.into_iter().collect())
+    } else {
+        Err(errors)
+    }
+}
+
 /// Public/private key pairs used by a party during key generation for one given shared key
 ///
 /// Public/private key pair `u_i,y_i` for the EC schema, and Public/private `paillier_keys` for homomorphic encryption schema.
@@ -173,7 +449,7 @@ impl InitialPublicKeys {
     }
 }
 
-fn is_valid_curve_point(pk: curv::PK) -> bool {
+pub(crate) fn is_valid_curve_point(pk: curv::PK) -> bool {
     curv::PK::from_slice(&pk.serialize_uncompressed()).is_ok()
 }
 
@@ -226,6 +502,35 @@ impl PaillierKeys {
         Self { ek, dk }
     }
 
+    /// produces new Paillier key pair from given source
+    ///
+    /// Keys are generated by the `paillier` crate unless the source is seeded, so that the production keys do not depend on
+    /// the prime generator of this crate. The seeded source makes the keys of deterministic tests reproducible.
+    pub fn random_from(source: &RandomnessSource) -> Self {
+        if source.is_seeded() {
+            Self::random_with_rng(&mut source.clone())
+        } else {
+            Self::random()
+        }
+    }
+
+    /// produces new Paillier key pair, drawing prime candidates from given generator
+    fn random_with_rng<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> Self {
+        let p = random_prime_with_rng(PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA, rng);
+        let q = loop {
+            let q = random_prime_with_rng(PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA, rng);
+            if q != p {
+                break q;
+            }
+        };
+        let n = p.borrow() * q.borrow();
+        let nn = n.borrow() * n.borrow();
+        Self {
+            ek: EncryptionKey { n, nn },
+            dk: DecryptionKey { p, q },
+        }
+    }
+
     /// decrypts given value `c`
     pub fn decrypt(&self, c: BigInt) -> RawPlaintext {
         Paillier::decrypt(&self.dk, &RawCiphertext::from(c))
@@ -258,25 +563,6 @@ impl Debug for PaillierKeys {
     }
 }
 
-struct ManagedPaillierDecryptionKey(Box<DecryptionKey>);
-
-impl Drop for ManagedPaillierDecryptionKey {
-    fn drop(&mut self) {
-        self.0.p = BigInt::zero();
-        self.0.q = BigInt::zero();
-        atomic::compiler_fence(atomic::Ordering::SeqCst);
-    }
-}
-
-struct ManagedSecretKey(Box<FE>);
-
-impl Drop for ManagedSecretKey {
-    fn drop(&mut self) {
-        self.0.zeroize();
-        atomic::compiler_fence(atomic::Ordering::SeqCst);
-    }
-}
-
 ///current recommended bit size for the primes in Paillier schema
 pub(crate) const PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA: usize = 1024;
 
@@ -334,8 +620,8 @@ struct CommitmentScheme {
 impl CommitmentScheme {
     /// creates commitment scheme from EC group element
     #[allow(non_snake_case)]
-    fn from_GE(elem: &GE) -> Self {
-        let decomm = BigInt::sample(256);
+    fn from_GE<R: RngCore + CryptoRng + ?Sized>(elem: &GE, rng: &mut R) -> Self {
+        let decomm = random::sample(rng, 256);
         let comm = HashCommitment::create_commitment_with_user_defined_randomness(
             &elem.bytes_compressed_to_big_int(),
             &decomm,
@@ -345,8 +631,8 @@ impl CommitmentScheme {
 
     /// creates commitment scheme from `BigInt`
     #[allow(non_snake_case)]
-    fn from_BigInt(message: &BigInt) -> Self {
-        let decomm = BigInt::sample(256);
+    fn from_BigInt<R: RngCore + CryptoRng + ?Sized>(message: &BigInt, rng: &mut R) -> Self {
+        let decomm = random::sample(rng, 256);
         let comm = HashCommitment::create_commitment_with_user_defined_randomness(message, &decomm);
         CommitmentScheme { comm, decomm }
     }
@@ -420,7 +706,12 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::ecdsa::{is_beta_subset_of_alpha, is_valid_curve_point};
+    use crate::algorithms::random::RandomnessSource;
+    use crate::ecdsa::{
+        is_beta_subset_of_alpha, is_valid_curve_point, InputDigest, PaillierKeys, Parameters,
+        PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA,
+    };
+    use crate::protocol::PartyIndex;
     use curv::arithmetic::traits::Converter;
     use curv::arithmetic::traits::Samplable;
     use curv::elliptic::curves::traits::ECPoint;
@@ -511,4 +802,58 @@ mod tests {
         assert!(!is_beta_subset_of_alpha(alpha.iter(), vec![4].iter()));
         assert!(!is_beta_subset_of_alpha(alpha.iter(), vec![4, 5].iter()));
     }
+
+    #[test]
+    fn input_digest_mismatches() {
+        let parties = (0..3).map(PartyIndex::from).collect::<Vec<_>>();
+        let reversed = parties.iter().rev().cloned().collect::<Vec<_>>();
+        let params = Parameters::new(2, 3).unwrap();
+        let hash: FE = ECScalar::new_random();
+
+        let ours = InputDigest::new()
+            .with_parameters("parameters", &params)
+            .with_parties("parties", &parties)
+            .with_scalar("message_hash", &hash);
+
+        let same = InputDigest::new()
+            .with_parameters("parameters", &params)
+            .with_parties("parties", &reversed)
+            .with_scalar("message_hash", &hash);
+        assert!(ours.mismatched_fields(&same).is_empty());
+
+        let other_hash: FE = ECScalar::new_random();
+        let theirs = InputDigest::new()
+            .with_parameters("parameters", &Parameters::new(3, 3).unwrap())
+            .with_parties("parties", &parties[..2])
+            .with_scalar("message_hash", &other_hash);
+        assert_eq!(
+            ours.mismatched_fields(&theirs),
+            vec!["message_hash", "parameters", "parties"]
+        );
+
+        let mismatches = ours.mismatches(vec![
+            (&parties[1], &same),
+            (&parties[2], &InputDigest::new()),
+        ]);
+        assert_eq!(mismatches.len(), 3);
+        assert!(mismatches.iter().all(|(party, _)| *party == parties[2]));
+    }
+
+    #[test]
+    fn seeded_paillier_keys() {
+        let keys = PaillierKeys::random_from(&RandomnessSource::seeded(5));
+        assert!(PaillierKeys::is_valid(&keys.ek, &keys.dk));
+        assert_eq!(keys.dk.p.bit_length(), PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA);
+        assert_eq!(keys.dk.q.bit_length(), PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA);
+        assert!(keys == PaillierKeys::random_from(&RandomnessSource::seeded(5)));
+    }
+
+    #[test]
+    fn os_paillier_keys_are_generated_by_library() {
+        let source = RandomnessSource::os();
+        let keys = PaillierKeys::random_from(&source);
+        assert!(PaillierKeys::is_valid(&keys.ek, &keys.dk));
+        assert!(keys.ek.n.bit_length() >= 2 * PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA - 1);
+        assert!(keys != PaillierKeys::random_from(&source));
+    }
 }
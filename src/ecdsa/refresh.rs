@@ -0,0 +1,1011 @@
+//!  Proactive key refresh
+//!
+//! Periodic refresh of the key held by a stable committee, which changes all shares and auxiliary keys of parties while the public key stays the same.
+//! Unlike [resharing](../resharing/index.html), no party sends a share of its own secret: every party `i` samples a random polynomial $` f_{i} `$ of degree `t`
+//! with zero free term, broadcasts commitments $` A_{ik} = g^{a_{ik}} `$ to its coefficients and sends $` f_{i}(x_{j}) `$ privately to each party `j`.
+//! Party `j` checks received values against commitments and adds them to its share, $` x'_{j} = x_{j} + \sum_{i} f_{i}(x_{j}) `$.
+//! As the sum of polynomials is zero at zero, shares interpolate to the same secret key. Public shares of all parties are updated from commitments the same way.
+//!
+//! In the same round every party rotates its auxiliary keys, in the style of the auxiliary info refresh of CGGMP: it generates new Paillier key,
+//! proven correct by ZK proof, and broadcasts new range proof setup if the key uses range proofs. The setup takes a while to generate, hence the caller supplies it.
+//!
+//! The whole committee of the current epoch takes part, and the refreshed key starts the next epoch with the same committee, so that signers holding shares
+//! of the refreshed key and of the old one are detected, see [`KeyMetadata`]. Parties finish when all of them have confirmed their new shares
+//! along with the digest of the refreshed public info, which detects a party that has sent different broadcasts to different parties.
+//! If the refresh fails, parties keep their old keys. A party which times out waiting for confirmations can't know whether others have finished,
+//! hence it should keep both keys until the committee agrees on the outcome.
+//!
+//! [`KeyMetadata`]: ../keygen/struct.KeyMetadata.html
+use crate::algorithms::nizk_rsa;
+use crate::algorithms::random::{self, RandomnessSource};
+use crate::algorithms::validation::ValidationError;
+use crate::algorithms::zkp::{ZkpPublicSetup, ZkpSetup};
+use crate::ecdsa::blame::{Accusation, BlameKind};
+use crate::ecdsa::keygen::{
+    ASecretShareLoader, CorrectKeyProof, MultiPartyInfo, MultiPartyPublicInfo, RangeProofSetups,
+    SecretShareLoader,
+};
+use crate::ecdsa::messages::refresh::{Phase1Broadcast, ShareVerified, ZeroShare};
+use crate::ecdsa::messages::SecretShare;
+use crate::ecdsa::{
+    is_broadcast_input_complete, to_hash_map_gen, InputDigest, PaillierKeys, Parameters,
+    PhaseTimeout, ProtocolError,
+};
+use crate::protocol::{Address, PartyIndex};
+use crate::secret::Secret;
+use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
+use curv::cryptographic_primitives::hashing::traits::Hash;
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use paillier::EncryptionKey;
+use std::collections::{BTreeSet, HashMap};
+use std::iter::FromIterator;
+use std::time::Duration;
+use thiserror::Error;
+use trace::trace;
+
+pub use super::messages::refresh::{InMsg, Message, OutMsg};
+
+/// Enumerates errors which can be reported by key refresh protocol
+#[derive(Debug, Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum RefreshError {
+    #[error("refresh: {0}")]
+    Timeout(#[from] PhaseTimeout),
+    #[error("unexpected message {message_type:?}, party {party}")]
+    UnknownMessageType {
+        message_type: Message,
+        party: PartyIndex,
+    },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("public shares of the key are unknown, the key has to be reshared first")]
+    PublicSharesMissing,
+    #[error("party {party} has sent {count} commitments to its zero sharing, {expected} expected")]
+    InvalidZeroCommitments {
+        party: PartyIndex,
+        count: usize,
+        expected: usize,
+    },
+    #[error("zero share of party {party} does not match its commitments")]
+    InvalidZeroShare { party: PartyIndex },
+    #[error("refreshed share does not match the public share")]
+    ShareVerificationFailed,
+    #[error("party {party} has refreshed the key differently")]
+    InconsistentRefresh { party: PartyIndex },
+    #[error("invalid correct key proof {proof}, party {party}")]
+    InvalidCorrectKeyProof { proof: String, party: PartyIndex },
+    #[error("missing range proof from {party}")]
+    RangeProofSetupMissing { party: PartyIndex },
+    #[error("unexpected range proof from {party}, proof {proof:?} ")]
+    RangeProofSetupUnexpected { proof: String, party: PartyIndex },
+    #[error("range proof setup: dlog proof failed , party {party}, proof {proof} ")]
+    RangeProofSetupDlogProofFailed { proof: String, party: PartyIndex },
+    #[error("protocol setup error: {0}")]
+    ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
+    #[error("{0}")]
+    GeneralError(String),
+}
+
+impl Accusation for RefreshError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            RefreshError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            RefreshError::InvalidMessage { party, .. } => Some((*party, BlameKind::InvalidMessage)),
+            RefreshError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            RefreshError::InvalidZeroCommitments { party, .. }
+            | RefreshError::InvalidZeroShare { party } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            RefreshError::InvalidCorrectKeyProof { party, .. } => {
+                Some((*party, BlameKind::InvalidCorrectKeyProof))
+            }
+            RefreshError::RangeProofSetupMissing { party } => {
+                Some((*party, BlameKind::RangeProofSetupMissing))
+            }
+            RefreshError::RangeProofSetupUnexpected { party, .. } => {
+                Some((*party, BlameKind::RangeProofSetupUnexpected))
+            }
+            RefreshError::RangeProofSetupDlogProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidRangeProofSetup))
+            }
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            RefreshError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
+/// Contains a vector of possible refresh errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<RefreshError, Message>;
+
+/// Result of key refresh protocol
+#[derive(Clone, Debug)]
+pub struct FinalState {
+    pub info: MultiPartyInfo,
+}
+
+type OutMsgVec = Vec<OutMsg>;
+pub type MachineResult = Result<FinalState, ErrorState>;
+
+/// Type definitions
+#[derive(Debug)]
+pub struct RefreshTraits;
+
+impl StateMachineTraits for RefreshTraits {
+    type InMsg = InMsg;
+    type OutMsg = OutMsg;
+    type FinalState = FinalState;
+    type ErrorState = ErrorState;
+}
+
+impl ProtocolError<Message> for RefreshError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        RefreshError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        RefreshError::InvalidMessage { party, error }
+    }
+}
+
+/// Digest of refresh inputs which all parties have to agree on
+fn input_digest(params: &Parameters, parties: &[PartyIndex]) -> InputDigest {
+    InputDigest::new()
+        .with_parameters("parameters", params)
+        .with_parties("parties", parties)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+pub(super) fn to_fe(x: usize) -> FE {
+    ECScalar::from(&BigInt::from(x as u64))
+}
+
+/// Value of the polynomial with zero free term at `x`, given its coefficients starting from the linear one
+pub(super) fn evaluate(coefficients: &[FE], x: usize) -> FE {
+    let x = to_fe(x);
+    // Horner's scheme, with the free term equal to zero
+    coefficients
+        .iter()
+        .rev()
+        .fold(FE::zero(), |acc, a| (acc + *a) * x)
+}
+
+/// Commitment $` g^{f_{i}(x)} `$ to the value of the polynomial with zero free term at `x`, given commitments to its coefficients starting from the linear one
+///
+/// Returns `None` for the empty list of commitments.
+pub(super) fn evaluate_commitments(commitments: &[GE], x: usize) -> Option<GE> {
+    let x = to_fe(x);
+    commitments.iter().rev().fold(None, |acc, c| {
+        let sum = match acc {
+            None => *c,
+            Some(acc) => acc + *c,
+        };
+        Some(sum * &x)
+    })
+}
+
+/// Digest of the refreshed public info, which all parties have to agree on
+///
+/// Covers key parameters, the epoch, public shares, Paillier keys and range proof setups of all parties.
+/// Also used by [threshold change](../threshold_change/index.html).
+pub(super) fn public_digest(info: &MultiPartyPublicInfo) -> BigInt {
+    let mut public_shares = info.public_shares.iter().collect::<Vec<_>>();
+    public_shares.sort_by_key(|(p, _)| **p);
+    let mut encryption_keys = info
+        .party_he_keys
+        .iter()
+        .chain(std::iter::once((&info.own_party_index, &info.own_ek)))
+        .collect::<Vec<_>>();
+    encryption_keys.sort_by_key(|(p, _)| **p);
+
+    let mut values = vec![
+        BigInt::from(&info.metadata.key_id.0[..]),
+        BigInt::from(info.metadata.epoch),
+        BigInt::from(info.key_params.threshold() as u64),
+        BigInt::from(info.key_params.share_count() as u64),
+    ];
+    values.extend(
+        public_shares
+            .into_iter()
+            .map(|(_, share)| share.bytes_compressed_to_big_int()),
+    );
+    values.extend(encryption_keys.into_iter().map(|(_, ek)| ek.n.clone()));
+    if let Some(setups) = &info.range_proof_setups {
+        let own = &setups.my_setup;
+        let mut setups = setups
+            .party_setups
+            .iter()
+            .map(|(p, s)| (*p, [&s.N_tilde, &s.h1, &s.h2]))
+            .chain(std::iter::once((
+                info.own_party_index,
+                [&own.N_tilde, &own.h1, &own.h2],
+            )))
+            .collect::<Vec<_>>();
+        setups.sort_by_key(|(p, _)| *p);
+        values.extend(
+            setups
+                .into_iter()
+                .flat_map(|(_, setup)| setup.iter().map(|v| (*v).clone()).collect::<Vec<_>>()),
+        );
+    }
+    HSha256::create_hash(&values.iter().collect::<Vec<_>>())
+}
+
+/// Initial phase of the protocol
+///
+/// * samples the zero sharing polynomial and new Paillier key
+/// * broadcasts commitments to coefficients of the polynomial, new Paillier key, ZK proof of its correctness and new range proof setup
+/// * collects same items from other parties and verifies them
+#[derive(Debug)]
+pub struct Phase1 {
+    info: MultiPartyPublicInfo,
+    share: Secret<SecretShare>,
+    other_parties: BTreeSet<PartyIndex>,
+    coefficients: Secret<Vec<FE>>,
+    zero_commitments: Vec<GE>,
+    paillier_keys: Secret<PaillierKeys>,
+    range_proof_setup: Option<ZkpSetup>,
+    inputs: InputDigest,
+    timeout: Option<Duration>,
+    rng: RandomnessSource,
+}
+
+#[trace(pretty, prefix = "Phase1::")]
+impl Phase1 {
+    /// Initializes the refresh of the key held by `parties`, which have to be the committee of the current epoch
+    ///
+    /// `range_proof_setup` replaces own setup of the key, it is required if and only if the key uses range proofs.
+    pub fn new(
+        multi_party_info: &MultiPartyInfo,
+        parties: &[PartyIndex],
+        range_proof_setup: Option<ZkpSetup>,
+        timeout: Option<Duration>,
+    ) -> Result<Self, RefreshError> {
+        Self::new_with_rng(
+            multi_party_info,
+            parties,
+            range_proof_setup,
+            timeout,
+            RandomnessSource::os(),
+        )
+    }
+
+    /// same as `new`, but the zero sharing polynomial, the new Paillier key and proofs of the range proof setup are sampled from `rng`
+    pub fn new_with_rng(
+        multi_party_info: &MultiPartyInfo,
+        parties: &[PartyIndex],
+        range_proof_setup: Option<ZkpSetup>,
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    ) -> Result<Self, RefreshError> {
+        Self::init(
+            &multi_party_info.public_info(),
+            multi_party_info,
+            parties,
+            range_proof_setup,
+            timeout,
+            rng,
+        )
+    }
+
+    /// Initializes the protocol which loads the secret share through `secret_loader` and samples its randomness from `rng`
+    ///
+    /// The share is held until the refreshed share is computed from it, and erased afterwards.
+    pub fn new_with_loader(
+        multi_party_info: &MultiPartyPublicInfo,
+        secret_loader: ASecretShareLoader,
+        parties: &[PartyIndex],
+        range_proof_setup: Option<ZkpSetup>,
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    ) -> Result<Self, RefreshError> {
+        Self::init(
+            multi_party_info,
+            &**secret_loader,
+            parties,
+            range_proof_setup,
+            timeout,
+            rng,
+        )
+    }
+
+    #[trace(disable(secret_loader, range_proof_setup, rng))]
+    fn init(
+        multi_party_info: &MultiPartyPublicInfo,
+        secret_loader: &dyn SecretShareLoader,
+        parties: &[PartyIndex],
+        range_proof_setup: Option<ZkpSetup>,
+        timeout: Option<Duration>,
+        mut rng: RandomnessSource,
+    ) -> Result<Self, RefreshError> {
+        let params = &multi_party_info.key_params;
+        if params.threshold() == 0 {
+            return Err(RefreshError::ProtocolSetupError(
+                "threshold of the key is zero".to_string(),
+            ));
+        }
+        let parties_as_set = BTreeSet::from_iter(parties.iter().cloned());
+        if parties_as_set.len() != parties.len() {
+            return Err(RefreshError::ProtocolSetupError(
+                "duplicate entries in parties' list".to_string(),
+            ));
+        }
+        let metadata = &multi_party_info.metadata;
+        match metadata.committee() {
+            Some(committee) if *committee == parties_as_set => {}
+            _ => {
+                return Err(RefreshError::ProtocolSetupError(format!(
+                    "parties differ from the committee of epoch {}",
+                    metadata.epoch
+                )))
+            }
+        }
+        let own_party_index = multi_party_info.own_party_index;
+        if !parties_as_set.contains(&own_party_index) {
+            return Err(RefreshError::ProtocolSetupError(
+                "own party index not in parties' list".to_string(),
+            ));
+        }
+        if parties
+            .iter()
+            .any(|p| !multi_party_info.party_to_point_map.points.contains_key(p))
+        {
+            return Err(RefreshError::ProtocolSetupError(
+                "x-coordinate of some party is unknown".to_string(),
+            ));
+        }
+        if parties
+            .iter()
+            .any(|p| !multi_party_info.public_shares.contains_key(p))
+        {
+            return Err(RefreshError::PublicSharesMissing);
+        }
+        match (&multi_party_info.range_proof_setups, &range_proof_setup) {
+            (Some(_), None) => {
+                return Err(RefreshError::ProtocolSetupError(
+                    "the key uses range proofs, new range proof setup is required".to_string(),
+                ))
+            }
+            (None, Some(_)) => {
+                return Err(RefreshError::ProtocolSetupError(
+                    "the key does not use range proofs".to_string(),
+                ))
+            }
+            _ => {}
+        }
+
+        let share = {
+            let x_i = secret_loader
+                .get_secret_share()
+                .map(Secret::from_box)
+                .map_err(|e| RefreshError::ProtocolSetupError(e.0))?;
+            Secret::new((multi_party_info.own_point, *x_i))
+        };
+        let coefficients = Secret::new(
+            (0..params.threshold())
+                .map(|_| random::sample_scalar(&mut rng))
+                .collect::<Vec<_>>(),
+        );
+        let g: GE = ECPoint::generator();
+        let zero_commitments = coefficients.iter().map(|a| g * a).collect();
+
+        let mut other_parties = parties_as_set;
+        other_parties.remove(&own_party_index);
+
+        Ok(Phase1 {
+            info: multi_party_info.clone(),
+            share,
+            other_parties,
+            coefficients,
+            zero_commitments,
+            paillier_keys: Secret::new(PaillierKeys::random_from(&rng)),
+            range_proof_setup,
+            inputs: input_digest(params, parties),
+            timeout,
+            rng,
+        })
+    }
+
+    /// checks inputs of other parties, commitments to their zero sharings, their new Paillier keys and range proof setups
+    #[trace(disable(input))]
+    fn verify_broadcasts(&self, input: &HashMap<PartyIndex, Phase1Broadcast>) -> Vec<RefreshError> {
+        let mut errors = self
+            .inputs
+            .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+            .into_iter()
+            .map(|(party, field)| RefreshError::InputMismatch { party, field })
+            .collect::<Vec<_>>();
+        for (party, msg) in input.iter() {
+            let party = *party;
+            if msg.metadata != self.info.metadata {
+                errors.push(RefreshError::InputMismatch {
+                    party,
+                    field: "metadata".to_string(),
+                });
+            }
+            if msg.public_key != self.info.public_key {
+                errors.push(RefreshError::InputMismatch {
+                    party,
+                    field: "public_key".to_string(),
+                });
+            }
+            if msg.zero_commitments.len() != self.zero_commitments.len() {
+                errors.push(RefreshError::InvalidZeroCommitments {
+                    party,
+                    count: msg.zero_commitments.len(),
+                    expected: self.zero_commitments.len(),
+                });
+            }
+            if nizk_rsa::verify(&msg.ek, &msg.correct_key_proof.0).is_err() {
+                errors.push(RefreshError::InvalidCorrectKeyProof {
+                    proof: format!("{:?}", msg.correct_key_proof),
+                    party,
+                });
+            }
+            match (&self.range_proof_setup, &msg.range_proof_setup) {
+                (Some(_), None) => errors.push(RefreshError::RangeProofSetupMissing { party }),
+                (None, Some(setup)) => errors.push(RefreshError::RangeProofSetupUnexpected {
+                    proof: format!("{:?}", setup.dlog_proof),
+                    party,
+                }),
+                (Some(_), Some(setup)) => {
+                    if setup.verify().is_err() {
+                        errors.push(RefreshError::RangeProofSetupDlogProofFailed {
+                            proof: format!("{:?}", setup.dlog_proof),
+                            party,
+                        })
+                    }
+                }
+                (None, None) => {}
+            }
+        }
+        errors
+    }
+}
+
+#[trace(pretty, prefix = "Phase1::")]
+impl State<RefreshTraits> for Phase1 {
+    fn start(&mut self) -> Option<OutMsgVec> {
+        log::info!("Phase1 starts");
+        let rng = &mut self.rng;
+        let broadcast = Phase1Broadcast {
+            inputs: self.inputs.clone(),
+            public_key: self.info.public_key,
+            metadata: self.info.metadata.clone(),
+            zero_commitments: self.zero_commitments.clone(),
+            ek: self.paillier_keys.ek.clone(),
+            correct_key_proof: CorrectKeyProof(nizk_rsa::gen_proof(&self.paillier_keys.dk)),
+            range_proof_setup: self
+                .range_proof_setup
+                .as_ref()
+                .map(|s| ZkpPublicSetup::from_private_zkp_setup_with_rng(s, rng)),
+        };
+        Some(
+            self.other_parties
+                .iter()
+                .map(|p| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R1(broadcast.clone()),
+                })
+                .collect(),
+        )
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+        matches!(msg.body, Message::R1(_))
+            && self.other_parties.contains(&msg.sender)
+            && !msg.is_duplicate(current_msg_set)
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+        is_broadcast_input_complete(current_msg_set, &self.other_parties)
+    }
+
+    fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RefreshTraits> {
+        let input = match to_hash_map_gen::<Phase1Broadcast, _, _>(&current_msg_set) {
+            Ok(input) => input,
+            Err(errors) => {
+                let error_state = ErrorState::new(errors).with_evidence("phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                return Transition::FinalState(Err(error_state));
+            }
+        };
+        let errors = self.verify_broadcasts(&input);
+        if !errors.is_empty() {
+            let error_state = ErrorState::new(errors).with_evidence("phase1", &current_msg_set);
+            log::error!("Phase1 returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
+        let mut zero_commitments = HashMap::new();
+        let mut party_he_keys = HashMap::new();
+        let mut party_setups = HashMap::new();
+        for (party, msg) in input.into_iter() {
+            zero_commitments.insert(party, msg.zero_commitments);
+            party_he_keys.insert(party, msg.ek);
+            if let Some(setup) = msg.range_proof_setup {
+                party_setups.insert(party, setup);
+            }
+        }
+        zero_commitments.insert(self.info.own_party_index, self.zero_commitments.clone());
+
+        Transition::NewState(Box::new(Phase2 {
+            info: self.info.clone(),
+            share: Secret::new(*self.share),
+            other_parties: self.other_parties.clone(),
+            coefficients: Secret::new((*self.coefficients).clone()),
+            zero_commitments,
+            paillier_keys: Secret::new((*self.paillier_keys).clone()),
+            party_he_keys,
+            range_proof_setups: self
+                .range_proof_setup
+                .clone()
+                .map(|my_setup| RangeProofSetups {
+                    my_setup,
+                    party_setups,
+                }),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase1", &self.other_parties, current_msg_set)
+    }
+}
+
+/// Second phase of the protocol
+///
+/// * sends values of own zero sharing polynomial to other parties
+/// * collects values sent to us and verifies them against commitments
+/// * computes the new share and new public shares of all parties
+struct Phase2 {
+    info: MultiPartyPublicInfo,
+    share: Secret<SecretShare>,
+    other_parties: BTreeSet<PartyIndex>,
+    coefficients: Secret<Vec<FE>>,
+    zero_commitments: HashMap<PartyIndex, Vec<GE>>,
+    paillier_keys: Secret<PaillierKeys>,
+    party_he_keys: HashMap<PartyIndex, EncryptionKey>,
+    range_proof_setups: Option<RangeProofSetups>,
+    timeout: Option<Duration>,
+}
+
+impl Phase2 {
+    /// public shares of all parties updated with their zero shares, returns `None` if some of the shares can't be updated
+    fn refreshed_public_shares(&self) -> Option<HashMap<PartyIndex, GE>> {
+        let points = &self.info.party_to_point_map.points;
+        self.zero_commitments
+            .keys()
+            .map(|party| {
+                let x = *points.get(party)?;
+                let public_share = *self.info.public_shares.get(party)?;
+                self.zero_commitments
+                    .values()
+                    .try_fold(public_share, |acc, commitments| {
+                        evaluate_commitments(commitments, x).map(|c| acc + c)
+                    })
+                    .map(|share| (*party, share))
+            })
+            .collect()
+    }
+}
+
+#[trace(pretty, prefix = "Phase2::")]
+impl State<RefreshTraits> for Phase2 {
+    fn start(&mut self) -> Option<OutMsgVec> {
+        log::debug!("Phase2 starts");
+        let points = &self.info.party_to_point_map.points;
+        Some(
+            self.other_parties
+                .iter()
+                .map(|p| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R2(ZeroShare {
+                        share: evaluate(&self.coefficients, points[p]),
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+        matches!(msg.body, Message::R2(_))
+            && self.other_parties.contains(&msg.sender)
+            && !msg.is_duplicate(current_msg_set)
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+        is_broadcast_input_complete(current_msg_set, &self.other_parties)
+    }
+
+    fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RefreshTraits> {
+        let fail = |errors: Vec<RefreshError>| {
+            let error_state = ErrorState::new(errors).with_evidence("phase2", &current_msg_set);
+            log::error!("Phase2 returns {:?}", error_state);
+            Transition::FinalState(Err(error_state))
+        };
+        let input = match to_hash_map_gen::<ZeroShare, _, _>(&current_msg_set) {
+            Ok(input) => Secret::new(
+                input
+                    .into_iter()
+                    .map(|(p, msg)| (p, msg.share))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            Err(errors) => return fail(errors),
+        };
+
+        let own_x = self.share.0;
+        let g: GE = ECPoint::generator();
+        let errors = input
+            .iter()
+            .filter(|(party, share)| {
+                evaluate_commitments(&self.zero_commitments[*party], own_x)
+                    .map(|expected| (g * *share).get_element() != expected.get_element())
+                    .unwrap_or(true)
+            })
+            .map(|(party, _)| RefreshError::InvalidZeroShare { party: *party })
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return fail(errors);
+        }
+
+        let public_shares = match self.refreshed_public_shares() {
+            Some(public_shares) => public_shares,
+            None => return fail(vec![RefreshError::PublicSharesMissing]),
+        };
+        let share = Secret::new((
+            own_x,
+            input.values().fold(
+                self.share.1 + evaluate(&self.coefficients, own_x),
+                |acc, zero_share| acc + *zero_share,
+            ),
+        ));
+        let public_share_matches = public_shares
+            .get(&self.info.own_party_index)
+            .map(|public_share| public_share.get_element() == (g * share.1).get_element())
+            .unwrap_or(false);
+        if !public_share_matches {
+            return fail(vec![RefreshError::ShareVerificationFailed]);
+        }
+
+        let committee = self
+            .zero_commitments
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let info = MultiPartyPublicInfo {
+            key_params: self.info.key_params,
+            own_party_index: self.info.own_party_index,
+            own_point: own_x,
+            public_key: self.info.public_key,
+            own_ek: self.paillier_keys.ek.clone(),
+            party_he_keys: self.party_he_keys.clone(),
+            party_to_point_map: self.info.party_to_point_map.clone(),
+            public_shares,
+            range_proof_setups: self.range_proof_setups.clone(),
+            metadata: self.info.metadata.next_epoch(committee),
+        };
+        log::info!("Phase2 succeeded, the share is refreshed");
+
+        Transition::NewState(Box::new(Phase3 {
+            digest: public_digest(&info),
+            info,
+            share,
+            other_parties: self.other_parties.clone(),
+            paillier_keys: Secret::new((*self.paillier_keys).clone()),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase2", &self.other_parties, current_msg_set)
+    }
+}
+
+/// Last phase of the protocol
+///
+/// * sends the digest of the refreshed public info to other parties, confirming that the new share is verified
+/// * collects confirmations of other parties and compares their digests with ours
+struct Phase3 {
+    info: MultiPartyPublicInfo,
+    share: Secret<SecretShare>,
+    other_parties: BTreeSet<PartyIndex>,
+    paillier_keys: Secret<PaillierKeys>,
+    digest: BigInt,
+    timeout: Option<Duration>,
+}
+
+#[trace(pretty, prefix = "Phase3::")]
+impl State<RefreshTraits> for Phase3 {
+    fn start(&mut self) -> Option<OutMsgVec> {
+        log::debug!("Phase3 starts");
+        Some(
+            self.other_parties
+                .iter()
+                .map(|p| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R3(ShareVerified {
+                        digest: self.digest.clone(),
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+        matches!(msg.body, Message::R3(_))
+            && self.other_parties.contains(&msg.sender)
+            && !msg.is_duplicate(current_msg_set)
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+        is_broadcast_input_complete(current_msg_set, &self.other_parties)
+    }
+
+    fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RefreshTraits> {
+        let errors = match to_hash_map_gen::<ShareVerified, _, _>(&current_msg_set) {
+            Ok(input) => input
+                .into_iter()
+                .filter(|(_, msg)| msg.digest != self.digest)
+                .map(|(party, _)| RefreshError::InconsistentRefresh { party })
+                .collect::<Vec<_>>(),
+            Err(errors) => errors,
+        };
+        if !errors.is_empty() {
+            let error_state = ErrorState::new(errors).with_evidence("phase3", &current_msg_set);
+            log::error!("Phase3 returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
+        log::info!("Phase3 succeeded");
+        Transition::FinalState(Ok(FinalState {
+            info: self
+                .info
+                .clone()
+                .into_multi_party_info(*self.share, (*self.paillier_keys).clone()),
+        }))
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase3", &self.other_parties, current_msg_set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Phase1, RefreshError, RefreshTraits};
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::zkp::ZkpSetup;
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::state_machine::BoxedState;
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::{load_keys, load_range_proof_setups, run_parties};
+    use crate::testkit::{Action, Adversary};
+    use anyhow::bail;
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+
+    /// Runs refresh among holders of `keys`, returns outcomes in the order of `keys`, `None` for parties which have not finished
+    fn run_refresh(
+        keys: &[MultiPartyInfo],
+        range_proof_setups: Option<Vec<ZkpSetup>>,
+        adversary: Option<Adversary<super::Message>>,
+    ) -> anyhow::Result<Vec<Option<super::MachineResult>>> {
+        let parties = keys.iter().map(|k| k.own_party_index).collect::<Vec<_>>();
+        let start_state = |key: &MultiPartyInfo| -> anyhow::Result<BoxedState<RefreshTraits>> {
+            let i = parties
+                .iter()
+                .position(|p| *p == key.own_party_index)
+                .expect("party of the key");
+            let range_proof_setup = range_proof_setups.as_ref().map(|setups| setups[i].clone());
+            Ok(Box::new(Phase1::new_with_rng(
+                key,
+                &parties,
+                range_proof_setup,
+                None,
+                RandomnessSource::seeded(0x72656672 + i as u64),
+            )?))
+        };
+        Ok(run_parties(0x72656672, keys, start_state, adversary)?
+            .outcomes
+            .into_iter()
+            .map(|node| node.outcome)
+            .collect())
+    }
+
+    fn refreshed_keys(
+        outcomes: Vec<Option<super::MachineResult>>,
+    ) -> anyhow::Result<Vec<MultiPartyInfo>> {
+        outcomes
+            .into_iter()
+            .map(|outcome| match outcome {
+                Some(Ok(final_state)) => Ok(final_state.info),
+                Some(Err(error_state)) => bail!("success expected, got {:?}", error_state),
+                None => bail!("success expected, the party has not finished"),
+            })
+            .collect()
+    }
+
+    /// Checks that refreshed keys hold new shares of the same secret key, new Paillier keys and start the next epoch
+    fn check_refreshed(keys: &[MultiPartyInfo], refreshed: &[MultiPartyInfo]) {
+        assert_eq!(refreshed.len(), keys.len());
+        let (points, shares): (Vec<FE>, Vec<FE>) = refreshed
+            .iter()
+            .map(|key| {
+                let x: FE = ECScalar::from(&BigInt::from(key.own_point() as u64));
+                (x, key.own_share())
+            })
+            .unzip();
+        let secret = VerifiableSS::lagrange_interpolation_at_zero(&points, &shares);
+        let g: GE = ECPoint::generator();
+        assert_eq!((g * secret).get_element(), keys[0].public_key.get_element());
+
+        let committee = keys[0].metadata.committee().cloned().unwrap_or_default();
+        for (old, new) in keys.iter().zip(refreshed.iter()) {
+            assert_eq!(new.own_party_index, old.own_party_index);
+            assert_eq!(new.own_point(), old.own_point());
+            assert_ne!(new.own_share().to_big_int(), old.own_share().to_big_int());
+            assert_ne!(new.own_he_keys.ek.n, old.own_he_keys.ek.n);
+            assert_eq!(new.metadata, old.metadata.next_epoch(committee.clone()));
+            assert_eq!(new.public_shares, refreshed[0].public_shares);
+            assert!(new.validate().is_empty(), "{:?}", new.validate());
+        }
+    }
+
+    #[test]
+    fn refresh_keeps_key_and_rotates_paillier_keys() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let refreshed = refreshed_keys(run_refresh(&keys, None, None)?)?;
+        check_refreshed(&keys, &refreshed);
+        for new in refreshed.iter() {
+            for other in refreshed
+                .iter()
+                .filter(|k| k.own_party_index != new.own_party_index)
+            {
+                assert_eq!(
+                    new.party_he_keys
+                        .get(&other.own_party_index)
+                        .map(|ek| &ek.n),
+                    Some(&other.own_he_keys.ek.n)
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_rotates_range_proof_setups() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("zkrp-keys")?;
+        let zkp_setups = load_range_proof_setups()?;
+        let new_setups = zkp_setups[..keys.len()].to_vec();
+
+        let refreshed = refreshed_keys(run_refresh(&keys, Some(new_setups.clone()), None)?)?;
+        check_refreshed(&keys, &refreshed);
+        for (new, setup) in refreshed.iter().zip(new_setups.iter()) {
+            let setups = match &new.range_proof_setups {
+                Some(setups) => setups,
+                None => bail!("range proof setups are missing"),
+            };
+            assert_eq!(setups.my_setup.N_tilde, setup.N_tilde);
+            for (other, other_setup) in refreshed.iter().zip(new_setups.iter()) {
+                if other.own_party_index != new.own_party_index {
+                    assert_eq!(
+                        setups
+                            .party_setups
+                            .get(&other.own_party_index)
+                            .map(|s| &s.N_tilde),
+                        Some(&other_setup.N_tilde)
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn refresh_requires_whole_committee() -> anyhow::Result<()> {
+        let keys = load_keys("keys")?;
+        let parties = vec![keys[0].own_party_index, keys[1].own_party_index];
+        match Phase1::new(&keys[0], &parties, None, None) {
+            Err(RefreshError::ProtocolSetupError(_)) => Ok(()),
+            result => bail!("setup error expected, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn refresh_detects_corrupted_zero_share() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let parties = keys.iter().map(|k| k.own_party_index).collect::<Vec<_>>();
+        let cheater = parties[parties.len() - 1];
+        let adversary = Adversary::new(cheater, &parties).in_phase(
+            "phase2",
+            Action::Corrupt(|msg: &mut super::Message| {
+                if let super::Message::R2(zero_share) = msg {
+                    scenarios::shift_by_one(&mut zero_share.share)
+                }
+            }),
+        );
+        let outcomes = run_refresh(&keys, None, Some(adversary))?;
+
+        assert_eq!(outcomes.len(), parties.len());
+        // the cheater waits for the honest parties, which have aborted
+        assert!(outcomes[parties.len() - 1].is_none());
+        for outcome in outcomes[..parties.len() - 1].iter() {
+            let error_state = match outcome {
+                Some(Err(error_state)) => error_state,
+                Some(Ok(_)) => bail!("error of an honest party expected"),
+                None => bail!("honest party has not finished"),
+            };
+            match error_state.errors.as_slice() {
+                [RefreshError::InvalidZeroShare { party }] => assert_eq!(*party, cheater),
+                errors => bail!("unexpected errors {:?}", errors),
+            }
+            assert_eq!(error_state.blame.len(), 1);
+            assert_eq!(error_state.blame[0].party, cheater);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1715 @@
+//!  Share repair and enrollment of a new party
+//!
+//! Unlike [resharing](../resharing/index.html), the protocol leaves existing shares intact. Any `t+1` current holders of the key, called helpers,
+//! jointly compute the value of the shared polynomial at the x-coordinate of the recipient:
+//! * if the recipient is a member of the committee which lost its share, its x-coordinate is kept and the share is restored
+//! * otherwise the recipient is enrolled with the next unused x-coordinate and the number of shares grows by one
+//!
+//! Each helper `i` computes its contribution $` \delta_{i} = \lambda_{i} x_{i} `$, where $` \lambda_{i} `$ is its Lagrange coefficient at the recipient's x-coordinate,
+//! splits it into random pieces $` \delta_{ij} `$, one per helper, and broadcasts commitments $` g^{\delta_{ij}} `$. Helper `j` sums the pieces it has received
+//! and sends the sum to the recipient, hence no helper learns the new share or contributions of other helpers.
+//! The recipient checks commitments of every helper against its public share $` \lambda_{i} X_{i} `$, each sum against commitments,
+//! and the new share against the public share interpolated at its x-coordinate.
+//!
+//! The recipient generates new Paillier key and shares it together with its optional range proof setup, so that helpers update their key info with it.
+//! Holders of the key which do not take part in the protocol learn neither, hence the recipient can sign only with helpers until infos of other holders are updated.
+use crate::algorithms::validation::ValidationError;
+use crate::ecdsa::blame::{Accusation, BlameKind};
+use crate::ecdsa::keygen::{KeyMetadata, MultiPartyPublicInfo};
+use crate::ecdsa::messages::repair::KeyView;
+use crate::ecdsa::{
+    is_broadcast_input_complete, to_hash_map_gen, InputDigest, Parameters, PhaseTimeout,
+    ProtocolError,
+};
+use crate::protocol::PartyIndex;
+use curv::elliptic::curves::traits::ECScalar;
+use curv::{BigInt, FE, GE};
+use std::collections::{BTreeSet, HashMap};
+use std::iter::FromIterator;
+use thiserror::Error;
+
+pub use super::messages::repair::{InMsg, Message, OutMsg};
+
+/// Enumerates errors which can be reported by share repair protocol
+#[derive(Debug, Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum RepairError {
+    #[error("repair: {0}")]
+    Timeout(#[from] PhaseTimeout),
+    #[error("unexpected message {message_type:?}, party {party}")]
+    UnknownMessageType {
+        message_type: Message,
+        party: PartyIndex,
+    },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("helpers describe the key differently")]
+    InconsistentKeyViews,
+    #[error("public shares of the key are unknown, the key has to be reshared first")]
+    PublicSharesMissing,
+    #[error("commitments to blinded pieces of party {party} do not match its public share")]
+    InvalidPieceCommitments { party: PartyIndex },
+    #[error("blinded piece of party {party} does not match its commitment")]
+    InvalidBlindedPiece { party: PartyIndex },
+    #[error("partial share of party {party} does not match commitments to its pieces")]
+    InvalidPartialShare { party: PartyIndex },
+    #[error("repaired share does not match the public share")]
+    ShareVerificationFailed,
+    #[error("invalid correct key proof {proof}, party {party}")]
+    InvalidCorrectKeyProof { proof: String, party: PartyIndex },
+    #[error("missing range proof from {party}")]
+    RangeProofSetupMissing { party: PartyIndex },
+    #[error("unexpected range proof from {party}, proof {proof:?} ")]
+    RangeProofSetupUnexpected { proof: String, party: PartyIndex },
+    #[error("range proof setup: dlog proof failed , party {party}, proof {proof} ")]
+    RangeProofSetupDlogProofFailed { proof: String, party: PartyIndex },
+    #[error("range proof setup of party {party} held by helpers is invalid: {reason}")]
+    InvalidRangeProofSetup { party: PartyIndex, reason: String },
+    #[error("protocol setup error: {0}")]
+    ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
+    #[error("{0}")]
+    GeneralError(String),
+}
+
+impl Accusation for RepairError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            RepairError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            RepairError::InvalidMessage { party, .. } => Some((*party, BlameKind::InvalidMessage)),
+            RepairError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            RepairError::InvalidPieceCommitments { party }
+            | RepairError::InvalidBlindedPiece { party }
+            | RepairError::InvalidPartialShare { party } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            RepairError::InvalidCorrectKeyProof { party, .. } => {
+                Some((*party, BlameKind::InvalidCorrectKeyProof))
+            }
+            RepairError::RangeProofSetupMissing { party } => {
+                Some((*party, BlameKind::RangeProofSetupMissing))
+            }
+            RepairError::RangeProofSetupUnexpected { party, .. } => {
+                Some((*party, BlameKind::RangeProofSetupUnexpected))
+            }
+            RepairError::RangeProofSetupDlogProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidRangeProofSetup))
+            }
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            RepairError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
+/// Contains a vector of possible repair errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<RepairError, Message>;
+
+impl ProtocolError<Message> for RepairError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        RepairError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        RepairError::InvalidMessage { party, error }
+    }
+}
+
+/// Digest of repair inputs which helpers and the recipient have to agree on
+fn input_digest(params: &Parameters, helpers: &[PartyIndex], recipient: PartyIndex) -> InputDigest {
+    InputDigest::new()
+        .with_parameters("parameters", params)
+        .with_parties("helpers", helpers)
+        .with_parties("recipient", &[recipient])
+}
+
+/// Checks the sizes of the set of helpers and returns it as a set
+fn check_helpers(
+    params: &Parameters,
+    helpers: &[PartyIndex],
+    recipient: PartyIndex,
+) -> Result<BTreeSet<PartyIndex>, RepairError> {
+    if helpers.len() <= params.threshold() {
+        return Err(RepairError::ProtocolSetupError(
+            "too few helpers".to_string(),
+        ));
+    }
+    if helpers.len() > params.share_count() {
+        return Err(RepairError::ProtocolSetupError(
+            "too many helpers".to_string(),
+        ));
+    }
+    let helpers_as_set = BTreeSet::from_iter(helpers.iter().cloned());
+    if helpers_as_set.len() != helpers.len() {
+        return Err(RepairError::ProtocolSetupError(
+            "duplicate entries in helpers' list".to_string(),
+        ));
+    }
+    if helpers_as_set.contains(&recipient) {
+        return Err(RepairError::ProtocolSetupError(
+            "recipient can't be a helper".to_string(),
+        ));
+    }
+    Ok(helpers_as_set)
+}
+
+/// x-coordinate of the recipient's share: the known one if the share is repaired, the next unused one if the recipient is enrolled
+fn target_point(points: &HashMap<PartyIndex, usize>, recipient: PartyIndex) -> usize {
+    points
+        .get(&recipient)
+        .copied()
+        .unwrap_or_else(|| points.values().max().copied().unwrap_or(0) + 1)
+}
+
+/// Lagrange coefficient of the point `own_x` for the interpolation at `target` over `points`
+#[allow(clippy::cast_possible_truncation)]
+fn lagrange_coefficient(points: &[usize], own_x: usize, target: usize) -> FE {
+    let to_fe = |x: usize| -> FE { ECScalar::from(&BigInt::from(x as u64)) };
+    let (own_x, target) = (to_fe(own_x), to_fe(target));
+    let one: FE = ECScalar::from(&BigInt::one());
+    let (num, denom) = points
+        .iter()
+        .map(|x| to_fe(*x))
+        .filter(|x| x.get_element() != own_x.get_element())
+        .fold((one, one), |(num, denom), x| {
+            (
+                num * target.sub(&x.get_element()),
+                denom * own_x.sub(&x.get_element()),
+            )
+        });
+    num * denom.invert()
+}
+
+/// Sums points on the curve, returns `None` for the empty list
+fn sum_points<I: IntoIterator<Item = GE>>(points: I) -> Option<GE> {
+    points.into_iter().fold(None, |acc, point| match acc {
+        None => Some(point),
+        Some(sum) => Some(sum + point),
+    })
+}
+
+/// Expected commitment $` g^{\delta_{i}} = \lambda_{i} X_{i} `$ to the contribution of each helper, and the public share of the recipient
+///
+/// Returns `None` if some of the helpers has no x-coordinate or public share.
+fn expected_commitments(
+    points: &HashMap<PartyIndex, usize>,
+    public_shares: &HashMap<PartyIndex, GE>,
+    helpers: &BTreeSet<PartyIndex>,
+    target: usize,
+) -> Option<(HashMap<PartyIndex, GE>, GE)> {
+    let helper_points = helpers
+        .iter()
+        .map(|p| points.get(p).copied())
+        .collect::<Option<Vec<_>>>()?;
+    let contributions = helpers
+        .iter()
+        .zip(helper_points.iter())
+        .map(|(p, x)| {
+            public_shares.get(p).map(|share| {
+                (
+                    *p,
+                    *share * &lagrange_coefficient(&helper_points, *x, target),
+                )
+            })
+        })
+        .collect::<Option<HashMap<_, _>>>()?;
+    let public_share = sum_points(contributions.values().copied())?;
+    Some((contributions, public_share))
+}
+
+/// Key parameters and metadata after the recipient has joined the committee, which changes them only if the recipient is enrolled
+fn with_recipient(
+    params: &Parameters,
+    metadata: &KeyMetadata,
+    points: &HashMap<PartyIndex, usize>,
+    recipient: PartyIndex,
+) -> (Parameters, KeyMetadata) {
+    if points.contains_key(&recipient) {
+        (*params, metadata.clone())
+    } else {
+        (
+            Parameters {
+                threshold: params.threshold,
+                share_count: params.share_count + 1,
+            },
+            metadata.with_member(recipient),
+        )
+    }
+}
+
+/// Public part of the key which a helper shares with the recipient
+fn key_view(info: &MultiPartyPublicInfo) -> KeyView {
+    let mut encryption_keys = info.party_he_keys.clone();
+    encryption_keys.insert(info.own_party_index, info.own_ek.clone());
+    KeyView {
+        key_params: info.key_params,
+        public_key: info.public_key,
+        metadata: info.metadata.clone(),
+        points: info.party_to_point_map.points.clone(),
+        public_shares: info.public_shares.clone(),
+        encryption_keys,
+        range_proof_setups: info
+            .range_proof_setups
+            .as_ref()
+            .map(|setups| setups.party_setups.clone()),
+    }
+}
+
+/// Contains the protocol part performed by a current holder of the key
+pub mod helper {
+    use super::{
+        check_helpers, expected_commitments, input_digest, is_broadcast_input_complete, key_view,
+        sum_points, target_point, to_hash_map_gen, with_recipient, ErrorState, InMsg, Message,
+        OutMsg, RepairError,
+    };
+    use crate::algorithms::nizk_rsa;
+    use crate::algorithms::random::{self, RandomnessSource};
+    use crate::algorithms::zkp::ZkpPublicSetup;
+    use crate::ecdsa::keygen::{
+        ASecretShareLoader, MultiPartyInfo, MultiPartyPublicInfo, SecretShareLoader,
+    };
+    use crate::ecdsa::messages::repair::{
+        BlindedPiece, HelperBroadcast, PartialShare, RecipientBroadcast,
+    };
+    use crate::ecdsa::InputDigest;
+    use crate::protocol::{Address, PartyIndex};
+    use crate::secret::Secret;
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{FE, GE};
+    use paillier::EncryptionKey;
+    use std::collections::{BTreeSet, HashMap};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of share repair protocol for a helper
+    ///
+    /// Public part of the helper's key updated with the x-coordinate, the public share, the Paillier key and the range proof setup of the recipient.
+    /// The secret share and the Paillier key of the helper do not change, see [`MultiPartyInfo::with_public_info`](../../keygen/struct.MultiPartyInfo.html#method.with_public_info).
+    #[derive(Clone, Debug)]
+    pub struct FinalState {
+        pub info: MultiPartyPublicInfo,
+    }
+
+    type OutMsgVec = Vec<OutMsg>;
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct RepairTraits;
+
+    impl StateMachineTraits for RepairTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// Initial phase of the protocol
+    ///
+    /// * splits own contribution to the recipient's share into blinded pieces
+    /// * broadcasts the view of the key and commitments to the pieces to other helpers and to the recipient
+    /// * collects same items from other helpers and the Paillier key of the recipient and verifies them
+    #[derive(Debug)]
+    pub struct Phase1 {
+        info: MultiPartyPublicInfo,
+        other_helpers: BTreeSet<PartyIndex>,
+        recipient: PartyIndex,
+        target: usize,
+        pieces: Secret<HashMap<PartyIndex, FE>>,
+        piece_commitments: HashMap<PartyIndex, GE>,
+        contributions: HashMap<PartyIndex, GE>,
+        recipient_public_share: GE,
+        inputs: InputDigest,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        pub fn new(
+            multi_party_info: &MultiPartyInfo,
+            helpers: &[PartyIndex],
+            recipient: PartyIndex,
+            timeout: Option<Duration>,
+        ) -> Result<Self, RepairError> {
+            Self::new_with_rng(
+                multi_party_info,
+                helpers,
+                recipient,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but blinding pieces of the contribution are sampled from `rng`
+        pub fn new_with_rng(
+            multi_party_info: &MultiPartyInfo,
+            helpers: &[PartyIndex],
+            recipient: PartyIndex,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, RepairError> {
+            Self::init(
+                &multi_party_info.public_info(),
+                multi_party_info,
+                helpers,
+                recipient,
+                timeout,
+                rng,
+            )
+        }
+
+        /// Initializes the protocol which loads the secret share through `secret_loader` and samples blinding pieces from `rng`
+        ///
+        /// The share is loaded to compute blinded pieces of the contribution and erased right afterwards.
+        pub fn new_with_loader(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: ASecretShareLoader,
+            helpers: &[PartyIndex],
+            recipient: PartyIndex,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, RepairError> {
+            Self::init(
+                multi_party_info,
+                &**secret_loader,
+                helpers,
+                recipient,
+                timeout,
+                rng,
+            )
+        }
+
+        #[trace(disable(secret_loader, rng))]
+        fn init(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: &dyn SecretShareLoader,
+            helpers: &[PartyIndex],
+            recipient: PartyIndex,
+            timeout: Option<Duration>,
+            mut rng: RandomnessSource,
+        ) -> Result<Self, RepairError> {
+            let helpers_as_set = check_helpers(&multi_party_info.key_params, helpers, recipient)?;
+            let own_party_index = multi_party_info.own_party_index;
+            if !helpers_as_set.contains(&own_party_index) {
+                return Err(RepairError::ProtocolSetupError(
+                    "own party index not in helpers' list".to_string(),
+                ));
+            }
+            match multi_party_info.metadata.committee() {
+                Some(committee) if helpers.iter().all(|p| committee.contains(p)) => {}
+                _ => {
+                    return Err(RepairError::ProtocolSetupError(format!(
+                        "helpers are not a subset of the committee of epoch {}",
+                        multi_party_info.metadata.epoch
+                    )))
+                }
+            }
+            if multi_party_info.public_shares.is_empty() {
+                return Err(RepairError::PublicSharesMissing);
+            }
+
+            let points = &multi_party_info.party_to_point_map.points;
+            let target = target_point(points, recipient);
+            let (contributions, recipient_public_share) = expected_commitments(
+                points,
+                &multi_party_info.public_shares,
+                &helpers_as_set,
+                target,
+            )
+            .ok_or(RepairError::PublicSharesMissing)?;
+
+            let pieces = {
+                let x_i = secret_loader
+                    .get_secret_share()
+                    .map(Secret::from_box)
+                    .map_err(|e| RepairError::ProtocolSetupError(e.0))?;
+                let own_x = multi_party_info.own_point;
+                let helper_points = helpers_as_set.iter().map(|p| points[p]).collect::<Vec<_>>();
+                let delta_i =
+                    Secret::new(*x_i * super::lagrange_coefficient(&helper_points, own_x, target));
+                let mut pieces = Secret::new(
+                    helpers_as_set
+                        .iter()
+                        .filter(|p| **p != own_party_index)
+                        .map(|p| (*p, random::sample_scalar(&mut rng)))
+                        .collect::<HashMap<_, _>>(),
+                );
+                let own_piece = pieces
+                    .values()
+                    .fold(*delta_i, |acc, piece| acc.sub(&piece.get_element()));
+                pieces.insert(own_party_index, own_piece);
+                pieces
+                // x_i and delta_i are erased here
+            };
+            let g: GE = ECPoint::generator();
+            let piece_commitments = pieces
+                .iter()
+                .map(|(p, piece)| (*p, g * piece))
+                .collect::<HashMap<_, _>>();
+
+            let mut other_helpers = helpers_as_set;
+            other_helpers.remove(&own_party_index);
+
+            Ok(Phase1 {
+                info: multi_party_info.clone(),
+                other_helpers,
+                recipient,
+                target,
+                pieces,
+                piece_commitments,
+                contributions,
+                recipient_public_share,
+                inputs: input_digest(&multi_party_info.key_params, helpers, recipient),
+                timeout,
+            })
+        }
+
+        fn helpers(&self) -> BTreeSet<PartyIndex> {
+            let mut helpers = self.other_helpers.clone();
+            helpers.insert(self.info.own_party_index);
+            helpers
+        }
+
+        /// parties which send messages in the first round
+        fn senders(&self) -> BTreeSet<PartyIndex> {
+            let mut senders = self.other_helpers.clone();
+            senders.insert(self.recipient);
+            senders
+        }
+
+        /// checks messages of other helpers: their inputs, the key they describe and commitments to their pieces
+        #[trace(disable(input))]
+        fn verify_helpers(&self, input: &HashMap<PartyIndex, HelperBroadcast>) -> Vec<RepairError> {
+            let helpers = self.helpers();
+            let mut errors = self
+                .inputs
+                .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+                .into_iter()
+                .map(|(party, field)| RepairError::InputMismatch { party, field })
+                .collect::<Vec<_>>();
+            for (party, msg) in input.iter() {
+                if msg.view.metadata != self.info.metadata {
+                    errors.push(RepairError::InputMismatch {
+                        party: *party,
+                        field: "metadata".to_string(),
+                    });
+                }
+                if msg.view.public_key != self.info.public_key {
+                    errors.push(RepairError::InputMismatch {
+                        party: *party,
+                        field: "public_key".to_string(),
+                    });
+                }
+                let committed = msg
+                    .piece_commitments
+                    .keys()
+                    .copied()
+                    .collect::<BTreeSet<_>>();
+                let sum = sum_points(msg.piece_commitments.values().copied());
+                if committed != helpers || sum.as_ref() != self.contributions.get(party) {
+                    errors.push(RepairError::InvalidPieceCommitments { party: *party });
+                }
+            }
+            errors
+        }
+
+        /// checks the Paillier key and the range proof setup of the recipient
+        #[trace(disable(msg))]
+        fn verify_recipient(&self, msg: &RecipientBroadcast) -> Vec<RepairError> {
+            let party = self.recipient;
+            let mut errors = self
+                .inputs
+                .mismatches(std::iter::once((&party, &msg.inputs)))
+                .into_iter()
+                .map(|(party, field)| RepairError::InputMismatch { party, field })
+                .collect::<Vec<_>>();
+            if nizk_rsa::verify(&msg.ek, &msg.correct_key_proof.0).is_err() {
+                errors.push(RepairError::InvalidCorrectKeyProof {
+                    proof: format!("{:?}", msg.correct_key_proof),
+                    party,
+                });
+            }
+            match (&self.info.range_proof_setups, &msg.range_proof_setup) {
+                (Some(_), None) => errors.push(RepairError::RangeProofSetupMissing { party }),
+                (None, Some(setup)) => errors.push(RepairError::RangeProofSetupUnexpected {
+                    proof: format!("{:?}", setup.dlog_proof),
+                    party,
+                }),
+                (Some(_), Some(setup)) => {
+                    if setup.verify().is_err() {
+                        errors.push(RepairError::RangeProofSetupDlogProofFailed {
+                            proof: format!("{:?}", setup.dlog_proof),
+                            party,
+                        })
+                    }
+                }
+                (None, None) => {}
+            }
+            errors
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<RepairTraits> for Phase1 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::info!("Phase1 (helper) starts");
+            let broadcast = HelperBroadcast {
+                inputs: self.inputs.clone(),
+                view: key_view(&self.info),
+                piece_commitments: self.piece_commitments.clone(),
+            };
+            Some(
+                self.senders()
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R1(broadcast.clone()),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            let expected = match msg.body {
+                Message::R1(_) => self.other_helpers.contains(&msg.sender),
+                Message::R2(_) => msg.sender == self.recipient,
+                _ => false,
+            };
+            expected && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.senders())
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            let (recipient_msgs, helper_msgs): (Vec<_>, Vec<_>) = current_msg_set
+                .iter()
+                .cloned()
+                .partition(|m| m.sender == self.recipient);
+            let input = match (
+                to_hash_map_gen::<HelperBroadcast, _, _>(&helper_msgs),
+                to_hash_map_gen::<RecipientBroadcast, _, _>(&recipient_msgs),
+            ) {
+                (Ok(helpers), Ok(mut recipient)) => match recipient.remove(&self.recipient) {
+                    Some(recipient) => Ok((helpers, recipient)),
+                    None => Err(vec![RepairError::GeneralError(
+                        "message of the recipient is missing".to_string(),
+                    )]),
+                },
+                (helpers, recipient) => Err(helpers
+                    .err()
+                    .into_iter()
+                    .chain(recipient.err())
+                    .flatten()
+                    .collect()),
+            };
+            let (helpers, recipient) = match input {
+                Ok(input) => input,
+                Err(errors) => {
+                    let error_state =
+                        ErrorState::new(errors).with_evidence("Helper.phase1", &current_msg_set);
+                    log::error!("Phase1 returns {:?}", error_state);
+                    return Transition::FinalState(Err(error_state));
+                }
+            };
+
+            let mut errors = self.verify_helpers(&helpers);
+            errors.extend(self.verify_recipient(&recipient));
+            if !errors.is_empty() {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Helper.phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                return Transition::FinalState(Err(error_state));
+            }
+
+            let mut piece_commitments = helpers
+                .into_iter()
+                .map(|(p, msg)| (p, msg.piece_commitments))
+                .collect::<HashMap<_, _>>();
+            piece_commitments.insert(self.info.own_party_index, self.piece_commitments.clone());
+
+            Transition::NewState(Box::new(Phase2 {
+                info: self.info.clone(),
+                other_helpers: self.other_helpers.clone(),
+                recipient: self.recipient,
+                target: self.target,
+                recipient_public_share: self.recipient_public_share,
+                recipient_ek: recipient.ek,
+                recipient_setup: recipient.range_proof_setup,
+                pieces: Secret::new((*self.pieces).clone()),
+                piece_commitments,
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Helper.phase1", &self.senders(), current_msg_set)
+        }
+    }
+
+    /// Second phase of the protocol
+    ///
+    /// * sends blinded pieces to other helpers
+    /// * collects pieces sent to us, verifies them against commitments and sums them up
+    struct Phase2 {
+        info: MultiPartyPublicInfo,
+        other_helpers: BTreeSet<PartyIndex>,
+        recipient: PartyIndex,
+        target: usize,
+        recipient_public_share: GE,
+        recipient_ek: EncryptionKey,
+        recipient_setup: Option<ZkpPublicSetup>,
+        pieces: Secret<HashMap<PartyIndex, FE>>,
+        piece_commitments: HashMap<PartyIndex, HashMap<PartyIndex, GE>>,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase2::")]
+    impl State<RepairTraits> for Phase2 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase2 (helper) starts");
+            Some(
+                self.other_helpers
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R3(BlindedPiece {
+                            piece: self.pieces[p],
+                        }),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R3(_))
+                && self.other_helpers.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.other_helpers)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            let input = match to_hash_map_gen::<BlindedPiece, _, _>(&current_msg_set) {
+                Ok(input) => Secret::new(
+                    input
+                        .into_iter()
+                        .map(|(p, msg)| (p, msg.piece))
+                        .collect::<HashMap<_, _>>(),
+                ),
+                Err(errors) => {
+                    let error_state =
+                        ErrorState::new(errors).with_evidence("Helper.phase2", &current_msg_set);
+                    log::error!("Phase2 returns {:?}", error_state);
+                    return Transition::FinalState(Err(error_state));
+                }
+            };
+
+            let own_party_index = self.info.own_party_index;
+            let g: GE = ECPoint::generator();
+            let errors = input
+                .iter()
+                .filter_map(|(party, piece)| {
+                    let commitment = self
+                        .piece_commitments
+                        .get(party)
+                        .and_then(|c| c.get(&own_party_index));
+                    if commitment == Some(&(g * piece)) {
+                        None
+                    } else {
+                        Some(RepairError::InvalidBlindedPiece { party: *party })
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !errors.is_empty() {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Helper.phase2", &current_msg_set);
+                log::error!("Phase2 returns {:?}", error_state);
+                return Transition::FinalState(Err(error_state));
+            }
+
+            let sum = Secret::new(
+                input
+                    .values()
+                    .fold(self.pieces[&own_party_index], |acc, piece| acc + *piece),
+            );
+
+            Transition::NewState(Box::new(Phase3 {
+                info: self.info.clone(),
+                other_helpers: self.other_helpers.clone(),
+                recipient: self.recipient,
+                target: self.target,
+                recipient_public_share: self.recipient_public_share,
+                recipient_ek: self.recipient_ek.clone(),
+                recipient_setup: self.recipient_setup.clone(),
+                sum,
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Helper.phase2", &self.other_helpers, current_msg_set)
+        }
+    }
+
+    /// Third phase of the protocol
+    ///
+    /// * sends the sum of pieces to the recipient
+    /// * waits until the recipient confirms that its share is verified
+    struct Phase3 {
+        info: MultiPartyPublicInfo,
+        other_helpers: BTreeSet<PartyIndex>,
+        recipient: PartyIndex,
+        target: usize,
+        recipient_public_share: GE,
+        recipient_ek: EncryptionKey,
+        recipient_setup: Option<ZkpPublicSetup>,
+        sum: Secret<FE>,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase3::")]
+    impl State<RepairTraits> for Phase3 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase3 (helper) starts");
+            Some(vec![OutMsg {
+                recipient: Address::Peer(self.recipient),
+                body: Message::R4(PartialShare { sum: *self.sum }),
+            }])
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::ShareVerified)
+                && msg.sender == self.recipient
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            !current_msg_set.is_empty()
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            let mut info = self.info.clone();
+            let (key_params, metadata) = with_recipient(
+                &info.key_params,
+                &info.metadata,
+                &info.party_to_point_map.points,
+                self.recipient,
+            );
+            info.key_params = key_params;
+            info.metadata = metadata;
+            info.party_to_point_map
+                .points
+                .insert(self.recipient, self.target);
+            info.public_shares
+                .insert(self.recipient, self.recipient_public_share);
+            info.party_he_keys
+                .insert(self.recipient, self.recipient_ek.clone());
+            if let (Some(setups), Some(setup)) =
+                (info.range_proof_setups.as_mut(), &self.recipient_setup)
+            {
+                setups.party_setups.insert(self.recipient, setup.clone());
+            }
+
+            Transition::NewState(Box::new(Phase4 {
+                other_helpers: self.other_helpers.clone(),
+                recipient: self.recipient,
+                info,
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new(
+                "Helper.phase3",
+                &std::iter::once(self.recipient).collect(),
+                current_msg_set,
+            )
+        }
+    }
+
+    /// Last phase of the protocol
+    ///
+    /// * sends `FinalAck` to the recipient and to other helpers
+    /// * collects `FinalAck` from other helpers and exits with updated info
+    struct Phase4 {
+        other_helpers: BTreeSet<PartyIndex>,
+        recipient: PartyIndex,
+        info: MultiPartyPublicInfo,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase4::")]
+    impl State<RepairTraits> for Phase4 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase4 (helper) starts");
+            Some(
+                self.other_helpers
+                    .iter()
+                    .chain(std::iter::once(&self.recipient))
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::FinalAck,
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::FinalAck)
+                && self.other_helpers.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.other_helpers)
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            log::info!("Phase4 succeeded");
+            Transition::FinalState(Ok(FinalState {
+                info: self.info.clone(),
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Helper.phase4", &self.other_helpers, current_msg_set)
+        }
+    }
+}
+
+/// Contains the protocol part performed by the party which receives the share
+pub mod recipient {
+    use super::{
+        check_helpers, expected_commitments, input_digest, is_broadcast_input_complete, sum_points,
+        target_point, to_hash_map_gen, with_recipient, ErrorState, InMsg, Message, OutMsg,
+        RepairError,
+    };
+    use crate::algorithms::nizk_rsa;
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::zkp::{ZkpPublicSetup, ZkpSetup};
+    use crate::ecdsa::all_mapped_equal;
+    use crate::ecdsa::keygen::{
+        CorrectKeyProof, KeyMetadata, MultiPartyInfo, MultiPartyPublicInfo, Party2PointMap,
+        RangeProofSetups,
+    };
+    use crate::ecdsa::messages::repair::{
+        HelperBroadcast, KeyView, PartialShare, RecipientBroadcast,
+    };
+    use crate::ecdsa::messages::SecretShare;
+    use crate::ecdsa::{InputDigest, PaillierKeys, Parameters};
+    use crate::protocol::{Address, PartyIndex};
+    use crate::secret::Secret;
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use curv::elliptic::curves::traits::ECPoint;
+    use curv::{BigInt, FE, GE};
+    use std::collections::{BTreeSet, HashMap};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of share repair protocol for the recipient
+    #[derive(Clone, Debug)]
+    pub struct FinalState {
+        pub info: MultiPartyInfo,
+    }
+
+    type OutMsgVec = Vec<OutMsg>;
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct RepairTraits;
+
+    impl StateMachineTraits for RepairTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// Values of the view of the key which all helpers have to agree on
+    ///
+    /// Only the presence of range proof setups is compared, as a helper does not hold its own public setup. The setups are merged separately.
+    #[allow(clippy::type_complexity)]
+    fn fingerprint(
+        view: &KeyView,
+    ) -> (
+        (usize, usize),
+        BigInt,
+        KeyMetadata,
+        Vec<(PartyIndex, usize)>,
+        Vec<(PartyIndex, BigInt)>,
+        Vec<(PartyIndex, BigInt)>,
+        bool,
+    ) {
+        let sorted = |mut list: Vec<(PartyIndex, _)>| {
+            list.sort_by_key(|(p, _)| *p);
+            list
+        };
+        (
+            (view.key_params.threshold(), view.key_params.share_count()),
+            view.public_key.bytes_compressed_to_big_int(),
+            view.metadata.clone(),
+            sorted(view.points.iter().map(|(p, x)| (*p, *x)).collect()),
+            sorted(
+                view.public_shares
+                    .iter()
+                    .map(|(p, share)| (*p, share.bytes_compressed_to_big_int()))
+                    .collect(),
+            ),
+            sorted(
+                view.encryption_keys
+                    .iter()
+                    .map(|(p, ek)| (*p, ek.n.clone()))
+                    .collect(),
+            ),
+            view.range_proof_setups.is_some(),
+        )
+    }
+
+    /// Initial phase of the protocol
+    ///
+    /// * broadcasts new Paillier key, ZK proof of its correctness and optional range proof setup to helpers
+    /// * collects views of the key and commitments to blinded pieces from helpers and verifies them
+    #[derive(Debug)]
+    pub struct Phase1 {
+        helpers: BTreeSet<PartyIndex>,
+        own_party_index: PartyIndex,
+        range_proof_setup: Option<ZkpSetup>,
+        paillier_keys: PaillierKeys,
+        inputs: InputDigest,
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        pub fn new(
+            params: &Parameters,
+            helpers: &[PartyIndex],
+            own_party_index: PartyIndex,
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+        ) -> Result<Self, RepairError> {
+            Self::new_with_rng(
+                params,
+                helpers,
+                own_party_index,
+                range_proof_setup,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but the new Paillier key and proofs of the range proof setup are sampled from `rng`
+        pub fn new_with_rng(
+            params: &Parameters,
+            helpers: &[PartyIndex],
+            own_party_index: PartyIndex,
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, RepairError> {
+            let helpers_as_set = check_helpers(params, helpers, own_party_index)?;
+            Ok(Phase1 {
+                helpers: helpers_as_set,
+                own_party_index,
+                range_proof_setup,
+                paillier_keys: PaillierKeys::random_from(&rng),
+                inputs: input_digest(params, helpers, own_party_index),
+                timeout,
+                rng,
+            })
+        }
+
+        /// merges range proof setups held by helpers, taking the setup of each party from helpers other than the party
+        #[trace(disable(input))]
+        fn merge_range_proof_setups(
+            &self,
+            input: &HashMap<PartyIndex, HelperBroadcast>,
+            parties: &BTreeSet<PartyIndex>,
+        ) -> Result<HashMap<PartyIndex, ZkpPublicSetup>, Vec<RepairError>> {
+            let mut errors = Vec::new();
+            let mut setups = HashMap::new();
+            for party in parties.iter().filter(|p| **p != self.own_party_index) {
+                let candidates = input
+                    .iter()
+                    .filter(|(helper, _)| *helper != party)
+                    .map(|(_, msg)| {
+                        msg.view
+                            .range_proof_setups
+                            .as_ref()
+                            .and_then(|setups| setups.get(party))
+                    })
+                    .collect::<Option<Vec<_>>>();
+                let setup = match candidates {
+                    Some(candidates)
+                        if !candidates.is_empty()
+                            && all_mapped_equal(candidates.iter(), |s| {
+                                (s.N_tilde.clone(), s.h1.clone(), s.h2.clone())
+                            }) =>
+                    {
+                        candidates[0]
+                    }
+                    _ => {
+                        errors.push(RepairError::InconsistentKeyViews);
+                        continue;
+                    }
+                };
+                if let Err(e) = setup.verify() {
+                    errors.push(RepairError::InvalidRangeProofSetup {
+                        party: *party,
+                        reason: format!("{:?}", e),
+                    });
+                }
+                setups.insert(*party, setup.clone());
+            }
+            if errors.is_empty() {
+                Ok(setups)
+            } else {
+                Err(errors)
+            }
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<RepairTraits> for Phase1 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::info!("Phase1 (recipient) starts");
+            let range_proof_setup = self
+                .range_proof_setup
+                .as_ref()
+                .map(|s| ZkpPublicSetup::from_private_zkp_setup_with_rng(s, &mut self.rng.clone()));
+            let broadcast = RecipientBroadcast {
+                inputs: self.inputs.clone(),
+                ek: self.paillier_keys.ek.clone(),
+                correct_key_proof: CorrectKeyProof(nizk_rsa::gen_proof(&self.paillier_keys.dk)),
+                range_proof_setup,
+            };
+            Some(
+                self.helpers
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::R2(broadcast.clone()),
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R1(_))
+                && self.helpers.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.helpers)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            let fail = |errors: Vec<RepairError>| {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Recipient.phase1", &current_msg_set);
+                log::error!("Phase1 returns {:?}", error_state);
+                Transition::FinalState(Err(error_state))
+            };
+            let input = match to_hash_map_gen::<HelperBroadcast, _, _>(&current_msg_set) {
+                Ok(input) => input,
+                Err(errors) => return fail(errors),
+            };
+
+            let mismatches = self
+                .inputs
+                .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+                .into_iter()
+                .map(|(party, field)| RepairError::InputMismatch { party, field })
+                .collect::<Vec<_>>();
+            if !mismatches.is_empty() {
+                return fail(mismatches);
+            }
+            if !all_mapped_equal(input.iter(), |(_, msg)| fingerprint(&msg.view)) {
+                return fail(vec![RepairError::InconsistentKeyViews]);
+            }
+
+            // panic() is unreachable as the set of helpers is not empty
+            let view = input.values().next().map(|msg| msg.view.clone()).unwrap();
+            if view.public_shares.is_empty() {
+                return fail(vec![RepairError::PublicSharesMissing]);
+            }
+            let target = target_point(&view.points, self.own_party_index);
+            let (contributions, public_share) = match expected_commitments(
+                &view.points,
+                &view.public_shares,
+                &self.helpers,
+                target,
+            ) {
+                Some(expected) => expected,
+                None => return fail(vec![RepairError::PublicSharesMissing]),
+            };
+            if let Some(stored) = view.public_shares.get(&self.own_party_index) {
+                if *stored != public_share {
+                    return fail(vec![RepairError::InconsistentKeyViews]);
+                }
+            }
+
+            let errors = input
+                .iter()
+                .filter_map(|(party, msg)| {
+                    let committed = msg
+                        .piece_commitments
+                        .keys()
+                        .copied()
+                        .collect::<BTreeSet<_>>();
+                    let sum = sum_points(msg.piece_commitments.values().copied());
+                    if committed != self.helpers || sum.as_ref() != contributions.get(party) {
+                        Some(RepairError::InvalidPieceCommitments { party: *party })
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !errors.is_empty() {
+                return fail(errors);
+            }
+
+            let range_proof_setups = match (&self.range_proof_setup, &view.range_proof_setups) {
+                (Some(my_setup), Some(_)) => {
+                    let parties = view.points.keys().copied().collect::<BTreeSet<_>>();
+                    match self.merge_range_proof_setups(&input, &parties) {
+                        Ok(party_setups) => Some(RangeProofSetups {
+                            my_setup: my_setup.clone(),
+                            party_setups,
+                        }),
+                        Err(errors) => return fail(errors),
+                    }
+                }
+                (None, None) => None,
+                (Some(_), None) => {
+                    return fail(vec![RepairError::ProtocolSetupError(
+                        "range proof setup is configured, but the key does not use range proofs"
+                            .to_string(),
+                    )])
+                }
+                (None, Some(_)) => {
+                    return fail(vec![RepairError::ProtocolSetupError(
+                        "the key uses range proofs, but range proof setup is not configured"
+                            .to_string(),
+                    )])
+                }
+            };
+
+            // commitments to pieces sent to each helper, summed over all helpers
+            let partial_share_commitments = self
+                .helpers
+                .iter()
+                .map(|receiver| {
+                    let commitments = input
+                        .values()
+                        .filter_map(|msg| msg.piece_commitments.get(receiver).copied());
+                    (*receiver, sum_points(commitments))
+                })
+                .filter_map(|(p, sum)| sum.map(|sum| (p, sum)))
+                .collect::<HashMap<_, _>>();
+
+            let (key_params, metadata) = with_recipient(
+                &view.key_params,
+                &view.metadata,
+                &view.points,
+                self.own_party_index,
+            );
+            let mut party_he_keys = view.encryption_keys;
+            party_he_keys.remove(&self.own_party_index);
+            let mut points = view.points;
+            points.insert(self.own_party_index, target);
+            let mut public_shares = view.public_shares;
+            public_shares.insert(self.own_party_index, public_share);
+
+            Transition::NewState(Box::new(Phase2 {
+                helpers: self.helpers.clone(),
+                partial_share_commitments,
+                public_share,
+                info: MultiPartyPublicInfo {
+                    key_params,
+                    own_party_index: self.own_party_index,
+                    own_point: target,
+                    public_key: view.public_key,
+                    own_ek: self.paillier_keys.ek.clone(),
+                    party_he_keys,
+                    party_to_point_map: Party2PointMap { points },
+                    public_shares,
+                    range_proof_setups,
+                    metadata,
+                },
+                paillier_keys: self.paillier_keys.clone(),
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Recipient.phase1", &self.helpers, current_msg_set)
+        }
+    }
+
+    /// Second phase of the protocol
+    ///
+    /// * collects partial shares from helpers and verifies them against commitments
+    /// * sums them up and verifies the new share against the public share
+    struct Phase2 {
+        helpers: BTreeSet<PartyIndex>,
+        partial_share_commitments: HashMap<PartyIndex, GE>,
+        public_share: GE,
+        info: MultiPartyPublicInfo,
+        paillier_keys: PaillierKeys,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase2::")]
+    impl State<RepairTraits> for Phase2 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase2 (recipient) starts");
+            None
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::R4(_))
+                && self.helpers.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.helpers)
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            let fail = |errors: Vec<RepairError>| {
+                let error_state =
+                    ErrorState::new(errors).with_evidence("Recipient.phase2", &current_msg_set);
+                log::error!("Phase2 returns {:?}", error_state);
+                Transition::FinalState(Err(error_state))
+            };
+            let input = match to_hash_map_gen::<PartialShare, _, _>(&current_msg_set) {
+                Ok(input) => Secret::new(
+                    input
+                        .into_iter()
+                        .map(|(p, msg)| (p, msg.sum))
+                        .collect::<HashMap<_, _>>(),
+                ),
+                Err(errors) => return fail(errors),
+            };
+
+            let g: GE = ECPoint::generator();
+            let errors = input
+                .iter()
+                .filter_map(|(party, sum)| {
+                    if self.partial_share_commitments.get(party) == Some(&(g * sum)) {
+                        None
+                    } else {
+                        Some(RepairError::InvalidPartialShare { party: *party })
+                    }
+                })
+                .collect::<Vec<_>>();
+            if !errors.is_empty() {
+                return fail(errors);
+            }
+
+            let share = Secret::new(input.values().fold(FE::zero(), |acc, sum| acc + *sum));
+            if g * &*share != self.public_share {
+                return fail(vec![RepairError::ShareVerificationFailed]);
+            }
+
+            Transition::NewState(Box::new(Phase3 {
+                helpers: self.helpers.clone(),
+                share: Secret::new((self.info.own_point, *share)),
+                info: self.info.clone(),
+                paillier_keys: self.paillier_keys.clone(),
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Recipient.phase2", &self.helpers, current_msg_set)
+        }
+    }
+
+    /// Last phase of the protocol
+    ///
+    /// * confirms to helpers that the share is verified
+    /// * collects `FinalAck` from helpers and exits
+    struct Phase3 {
+        helpers: BTreeSet<PartyIndex>,
+        share: Secret<SecretShare>,
+        info: MultiPartyPublicInfo,
+        paillier_keys: PaillierKeys,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase3::")]
+    impl State<RepairTraits> for Phase3 {
+        fn start(&mut self) -> Option<OutMsgVec> {
+            log::debug!("Phase3 (recipient) starts");
+            Some(
+                self.helpers
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::ShareVerified,
+                    })
+                    .collect(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::FinalAck)
+                && self.helpers.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            is_broadcast_input_complete(current_msg_set, &self.helpers)
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<RepairTraits> {
+            log::info!("Phase3 succeeded");
+            Transition::FinalState(Ok(FinalState {
+                info: self
+                    .info
+                    .clone()
+                    .into_multi_party_info(*self.share, self.paillier_keys.clone()),
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Recipient.phase3", &self.helpers, current_msg_set)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{helper, recipient, RepairError};
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::zkp::ZkpSetup;
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::ecdsa::PhaseTimeout;
+    use crate::protocol::PartyIndex;
+    use crate::state_machine::BoxedState;
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::{key_of, load_keys, load_range_proof_setups, Run};
+    use crate::testkit::{Action, Adversary};
+    use anyhow::bail;
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    enum Outcome {
+        Helper(helper::MachineResult),
+        Recipient(recipient::MachineResult),
+    }
+
+    /// Runs repair, returns outcomes of helpers followed by the outcome of the recipient, `None` for parties which have not finished
+    ///
+    /// The last helper is malicious, if `adversary` is given.
+    fn run_repair(
+        keys: &[MultiPartyInfo],
+        helpers: &[PartyIndex],
+        recipient: PartyIndex,
+        range_proof_setup: Option<ZkpSetup>,
+        adversary: Option<Adversary<super::Message>>,
+    ) -> anyhow::Result<Vec<Option<Outcome>>> {
+        let mut simulation =
+            Run::new(0x7265706169).with_adversary(helpers[helpers.len() - 1], adversary);
+        for (i, party) in helpers.iter().enumerate() {
+            let start_state: BoxedState<helper::RepairTraits> =
+                Box::new(helper::Phase1::new_with_rng(
+                    key_of(keys, *party),
+                    helpers,
+                    recipient,
+                    None,
+                    RandomnessSource::seeded(i as u64),
+                )?);
+            simulation.add_node(
+                format!("helper {}", party),
+                *party,
+                start_state,
+                Outcome::Helper,
+            );
+        }
+        let start_state: BoxedState<recipient::RepairTraits> =
+            Box::new(recipient::Phase1::new_with_rng(
+                &keys[0].key_params,
+                helpers,
+                recipient,
+                range_proof_setup,
+                // the recipient gives up on helpers which have aborted
+                Some(Duration::from_secs(10)),
+                RandomnessSource::seeded(7),
+            )?);
+        simulation.add_node("recipient", recipient, start_state, Outcome::Recipient);
+
+        Ok(simulation
+            .run()
+            .outcomes
+            .into_iter()
+            .map(|node| node.outcome)
+            .collect())
+    }
+
+    fn split_outcomes(
+        outcomes: Vec<Option<Outcome>>,
+    ) -> anyhow::Result<(Vec<helper::FinalState>, recipient::FinalState)> {
+        let mut helper_states = Vec::new();
+        let mut recipient_state = None;
+        for outcome in outcomes {
+            match outcome {
+                Some(Outcome::Helper(Ok(state))) => helper_states.push(state),
+                Some(Outcome::Recipient(Ok(state))) => recipient_state = Some(state),
+                outcome => bail!("success expected, got {:?}", outcome),
+            }
+        }
+        match recipient_state {
+            Some(state) => Ok((helper_states, state)),
+            None => bail!("recipient has not finished"),
+        }
+    }
+
+    #[test]
+    fn repair_restores_lost_share() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let helpers = vec![PartyIndex::from(0), PartyIndex::from(1)];
+        let outcomes = run_repair(&keys, &helpers, PartyIndex::from(2), None, None)?;
+        let (helper_states, recipient_state) = split_outcomes(outcomes)?;
+        assert_eq!(helper_states.len(), helpers.len());
+
+        let repaired = &recipient_state.info;
+        assert_eq!(repaired.secret_share, keys[2].secret_share);
+        assert_eq!(repaired.public_shares, keys[2].public_shares);
+        assert_eq!(repaired.metadata, keys[2].metadata);
+        assert!(repaired.validate().is_empty());
+
+        for state in helper_states.iter() {
+            assert_eq!(state.info.key_params.share_count(), 3);
+            assert_eq!(
+                state
+                    .info
+                    .party_he_keys
+                    .get(&PartyIndex::from(2))
+                    .map(|ek| &ek.n),
+                Some(&repaired.own_he_keys.ek.n)
+            );
+            let updated =
+                key_of(&keys, state.info.own_party_index).with_public_info(state.info.clone());
+            assert!(updated.validate().is_empty());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn enrollment_with_range_proofs() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("zkrp-keys")?;
+        let zkp_setups = load_range_proof_setups()?;
+        let helpers = vec![PartyIndex::from(1), PartyIndex::from(2)];
+        let newcomer = PartyIndex::from(3);
+        let outcomes = run_repair(&keys, &helpers, newcomer, Some(zkp_setups[3].clone()), None)?;
+        let (helper_states, recipient_state) = split_outcomes(outcomes)?;
+
+        let enrolled = &recipient_state.info;
+        assert_eq!(enrolled.secret_share.0, 4);
+        assert_eq!(enrolled.key_params.share_count(), 4);
+        assert_eq!(enrolled.metadata.epoch, keys[0].metadata.epoch);
+        assert!(enrolled.metadata.committee().unwrap().contains(&newcomer));
+        assert!(enrolled.validate().is_empty());
+
+        // the new share and the share of a party which did not help reconstruct the key
+        let shares = [keys[0].secret_share, enrolled.secret_share];
+        let (points, values): (Vec<FE>, Vec<FE>) = shares
+            .iter()
+            .map(|(x, y)| {
+                let x: FE = ECScalar::from(&BigInt::from(*x as u64));
+                (x, *y)
+            })
+            .unzip();
+        let secret = VerifiableSS::lagrange_interpolation_at_zero(&points, &values);
+        let g: GE = ECPoint::generator();
+        assert_eq!(g * secret, enrolled.public_key);
+
+        for state in helper_states.iter() {
+            assert_eq!(state.info.public_shares, enrolled.public_shares);
+            assert_eq!(state.info.metadata, enrolled.metadata);
+            assert!(state
+                .info
+                .range_proof_setups
+                .as_ref()
+                .map_or(false, |setups| setups.party_setups.contains_key(&newcomer)));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn repair_detects_corrupted_blinded_piece() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let helpers = vec![PartyIndex::from(0), PartyIndex::from(1)];
+        let adversary = Adversary::new(helpers[1], &helpers).in_phase(
+            "Helper.phase2",
+            Action::Corrupt(|msg: &mut super::Message| {
+                if let super::Message::R3(blinded) = msg {
+                    scenarios::shift_by_one(&mut blinded.piece)
+                }
+            }),
+        );
+        let outcomes = run_repair(&keys, &helpers, PartyIndex::from(2), None, Some(adversary))?;
+
+        let (honest_helper, malicious_helper, recipient) = match outcomes.as_slice() {
+            [honest_helper, malicious_helper, recipient] => {
+                (honest_helper, malicious_helper, recipient)
+            }
+            outcomes => bail!("outcomes of 3 parties expected, got {:?}", outcomes),
+        };
+        let error_state = match honest_helper {
+            Some(Outcome::Helper(Err(error_state))) => error_state,
+            outcome => bail!("error of the honest helper expected, got {:?}", outcome),
+        };
+        match error_state.errors.as_slice() {
+            [RepairError::InvalidBlindedPiece { party }] => assert_eq!(*party, helpers[1]),
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        assert_eq!(error_state.blame.len(), 1);
+        assert_eq!(error_state.blame[0].party, helpers[1]);
+
+        // the malicious helper waits for the honest one, which has aborted
+        assert!(malicious_helper.is_none());
+        // the recipient misses the partial share of the honest helper
+        let error_state = match recipient {
+            Some(Outcome::Recipient(Err(error_state))) => error_state,
+            outcome => bail!("error of the recipient expected, got {:?}", outcome),
+        };
+        match error_state.errors.as_slice() {
+            [RepairError::Timeout(PhaseTimeout { missing, .. })] => {
+                assert_eq!(missing.iter().collect::<Vec<_>>(), vec![&helpers[0]])
+            }
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        Ok(())
+    }
+}
@@ -4,6 +4,32 @@
 //! New shares are generated by the set of current shareholders, after which existing shares are destroyed. Eventually old shareholders do not have access to the key anymore.
 //!
 //! The special case where the old party set is equal to the new party set is called "key refresh". In this case every party just updates own shard with new value.
+//!
+//! A party which is a member of both committees may run roles of [old member](old_member/index.html) and [new member](new_member/index.html) as two separate machines,
+//! which route messages between each other by [`old_member::is_message_to_committee`] and [`new_member::is_message_to_committee`], or it runs the single machine of [dual member](dual_member/index.html).
+//!
+//! [`old_member::is_message_to_committee`]: old_member/fn.is_message_to_committee.html
+//! [`new_member::is_message_to_committee`]: new_member/fn.is_message_to_committee.html
+//!
+//! # Commit
+//!
+//! Shares of both generations are switched by two-phase commit, so that old shares are not destroyed before every new member holds a valid share:
+//! * prepare: each new member verifies its share and sends `Prepared` to old members
+//! * commit: each old member which has received `Prepared` from all new members sends `Commit` to them. Each new member which has received `Commit`
+//! from t+1 old members, t being the threshold of the old key, commits to its share and sends `FinalAck` to all parties. At least one of them is honest,
+//! which proves that all new members are prepared, hence a new member which misses `Commit` of other old members commits along with the others.
+//! It exits with the new share even if acknowledgements of other new members are lost.
+//! * each old member which has received `FinalAck` from all new members deletes its old share, by means of [`SecretShareDeleter`] if one is set, and exits successfully
+//!
+//! A failure before the commit step is rolled back: new members drop their shares and old members keep theirs.
+//! A new member which has received `Commit` from at most t old members drops its share as well, then old members miss its `FinalAck` and keep their shares.
+//! If an old member fails in the commit step, some new members may have committed already, hence the old member keeps its share
+//! and the application has to find out the outcome from new members before deleting it.
+//!
+//! [`SecretShareDeleter`]: ../keygen/trait.SecretShareDeleter.html
+use crate::algorithms::validation::ValidationError;
+use crate::ecdsa::blame::{Accusation, BlameKind};
+use crate::ecdsa::{is_broadcast_input_complete, to_hash_map_gen, PhaseTimeout, ProtocolError};
 use crate::protocol::PartyIndex;
 
 use thiserror::Error;
@@ -11,13 +37,13 @@ use thiserror::Error;
 pub use super::messages::resharing::{InMsg, Message, OutMsg, Phase1Broadcast};
 
 use crate::ecdsa::messages::SecretShare;
+use crate::ecdsa::InputDigest;
+use crate::Parameters;
 use curv::{BigInt, FE};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeSet, HashMap};
 use std::fmt::Debug;
 
-use trace::trace;
-
 /// Contains  zero knowledge proof of Paillier key's correctness
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CorrectKeyProof(Vec<BigInt>);
@@ -26,8 +52,8 @@ pub struct CorrectKeyProof(Vec<BigInt>);
 #[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
 pub enum ResharingError {
-    #[error("resharing: timeout in {phase}")]
-    Timeout { phase: String },
+    #[error("resharing: {0}")]
+    Timeout(#[from] PhaseTimeout),
     #[error("resharing: invalid empty message set {desc}")]
     EmptyMessageSet { desc: String },
     #[error("invalid decommitment {decomm}, commitment {comm}, party {party}")]
@@ -38,6 +64,8 @@ pub enum ResharingError {
     },
     #[error("public keys of old committee members are not same")]
     InconsistentPublicKeys,
+    #[error("key metadata of old committee members are not same, shares of different keys or epochs are reshared")]
+    InconsistentKeyMetadata,
     #[error("invalid secret sharing {vss}, party {party}")]
     InvalidVSS { vss: String, party: PartyIndex },
     #[error("received point has wrong X coordinate: {x_coord}")]
@@ -57,72 +85,96 @@ pub enum ResharingError {
     RangeProofSetupUnexpected { proof: String, party: PartyIndex },
     #[error("range proof setup: dlog proof failed , party {party}, proof {proof} ")]
     RangeProofSetupDlogProofFailed { proof: String, party: PartyIndex },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("own secret share does not match own public share computed from VSS commitments")]
+    OwnPublicShareMismatch,
+    #[error("old secret share could not be deleted: {0}")]
+    ShareDeletionFailed(String),
     #[error("protocol setup error: {0}")]
     ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
     #[error("{0}")]
     GeneralError(String),
 }
 
-/// Contains a vector of possible resharing errors
-#[derive(Debug)]
-pub struct ErrorState {
-    pub errors: Vec<ResharingError>,
-}
-
-/// the state the machine returns in case of error(s)
-impl ErrorState {
-    pub fn new(errors: Vec<ResharingError>) -> Self {
-        ErrorState { errors }
+impl Accusation for ResharingError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            ResharingError::InvalidComm { party, .. } => {
+                Some((*party, BlameKind::InvalidCommitment))
+            }
+            ResharingError::InvalidVSS { party, .. } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            ResharingError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            ResharingError::InvalidMessage { party, .. } => {
+                Some((*party, BlameKind::InvalidMessage))
+            }
+            ResharingError::InvalidDlogProof { party, .. } => {
+                Some((*party, BlameKind::InvalidDlogProof))
+            }
+            ResharingError::InvalidCorrectKeyProof { party, .. } => {
+                Some((*party, BlameKind::InvalidCorrectKeyProof))
+            }
+            ResharingError::RangeProofSetupMissing { party } => {
+                Some((*party, BlameKind::RangeProofSetupMissing))
+            }
+            ResharingError::RangeProofSetupUnexpected { party, .. } => {
+                Some((*party, BlameKind::RangeProofSetupUnexpected))
+            }
+            ResharingError::RangeProofSetupDlogProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidRangeProofSetup))
+            }
+            ResharingError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            _ => None,
+        }
     }
-    pub fn append(self, rhs: ErrorState) -> Self {
-        let mut errors = self.errors;
-        errors.extend(rhs.errors.into_iter());
-        Self { errors }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            ResharingError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
     }
 }
 
-/// Checks whether all expected messages have been received so far from other parties  
-fn is_broadcast_input_complete(
-    current_msg_set: &[InMsg],
-    other_parties: &BTreeSet<PartyIndex>,
-) -> bool {
-    let senders = current_msg_set.iter().map(|m| m.sender).collect::<Vec<_>>();
-    other_parties.iter().all(|p| senders.contains(p))
-}
+/// Contains a vector of possible resharing errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<ResharingError, Message>;
 
-/// Extracts payloads form enum variants of input message into the hash map
-#[trace(disable(current_msg_set), res = "{:?}")]
-fn to_hash_map_gen<K, V>(current_msg_set: Vec<InMsg>) -> Result<HashMap<K, V>, Vec<ResharingError>>
-where
-    K: std::cmp::Eq + std::hash::Hash + std::convert::From<PartyIndex> + std::fmt::Debug,
-    V: std::fmt::Debug,
-    Option<V>: std::convert::From<Message>,
-{
-    let (converted_messages, errors) =
-        current_msg_set
-            .iter()
-            .fold((vec![], vec![]), |(mut values, mut errors), m| {
-                let body: Option<V> = m.body.clone().into();
-                match body {
-                    Some(b) => values.push((m.sender, b)),
-                    None => errors.push(ResharingError::UnknownMessageType {
-                        message_type: m.body.clone(),
-                        party: m.sender,
-                    }),
-                };
-                (values, errors)
-            });
+impl ProtocolError<Message> for ResharingError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        ResharingError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
 
-    if errors.is_empty() {
-        Ok(converted_messages
-            .into_iter()
-            .map(|(party, body)| (party.into(), body))
-            .collect::<HashMap<K, V>>())
-    } else {
-        Err(errors)
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        ResharingError::InvalidMessage { party, error }
     }
 }
 
+/// Digest of resharing inputs which members of both committees have to agree on
+fn input_digest(
+    old_params: &Parameters,
+    new_params: &Parameters,
+    old_committee: &[PartyIndex],
+    new_committee: &[PartyIndex],
+) -> InputDigest {
+    InputDigest::new()
+        .with_parameters("old_parameters", old_params)
+        .with_parameters("new_parameters", new_params)
+        .with_parties("old_committee", old_committee)
+        .with_parties("new_committee", new_committee)
+}
+
 fn map_parties_to_shares(
     parties: &[PartyIndex],
     outgoing_shares: &[FE],
@@ -145,12 +197,19 @@ fn map_parties_to_shares(
 pub mod old_member {
 
     use super::ErrorState;
-    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::vss;
+    use crate::ecdsa::keygen::{
+        ASecretShareDeleter, ASecretShareLoader, KeyMetadata, MultiPartyInfo, MultiPartyPublicInfo,
+        SecretShareLoader,
+    };
     use crate::ecdsa::messages::resharing::{InMsg, Message, OutMsg, Phase1Broadcast, VSS};
 
-    use crate::ecdsa::resharing::{map_parties_to_shares, ResharingError};
+    use crate::ecdsa::resharing::{input_digest, map_parties_to_shares, ResharingError};
+    use crate::ecdsa::InputDigest;
     use crate::protocol::{Address, PartyIndex};
-    use crate::state_machine::{State, StateMachineTraits, Transition};
+    use crate::secret::{Erase, Secret};
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
     use crate::Parameters;
     use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
     use curv::cryptographic_primitives::hashing::traits::Hash;
@@ -162,7 +221,6 @@ pub mod old_member {
     use std::iter::FromIterator;
     use std::time::Duration;
     use trace::trace;
-    use zeroize::Zeroize;
 
     #[derive(Clone, Debug, super::Serialize, super::Deserialize)]
     pub struct FinalState;
@@ -191,9 +249,12 @@ pub mod old_member {
     pub struct Phase1 {
         new_committee: BTreeSet<PartyIndex>,
         vss_scheme: VerifiableSS,
-        outgoing_shares: Vec<FE>,
+        outgoing_shares: Secret<Vec<FE>>,
         vss_comm: BigInt,
         y: GE,
+        inputs: InputDigest,
+        metadata: KeyMetadata,
+        share_deleter: Option<ASecretShareDeleter>,
         timeout: Option<Duration>,
     }
 
@@ -205,6 +266,69 @@ pub mod old_member {
             old_committee: &[PartyIndex],
             new_committee: &[PartyIndex],
             timeout: Option<Duration>,
+        ) -> Result<Self, ResharingError> {
+            Self::new_with_rng(
+                multi_party_info,
+                new_params,
+                old_committee,
+                new_committee,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but the coefficients of Feldman's VSS are sampled from `rng`
+        pub fn new_with_rng(
+            multi_party_info: &MultiPartyInfo,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ResharingError> {
+            Self::init(
+                &multi_party_info.public_info(),
+                multi_party_info,
+                new_params,
+                old_committee,
+                new_committee,
+                timeout,
+                rng,
+            )
+        }
+
+        /// Initializes the protocol which loads the secret share through `secret_loader` and samples the coefficients of Feldman's VSS from `rng`
+        ///
+        /// The share is loaded to compute new shares for the new committee and erased right afterwards.
+        pub fn new_with_loader(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: ASecretShareLoader,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ResharingError> {
+            Self::init(
+                multi_party_info,
+                &**secret_loader,
+                new_params,
+                old_committee,
+                new_committee,
+                timeout,
+                rng,
+            )
+        }
+
+        #[trace(disable(secret_loader, rng))]
+        fn init(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: &dyn SecretShareLoader,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            timeout: Option<Duration>,
+            mut rng: RandomnessSource,
         ) -> Result<Self, ResharingError> {
             //check if old committee is sized correctly
             if old_committee.len() <= multi_party_info.key_params.threshold() {
@@ -238,26 +362,63 @@ pub mod old_member {
                 ));
             }
 
-            let own_x: FE = ECScalar::from(&BigInt::from(multi_party_info.own_point() as u64));
+            // check if old committee holds shares of the current epoch
+            let metadata = &multi_party_info.metadata;
+            match metadata.committee() {
+                Some(committee) if old_committee.iter().all(|p| committee.contains(p)) => {}
+                _ => {
+                    return Err(ResharingError::ProtocolSetupError(format!(
+                        "old committee is not a subset of the committee of epoch {}",
+                        metadata.epoch
+                    )))
+                }
+            }
+
+            let own_x: FE = ECScalar::from(&BigInt::from(multi_party_info.own_point as u64));
             let multiplier = multi_party_info
                 .party_to_point_map
                 .calculate_lagrange_multiplier(old_committee, own_x);
-            let w_i = multi_party_info.own_share() * multiplier;
-
-            let (vss_scheme, outgoing_shares) =
-                VerifiableSS::share(new_params.threshold, new_params.share_count, &w_i);
+            let (vss_scheme, outgoing_shares) = {
+                let x_i = secret_loader
+                    .get_secret_share()
+                    .map(Secret::from_box)
+                    .map_err(|e| ResharingError::ProtocolSetupError(e.0))?;
+                let w_i = Secret::new(*x_i * multiplier);
+                let (vss_scheme, shares) =
+                    vss::share(new_params.threshold, new_params.share_count, &w_i, &mut rng);
+                (vss_scheme, Secret::new(shares))
+                // x_i and w_i are erased here
+            };
             let vss_refs = vss_scheme.commitments.iter().collect::<Vec<_>>();
             let vss_comm = HSha256::create_hash_from_ge(&vss_refs).to_big_int();
 
+            let inputs = input_digest(
+                &multi_party_info.key_params,
+                new_params,
+                old_committee,
+                new_committee,
+            );
+
             Ok(Phase1 {
                 new_committee: new_parties_as_set,
                 vss_scheme,
                 outgoing_shares,
                 vss_comm,
                 y: multi_party_info.public_key,
+                inputs,
+                metadata: metadata.clone(),
+                share_deleter: None,
                 timeout,
             })
         }
+
+        /// Sets the deleter of the old secret share, which is called when all members of new committee have committed to their new shares
+        ///
+        /// Without the deleter, the application deletes the old share itself when the protocol succeeds.
+        pub fn with_share_deleter(mut self, share_deleter: ASecretShareDeleter) -> Self {
+            self.share_deleter = Some(share_deleter);
+            self
+        }
     }
 
     #[trace(pretty, prefix = "Phase1::")]
@@ -273,6 +434,8 @@ pub mod old_member {
                     body: Message::R1(Phase1Broadcast {
                         y: self.y,
                         vss_commitment: self.vss_comm.clone(),
+                        inputs: self.inputs.clone(),
+                        metadata: self.metadata.clone(),
                     }),
                 })
                 .collect::<Vec<_>>();
@@ -294,7 +457,8 @@ pub mod old_member {
             Transition::NewState(Box::new(Phase2 {
                 new_committee: self.new_committee.clone(),
                 vss_scheme: self.vss_scheme.clone(),
-                outgoing_shares: RefCell::new(self.outgoing_shares.clone()),
+                outgoing_shares: RefCell::new(Secret::new((*self.outgoing_shares).clone())),
+                share_deleter: self.share_deleter.clone(),
                 timeout: self.timeout,
             }))
         }
@@ -303,31 +467,36 @@ pub mod old_member {
             self.timeout
         }
 
-        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-            Err(ErrorState::new(vec![super::ResharingError::Timeout {
-                phase: "Old.phase1".to_owned(),
-            }]))
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Old.phase1", &self.new_committee, current_msg_set)
         }
     }
 
-    /// Second phase of the protocol
+    /// Second phase of the protocol, the prepare step of the commit
     ///
     /// * Shares new Shamir's secrets and their respective Feldman's VSS with members of new committee
-    /// * Collect `FinalAck` messages and exits  
+    /// * Collects `Prepared` messages, by which new members confirm that their shares are verified
+    ///
+    /// If the phase fails, the old share is kept.
     struct Phase2 {
         new_committee: BTreeSet<PartyIndex>,
         vss_scheme: VerifiableSS,
-        outgoing_shares: RefCell<Vec<FE>>,
+        outgoing_shares: RefCell<Secret<Vec<FE>>>,
+        share_deleter: Option<ASecretShareDeleter>,
         timeout: Option<Duration>,
     }
 
     impl Phase2 {
-        fn zeroize_secret_shares(&self) {
-            // Simultaneously remove and zeroize elements.
-            self.outgoing_shares
-                .borrow_mut()
-                .drain(..)
-                .for_each(|mut share| share.zeroize());
+        fn erase_secret_shares(&self) {
+            self.outgoing_shares.borrow_mut().erase();
         }
     }
 
@@ -335,17 +504,17 @@ pub mod old_member {
     impl State<KeyResharingTraits> for Phase2 {
         fn start(&mut self) -> Option<Vec<OutMsg>> {
             log::debug!("Phase2 (old member) starts");
-            let output = map_parties_to_shares(
+            let output = Secret::new(map_parties_to_shares(
                 &self.new_committee.iter().cloned().collect::<Vec<_>>(),
                 &self.outgoing_shares.borrow(),
-            );
+            ));
             Some(
                 output
-                    .into_iter()
-                    .map(|(p, (x, share))| OutMsg {
-                        recipient: Address::Peer(p),
+                    .iter()
+                    .map(|(p, share)| OutMsg {
+                        recipient: Address::Peer(*p),
                         body: Message::R3(VSS {
-                            share: (x, share),
+                            share: *share,
                             vss: self.vss_scheme.clone(),
                         }),
                     })
@@ -355,7 +524,73 @@ pub mod old_member {
 
         #[trace(disable(current_msg_set))]
         fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
-            matches!(msg.body, Message::FinalAck if self.new_committee.contains(&msg.sender) && !msg.is_duplicate(current_msg_set))
+            matches!(msg.body, Message::Prepared if self.new_committee.contains(&msg.sender) && !msg.is_duplicate(current_msg_set))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            super::is_broadcast_input_complete(current_msg_set, &self.new_committee)
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
+            self.erase_secret_shares();
+            Transition::NewState(Box::new(Phase3 {
+                new_committee: self.new_committee.clone(),
+                share_deleter: self.share_deleter.clone(),
+                timeout: self.timeout,
+            }))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Old.phase2", &self.new_committee, current_msg_set)
+        }
+    }
+
+    /// Last phase of the protocol, the commit step
+    ///
+    /// * sends `Commit` to members of new committee
+    /// * collects `FinalAck` messages, by which new members confirm that they have committed to their shares
+    /// * deletes the old share by means of the deleter, if any, and exits
+    ///
+    /// If the phase fails, the old share is kept, as some of new members may have not committed.
+    struct Phase3 {
+        new_committee: BTreeSet<PartyIndex>,
+        share_deleter: Option<ASecretShareDeleter>,
+        timeout: Option<Duration>,
+    }
+
+    #[trace(pretty, prefix = "Phase3::")]
+    impl State<KeyResharingTraits> for Phase3 {
+        fn start(&mut self) -> Option<Vec<OutMsg>> {
+            log::debug!("Phase3 (old member) starts");
+            Some(
+                self.new_committee
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::Commit,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::FinalAck)
+                && self.new_committee.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
         }
 
         #[trace(disable(current_msg_set))]
@@ -364,8 +599,15 @@ pub mod old_member {
         }
 
         fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
-            log::info!("Phase2 succeeded");
-            self.zeroize_secret_shares();
+            if let Some(share_deleter) = &self.share_deleter {
+                if let Err(e) = share_deleter.delete_secret_share() {
+                    let error_state =
+                        ErrorState::new(vec![ResharingError::ShareDeletionFailed(e.0)]);
+                    log::error!("Phase3 returns {:?}", error_state);
+                    return Transition::FinalState(Err(error_state));
+                }
+            }
+            log::info!("Phase3 succeeded");
             Transition::FinalState(Ok(FinalState {}))
         }
 
@@ -373,29 +615,40 @@ pub mod old_member {
             self.timeout
         }
 
-        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-            Err(ErrorState::new(vec![super::ResharingError::Timeout {
-                phase: "Old.phase2".to_owned(),
-            }]))
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("Old.phase3", &self.new_committee, current_msg_set)
         }
     }
 
     /// Helper function which returns true if a given message of the resharing protocol should be forwarded to a member of old committee
     pub fn is_message_to_committee(msg: &Message) -> bool {
-        matches!(msg, Message::Ack | Message::FinalAck)
+        matches!(msg, Message::Ack | Message::Prepared | Message::FinalAck)
     }
 }
 /// Contains the protocol part performed by a member of new committee
 pub mod new_member {
     use crate::algorithms::nizk_rsa;
-    use crate::ecdsa::keygen::{CorrectKeyProof, MultiPartyInfo, Party2PointMap, RangeProofSetups};
+    use crate::algorithms::random::RandomnessSource;
+    use crate::ecdsa::keygen::{
+        compute_public_shares, CorrectKeyProof, KeyMetadata, MultiPartyInfo, MultiPartyPublicInfo,
+        Party2PointMap, RangeProofSetups,
+    };
     use crate::ecdsa::messages::resharing::{Phase1Broadcast, Phase2Broadcast, VSS};
+    use crate::ecdsa::resharing::input_digest;
     use crate::ecdsa::resharing::{
         map_parties_to_shares, to_hash_map_gen, ErrorState, InMsg, Message, OutMsg, ResharingError,
     };
-    use crate::ecdsa::{all_mapped_equal, PaillierKeys, PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA};
+    use crate::ecdsa::{all_mapped_equal, InputDigest, PaillierKeys};
     use crate::protocol::{Address, PartyIndex};
-    use crate::state_machine::{State, StateMachineTraits, Transition};
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
     use crate::Parameters;
     use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
     use curv::cryptographic_primitives::hashing::traits::Hash;
@@ -403,10 +656,11 @@ pub mod new_member {
     use curv::elliptic::curves::traits::{ECPoint, ECScalar};
     use curv::{BigInt, FE, GE};
 
-    use paillier::{EncryptionKey, KeyGeneration, Paillier};
+    use paillier::EncryptionKey;
 
     use crate::algorithms::zkp::{ZkpPublicSetup, ZkpSetup};
     use crate::ecdsa::messages::SecretShare;
+    use crate::secret::Secret;
     use std::collections::{BTreeSet, HashMap};
     use std::iter::FromIterator;
     use std::time::Duration;
@@ -418,6 +672,9 @@ pub mod new_member {
         pub info: MultiPartyInfo,
     }
 
+    /// Name of the phase in which the new member has committed to its share
+    pub(super) const COMMIT_PHASE: &str = "New.phase5";
+
     #[doc(hidden)]
     type OutMsgVec = Vec<OutMsg>;
 
@@ -438,16 +695,24 @@ pub mod new_member {
     ///
     /// * Sends nothing out
     /// * collects commitments to public key and to Feldman's VSS
-    /// * verifies that all public keys are same
-    #[derive(Clone, Debug)]
+    /// * verifies that all public keys and key metadata are same
+    #[derive(Debug)]
     pub struct Phase1 {
+        setup: Setup,
+        range_proof_setup: Option<Secret<ZkpSetup>>,
+    }
+
+    /// Public parameters of the protocol, which are passed from one phase to the next
+    #[derive(Clone, Debug)]
+    struct Setup {
         old_params: Parameters,
         new_params: Parameters,
         old_committee: BTreeSet<PartyIndex>,
         others_from_new_committee: BTreeSet<PartyIndex>,
         own_party_index: PartyIndex,
-        range_proof_setup: Option<ZkpSetup>,
+        inputs: InputDigest,
         timeout: Option<Duration>,
+        rng: RandomnessSource,
     }
 
     #[trace(pretty, prefix = "Phase1::")]
@@ -460,6 +725,30 @@ pub mod new_member {
             own_party_index: PartyIndex,
             range_proof_setup: Option<ZkpSetup>,
             timeout: Option<Duration>,
+        ) -> Result<Self, ResharingError> {
+            Self::new_with_rng(
+                old_params,
+                new_params,
+                old_committee,
+                new_committee,
+                own_party_index,
+                range_proof_setup,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but the new Paillier key and proofs of the range proof setup are sampled from `rng`
+        #[allow(clippy::too_many_arguments)]
+        pub fn new_with_rng(
+            old_params: &Parameters,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            own_party_index: PartyIndex,
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
         ) -> Result<Self, ResharingError> {
             // check if old committee is sized correctly
             if old_committee.len() <= old_params.threshold() {
@@ -511,17 +800,37 @@ pub mod new_member {
             others_from_new_committee.remove(&own_party_index);
 
             Ok(Phase1 {
-                old_params: *old_params,
-                new_params: *new_params,
-                old_committee: BTreeSet::from_iter(old_committee.iter().cloned()),
-                others_from_new_committee,
-                own_party_index,
-                range_proof_setup,
-                timeout,
+                setup: Setup {
+                    old_params: *old_params,
+                    new_params: *new_params,
+                    old_committee: BTreeSet::from_iter(old_committee.iter().cloned()),
+                    others_from_new_committee,
+                    own_party_index,
+                    inputs: input_digest(old_params, new_params, old_committee, new_committee),
+                    timeout,
+                    rng,
+                },
+                range_proof_setup: range_proof_setup.map(Secret::new),
             })
         }
     }
 
+    #[trace(pretty, prefix = "Setup::")]
+    impl Setup {
+        /// compares digests of protocol inputs received from other parties with ours
+        #[trace(disable(received))]
+        fn input_mismatches<'a, I>(&self, received: I) -> Vec<ResharingError>
+        where
+            I: IntoIterator<Item = (&'a PartyIndex, &'a InputDigest)>,
+        {
+            self.inputs
+                .mismatches(received)
+                .into_iter()
+                .map(|(party, field)| ResharingError::InputMismatch { party, field })
+                .collect()
+        }
+    }
+
     #[trace(pretty, prefix = "Phase1::")]
     impl State<KeyResharingTraits> for Phase1 {
         fn start(&mut self) -> Option<OutMsgVec> {
@@ -536,11 +845,11 @@ pub mod new_member {
 
         #[trace(disable(current_msg_set))]
         fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
-            super::is_broadcast_input_complete(current_msg_set, &self.old_committee)
+            super::is_broadcast_input_complete(current_msg_set, &self.setup.old_committee)
         }
 
         fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
-            match to_hash_map_gen::<PartyIndex, Phase1Broadcast>(current_msg_set) {
+            match to_hash_map_gen::<Phase1Broadcast, _, _>(&current_msg_set) {
                 Ok(input) => {
                     if input.is_empty() {
                         let error_state = ErrorState::new(vec![ResharingError::EmptyMessageSet {
@@ -550,6 +859,16 @@ pub mod new_member {
                         return Transition::FinalState(Err(error_state));
                     }
 
+                    let mismatches = self
+                        .setup
+                        .input_mismatches(input.iter().map(|(p, m)| (p, &m.inputs)));
+                    if !mismatches.is_empty() {
+                        let error_state = ErrorState::new(mismatches)
+                            .with_evidence("New.phase1", &current_msg_set);
+                        log::error!("Phase1 returns {:?}", error_state);
+                        return Transition::FinalState(Err(error_state));
+                    }
+
                     let different_public_keys = !all_mapped_equal(input.iter(), |(_, msg)| {
                         msg.y.bytes_compressed_to_big_int()
                     });
@@ -560,40 +879,65 @@ pub mod new_member {
                         return Transition::FinalState(Err(error_state));
                     }
 
+                    let different_metadata =
+                        !all_mapped_equal(input.iter(), |(_, msg)| msg.metadata.clone());
+                    if different_metadata {
+                        let error_state =
+                            ErrorState::new(vec![ResharingError::InconsistentKeyMetadata]);
+                        log::error!("Phase1 returns {:?}", error_state);
+                        return Transition::FinalState(Err(error_state));
+                    }
+
                     // Actual consuming happens here
-                    let (ek, dk) = Paillier::keypair_with_modulus_size(
-                        2 * PRIME_BIT_LENGTH_IN_PAILLIER_SCHEMA,
-                    )
-                    .keys();
+                    let my_paillier_keys = Secret::new(PaillierKeys::random_from(&self.setup.rng));
                     let y = input.iter().next().map(|(_, msg)| msg.y).unwrap();
+                    let mut new_committee = self.setup.others_from_new_committee.clone();
+                    new_committee.insert(self.setup.own_party_index);
+                    let metadata = input
+                        .iter()
+                        .next()
+                        .map(|(_, msg)| msg.metadata.next_epoch(new_committee))
+                        .unwrap();
                     let vss_comms = input
                         .into_iter()
                         .map(|(p, m)| (p, m.vss_commitment))
                         .collect::<HashMap<_, _>>();
 
                     Transition::NewState(Box::new(Phase2 {
-                        previous_phase: (*self).clone(),
+                        setup: self.setup.clone(),
+                        range_proof_setup: self
+                            .range_proof_setup
+                            .as_ref()
+                            .map(|s| Secret::new((**s).clone())),
                         y,
+                        metadata,
                         vss_comms,
-                        my_paillier_keys: PaillierKeys { dk, ek },
+                        my_paillier_keys,
                     }))
                 }
                 Err(e) => {
-                    let error_state = ErrorState::new(e);
+                    let error_state =
+                        ErrorState::new(e).with_evidence("New.phase1", &current_msg_set);
                     log::error!("Phase 1 returns {:?}", error_state);
                     Transition::FinalState(Err(error_state))
                 }
             }
         }
 
-        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-            Err(ErrorState::new(vec![super::ResharingError::Timeout {
-                phase: "Old.phase1".to_owned(),
-            }]))
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("New.phase1", &self.setup.old_committee, current_msg_set)
         }
 
         fn timeout(&self) -> Option<Duration> {
-            self.timeout
+            self.setup.timeout
         }
     }
 
@@ -601,12 +945,13 @@ pub mod new_member {
     ///
     /// * Broadcasts public Paillier key, ZK proof of its correctness and optional `RangeProof` setup to other members of new committee
     /// * Collects and verifies same items from other parties
-    #[derive(Clone)]
     pub struct Phase2 {
-        previous_phase: Phase1,
+        setup: Setup,
+        range_proof_setup: Option<Secret<ZkpSetup>>,
         y: GE,
+        metadata: KeyMetadata,
         vss_comms: HashMap<PartyIndex, BigInt>,
-        my_paillier_keys: PaillierKeys,
+        my_paillier_keys: Secret<PaillierKeys>,
     }
 
     #[trace(pretty, prefix = "Phase2::")]
@@ -615,7 +960,7 @@ pub mod new_member {
             &self,
             input: &HashMap<PartyIndex, Phase2Broadcast>,
         ) -> Result<Option<RangeProofSetups>, Vec<ResharingError>> {
-            let my_range_proof_setup = &self.previous_phase.range_proof_setup;
+            let my_range_proof_setup = &self.range_proof_setup;
             let verification_errors = input
                 .iter()
                 .filter_map(
@@ -646,7 +991,7 @@ pub mod new_member {
                 // either local setup exists and all parties shared their setups
                 // or local setup does not exist and all parties did not share theirs setups
                 Ok(my_range_proof_setup.as_ref().map(|s| RangeProofSetups {
-                    my_setup: s.clone(),
+                    my_setup: (**s).clone(),
                     party_setups: input
                         .iter()
                         .map(|(&p, m)| {
@@ -670,15 +1015,13 @@ pub mod new_member {
     impl State<KeyResharingTraits> for Phase2 {
         fn start(&mut self) -> Option<OutMsgVec> {
             log::debug!("Phase2 (new member) starts");
-            let range_proof_setup = self
-                .previous_phase
-                .range_proof_setup
-                .as_ref()
-                .map(|s| ZkpPublicSetup::from_private_zkp_setup(s));
+            let range_proof_setup = self.range_proof_setup.as_ref().map(|s| {
+                ZkpPublicSetup::from_private_zkp_setup_with_rng(&**s, &mut self.setup.rng.clone())
+            });
             let proof = nizk_rsa::gen_proof(&self.my_paillier_keys.dk);
             #[allow(clippy::if_not_else)]
             let output = self
-                .previous_phase
+                .setup
                 .others_from_new_committee
                 .iter()
                 .map(|p| OutMsg {
@@ -687,6 +1030,7 @@ pub mod new_member {
                         ek: self.my_paillier_keys.ek.clone(),
                         correct_key_proof: CorrectKeyProof(proof.clone()),
                         range_proof_setup: range_proof_setup.clone(),
+                        inputs: self.setup.inputs.clone(),
                     }),
                 })
                 .collect();
@@ -697,7 +1041,7 @@ pub mod new_member {
         fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
             matches!(msg.body,
             Message::R2(_)
-            if self.previous_phase.others_from_new_committee.contains(&msg.sender)
+            if self.setup.others_from_new_committee.contains(&msg.sender)
             && !msg.is_duplicate(current_msg_set))
         }
 
@@ -705,13 +1049,23 @@ pub mod new_member {
         fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
             super::is_broadcast_input_complete(
                 current_msg_set,
-                &self.previous_phase.others_from_new_committee,
+                &self.setup.others_from_new_committee,
             )
         }
 
         fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
-            match to_hash_map_gen::<PartyIndex, Phase2Broadcast>(current_msg_set) {
+            match to_hash_map_gen::<Phase2Broadcast, _, _>(&current_msg_set) {
                 Ok(input) => {
+                    let mismatches = self
+                        .setup
+                        .input_mismatches(input.iter().map(|(p, m)| (p, &m.inputs)));
+                    if !mismatches.is_empty() {
+                        let error_state = ErrorState::new(mismatches)
+                            .with_evidence("New.phase2", &current_msg_set);
+                        log::error!("Phase 2 returns {:?}", error_state);
+                        return Transition::FinalState(Err(error_state));
+                    }
+
                     let mut errors = input
                         .iter()
                         .filter_map(|(party, msg)| {
@@ -738,24 +1092,28 @@ pub mod new_member {
                         .into_iter()
                         .map(|(p, msg)| (p, msg.ek))
                         .collect::<HashMap<_, _>>();
-                    other_paillier_keys.insert(
-                        self.previous_phase.own_party_index,
-                        self.my_paillier_keys.ek.clone(),
-                    );
+                    other_paillier_keys
+                        .insert(self.setup.own_party_index, self.my_paillier_keys.ek.clone());
 
                     if !errors.is_empty() {
-                        let error_state = ErrorState::new(errors);
+                        let error_state =
+                            ErrorState::new(errors).with_evidence("New.phase2", &current_msg_set);
                         log::error!("Phase 2 returns {:?}", error_state);
                         return Transition::FinalState(Err(error_state));
                     }
                     Transition::NewState(Box::new(Phase3 {
-                        previous_phase: (*self).clone(),
+                        setup: self.setup.clone(),
+                        y: self.y,
+                        metadata: self.metadata.clone(),
+                        vss_comms: self.vss_comms.clone(),
+                        my_paillier_keys: Secret::new((*self.my_paillier_keys).clone()),
                         other_paillier_keys,
-                        range_proof_setups,
+                        range_proof_setups: range_proof_setups.map(Secret::new),
                     }))
                 }
                 Err(e) => {
-                    let error_state = ErrorState::new(e);
+                    let error_state =
+                        ErrorState::new(e).with_evidence("New.phase2", &current_msg_set);
                     log::error!("Phase 2 return {:?}", error_state);
                     Transition::FinalState(Err(error_state))
                 }
@@ -763,13 +1121,23 @@ pub mod new_member {
         }
 
         fn timeout(&self) -> Option<Duration> {
-            self.previous_phase.timeout
+            self.setup.timeout
         }
 
-        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-            Err(ErrorState::new(vec![super::ResharingError::Timeout {
-                phase: "New.phase2".to_owned(),
-            }]))
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new(
+                "New.phase2",
+                &self.setup.others_from_new_committee,
+                current_msg_set,
+            )
         }
     }
 
@@ -778,11 +1146,14 @@ pub mod new_member {
     /// * sends ACK to old members
     /// * collects new Shamir's secrets and FVSS from them
     /// * verifies FVSS
-    #[derive(Clone)]
-    struct Phase3 {
-        previous_phase: Phase2,
+    pub(super) struct Phase3 {
+        setup: Setup,
+        y: GE,
+        metadata: KeyMetadata,
+        vss_comms: HashMap<PartyIndex, BigInt>,
+        my_paillier_keys: Secret<PaillierKeys>,
         other_paillier_keys: HashMap<PartyIndex, EncryptionKey>,
-        range_proof_setups: Option<RangeProofSetups>,
+        range_proof_setups: Option<Secret<RangeProofSetups>>,
     }
 
     #[trace(pretty, prefix = "Phase3::")]
@@ -790,8 +1161,7 @@ pub mod new_member {
         fn start(&mut self) -> Option<Vec<OutMsg>> {
             log::debug!("Phase3 (new member) starts");
             Some(
-                self.previous_phase
-                    .previous_phase
+                self.setup
                     .old_committee
                     .iter()
                     .map(|p| OutMsg {
@@ -806,22 +1176,20 @@ pub mod new_member {
         fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
             matches!(msg.body,
             Message::R3(_)
-            if self.previous_phase.previous_phase.old_committee.contains(&msg.sender)
+            if self.setup.old_committee.contains(&msg.sender)
             && !msg.is_duplicate(current_msg_set))
         }
 
         #[trace(disable(current_msg_set))]
         fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
-            super::is_broadcast_input_complete(
-                current_msg_set,
-                &self.previous_phase.previous_phase.old_committee,
-            )
+            super::is_broadcast_input_complete(current_msg_set, &self.setup.old_committee)
         }
 
         fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
-            match to_hash_map_gen::<PartyIndex, VSS>(current_msg_set) {
+            match to_hash_map_gen::<VSS, _, _>(&current_msg_set) {
                 Err(e) => {
-                    let error_state = ErrorState::new(e);
+                    let error_state =
+                        ErrorState::new(e).with_evidence("New.phase3", &current_msg_set);
                     log::error!("Phase 3 returns {:?}", error_state);
                     Transition::FinalState(Err(error_state))
                 }
@@ -844,13 +1212,36 @@ pub mod new_member {
                         return Transition::FinalState(Err(error_state));
                     }
 
-                    let (my_x, my_share) = (
-                        input.iter().next().map(|(_, vss)| vss.share.0).unwrap(),
+                    let my_x = input.iter().next().map(|(_, vss)| vss.share.0).unwrap();
+                    let my_share = Secret::new(
                         input
                             .iter()
                             .fold(FE::zero(), |acc, (_, vss)| acc + vss.share.1),
                     );
 
+                    let self_setup = &self.setup;
+                    let mut new_committee = self_setup
+                        .others_from_new_committee
+                        .iter()
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    new_committee.push(self_setup.own_party_index);
+                    let party_to_point_map = Party2PointMap {
+                        // using dummy shares  as we need x-coords only
+                        points: map_parties_to_shares(
+                            new_committee.as_slice(),
+                            &vec![FE::zero(); new_committee.len()],
+                        )
+                        .into_iter()
+                        .map(|(party, (point, _))| (party, point))
+                        .collect::<HashMap<_, _>>(),
+                    };
+                    let vss_schemes = input.values().map(|vss| &vss.vss).collect::<Vec<_>>();
+                    // panic() is unreachable as the input is checked for emptiness above
+                    let public_shares =
+                        compute_public_shares(&vss_schemes, &party_to_point_map.points)
+                            .expect("VSS schemes are missing");
+
                     // check commitment errors
                     let commitment_errors = input
                         .into_iter()
@@ -858,7 +1249,7 @@ pub mod new_member {
                             let ((_, x_i), vss) = (vss.share, vss.vss);
                             let vss_refs = vss.commitments.iter().collect::<Vec<_>>();
                             let decomm = HSha256::create_hash_from_ge(&vss_refs).to_big_int();
-                            match self.previous_phase.vss_comms.get(&p) {
+                            match self.vss_comms.get(&p) {
                                 Some(comm) => {
                                     if *comm == decomm {
                                         match vss.validate_share(&x_i, my_x) {
@@ -885,158 +1276,839 @@ pub mod new_member {
                         .collect::<Vec<_>>();
 
                     if !commitment_errors.is_empty() {
-                        let error_state = ErrorState::new(commitment_errors);
+                        let error_state = ErrorState::new(commitment_errors)
+                            .with_evidence("New.phase3", &current_msg_set);
+                        log::error!("Phase3 returns {:?}", error_state);
+                        return Transition::FinalState(Err(error_state));
+                    }
+
+                    let g: GE = ECPoint::generator();
+                    if public_shares.get(&self_setup.own_party_index) != Some(&(g * &*my_share)) {
+                        let error_state =
+                            ErrorState::new(vec![ResharingError::OwnPublicShareMismatch])
+                                .with_evidence("New.phase3", &current_msg_set);
                         log::error!("Phase3 returns {:?}", error_state);
                         return Transition::FinalState(Err(error_state));
                     }
+
                     Transition::NewState(Box::new(Phase4 {
-                        previous_phase: (*self).clone(),
-                        share: (my_x, my_share),
+                        setup: self.setup.clone(),
+                        y: self.y,
+                        metadata: self.metadata.clone(),
+                        my_paillier_keys: Secret::new((*self.my_paillier_keys).clone()),
+                        other_paillier_keys: self.other_paillier_keys.clone(),
+                        range_proof_setups: self
+                            .range_proof_setups
+                            .as_ref()
+                            .map(|s| Secret::new((**s).clone())),
+                        share: Secret::new((my_x, *my_share)),
+                        party_to_point_map,
+                        public_shares,
                     }))
                 }
             }
         }
 
-        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-            Err(ErrorState::new(vec![super::ResharingError::Timeout {
-                phase: "Old.phase3".to_owned(),
-            }]))
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("New.phase3", &self.setup.old_committee, current_msg_set)
         }
 
         fn timeout(&self) -> Option<Duration> {
-            self.previous_phase.previous_phase.timeout
+            self.setup.timeout
         }
     }
 
-    /// Last phase of the protocol
+    /// Fourth phase of the protocol, the prepare step of the commit
     ///
-    /// * sends `FinalAck` messages to all parties, including members of old and new committees
-    /// * collects `FinalAck` from membeers of new committee and exits  
+    /// * sends `Prepared` to members of old committee, confirming that the new share is verified
+    /// * waits for `Commit` from t+1 members of old committee, which they send only when all new members are prepared
+    ///
+    /// A malicious old member may send `Commit` before all new members are prepared, hence a single `Commit` is not enough, while among t+1 of them
+    /// at least one comes from an honest member. `Commit` of other old members is not awaited, so that the share is committed even if some of them are lost.
+    /// If fewer `Commit` messages arrive, the new share is dropped, while old members keep their shares.
     struct Phase4 {
-        previous_phase: Phase3,
-        share: SecretShare,
+        setup: Setup,
+        y: GE,
+        metadata: KeyMetadata,
+        my_paillier_keys: Secret<PaillierKeys>,
+        other_paillier_keys: HashMap<PartyIndex, EncryptionKey>,
+        range_proof_setups: Option<Secret<RangeProofSetups>>,
+        share: Secret<SecretShare>,
+        party_to_point_map: Party2PointMap,
+        public_shares: HashMap<PartyIndex, GE>,
     }
 
     #[trace(pretty, prefix = "Phase4::")]
     impl State<KeyResharingTraits> for Phase4 {
         fn start(&mut self) -> Option<Vec<OutMsg>> {
             log::debug!("Phase4 (new member) starts");
-            let self_setup = &self.previous_phase.previous_phase.previous_phase;
             Some(
-                #[allow(clippy::filter_map)]
-                self_setup
+                self.setup
                     .old_committee
                     .iter()
                     .map(|p| OutMsg {
                         recipient: Address::Peer(*p),
-                        body: Message::FinalAck,
+                        body: Message::Prepared,
                     })
-                    .chain(self_setup.others_from_new_committee.iter().map(|p| OutMsg {
-                        recipient: Address::Peer(*p),
-                        body: Message::FinalAck,
-                    }))
                     .collect::<Vec<_>>(),
             )
         }
 
         #[trace(disable(current_msg_set))]
         fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
-            matches!(msg.body, Message::FinalAck
-            if self.previous_phase.previous_phase.previous_phase.others_from_new_committee.contains(&msg.sender)
+            matches!(msg.body, Message::Commit
+            if self.setup.old_committee.contains(&msg.sender)
             && !msg.is_duplicate(current_msg_set))
         }
 
         #[trace(disable(current_msg_set))]
         fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
-            super::is_broadcast_input_complete(
-                current_msg_set,
-                &self
-                    .previous_phase
-                    .previous_phase
-                    .previous_phase
-                    .others_from_new_committee,
-            )
+            // t+1 `Commit` messages include the decision of an honest old member
+            current_msg_set.len() > self.setup.old_params.threshold()
         }
 
         fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
-            let mut new_committee = self
-                .previous_phase
-                .previous_phase
-                .previous_phase
-                .others_from_new_committee
-                .iter()
-                .cloned()
-                .collect::<Vec<_>>();
-
-            new_committee.push(
-                self.previous_phase
-                    .previous_phase
-                    .previous_phase
-                    .own_party_index,
-            );
-
-            let party_mapping_to_points = Party2PointMap {
-                // using dummy shares  as we need x-coords only
-                points: map_parties_to_shares(
-                    new_committee.as_slice(),
-                    &vec![FE::zero(); new_committee.len()],
-                )
-                .into_iter()
-                .map(|(party, (point, _))| (party, point))
-                .collect::<HashMap<_, _>>(),
-            };
-            log::info!("Phase4 succeeded");
-            Transition::FinalState(Ok(FinalState {
-                info: MultiPartyInfo {
-                    key_params: self.previous_phase.previous_phase.previous_phase.new_params,
-                    own_party_index: self
-                        .previous_phase
-                        .previous_phase
-                        .previous_phase
-                        .own_party_index,
-                    secret_share: self.share,
-                    public_key: self.previous_phase.previous_phase.y,
-                    own_he_keys: self.previous_phase.previous_phase.my_paillier_keys.clone(),
-                    party_he_keys: self.previous_phase.other_paillier_keys.clone(),
-                    party_to_point_map: party_mapping_to_points,
-                    range_proof_setups: self.previous_phase.range_proof_setups.clone(),
+            Transition::NewState(Box::new(Phase5 {
+                old_committee: self.setup.old_committee.clone(),
+                others_from_new_committee: self.setup.others_from_new_committee.clone(),
+                share: Secret::new(*self.share),
+                own_he_keys: Secret::new((*self.my_paillier_keys).clone()),
+                info: MultiPartyPublicInfo {
+                    key_params: self.setup.new_params,
+                    own_party_index: self.setup.own_party_index,
+                    own_point: self.share.0,
+                    public_key: self.y,
+                    own_ek: self.my_paillier_keys.ek.clone(),
+                    party_he_keys: self.other_paillier_keys.clone(),
+                    party_to_point_map: self.party_to_point_map.clone(),
+                    public_shares: self.public_shares.clone(),
+                    range_proof_setups: self.range_proof_setups.as_ref().map(|s| (**s).clone()),
+                    metadata: self.metadata.clone(),
                 },
+                timeout: self.setup.timeout,
             }))
         }
 
         fn timeout(&self) -> Option<Duration> {
-            self.previous_phase.previous_phase.previous_phase.timeout
+            self.setup.timeout
         }
 
-        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-            Err(ErrorState::new(vec![super::ResharingError::Timeout {
-                phase: "New.phase4".to_owned(),
-            }]))
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            Err(ErrorState::timeout(
+                self.status(&current_msg_set),
+                &current_msg_set,
+            ))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new("New.phase4", &self.setup.old_committee, current_msg_set)
         }
     }
-    /// Helper function which returns true if a given message of the resharing protocol should be forwarded to a member of new committee
-    pub fn is_message_to_committee(msg: &Message) -> bool {
-        !matches!(msg, Message::Ack)
+
+    /// Last phase of the protocol, the commit step
+    ///
+    /// * sends `FinalAck` messages to all parties, including members of old and new committees, confirming that the new share is committed
+    /// * collects `FinalAck` from members of new committee and exits
+    ///
+    /// The new share is committed once the phase starts, hence the phase exits with the new share even if it times out.
+    struct Phase5 {
+        old_committee: BTreeSet<PartyIndex>,
+        others_from_new_committee: BTreeSet<PartyIndex>,
+        share: Secret<SecretShare>,
+        own_he_keys: Secret<PaillierKeys>,
+        info: MultiPartyPublicInfo,
+        timeout: Option<Duration>,
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::algorithms::zkp::ZkpSetup;
-    use crate::ecdsa::keygen::MultiPartyInfo;
-    use crate::ecdsa::messages::SecretShare;
-    use crate::ecdsa::resharing::new_member::KeyResharingTraits;
-    use crate::ecdsa::resharing::old_member::KeyResharingTraits as OldKeyResharingTraits;
-    use crate::ecdsa::resharing::{InMsg, OutMsg};
-    use crate::protocol::{Address, InputMessage, PartyIndex};
-    use crate::state_machine::sync_channels::StateMachine;
-    use crate::Parameters;
-    use anyhow::bail;
-    use crossbeam_channel::{Receiver, Sender};
-    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    impl Phase5 {
+        fn final_state(&self) -> FinalState {
+            FinalState {
+                info: self
+                    .info
+                    .clone()
+                    .into_multi_party_info(*self.share, (*self.own_he_keys).clone()),
+            }
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase5::")]
+    impl State<KeyResharingTraits> for Phase5 {
+        fn start(&mut self) -> Option<Vec<OutMsg>> {
+            log::debug!("Phase5 (new member) starts");
+            Some(
+                self.old_committee
+                    .iter()
+                    .chain(self.others_from_new_committee.iter())
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::FinalAck,
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            matches!(msg.body, Message::FinalAck)
+                && self.others_from_new_committee.contains(&msg.sender)
+                && !msg.is_duplicate(current_msg_set)
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            super::is_broadcast_input_complete(current_msg_set, &self.others_from_new_committee)
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
+            log::info!("Phase5 succeeded");
+            Transition::FinalState(Ok(self.final_state()))
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            log::warn!(
+                "Phase5 timed out, the share is committed nevertheless, missing FinalAck from {:?}",
+                self.status(&current_msg_set).missing_senders()
+            );
+            Ok(self.final_state())
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+            PhaseStatus::new(
+                COMMIT_PHASE,
+                &self.others_from_new_committee,
+                current_msg_set,
+            )
+        }
+    }
+
+    /// Helper function which returns true if a given message of the resharing protocol should be forwarded to a member of new committee
+    pub fn is_message_to_committee(msg: &Message) -> bool {
+        !matches!(msg, Message::Ack | Message::Prepared)
+    }
+}
+
+/// Resharing on behalf of a party which is a member of both old and new committees
+///
+/// The party runs both roles within one state machine: the role of [`old_member`] reshares its old share, while the role of [`new_member`] receives the new one.
+/// Each role is executed by its own [`Stepper`]. Messages which one role addresses to own party are passed to the other role directly,
+/// messages of other parties are passed to the roles they are destined for, same way [`old_member::is_message_to_committee`] and [`new_member::is_message_to_committee`]
+/// route them between separate nodes. Hence other parties can't tell whether the party runs one machine or two.
+///
+/// The machine finishes when both roles have finished. If either role fails before the new share is committed, the machine fails and the old share is kept.
+/// Once the new share is committed, the machine returns it even if the old role fails afterwards, which is reported by [`FinalState`].
+///
+/// The timeout of the machine is the shortest timeout of current phases of both roles, and it restarts whenever the machine receives a message.
+///
+/// [`old_member`]: ../old_member/index.html
+/// [`new_member`]: ../new_member/index.html
+/// [`old_member::is_message_to_committee`]: ../old_member/fn.is_message_to_committee.html
+/// [`new_member::is_message_to_committee`]: ../new_member/fn.is_message_to_committee.html
+/// [`Stepper`]: ../../../state_machine/stepper/struct.Stepper.html
+/// [`FinalState`]: struct.FinalState.html
+pub mod dual_member {
+    use super::{new_member, old_member, ErrorState, ResharingError};
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::zkp::ZkpSetup;
+    use crate::ecdsa::keygen::{
+        ASecretShareDeleter, ASecretShareLoader, MultiPartyInfo, MultiPartyPublicInfo,
+    };
+    use crate::ecdsa::messages::resharing::{InMsg, Message, OutMsg};
+    use crate::protocol::{Address, InputMessage, PartyIndex};
+    use crate::state_machine::stepper::Stepper;
+    use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+    use crate::Parameters;
+    use std::cell::RefCell;
+    use std::collections::BTreeSet;
+    use std::fmt::{Debug, Error, Formatter};
+    use std::time::Duration;
+    use trace::trace;
+
+    /// Result of resharing protocol
+    #[derive(Debug)]
+    pub struct FinalState {
+        pub info: MultiPartyInfo,
+        /// true if all members of new committee have acknowledged the commit, so that the old share can be replaced by the new one.
+        /// Otherwise some new members may have not committed to their shares, and the old share should be kept along with the new one
+        pub old_share_released: bool,
+    }
+
+    pub type MachineResult = Result<FinalState, ErrorState>;
+
+    /// Type definitions
+    #[derive(Debug)]
+    pub struct KeyResharingTraits;
+
+    impl StateMachineTraits for KeyResharingTraits {
+        type InMsg = InMsg;
+        type OutMsg = OutMsg;
+        type FinalState = FinalState;
+        type ErrorState = ErrorState;
+    }
+
+    /// Both roles of the party along with their outcomes
+    struct Roles {
+        own_party_index: PartyIndex,
+        old: Stepper<old_member::KeyResharingTraits>,
+        old_outcome: Option<old_member::MachineResult>,
+        new: Stepper<new_member::KeyResharingTraits>,
+        new_outcome: Option<new_member::MachineResult>,
+    }
+
+    impl Roles {
+        /// Starts both roles and returns their output to other parties
+        fn start(&mut self) -> Vec<OutMsg> {
+            let old_output = self.old.start();
+            let new_output = self.new.start();
+            self.relay(old_output, new_output)
+        }
+
+        /// Tells whether the current phase of either role expects the message, given `pending` messages accepted by the machine but not delivered yet
+        fn expects(&self, msg: &InMsg, pending: &[InMsg]) -> bool {
+            let expected_by_old = self.old_outcome.is_none()
+                && old_member::is_message_to_committee(&msg.body)
+                && self.old.is_message_expected(
+                    msg,
+                    &accepted(&self.old, pending, old_member::is_message_to_committee),
+                );
+            let expected_by_new = self.new_outcome.is_none()
+                && new_member::is_message_to_committee(&msg.body)
+                && self.new.is_message_expected(
+                    msg,
+                    &accepted(&self.new, pending, new_member::is_message_to_committee),
+                );
+            expected_by_old || expected_by_new
+        }
+
+        /// Tells whether the current phase of either role would be complete once `pending` messages are delivered
+        fn completes_phase(&self, pending: &[InMsg]) -> bool {
+            let old_pending = accepted(&self.old, pending, old_member::is_message_to_committee);
+            let new_pending = accepted(&self.new, pending, new_member::is_message_to_committee);
+            (self.old_outcome.is_none()
+                && !old_pending.is_empty()
+                && self.old.is_input_complete(&old_pending))
+                || (self.new_outcome.is_none()
+                    && !new_pending.is_empty()
+                    && self.new.is_input_complete(&new_pending))
+        }
+
+        /// Passes the message of other party to the roles it is destined for and returns the output to other parties
+        fn deliver(&mut self, msg: InMsg) -> Vec<OutMsg> {
+            if old_member::is_message_to_committee(&msg.body) {
+                self.deliver_to_old(msg.clone());
+            }
+            if new_member::is_message_to_committee(&msg.body) {
+                self.deliver_to_new(msg);
+            }
+            let old_output = self.old.take_output();
+            let new_output = self.new.take_output();
+            self.relay(old_output, new_output)
+        }
+
+        /// Passes messages addressed to own party to the other role until roles have nothing to pass, returns the output to other parties
+        fn relay(
+            &mut self,
+            mut old_output: Vec<OutMsg>,
+            mut new_output: Vec<OutMsg>,
+        ) -> Vec<OutMsg> {
+            let mut output = Vec::new();
+            while !old_output.is_empty() || !new_output.is_empty() {
+                for msg in old_output.drain(..) {
+                    if self.is_to_self(&msg) {
+                        self.deliver_to_new(InputMessage {
+                            sender: self.own_party_index,
+                            body: msg.body,
+                        });
+                    } else {
+                        output.push(msg);
+                    }
+                }
+                for msg in new_output.drain(..) {
+                    if self.is_to_self(&msg) {
+                        self.deliver_to_old(InputMessage {
+                            sender: self.own_party_index,
+                            body: msg.body,
+                        });
+                    } else {
+                        output.push(msg);
+                    }
+                }
+                old_output = self.old.take_output();
+                new_output = self.new.take_output();
+            }
+            output
+        }
+
+        fn is_to_self(&self, msg: &OutMsg) -> bool {
+            match &msg.recipient {
+                Address::Peer(party) => *party == self.own_party_index,
+                Address::Broadcast => false,
+            }
+        }
+
+        fn deliver_to_old(&mut self, msg: InMsg) {
+            if self.old_outcome.is_none() {
+                self.old_outcome = self.old.deliver(msg);
+            }
+        }
+
+        fn deliver_to_new(&mut self, msg: InMsg) {
+            if self.new_outcome.is_none() {
+                self.new_outcome = self.new.deliver(msg);
+            }
+        }
+
+        fn is_new_share_committed(&self) -> bool {
+            match &self.new_outcome {
+                Some(outcome) => outcome.is_ok(),
+                None => self.new.status().phase == new_member::COMMIT_PHASE,
+            }
+        }
+
+        /// Returns the outcome of the machine once it is known
+        fn conclude(&mut self) -> Option<MachineResult> {
+            match (self.old_outcome.take(), self.new_outcome.take()) {
+                (old_outcome, Some(Err(new_error))) => Some(Err(match old_outcome {
+                    Some(Err(old_error)) => old_error.append(new_error),
+                    _ => new_error,
+                })),
+                (Some(Err(old_error)), None) if !self.is_new_share_committed() => {
+                    Some(Err(old_error))
+                }
+                (Some(old_outcome), Some(Ok(new_final_state))) => {
+                    if let Err(old_error) = &old_outcome {
+                        log::warn!(
+                            "the new share is committed while the old member has failed: {:?}",
+                            old_error
+                        );
+                    }
+                    Some(Ok(FinalState {
+                        info: new_final_state.info,
+                        old_share_released: old_outcome.is_ok(),
+                    }))
+                }
+                (old_outcome, new_outcome) => {
+                    self.old_outcome = old_outcome;
+                    self.new_outcome = new_outcome;
+                    None
+                }
+            }
+        }
+
+        /// Terminates current phases of both roles by their timeouts
+        fn expire(&mut self) -> MachineResult {
+            if self.old_outcome.is_none() {
+                self.old_outcome = Some(self.old.expire());
+            }
+            if self.new_outcome.is_none() {
+                self.new_outcome = Some(self.new.expire());
+            }
+            self.conclude().expect("both roles have finished")
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            let old_timeout = self
+                .old_outcome
+                .as_ref()
+                .map_or(self.old.timeout(), |_| None);
+            let new_timeout = self
+                .new_outcome
+                .as_ref()
+                .map_or(self.new.timeout(), |_| None);
+            old_timeout.into_iter().chain(new_timeout).min()
+        }
+
+        /// Combines statuses of both roles, a party is missing if either role misses its message
+        fn status(&self) -> PhaseStatus {
+            let mut statuses = Vec::new();
+            if self.old_outcome.is_none() {
+                statuses.push(self.old.status());
+            }
+            if self.new_outcome.is_none() {
+                statuses.push(self.new.status());
+            }
+            let expected_senders = statuses
+                .iter()
+                .flat_map(|s| s.expected_senders.iter().cloned())
+                .collect::<BTreeSet<_>>();
+            let missing_senders = statuses
+                .iter()
+                .flat_map(|s| s.missing_senders())
+                .collect::<BTreeSet<_>>();
+            PhaseStatus {
+                phase: statuses
+                    .iter()
+                    .map(|s| s.phase.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                received_senders: expected_senders
+                    .difference(&missing_senders)
+                    .cloned()
+                    .collect(),
+                expected_senders,
+            }
+        }
+    }
+
+    /// Messages among `pending` which are destined for the role and expected by its current phase, in order of arrival
+    fn accepted<T>(
+        role: &Stepper<T>,
+        pending: &[InMsg],
+        is_destined: fn(&Message) -> bool,
+    ) -> Vec<InMsg>
+    where
+        T: StateMachineTraits<InMsg = InMsg>,
+    {
+        let mut accepted = Vec::new();
+        for msg in pending.iter().filter(|msg| is_destined(&msg.body)) {
+            if role.is_message_expected(msg, &accepted) {
+                accepted.push(msg.clone());
+            }
+        }
+        accepted
+    }
+
+    /// The phase of the machine, which relays messages between both roles and other parties
+    ///
+    /// The phase accepts only messages which the current phase of either role expects, other messages are deferred by the machine.
+    /// Accepted messages are delivered to roles once either role can complete its phase with them, then the machine moves to the next instance of the phase,
+    /// so that the output of roles is sent when the instance starts. The timeout of the instance is the earliest timeout of current phases of roles,
+    /// hence the deadline of a role's phase is not extended by messages of other parties, but it restarts when the other role moves to its next phase.
+    pub struct Phase1 {
+        /// roles which are not started yet, so that they can still be configured
+        initial: Option<(PartyIndex, old_member::Phase1, new_member::Phase1)>,
+        roles: RefCell<Option<Roles>>,
+        /// output of roles to send when the phase starts, `None` if roles are not started yet
+        output: Option<Vec<OutMsg>>,
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl Phase1 {
+        pub fn new(
+            multi_party_info: &MultiPartyInfo,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+        ) -> Result<Self, ResharingError> {
+            Self::new_with_rng(
+                multi_party_info,
+                new_params,
+                old_committee,
+                new_committee,
+                range_proof_setup,
+                timeout,
+                RandomnessSource::os(),
+            )
+        }
+
+        /// same as `new`, but both roles sample their randomness from `rng`
+        #[trace(disable(rng))]
+        pub fn new_with_rng(
+            multi_party_info: &MultiPartyInfo,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ResharingError> {
+            check_old_membership(multi_party_info.own_party_index, old_committee)?;
+            let old = old_member::Phase1::new_with_rng(
+                multi_party_info,
+                new_params,
+                old_committee,
+                new_committee,
+                timeout,
+                rng.clone(),
+            )?;
+            let new = new_member::Phase1::new_with_rng(
+                &multi_party_info.key_params,
+                new_params,
+                old_committee,
+                new_committee,
+                multi_party_info.own_party_index,
+                range_proof_setup,
+                timeout,
+                rng,
+            )?;
+            Ok(Self::from_roles(multi_party_info.own_party_index, old, new))
+        }
+
+        /// Initializes the protocol whose old role loads the secret share through `secret_loader`, both roles sample their randomness from `rng`
+        ///
+        /// See [`old_member::Phase1::new_with_loader`](../old_member/struct.Phase1.html#method.new_with_loader).
+        #[allow(clippy::too_many_arguments)]
+        #[trace(disable(secret_loader, rng))]
+        pub fn new_with_loader(
+            multi_party_info: &MultiPartyPublicInfo,
+            secret_loader: ASecretShareLoader,
+            new_params: &Parameters,
+            old_committee: &[PartyIndex],
+            new_committee: &[PartyIndex],
+            range_proof_setup: Option<ZkpSetup>,
+            timeout: Option<Duration>,
+            rng: RandomnessSource,
+        ) -> Result<Self, ResharingError> {
+            check_old_membership(multi_party_info.own_party_index, old_committee)?;
+            let old = old_member::Phase1::new_with_loader(
+                multi_party_info,
+                secret_loader,
+                new_params,
+                old_committee,
+                new_committee,
+                timeout,
+                rng.clone(),
+            )?;
+            let new = new_member::Phase1::new_with_rng(
+                &multi_party_info.key_params,
+                new_params,
+                old_committee,
+                new_committee,
+                multi_party_info.own_party_index,
+                range_proof_setup,
+                timeout,
+                rng,
+            )?;
+            Ok(Self::from_roles(multi_party_info.own_party_index, old, new))
+        }
+
+        /// Sets the deleter of the old secret share, which is called when all members of new committee have committed to their new shares
+        ///
+        /// See [`old_member::Phase1::with_share_deleter`](../old_member/struct.Phase1.html#method.with_share_deleter).
+        pub fn with_share_deleter(mut self, share_deleter: ASecretShareDeleter) -> Self {
+            self.initial = self.initial.map(|(own_party_index, old, new)| {
+                (own_party_index, old.with_share_deleter(share_deleter), new)
+            });
+            self
+        }
+
+        fn from_roles(
+            own_party_index: PartyIndex,
+            old: old_member::Phase1,
+            new: new_member::Phase1,
+        ) -> Self {
+            Phase1 {
+                initial: Some((own_party_index, old, new)),
+                roles: RefCell::new(None),
+                output: None,
+            }
+        }
+    }
+
+    fn check_old_membership(
+        own_party_index: PartyIndex,
+        old_committee: &[PartyIndex],
+    ) -> Result<(), ResharingError> {
+        if old_committee.contains(&own_party_index) {
+            Ok(())
+        } else {
+            Err(ResharingError::ProtocolSetupError(
+                "own party index not in old committee list".to_string(),
+            ))
+        }
+    }
+
+    impl Debug for Phase1 {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+            match (&self.initial, &*self.roles.borrow()) {
+                (Some(_), _) => write!(f, "dual_member::Phase1 (not started)"),
+                (None, Some(roles)) => {
+                    write!(f, "dual_member::Phase1 ({})", roles.status().phase)
+                }
+                (None, None) => write!(f, "dual_member::Phase1 (consumed)"),
+            }
+        }
+    }
+
+    #[trace(pretty, prefix = "Phase1::")]
+    impl State<KeyResharingTraits> for Phase1 {
+        fn start(&mut self) -> Option<Vec<OutMsg>> {
+            match (self.output.take(), self.initial.take()) {
+                (Some(output), _) => Some(output),
+                (None, Some((own_party_index, old, new))) => {
+                    log::debug!("Phase1 (dual member) starts");
+                    let mut roles = Roles {
+                        own_party_index,
+                        old: Stepper::new(Box::new(old)),
+                        old_outcome: None,
+                        new: Stepper::new(Box::new(new)),
+                        new_outcome: None,
+                    };
+                    let output = roles.start();
+                    *self.roles.get_mut() = Some(roles);
+                    Some(output)
+                }
+                (None, None) => None,
+            }
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+            self.roles
+                .borrow()
+                .as_ref()
+                .map_or(false, |roles| roles.expects(msg, current_msg_set))
+        }
+
+        #[trace(disable(current_msg_set))]
+        fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+            self.roles
+                .borrow()
+                .as_ref()
+                .map_or(false, |roles| roles.completes_phase(current_msg_set))
+        }
+
+        fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
+            let mut roles = match self.roles.borrow_mut().take() {
+                Some(roles) => roles,
+                None => return Transition::FinalState(Err(consumed_error())),
+            };
+            let output = current_msg_set
+                .into_iter()
+                .flat_map(|msg| roles.deliver(msg))
+                .collect::<Vec<_>>();
+            match roles.conclude() {
+                Some(outcome) if output.is_empty() => Transition::FinalState(outcome),
+                Some(outcome) => Transition::NewState(Box::new(Flush {
+                    output: Some(output),
+                    outcome: RefCell::new(Some(outcome)),
+                })),
+                None => Transition::NewState(Box::new(Phase1 {
+                    roles: RefCell::new(Some(roles)),
+                    output: Some(output),
+                })),
+            }
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.roles.borrow().as_ref().and_then(Roles::timeout)
+        }
+
+        fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+            match self.roles.borrow_mut().take() {
+                Some(mut roles) => {
+                    // accepted messages complete no phase of roles, they are delivered so that timeouts of roles report their senders
+                    for msg in current_msg_set {
+                        roles.deliver(msg);
+                    }
+                    roles.expire()
+                }
+                None => Err(consumed_error()),
+            }
+        }
+
+        fn status(&self, _current_msg_set: &[InMsg]) -> PhaseStatus {
+            self.roles
+                .borrow()
+                .as_ref()
+                .map(Roles::status)
+                .unwrap_or_default()
+        }
+    }
+
+    /// Last phase of the machine, which sends the remaining output of roles and exits immediately
+    ///
+    /// The machine can't send messages on its final transition, hence the phase exits by zero timeout.
+    struct Flush {
+        output: Option<Vec<OutMsg>>,
+        outcome: RefCell<Option<MachineResult>>,
+    }
+
+    impl Debug for Flush {
+        fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+            write!(f, "dual_member::Flush")
+        }
+    }
+
+    #[trace(pretty, prefix = "Flush::")]
+    impl State<KeyResharingTraits> for Flush {
+        fn start(&mut self) -> Option<Vec<OutMsg>> {
+            self.output.take()
+        }
+
+        fn is_message_expected(&self, _msg: &InMsg, _current_msg_set: &[InMsg]) -> bool {
+            false
+        }
+
+        fn is_input_complete(&self, _current_msg_set: &[InMsg]) -> bool {
+            false
+        }
+
+        fn consume(&self, _current_msg_set: Vec<InMsg>) -> Transition<KeyResharingTraits> {
+            Transition::FinalState(
+                self.outcome
+                    .borrow_mut()
+                    .take()
+                    .unwrap_or_else(|| Err(consumed_error())),
+            )
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_secs(0))
+        }
+
+        fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
+            self.outcome
+                .borrow_mut()
+                .take()
+                .unwrap_or_else(|| Err(consumed_error()))
+        }
+    }
+
+    fn consumed_error() -> ErrorState {
+        ErrorState::new(vec![ResharingError::GeneralError(
+            "dual member: the phase is consumed already".to_string(),
+        )])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::zkp::ZkpSetup;
+    use crate::ecdsa::keygen::{
+        ASecretShareDeleter, MultiPartyInfo, SecretKeyLoaderError, SecretShareDeleter,
+    };
+    use crate::ecdsa::messages::SecretShare;
+    use crate::ecdsa::resharing::new_member::KeyResharingTraits;
+    use crate::ecdsa::resharing::old_member;
+    use crate::ecdsa::resharing::old_member::KeyResharingTraits as OldKeyResharingTraits;
+    use crate::ecdsa::resharing::{
+        dual_member, new_member, ErrorState, InMsg, Message, OutMsg, ResharingError,
+    };
+    use crate::protocol::{Address, InputMessage, PartyIndex};
+    use crate::secret::AmbiguousIfClone;
+    use crate::state_machine::sync_channels::StateMachine;
+    use crate::state_machine::{BoxedState, State};
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::{key_of, load_keys, Run};
+    use crate::testkit::{Action, Adversary};
+    use crate::Parameters;
+    use anyhow::bail;
+    use crossbeam_channel::{Receiver, Sender};
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
     use curv::elliptic::curves::traits::{ECPoint, ECScalar};
     use curv::{BigInt, FE, GE};
+    use std::collections::BTreeSet;
     use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
     use std::{fs, thread};
 
     struct Node {
@@ -1050,6 +2122,25 @@ mod tests {
         source: PartyIndex,
     }
 
+    /// Counts deletions instead of deleting anything
+    #[derive(Debug, Default)]
+    struct CountingDeleter {
+        deletions: AtomicUsize,
+    }
+
+    impl SecretShareDeleter for Arc<CountingDeleter> {
+        fn delete_secret_share(&self) -> Result<(), SecretKeyLoaderError> {
+            self.deletions.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    fn counting_deleter() -> (Arc<CountingDeleter>, ASecretShareDeleter) {
+        let counter = Arc::new(CountingDeleter::default());
+        let deleter: ASecretShareDeleter = Arc::new(Box::new(counter.clone()));
+        (counter, deleter)
+    }
+
     #[test]
     fn resharing() -> anyhow::Result<()> {
         let _ = env_logger::builder().is_test(true).try_init();
@@ -1062,6 +2153,18 @@ mod tests {
         sharing_helper(true)
     }
 
+    #[test]
+    fn old_member_phase_is_not_clone() {
+        <old_member::Phase1 as AmbiguousIfClone<_>>::check();
+    }
+
+    #[test]
+    fn new_member_phases_are_not_clone() {
+        <new_member::Phase1 as AmbiguousIfClone<_>>::check();
+        <new_member::Phase2 as AmbiguousIfClone<_>>::check();
+        <new_member::Phase3 as AmbiguousIfClone<_>>::check();
+    }
+
     pub fn sharing_helper(use_range_proofs: bool) -> anyhow::Result<()> {
         let _ = env_logger::builder().is_test(true).try_init();
         let old_params = Parameters {
@@ -1082,6 +2185,7 @@ mod tests {
 
         let mut old_handles = Vec::new();
         let mut new_handles = Vec::new();
+        let (deletions, share_deleter) = counting_deleter();
 
         // start new committee first
         for i in new_committee.clone() {
@@ -1171,14 +2275,18 @@ mod tests {
                 .map(|i| (*i).into())
                 .collect::<Vec<PartyIndex>>();
 
+            let share_deleter = share_deleter.clone();
             let join_handle = thread::spawn(move || {
-                let start_state = Box::new(super::old_member::Phase1::new(
-                    &multi_party_shared_info,
-                    &new_params,
-                    &oc_clone,
-                    &nc_clone,
-                    None,
-                )?);
+                let start_state = Box::new(
+                    super::old_member::Phase1::new(
+                        &multi_party_shared_info,
+                        &new_params,
+                        &oc_clone,
+                        &nc_clone,
+                        None,
+                    )?
+                    .with_share_deleter(share_deleter),
+                );
 
                 let mut old_member_machine =
                     StateMachine::<OldKeyResharingTraits>::new(start_state, &rx, &tx);
@@ -1327,6 +2435,11 @@ mod tests {
             });
             assert!(false, "Some state machines returned error");
         }
+        // every old member deletes its share once new members have committed
+        assert_eq!(
+            deletions.deletions.load(Ordering::SeqCst),
+            old_committee.len()
+        );
 
         let new_final_states = new_committee_result
             .into_iter()
@@ -1353,9 +2466,603 @@ mod tests {
             "new private key key does not match odl public key"
         );
 
+        let path = if use_range_proofs {
+            "tests/data/zkrp-keys.0.json"
+        } else {
+            "tests/data/keys.0.json"
+        };
+        let old_metadata =
+            serde_json::from_str::<MultiPartyInfo>(&fs::read_to_string(path)?)?.metadata;
+        let new_committee = new_committee
+            .iter()
+            .map(|i| (*i).into())
+            .collect::<BTreeSet<PartyIndex>>();
+        for state in new_final_states.iter() {
+            assert_eq!(
+                state.info.metadata,
+                old_metadata.next_epoch(new_committee.clone())
+            );
+        }
+
+        let public_shares = &new_final_states[0].info.public_shares;
+        assert_eq!(public_shares.len(), new_committee.len());
+        for state in new_final_states.iter() {
+            assert_eq!(state.info.public_shares, *public_shares);
+            assert_eq!(
+                public_shares.get(&state.info.own_party_index),
+                Some(&(g * state.info.own_share()))
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn resharing_detects_corrupted_vss() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let old_params = Parameters {
+            share_count: 3,
+            threshold: 1,
+        };
+        let new_params = Parameters {
+            share_count: 4,
+            threshold: 1,
+        };
+        let old_committee = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+        let new_committee = (0..=3).map(PartyIndex::from).collect::<Vec<_>>();
+        let adversary = Adversary::new(old_committee[2], &old_committee).in_phase(
+            "Old.phase2",
+            Action::Corrupt(|msg: &mut Message| {
+                if let Message::R3(vss) = msg {
+                    scenarios::shift_by_one(&mut vss.share.1)
+                }
+            }),
+        );
+
+        let (deletions, share_deleter) = counting_deleter();
+
+        let keys = load_keys("keys")?;
+        let mut simulation = Run::new(0x726573686172);
+        for (i, party) in old_committee.iter().enumerate() {
+            let start_state: BoxedState<OldKeyResharingTraits> = Box::new(
+                super::old_member::Phase1::new(
+                    key_of(&keys, *party),
+                    &new_params,
+                    &old_committee,
+                    &new_committee,
+                    None,
+                )?
+                .with_share_deleter(share_deleter.clone()),
+            );
+            let start_state = if i == 2 {
+                adversary.wrap(start_state)
+            } else {
+                start_state
+            };
+            simulation.add_node_with_filter(
+                format!("old {}", i),
+                *party,
+                start_state,
+                super::old_member::is_message_to_committee,
+                |outcome| outcome.map(|_| ()),
+            );
+        }
+        for (i, party) in new_committee.iter().enumerate() {
+            let start_state: BoxedState<KeyResharingTraits> =
+                Box::new(super::new_member::Phase1::new(
+                    &old_params,
+                    &new_params,
+                    &old_committee,
+                    &new_committee,
+                    *party,
+                    None,
+                    None,
+                )?);
+            simulation.add_node_with_filter(
+                format!("new {}", i),
+                *party,
+                start_state,
+                super::new_member::is_message_to_committee,
+                |outcome| outcome.map(|_| ()),
+            );
+        }
+
+        let report = simulation.run();
+        for node in &report.outcomes[old_committee.len()..] {
+            let error_state = match &node.outcome {
+                Some(Err(error_state)) => error_state,
+                outcome => bail!("{}: error expected, got {:?}", node.name, outcome),
+            };
+            match error_state.errors.as_slice() {
+                [ResharingError::InvalidVSS { party, .. }] => assert_eq!(*party, old_committee[2]),
+                errors => bail!("{}: unexpected errors {:?}", node.name, errors),
+            }
+            assert_eq!(error_state.blame.len(), 1);
+            assert_eq!(error_state.blame[0].party, old_committee[2]);
+        }
+        // no new member has prepared, hence old members keep their shares
+        for node in &report.outcomes[..old_committee.len()] {
+            assert!(
+                !matches!(node.outcome, Some(Ok(_))),
+                "{}: old member should not succeed",
+                node.name
+            );
+        }
+        assert_eq!(deletions.deletions.load(Ordering::SeqCst), 0);
         Ok(())
     }
 
+    #[test]
+    fn resharing_commits_despite_lost_commit() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let new_params = Parameters {
+            share_count: 4,
+            threshold: 1,
+        };
+        let old_committee = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+        let new_committee = (0..=3).map(PartyIndex::from).collect::<Vec<_>>();
+        let old_infos = load_keys("keys")?;
+        let timeout = Some(Duration::from_secs(10));
+        // `Commit` of old member 0 to new member 3 is lost
+        let adversary = Adversary::new(old_committee[0], &old_committee).in_phase(
+            "Old.phase3",
+            Action::Replay(
+                new_committee[..3]
+                    .iter()
+                    .map(|p| OutMsg {
+                        recipient: Address::Peer(*p),
+                        body: Message::Commit,
+                    })
+                    .collect(),
+            ),
+        );
+
+        let (deletions, share_deleter) = counting_deleter();
+
+        let mut simulation =
+            Run::<_, Result<Option<MultiPartyInfo>, ErrorState>>::new(0x636f6d6d6974);
+        for (i, info) in old_infos.iter().enumerate() {
+            let start_state: BoxedState<OldKeyResharingTraits> = Box::new(
+                old_member::Phase1::new(
+                    info,
+                    &new_params,
+                    &old_committee,
+                    &new_committee,
+                    timeout,
+                )?
+                .with_share_deleter(share_deleter.clone()),
+            );
+            let start_state = if i == 0 {
+                adversary.wrap(start_state)
+            } else {
+                start_state
+            };
+            simulation.add_node_with_filter(
+                format!("old {}", i),
+                info.own_party_index,
+                start_state,
+                old_member::is_message_to_committee,
+                |outcome| outcome.map(|_| None),
+            );
+        }
+        for (i, party) in new_committee.iter().enumerate() {
+            let start_state: BoxedState<KeyResharingTraits> = Box::new(new_member::Phase1::new(
+                &old_infos[0].key_params,
+                &new_params,
+                &old_committee,
+                &new_committee,
+                *party,
+                None,
+                timeout,
+            )?);
+            simulation.add_node_with_filter(
+                format!("new {}", i),
+                *party,
+                start_state,
+                new_member::is_message_to_committee,
+                |outcome| outcome.map(|final_state| Some(final_state.info)),
+            );
+        }
+
+        let mut new_infos = Vec::new();
+        for outcome in simulation.run().into_outcomes()? {
+            match outcome {
+                Ok(Some(info)) => new_infos.push(info),
+                Ok(None) => {}
+                Err(error_state) => bail!("resharing failed: {:?}", error_state),
+            }
+        }
+        // every new member has committed, hence every old member has deleted its share
+        assert_eq!(new_infos.len(), new_committee.len());
+        assert_eq!(
+            deletions.deletions.load(Ordering::SeqCst),
+            old_committee.len()
+        );
+        check_reshared_key(&old_infos[0], &new_infos, &new_committee);
+        Ok(())
+    }
+
+    #[test]
+    fn early_commit_is_not_enough_to_commit() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let new_params = Parameters {
+            share_count: 4,
+            threshold: 1,
+        };
+        let old_committee = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+        let new_committee = (0..=3).map(PartyIndex::from).collect::<Vec<_>>();
+        let old_infos = load_keys("keys")?;
+        let timeout = Some(Duration::from_secs(10));
+        // old member 0 sends `Commit` ahead of time and deals an invalid share to new member 3, which never gets prepared
+        let adversary = Adversary::new(old_committee[0], &old_committee)
+            .in_phase(
+                "Old.phase1",
+                Action::Append(
+                    new_committee
+                        .iter()
+                        .map(|p| OutMsg {
+                            recipient: Address::Peer(*p),
+                            body: Message::Commit,
+                        })
+                        .collect(),
+                ),
+            )
+            .in_phase(
+                "Old.phase2",
+                Action::Equivocate {
+                    victims: std::iter::once(new_committee[3]).collect(),
+                    mutate: |msg: &mut Message| {
+                        if let Message::R3(vss) = msg {
+                            scenarios::shift_by_one(&mut vss.share.1)
+                        }
+                    },
+                },
+            );
+
+        let (deletions, share_deleter) = counting_deleter();
+
+        let mut simulation =
+            Run::<_, Result<Option<MultiPartyInfo>, ErrorState>>::new(0x636f6d6d6974);
+        for (i, info) in old_infos.iter().enumerate() {
+            let start_state: BoxedState<OldKeyResharingTraits> = Box::new(
+                old_member::Phase1::new(
+                    info,
+                    &new_params,
+                    &old_committee,
+                    &new_committee,
+                    timeout,
+                )?
+                .with_share_deleter(share_deleter.clone()),
+            );
+            let start_state = if i == 0 {
+                adversary.wrap(start_state)
+            } else {
+                start_state
+            };
+            simulation.add_node_with_filter(
+                format!("old {}", i),
+                info.own_party_index,
+                start_state,
+                old_member::is_message_to_committee,
+                |outcome| outcome.map(|_| None),
+            );
+        }
+        for (i, party) in new_committee.iter().enumerate() {
+            let start_state: BoxedState<KeyResharingTraits> = Box::new(new_member::Phase1::new(
+                &old_infos[0].key_params,
+                &new_params,
+                &old_committee,
+                &new_committee,
+                *party,
+                None,
+                timeout,
+            )?);
+            simulation.add_node_with_filter(
+                format!("new {}", i),
+                *party,
+                start_state,
+                new_member::is_message_to_committee,
+                |outcome| outcome.map(|final_state| Some(final_state.info)),
+            );
+        }
+
+        let report = simulation.run();
+        for node in &report.outcomes {
+            if let Some(Ok(Some(_))) = &node.outcome {
+                bail!("{} has committed to the new share", node.name);
+            }
+        }
+        // prepared new members have received the early `Commit` only
+        for node in &report.outcomes[old_committee.len()..old_committee.len() + 3] {
+            match &node.outcome {
+                Some(Err(error_state)) => match error_state.errors.as_slice() {
+                    [ResharingError::Timeout(timeout)] => {
+                        assert_eq!(timeout.phase, "New.phase4");
+                        assert_eq!(
+                            timeout.responded,
+                            std::iter::once(old_committee[0]).collect::<BTreeSet<_>>()
+                        );
+                    }
+                    errors => bail!("{}: timeout expected, got {:?}", node.name, errors),
+                },
+                outcome => bail!("{}: error expected, got {:?}", node.name, outcome),
+            }
+        }
+        assert_eq!(deletions.deletions.load(Ordering::SeqCst), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn seeded_resharing_is_reproducible() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let new_params = Parameters {
+            share_count: 3,
+            threshold: 1,
+        };
+        let old_committee = (0..=1).map(PartyIndex::from).collect::<Vec<_>>();
+        let new_committee = (1..=3).map(PartyIndex::from).collect::<Vec<_>>();
+        let keys = load_keys("keys")?;
+        let run = |seed: u64| -> anyhow::Result<Vec<serde_json::Value>> {
+            let mut simulation = Run::<_, Result<Option<MultiPartyInfo>, ErrorState>>::new(seed);
+            for (i, party) in old_committee.iter().enumerate() {
+                let start_state: BoxedState<OldKeyResharingTraits> =
+                    Box::new(old_member::Phase1::new_with_rng(
+                        key_of(&keys, *party),
+                        &new_params,
+                        &old_committee,
+                        &new_committee,
+                        None,
+                        RandomnessSource::seeded(seed + i as u64),
+                    )?);
+                simulation.add_node_with_filter(
+                    format!("old {}", i),
+                    *party,
+                    start_state,
+                    old_member::is_message_to_committee,
+                    |outcome| outcome.map(|_| None),
+                );
+            }
+            for (i, party) in new_committee.iter().enumerate() {
+                let start_state: BoxedState<KeyResharingTraits> =
+                    Box::new(new_member::Phase1::new_with_rng(
+                        &keys[0].key_params,
+                        &new_params,
+                        &old_committee,
+                        &new_committee,
+                        *party,
+                        None,
+                        None,
+                        RandomnessSource::seeded(seed + (old_committee.len() + i) as u64),
+                    )?);
+                simulation.add_node_with_filter(
+                    format!("new {}", i),
+                    *party,
+                    start_state,
+                    new_member::is_message_to_committee,
+                    |outcome| outcome.map(|final_state| Some(final_state.info)),
+                );
+            }
+            let mut new_infos = Vec::new();
+            for outcome in simulation.run().into_outcomes()? {
+                match outcome {
+                    // values of JSON objects are ordered by keys, unlike hash maps of the key info
+                    Ok(Some(info)) => new_infos.push(serde_json::to_value(&info)?),
+                    Ok(None) => {}
+                    Err(error_state) => bail!("resharing failed: {:?}", error_state),
+                }
+            }
+            Ok(new_infos)
+        };
+
+        let (first, second) = (run(0x7265706c6179)?, run(0x7265706c6179)?);
+        assert_eq!(first.len(), new_committee.len());
+        assert_eq!(first, second);
+        Ok(())
+    }
+
+    #[test]
+    fn dual_member_resharing_with_overlapping_committees() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let new_params = Parameters {
+            share_count: 3,
+            threshold: 1,
+        };
+        // party 0 leaves, parties 1 and 2 stay, party 3 joins
+        let old_committee = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+        let new_committee = (1..=3).map(PartyIndex::from).collect::<Vec<_>>();
+        let old_infos = load_keys("keys")?;
+        let new_infos = simulate_dual_resharing(&old_committee, &new_committee, &new_params, None)?;
+        check_reshared_key(&old_infos[0], &new_infos, &new_committee);
+        Ok(())
+    }
+
+    #[test]
+    fn dual_member_key_refresh() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let params = Parameters {
+            share_count: 3,
+            threshold: 1,
+        };
+        let committee = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+        let old_infos = load_keys("keys")?;
+        let new_infos = simulate_dual_resharing(&committee, &committee, &params, None)?;
+        check_reshared_key(&old_infos[0], &new_infos, &committee);
+        for (old_info, new_info) in old_infos.iter().zip(new_infos.iter()) {
+            assert_eq!(new_info.own_party_index, old_info.own_party_index);
+            assert_ne!(
+                new_info.own_share().to_big_int(),
+                old_info.own_share().to_big_int(),
+                "the share of {} is not refreshed",
+                new_info.own_party_index
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn dual_member_defers_junk() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let new_params = Parameters {
+            share_count: 3,
+            threshold: 1,
+        };
+        // party 0 leaves, parties 1 and 2 stay, party 3 joins
+        let old_committee = (0..=2).map(PartyIndex::from).collect::<Vec<_>>();
+        let new_committee = (1..=3).map(PartyIndex::from).collect::<Vec<_>>();
+        let old_infos = load_keys("keys")?;
+
+        // the leaving party sends messages which only new members send, and `Commit` ahead of time
+        let junk = vec![Message::Prepared, Message::FinalAck, Message::Commit];
+        let adversary = Adversary::new(old_committee[0], &old_committee).in_phase(
+            "Old.phase1",
+            Action::Append(
+                new_committee[..2]
+                    .iter()
+                    .flat_map(|p| {
+                        junk.iter().map(move |body| OutMsg {
+                            recipient: Address::Peer(*p),
+                            body: body.clone(),
+                        })
+                    })
+                    .collect(),
+            ),
+        );
+        let new_infos =
+            simulate_dual_resharing(&old_committee, &new_committee, &new_params, Some(adversary))?;
+        check_reshared_key(&old_infos[0], &new_infos, &new_committee);
+
+        let mut dual = dual_member::Phase1::new(
+            key_of(&old_infos, new_committee[0]),
+            &new_params,
+            &old_committee,
+            &new_committee,
+            None,
+            None,
+        )?;
+        dual.start();
+        let unexpected = vec![
+            (old_committee[0], Message::Prepared),
+            (old_committee[0], Message::FinalAck),
+            (new_committee[2], Message::Commit),
+        ];
+        for (sender, body) in unexpected {
+            let msg = InputMessage { sender, body };
+            assert!(!dual.is_message_expected(&msg, &[]), "{:?}", msg);
+        }
+        Ok(())
+    }
+
+    /// Runs resharing where parties which are members of both committees run the dual role, returns new keys ordered as `new_committee`
+    ///
+    /// The first member of old committee is malicious, if `adversary` is given.
+    fn simulate_dual_resharing(
+        old_committee: &[PartyIndex],
+        new_committee: &[PartyIndex],
+        new_params: &Parameters,
+        adversary: Option<Adversary<Message>>,
+    ) -> anyhow::Result<Vec<MultiPartyInfo>> {
+        let keys = load_keys("keys")?;
+        let old_infos = old_committee
+            .iter()
+            .map(|party| key_of(&keys, *party))
+            .collect::<Vec<_>>();
+        let mut simulation = Run::<_, Result<Option<MultiPartyInfo>, ErrorState>>::new(0x6475616c)
+            .with_adversary(old_committee[0], adversary);
+        for info in old_infos.iter() {
+            let party = info.own_party_index;
+            if new_committee.contains(&party) {
+                let start_state: BoxedState<dual_member::KeyResharingTraits> =
+                    Box::new(dual_member::Phase1::new(
+                        info,
+                        new_params,
+                        old_committee,
+                        new_committee,
+                        None,
+                        None,
+                    )?);
+                simulation.add_node(format!("dual {}", party), party, start_state, |outcome| {
+                    outcome.map(|final_state| {
+                        assert!(final_state.old_share_released);
+                        Some(final_state.info)
+                    })
+                });
+            } else {
+                let start_state: BoxedState<OldKeyResharingTraits> = Box::new(
+                    old_member::Phase1::new(info, new_params, old_committee, new_committee, None)?,
+                );
+                simulation.add_node_with_filter(
+                    format!("old {}", party),
+                    party,
+                    start_state,
+                    old_member::is_message_to_committee,
+                    |outcome| outcome.map(|_| None),
+                );
+            }
+        }
+        for party in new_committee.iter().filter(|p| !old_committee.contains(p)) {
+            let start_state: BoxedState<KeyResharingTraits> = Box::new(new_member::Phase1::new(
+                &old_infos[0].key_params,
+                new_params,
+                old_committee,
+                new_committee,
+                *party,
+                None,
+                None,
+            )?);
+            simulation.add_node_with_filter(
+                format!("new {}", party),
+                *party,
+                start_state,
+                new_member::is_message_to_committee,
+                |outcome| outcome.map(|final_state| Some(final_state.info)),
+            );
+        }
+
+        let mut new_infos = Vec::new();
+        for outcome in simulation.run().into_outcomes()? {
+            match outcome {
+                Ok(Some(info)) => new_infos.push(info),
+                Ok(None) => {}
+                Err(error_state) => bail!("resharing failed: {:?}", error_state),
+            }
+        }
+        new_infos.sort_by_key(|info| {
+            new_committee
+                .iter()
+                .position(|p| *p == info.own_party_index)
+        });
+        assert_eq!(new_infos.len(), new_committee.len());
+        Ok(new_infos)
+    }
+
+    /// Checks that new keys share the key of `old_info` and that they belong to the next epoch
+    fn check_reshared_key(
+        old_info: &MultiPartyInfo,
+        new_infos: &[MultiPartyInfo],
+        new_committee: &[PartyIndex],
+    ) {
+        let secret_shares = new_infos
+            .iter()
+            .map(|info| info.secret_share)
+            .collect::<Vec<_>>();
+        let g: GE = ECPoint::generator();
+        assert_eq!(
+            (g * reconstruct(&secret_shares)).get_element(),
+            old_info.public_key.get_element()
+        );
+        let new_committee = new_committee.iter().cloned().collect::<BTreeSet<_>>();
+        for info in new_infos {
+            assert_eq!(
+                info.metadata,
+                old_info.metadata.next_epoch(new_committee.clone())
+            );
+            assert_eq!(
+                info.public_shares.get(&info.own_party_index),
+                Some(&(g * info.own_share()))
+            );
+        }
+    }
+
     pub fn reconstruct(secret_shares: &[SecretShare]) -> FE {
         let (points, shares): (Vec<FE>, Vec<FE>) = secret_shares
             .iter()
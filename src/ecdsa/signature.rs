@@ -59,16 +59,19 @@
 //!
 
 #![allow(non_snake_case)]
-use super::keygen::MultiPartyInfo;
+use super::keygen::{ASecretShareLoader, MultiPartyInfo, MultiPartyPublicInfo, SecretShareHolder};
 use super::messages::signing::{
     Phase3data, Phase5Com1, Phase5Com2, Phase5Decom1, Phase5Decom2, Phase5Edata,
     SignBroadcastPhase1, SignDecommitPhase4,
 };
 use super::signature::phase5::LocalSignature;
+use crate::ecdsa::blame::{Accusation, BlameKind};
 use crate::ecdsa::{
-    is_valid_curve_point, CommitmentScheme, MessageHashType, PaillierKeys, SigningParameters,
+    is_broadcast_input_complete, is_valid_curve_point, to_hash_map_gen, CommitmentScheme,
+    InputDigest, MessageHashType, PaillierKeys, PhaseTimeout, ProtocolError, SigningParameters,
 };
 use crate::protocol::{Address, PartyIndex};
+use crate::secret::Secret;
 
 use curv::cryptographic_primitives::hashing::hash_sha256::HSha256;
 use curv::cryptographic_primitives::hashing::traits::Hash;
@@ -84,23 +87,26 @@ use thiserror::Error;
 use std::collections::{BTreeSet, HashMap};
 
 pub use super::messages::signing::{InMsg, Message, OutMsg};
-use crate::state_machine::{State, StateMachineTraits, Transition};
+use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
 use std::time::Duration;
 
+use crate::algorithms::random::{self, RandomnessSource};
+use crate::algorithms::validation::{check_ciphertext, ValidationError};
 use crate::algorithms::zkp::MTAMode::{MtA, MtAwc};
 use crate::algorithms::zkp::{AliceProof, BobProofType, MessageA, MessageB};
 use crate::ecdsa::keygen::RangeProofSetups;
 use crate::ecdsa::signature::mta::MtaAliceOutput;
-use paillier::{Decrypt, EncryptionKey, Paillier, RawCiphertext};
+use paillier::{Decrypt, DecryptionKey, EncryptionKey, Paillier, RawCiphertext};
 use std::iter::FromIterator;
+use std::sync::Arc;
 use trace::trace;
 
 /// Enumerates error types which can be raised by signing protocol
 #[derive(Debug, Error)]
 #[allow(clippy::large_enum_variant)]
 pub enum SigningError {
-    #[error("signing: timeout in {phase}")]
-    Timeout { phase: String },
+    #[error("signing: {0}")]
+    Timeout(#[from] PhaseTimeout),
     #[error("unexpected message {message_type:?}, party {party}")]
     UnexpectedMessageType {
         message_type: Message,
@@ -139,14 +145,127 @@ pub enum SigningError {
     Phase5ValidationFailed,
     #[error("signature verification failed")]
     SignatureVerificationFailed,
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("party {party} holds the key share of epoch {epoch}, while own share is of epoch {own_epoch}, shares of the older epoch are stale")]
+    EpochMismatch {
+        party: PartyIndex,
+        epoch: u64,
+        own_epoch: u64,
+    },
     #[error("protocol setup error: {0}")]
     ProtocolSetupError(String),
     #[error("invalid public key {point}")]
     InvalidPublicKey { point: String },
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
     #[error("{0}")]
     GeneralError(String),
 }
 
+impl Accusation for SigningError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            SigningError::UnexpectedMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            SigningError::AliceRangeProofIncomplete { party, .. }
+            | SigningError::AliceProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidAliceProof))
+            }
+            SigningError::BobProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidBobProof))
+            }
+            SigningError::DlogProofFailed { party, .. } => {
+                Some((*party, BlameKind::InvalidDlogProof))
+            }
+            SigningError::InvalidDecommitment { party } => {
+                Some((*party, BlameKind::InvalidCommitment))
+            }
+            SigningError::InvalidElGamalProof { party } => {
+                Some((*party, BlameKind::InvalidElGamalProof))
+            }
+            SigningError::InputMismatch { party, .. } => Some((*party, BlameKind::InputMismatch)),
+            SigningError::EpochMismatch {
+                party,
+                epoch,
+                own_epoch,
+            } if epoch < own_epoch => Some((*party, BlameKind::StaleKeyShare)),
+            SigningError::InvalidMessage { party, .. } => Some((*party, BlameKind::InvalidMessage)),
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            SigningError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
+impl ProtocolError<Message> for SigningError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        SigningError::UnexpectedMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        SigningError::InvalidMessage { party, error }
+    }
+}
+
+/// loads own Paillier decryption key and checks that it matches the encryption key
+fn load_paillier_secret(
+    secret_loader: &ASecretShareLoader,
+    ek: &EncryptionKey,
+) -> Result<Secret<DecryptionKey>, SigningError> {
+    let dk = secret_loader
+        .get_paillier_secret()
+        .map(Secret::from_box)
+        .map_err(|e| SigningError::GeneralError(e.0))?;
+    if PaillierKeys::is_valid(ek, &dk) {
+        Ok(dk)
+    } else {
+        Err(SigningError::ProtocolSetupError(
+            "invalid own Paillier key".to_string(),
+        ))
+    }
+}
+
+/// checks that Paillier ciphertexts received from other parties belong to $` \mathbb{Z}_{N^2}^{\star} `$ of the key they are encrypted with
+fn check_ciphertexts<'a, I>(field: &'static str, ciphertexts: I) -> Result<(), Vec<SigningError>>
+where
+    I: IntoIterator<Item = (&'a PartyIndex, &'a BigInt, Option<&'a EncryptionKey>)>,
+{
+    let errors = ciphertexts
+        .into_iter()
+        .filter_map(|(party, c, ek)| {
+            ek.map_or(
+                Err(ValidationError::InvalidPaillierKey(
+                    "key not found".to_string(),
+                )),
+                |ek| check_ciphertext(field, c, ek),
+            )
+            .err()
+            .map(|error| SigningError::InvalidMessage {
+                party: *party,
+                error,
+            })
+        })
+        .collect::<Vec<_>>();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ECDSAError {
     #[error("{desc}")]
@@ -157,12 +276,11 @@ pub enum ECDSAError {
 mod mta {
     // Multiplication to addition
     use super::{
-        trace, AliceProof, BigInt, BobProofType, Decrypt, EncryptionKey, Paillier, PartyIndex,
-        RawCiphertext, SigningError,
+        trace, AliceProof, BigInt, BobProofType, Decrypt, DecryptionKey, EncryptionKey, Paillier,
+        PartyIndex, RawCiphertext, Secret, SigningError,
     };
     use crate::algorithms::zkp::BobProofType::{RangeProof, RangeProofExt};
     use crate::algorithms::zkp::{MessageA, ZkpSetup};
-    use crate::ecdsa::PaillierKeys;
     use curv::cryptographic_primitives::proofs::sigma_dlog::{DLogProof, ProveDLog};
     use curv::elliptic::curves::traits::{ECPoint, ECScalar};
     use curv::{FE, GE};
@@ -211,16 +329,17 @@ mod mta {
     ///
     /// Supports three choices of the proof:   `DLogProofs`, `BobProof`, and `BobProofExt`.
     /// Need the initial value of $` a `$ provided by Alice at the start of MtA
-    #[trace(pretty)]
+    #[trace(pretty, disable(a, alice_dk))]
     pub(crate) fn verify_bob_range_proof(
         party: &PartyIndex,
         proof: &BobProofType,
         mta_output: &BigInt,
         a: &FE,
         a_enc: &BigInt,
-        alice_keys: &PaillierKeys,
+        alice_ek: &EncryptionKey,
+        alice_dk: &DecryptionKey,
         alice_setup: Option<&ZkpSetup>,
-    ) -> Result<FE, Vec<SigningError>> {
+    ) -> Result<Secret<FE>, Vec<SigningError>> {
         match proof {
             RangeProof(_) | RangeProofExt(_) if alice_setup.is_none() => {
                 return Err(vec![SigningError::LocalZkpSetupNotFound { party: *party }])
@@ -228,15 +347,15 @@ mod mta {
             _ => {}
         }
 
-        let alice_share = Paillier::decrypt(&alice_keys.dk, RawCiphertext::from(mta_output));
-        let alice_share = alice_share.0.into_owned();
-        let alpha: FE = ECScalar::from(&alice_share);
+        let alice_share = Paillier::decrypt(alice_dk, RawCiphertext::from(mta_output));
+        let alice_share = Secret::new(alice_share.0.into_owned());
+        let alpha: Secret<FE> = Secret::new(ECScalar::from(&alice_share));
         let mut errors = Vec::new();
         match proof {
             // the simplified proof as defined in GG18, ch.5 , p.19
             BobProofType::DLogProofs(dlog_proofs) => {
                 let g: GE = ECPoint::generator();
-                let g_alpha = g * alpha;
+                let g_alpha = g * *alpha;
                 let ba_btag = dlog_proofs.b_proof.pk * a + dlog_proofs.beta_tag_proof.pk;
                 if DLogProof::verify(&dlog_proofs.b_proof).is_err() {
                     errors.push(SigningError::DlogProofFailed {
@@ -260,7 +379,7 @@ mod mta {
             }
             // Bob's range proof
             RangeProof(range_proof) => {
-                if !range_proof.verify(a_enc, &mta_output, &alice_keys.ek, &alice_setup.unwrap()) {
+                if !range_proof.verify(a_enc, &mta_output, alice_ek, &alice_setup.unwrap()) {
                     errors.push(SigningError::BobProofFailed {
                         party: *party,
                         proof: proof.clone(),
@@ -269,7 +388,7 @@ mod mta {
             }
             // Bob's range proof with proof of knowing b and beta_prim
             RangeProofExt(range_proof) => {
-                if !range_proof.verify(a_enc, &mta_output, &alice_keys.ek, &alice_setup.unwrap()) {
+                if !range_proof.verify(a_enc, &mta_output, alice_ek, &alice_setup.unwrap()) {
                     errors.push(SigningError::BobProofFailed {
                         party: *party,
                         proof: proof.clone(),
@@ -291,16 +410,20 @@ mod phase5 {
         trace, CommitmentScheme, ECDSAError, ECPoint, ECScalar, HSha256, Hash, MessageHashType, FE,
         GE,
     };
+    use crate::algorithms::random;
     use crate::ecdsa::messages::signing::{Phase5Com1, Phase5Com2, Phase5Decom1, Phase5Decom2};
     use crate::ecdsa::signature::ECDSAError::VerificationFailed;
     use crate::ecdsa::Signature;
+    use crate::secret::Erase;
     use curv::cryptographic_primitives::proofs::sigma_correct_homomorphic_elgamal_enc::{
         HomoELGamalProof, HomoElGamalStatement, HomoElGamalWitness,
     };
-    use serde::{Deserialize, Serialize};
+    use rand::{CryptoRng, RngCore};
+    use std::fmt;
+    use std::fmt::{Debug, Formatter};
 
     /// Represents the partial signature used by multiple sub-phases of phase 5 of the protocol
-    #[derive(Clone, Debug, Serialize, Deserialize)]
+    #[derive(Clone)]
     pub struct LocalSignature {
         pub l_i: FE,
         pub rho_i: FE,
@@ -308,17 +431,44 @@ mod phase5 {
         pub s_i: FE,
     }
 
+    impl Debug for LocalSignature {
+        /// hides secret values, $` s_{i} `$ is revealed in phase 5E only
+        fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+            f.debug_struct("LocalSignature")
+                .field("l_i", &"[***]".to_owned())
+                .field("rho_i", &"[***]".to_owned())
+                .field("R", &self.R)
+                .field("s_i", &"[***]".to_owned())
+                .finish()
+        }
+    }
+
+    impl Erase for LocalSignature {
+        fn erase(&mut self) {
+            self.l_i.erase();
+            self.rho_i.erase();
+            self.s_i.erase();
+        }
+    }
+
     #[trace(pretty, prefix = "LocalSignature::")]
     impl LocalSignature {
         /// Initializes the data with $` R, \space k_{i}, \space \sigma_{i} `$ .
         /// Sets (t,t) sharing of the desired signature to $` s_{i} = m k_{i} + r \sigma_{i} `$.
         /// Chooses  $` \ell_{i}, \space \rho_{i}  \underset{R}{\in} Z_q `$     
-        pub fn new(message_hash: &MessageHashType, R: &GE, k_i: &FE, sigma_i: &FE) -> Self {
+        #[trace(disable(k_i, sigma_i, rng))]
+        pub fn new<T: RngCore + CryptoRng + ?Sized>(
+            message_hash: &MessageHashType,
+            R: &GE,
+            k_i: &FE,
+            sigma_i: &FE,
+            rng: &mut T,
+        ) -> Self {
             // H'(R) = Rx mod q
             let r: FE = ECScalar::from(&R.x_coor().unwrap().mod_floor(&FE::q()));
             let s_i = (*message_hash) * k_i + r * sigma_i; // <- partial signature
-            let l_i: FE = ECScalar::new_random();
-            let rho_i: FE = ECScalar::new_random();
+            let l_i: FE = random::sample_scalar(rng);
+            let rho_i: FE = random::sample_scalar(rng);
             Self {
                 l_i,
                 rho_i,
@@ -328,14 +478,18 @@ mod phase5 {
         }
 
         /// generates (Comm,Decomm) for $` V_{i} , \space A_{i} `$
-        pub fn phase5b_proof(&self) -> (Phase5Com1, Phase5Decom1) {
+        #[trace(disable(rng))]
+        pub fn phase5b_proof<R: RngCore + CryptoRng + ?Sized>(
+            &self,
+            rng: &mut R,
+        ) -> (Phase5Com1, Phase5Decom1) {
             let g: GE = ECPoint::generator();
             let A_i = g * self.rho_i;
             let l_i_rho_i = self.l_i.mul(&self.rho_i.get_element());
             let V_i = self.R * self.s_i + g * self.l_i;
             let B_i = g * l_i_rho_i;
             let input_hash = HSha256::create_hash_from_ge(&[&V_i, &A_i, &B_i]).to_big_int();
-            let commitment_scheme = CommitmentScheme::from_BigInt(&input_hash);
+            let commitment_scheme = CommitmentScheme::from_BigInt(&input_hash, rng);
 
             let witness = HomoElGamalWitness {
                 r: self.l_i,
@@ -364,11 +518,17 @@ mod phase5 {
         }
 
         /// generates (Comm, Decomm) for $` U_{i}, \space T_{i} `$
-        pub fn phase5d_proof(&self, v: GE, a: GE) -> (Phase5Com2, Phase5Decom2) {
+        #[trace(disable(rng))]
+        pub fn phase5d_proof<R: RngCore + CryptoRng + ?Sized>(
+            &self,
+            v: GE,
+            a: GE,
+            rng: &mut R,
+        ) -> (Phase5Com2, Phase5Decom2) {
             let u_i = v * self.rho_i;
             let t_i = a * self.l_i;
             let input_hash = HSha256::create_hash_from_ge(&[&u_i, &t_i]).to_big_int();
-            let scheme = CommitmentScheme::from_BigInt(&input_hash);
+            let scheme = CommitmentScheme::from_BigInt(&input_hash, rng);
             (
                 Phase5Com2 { com: scheme.comm },
                 Phase5Decom2 {
@@ -422,59 +582,9 @@ pub struct SignedMessage {
     pub hash: MessageHashType,
 }
 
-/// vector of signing errors
-#[derive(Debug)]
-pub struct ErrorState {
-    errors: Vec<SigningError>,
-}
+/// vector of signing errors and of blame records derived from these errors
+pub type ErrorState = super::ErrorState<SigningError, Message>;
 
-impl ErrorState {
-    pub fn new(errors: Vec<SigningError>) -> Self {
-        ErrorState { errors }
-    }
-}
-
-/// Checks whether all expected messages have been received so far from other parties  
-fn is_broadcast_input_complete(
-    current_msg_set: &[InMsg],
-    other_parties: &BTreeSet<PartyIndex>,
-) -> bool {
-    let senders = current_msg_set.iter().map(|m| m.sender).collect::<Vec<_>>();
-    other_parties.iter().all(|p| senders.contains(p))
-}
-
-/// Extracts payloads form enum variants of input message into the hash map
-#[trace(disable(current_msg_set), res = "{:?}")]
-fn to_hash_map_gen<K, V>(current_msg_set: Vec<InMsg>) -> Result<HashMap<K, V>, SigningError>
-where
-    K: Eq + std::hash::Hash + From<PartyIndex> + std::fmt::Debug,
-    V: std::fmt::Debug,
-    Option<V>: From<Message>,
-{
-    let content = current_msg_set
-        .iter()
-        .map(|m| {
-            let body: Option<V> = m.body.clone().into();
-            (m.sender, m.body.clone(), body)
-        })
-        .collect::<Vec<(_, _, _)>>();
-
-    // returns first failed result of  into() operation on a message
-    if let Some((p, m)) = content.iter().find_map(|(party, msg_type, body)| {
-        body.as_ref()
-            .map_or_else(|| Some((party, msg_type)), |_| None)
-    }) {
-        Err(SigningError::UnexpectedMessageType {
-            message_type: m.clone(),
-            party: *p,
-        })
-    } else {
-        Ok(content
-            .into_iter()
-            .map(|(party, _message, body)| (party.into(), body.unwrap()))
-            .collect::<HashMap<K, V>>())
-    }
-}
 /// First phase of the signing protocol
 ///
 /// * Initializes the protocol, see [`Phase1::new`](struct.Phase1.html#method.new)
@@ -484,13 +594,15 @@ where
 #[derive(Debug)]
 pub struct Phase1 {
     pub params: SigningParameters,
-    pub multi_party_info: MultiPartyInfo,
+    pub multi_party_info: MultiPartyPublicInfo,
+    secret_loader: ASecretShareLoader,
     other_parties: BTreeSet<PartyIndex>,
-    gamma_i: FE,
-    k_i: FE,
+    gamma_i: Secret<FE>,
+    k_i: Secret<FE>,
     mta_a: MtaAliceOutput,
     comm_scheme: CommitmentScheme,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 #[trace(pretty, prefix = "Phase1::")]
@@ -505,6 +617,58 @@ impl Phase1 {
         multi_party_info: MultiPartyInfo,
         parties: &[PartyIndex],
         timeout: Option<Duration>,
+    ) -> Result<Self, SigningError> {
+        Self::new_with_rng(
+            message_hash,
+            multi_party_info,
+            parties,
+            timeout,
+            RandomnessSource::os(),
+        )
+    }
+
+    /// Initializes the protocol, which draws its secrets, nonces and blinding factors from `rng` in this and all subsequent phases
+    ///
+    /// Randomness of discrete log and ElGamal proofs is sampled by `curv` internally and does not depend on `rng`.
+    /// The secrets of `multi_party_info` are moved to [`SecretShareHolder`](../keygen/struct.SecretShareHolder.html), which is dropped after phase 2.
+    pub fn new_with_rng(
+        message_hash: MessageHashType,
+        multi_party_info: MultiPartyInfo,
+        parties: &[PartyIndex],
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    ) -> Result<Self, SigningError> {
+        if !PaillierKeys::is_valid(
+            &multi_party_info.own_he_keys.ek,
+            &multi_party_info.own_he_keys.dk,
+        ) {
+            return Err(SigningError::ProtocolSetupError(format!(
+                "invalid own Paillier key {}",
+                &multi_party_info.own_he_keys
+            )));
+        }
+        let (public_info, secrets) = SecretShareHolder::split(multi_party_info);
+        Self::new_with_loader(
+            message_hash,
+            public_info,
+            Arc::new(Box::new(secrets)),
+            parties,
+            timeout,
+            rng,
+        )
+    }
+
+    /// Initializes the protocol which loads the secret share and the Paillier decryption key through `secret_loader`
+    ///
+    /// The secrets are loaded in the phases where they are used and erased right afterwards. The decryption key is checked against the encryption key
+    /// when it is loaded, the protocol fails with [`SigningError::ProtocolSetupError`](enum.SigningError.html#variant.ProtocolSetupError) if they do not match.
+    pub fn new_with_loader(
+        message_hash: MessageHashType,
+        multi_party_info: MultiPartyPublicInfo,
+        secret_loader: ASecretShareLoader,
+        parties: &[PartyIndex],
+        timeout: Option<Duration>,
+        mut rng: RandomnessSource,
     ) -> Result<Self, SigningError> {
         let signing_parties = BTreeSet::from_iter(parties.iter().cloned());
         if signing_parties.len() != parties.len() {
@@ -551,45 +715,38 @@ impl Phase1 {
             )));
         }
 
-        if !PaillierKeys::is_valid(
-            &multi_party_info.own_he_keys.ek,
-            &multi_party_info.own_he_keys.dk,
-        ) {
-            return Err(SigningError::ProtocolSetupError(format!(
-                "invalid own Paillier key {}",
-                &multi_party_info.own_he_keys
-            )));
-        }
-
         let public_key = multi_party_info.public_key.get_element();
         if !is_valid_curve_point(public_key) {
             return Err(SigningError::InvalidPublicKey {
                 point: format!("{:?}", public_key),
             });
         }
-        let k_i = ECScalar::new_random();
+        let k_i = Secret::new(random::sample_scalar(&mut rng));
+        let own_ek = &multi_party_info.own_ek;
 
         let mta_a = if let Some(setups) = &multi_party_info.range_proof_setups {
+            // sorted, so that seeded generators produce the same messages
+            let mut party_setups = setups.party_setups.iter().collect::<Vec<_>>();
+            party_setups.sort_by_key(|(p, _)| **p);
             MtaAliceOutput::WithRangeProofs(
-                setups
-                    .party_setups
-                    .iter()
+                party_setups
+                    .into_iter()
                     .map(|(p, setup)| {
                         (
                             *p,
-                            MessageA::new(&k_i, &multi_party_info.own_he_keys.ek, Some(setup)),
+                            MessageA::new_with_rng(&k_i, own_ek, Some(setup), &mut rng),
                         )
                     })
                     .collect::<HashMap<_, _>>(),
             )
         } else {
-            MtaAliceOutput::Simple(MessageA::new(&k_i, &multi_party_info.own_he_keys.ek, None))
+            MtaAliceOutput::Simple(MessageA::new_with_rng(&k_i, own_ek, None, &mut rng))
         };
 
-        let gamma_i: FE = ECScalar::new_random();
+        let gamma_i: Secret<FE> = Secret::new(random::sample_scalar(&mut rng));
         let g: GE = ECPoint::generator();
-        let g_gamma_i = g * gamma_i;
-        let comm_scheme = CommitmentScheme::from_GE(&g_gamma_i);
+        let g_gamma_i = g * *gamma_i;
+        let comm_scheme = CommitmentScheme::from_GE(&g_gamma_i, &mut rng);
 
         Ok(Phase1 {
             params: SigningParameters {
@@ -598,12 +755,14 @@ impl Phase1 {
                 message_hash,
             },
             multi_party_info,
+            secret_loader,
             other_parties,
             gamma_i,
             k_i,
             mta_a,
             comm_scheme,
             timeout,
+            rng,
         })
     }
     /// Checks if Shamir's secret sharing points are known for each other party involved into the signing protocol
@@ -621,6 +780,34 @@ impl Phase1 {
 
 #[trace(pretty, prefix = "Phase1::")]
 impl Phase1 {
+    /// digest of the message hash, signing parties and the key which all signers have to agree on
+    fn input_digest(&self) -> InputDigest {
+        InputDigest::new()
+            .with_scalar("message_hash", &self.params.message_hash)
+            .with_parties("signing_parties", &self.params.signing_parties)
+            .with_parameters("key_parameters", &self.params.keygen_params)
+            .with_point("public_key", &self.multi_party_info.public_key)
+            .with_key_id("key_id", &self.multi_party_info.metadata.key_id)
+    }
+
+    /// reports parties whose key shares are of another epoch than ours
+    #[trace(disable(epochs))]
+    fn epoch_mismatches<'a, I>(&self, epochs: I) -> Vec<SigningError>
+    where
+        I: IntoIterator<Item = (&'a PartyIndex, u64)>,
+    {
+        let own_epoch = self.multi_party_info.metadata.epoch;
+        epochs
+            .into_iter()
+            .filter(|(_, epoch)| *epoch != own_epoch)
+            .map(|(party, epoch)| SigningError::EpochMismatch {
+                party: *party,
+                epoch,
+                own_epoch,
+            })
+            .collect()
+    }
+
     ///  verifies that every party sent correct Alice's MtA input
     fn verify_alice_range_proofs(
         &self,
@@ -655,12 +842,15 @@ impl State<SigningTraits> for Phase1 {
     fn start(&mut self) -> Option<OutMsgVec> {
         log::info!("Phase 1 starts");
 
+        let inputs = self.input_digest();
         let output = match &self.mta_a {
             MtaAliceOutput::Simple(msg) => vec![OutMsg {
                 recipient: Address::Broadcast,
                 body: Message::R1(SignBroadcastPhase1 {
                     com: self.comm_scheme.comm.clone(),
                     mta_a: msg.clone(),
+                    inputs,
+                    epoch: self.multi_party_info.metadata.epoch,
                 }),
             }],
             MtaAliceOutput::WithRangeProofs(map) => map
@@ -670,6 +860,8 @@ impl State<SigningTraits> for Phase1 {
                     body: Message::R1(SignBroadcastPhase1 {
                         com: self.comm_scheme.comm.clone(),
                         mta_a: msg.clone(),
+                        inputs: inputs.clone(),
+                        epoch: self.multi_party_info.metadata.epoch,
                     }),
                 })
                 .collect::<Vec<_>>(),
@@ -690,15 +882,28 @@ impl State<SigningTraits> for Phase1 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
-        let responses = match to_hash_map_gen::<PartyIndex, SignBroadcastPhase1>(current_msg_set) {
+        let responses = match to_hash_map_gen::<SignBroadcastPhase1, _, _>(&current_msg_set) {
             Err(e) => {
-                let error_state = ErrorState::new(vec![e]);
+                let error_state = ErrorState::new(e).with_evidence("phase1", &current_msg_set);
                 log::error!("Phase 1 returns {:?}", error_state);
                 return Transition::FinalState(Err(error_state));
             }
             Ok(msg_map) => msg_map,
         };
 
+        let mut mismatches = self
+            .input_digest()
+            .mismatches(responses.iter().map(|(p, m)| (p, &m.inputs)))
+            .into_iter()
+            .map(|(party, field)| SigningError::InputMismatch { party, field })
+            .collect::<Vec<_>>();
+        mismatches.extend(self.epoch_mismatches(responses.iter().map(|(p, m)| (p, m.epoch))));
+        if !mismatches.is_empty() {
+            let error_state = ErrorState::new(mismatches).with_evidence("phase1", &current_msg_set);
+            log::error!("Phase 1 returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
         let mta_inputs = responses
             .iter()
             .map(|(party, msg)| (*party, msg.mta_a.clone()))
@@ -708,9 +913,24 @@ impl State<SigningTraits> for Phase1 {
             .map(|(party, msg)| (*party, msg.com.clone()))
             .collect::<HashMap<_, _>>();
 
+        if let Err(e) = check_ciphertexts(
+            "MessageA.c",
+            mta_inputs.iter().map(|(party, msg)| {
+                (
+                    party,
+                    &msg.c,
+                    self.multi_party_info.party_he_keys.get(party),
+                )
+            }),
+        ) {
+            let error_state = ErrorState::new(e).with_evidence("phase1", &current_msg_set);
+            log::error!("Phase 1 returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
         if let Some(range_proof_setup) = &self.multi_party_info.range_proof_setups {
             if let Err(e) = self.verify_alice_range_proofs(&mta_inputs, range_proof_setup) {
-                let error_state = ErrorState::new(e);
+                let error_state = ErrorState::new(e).with_evidence("phase1", &current_msg_set);
                 log::error!("Phase 1 returns {:?}", error_state);
                 return Transition::FinalState(Err(error_state));
             }
@@ -718,22 +938,30 @@ impl State<SigningTraits> for Phase1 {
         Transition::NewState(Box::new(Phase2a {
             params: self.params.clone(),
             multi_party_info: self.multi_party_info.clone(),
+            secret_loader: self.secret_loader.clone(),
             other_parties: self.other_parties.clone(),
-            gamma_i: self.gamma_i,
-            k_i: self.k_i,
+            gamma_i: Secret::new(*self.gamma_i),
+            k_i: Secret::new(*self.k_i),
             comm_scheme: self.comm_scheme.clone(),
             mta_inputs,
             commitments,
             mta_a: self.mta_a.clone(),
-            beta_outputs: HashMap::new(),
+            beta_outputs: Secret::new(HashMap::new()),
             timeout: self.timeout,
+            rng: self.rng.clone(),
         }))
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase1".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase1", &self.other_parties, current_msg_set)
     }
 
     fn timeout(&self) -> Option<Duration> {
@@ -748,16 +976,45 @@ impl State<SigningTraits> for Phase1 {
 /// * Computes $` \delta_{i} = k_{i}\gamma_{i} + \sum_{i \not = j} \alpha_{ij} + \sum_{i \not = j} \beta_{ij} `$
 struct Phase2a {
     params: SigningParameters,
-    multi_party_info: MultiPartyInfo,
+    multi_party_info: MultiPartyPublicInfo,
+    secret_loader: ASecretShareLoader,
     other_parties: BTreeSet<PartyIndex>,
-    gamma_i: FE,
-    k_i: FE,
+    gamma_i: Secret<FE>,
+    k_i: Secret<FE>,
     comm_scheme: CommitmentScheme,
     commitments: HashMap<PartyIndex, BigInt>,
     mta_a: MtaAliceOutput,
     mta_inputs: HashMap<PartyIndex, MessageA>,
-    beta_outputs: HashMap<PartyIndex, FE>,
+    beta_outputs: Secret<HashMap<PartyIndex, FE>>,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
+}
+
+// not traced, as the result is secret
+impl Phase2a {
+    /// converts own secret share to the additive share $` w_{i} `$ of the signing key, using Lagrange coefficient for the set of signing parties
+    fn additive_share(&self) -> Result<Secret<FE>, SigningError> {
+        let x_i = self
+            .secret_loader
+            .get_secret_share()
+            .map(Secret::from_box)
+            .map_err(|e| SigningError::GeneralError(e.0))?;
+        let own_x: FE = ECScalar::from(&BigInt::from(self.multi_party_info.own_point as u64));
+
+        let signing_parties_as_vec = self
+            .params
+            .signing_parties
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let multiplier = self
+            .multi_party_info
+            .party_to_point_map
+            .calculate_lagrange_multiplier(signing_parties_as_vec.as_slice(), own_x);
+        Ok(Secret::new(*x_i * multiplier))
+        // x_i is erased here
+    }
 }
 
 #[trace(pretty, prefix = "Phase2a::")]
@@ -765,7 +1022,9 @@ impl State<SigningTraits> for Phase2a {
     fn start(&mut self) -> Option<OutMsgVec> {
         log::debug!("Phase 2a starts");
         let mut result = Vec::new();
-        for (party, messageA) in &self.mta_inputs {
+        let mut mta_inputs = self.mta_inputs.iter().collect::<Vec<_>>();
+        mta_inputs.sort_by_key(|(party, _)| **party);
+        for (party, messageA) in mta_inputs {
             if let Some(party_ek) = self.multi_party_info.party_he_keys.get(party) {
                 let alice_zkp_setup = self
                     .multi_party_info
@@ -773,14 +1032,15 @@ impl State<SigningTraits> for Phase2a {
                     .as_ref()
                     .map(|s| s.party_setups.get(party).expect("zkp setup not found"));
 
-                let (message, beta_prime) = MessageB::new(
+                let (message, beta_prime) = MessageB::new_with_rng(
                     &self.gamma_i,
                     party_ek,
                     alice_zkp_setup,
                     messageA,
                     MtA, // first round of Mta goes without extra checks
+                    &mut self.rng,
                 );
-                self.beta_outputs.insert(*party, beta_prime);
+                self.beta_outputs.insert(*party, *beta_prime);
                 result.push(OutMsg {
                     recipient: Address::Peer(*party),
                     body: Message::R2(message),
@@ -812,15 +1072,34 @@ impl State<SigningTraits> for Phase2a {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
-        let responses = match to_hash_map_gen::<PartyIndex, MessageB>(current_msg_set) {
+        let responses = match to_hash_map_gen::<MessageB, _, _>(&current_msg_set) {
             Ok(map) => map,
             Err(e) => {
-                let error_state = ErrorState::new(vec![e]);
+                let error_state = ErrorState::new(e).with_evidence("phase2a", &current_msg_set);
                 log::error!("Phase 2a returns {:?}", error_state);
                 return Transition::FinalState(Err(error_state));
             }
         };
 
+        if let Err(e) = check_ciphertexts(
+            "MessageB.c",
+            responses
+                .iter()
+                .map(|(party, msg)| (party, &msg.c, Some(&self.multi_party_info.own_ek))),
+        ) {
+            let error_state = ErrorState::new(e).with_evidence("phase2a", &current_msg_set);
+            log::error!("Phase 2a returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
+        let dk = match load_paillier_secret(&self.secret_loader, &self.multi_party_info.own_ek) {
+            Ok(dk) => dk,
+            Err(e) => {
+                log::error!("Phase 2a can't load the secret: {}", e);
+                return Transition::FinalState(Err(ErrorState::new(vec![e])));
+            }
+        };
+
         let mut errors = Vec::new();
 
         let mut alpha_vec = Vec::new();
@@ -844,7 +1123,8 @@ impl State<SigningTraits> for Phase2a {
                 &msg.c,
                 &self.k_i,
                 &mta_a_message.c,
-                &self.multi_party_info.own_he_keys,
+                &self.multi_party_info.own_ek,
+                &dk,
                 my_setup,
             ) {
                 Ok(alpha) => alpha_vec.push(alpha),
@@ -853,41 +1133,57 @@ impl State<SigningTraits> for Phase2a {
         }
 
         if !errors.is_empty() {
-            let error_state = ErrorState::new(errors);
+            let error_state = ErrorState::new(errors).with_evidence("phase2a", &current_msg_set);
             log::error!("Phase 2a returns {:?}", error_state);
             return Transition::FinalState(Err(error_state));
         }
 
-        let ki_gamma_i = self.k_i.mul(&self.gamma_i.get_element());
-        let delta_i = alpha_vec.iter().fold(FE::zero(), |acc, x| acc + x)
+        let ki_gamma_i = Secret::new(self.k_i.mul(&self.gamma_i.get_element()));
+        let delta_i = alpha_vec.iter().fold(FE::zero(), |acc, x| acc + **x)
             + self
                 .beta_outputs
                 .values()
                 .fold(FE::zero(), |acc, x| acc + x)
-            + ki_gamma_i;
+            + *ki_gamma_i;
         // k * gamma = sum(delta) across the cluster
 
+        let w_i = match self.additive_share() {
+            Ok(w_i) => w_i,
+            Err(e) => {
+                log::error!("Phase 2a can't load the secret: {}", e);
+                return Transition::FinalState(Err(ErrorState::new(vec![e])));
+            }
+        };
+
         Transition::NewState(Box::new(Phase2b {
             params: self.params.clone(),
             multi_party_shared_info: self.multi_party_info.clone(),
+            secret_loader: self.secret_loader.clone(),
             other_parties: self.other_parties.clone(),
-            gamma_i: self.gamma_i,
-            k_i: self.k_i,
-            w_i: FE::zero(),
+            gamma_i: Secret::new(*self.gamma_i),
+            k_i: Secret::new(*self.k_i),
+            w_i,
             comm_scheme: self.comm_scheme.clone(),
             commitments: self.commitments.clone(),
             mta_a: self.mta_a.clone(),
             mta_inputs: self.mta_inputs.clone(),
             delta_i,
-            omega_outputs: HashMap::new(),
+            omega_outputs: Secret::new(HashMap::new()),
             timeout: self.timeout,
+            rng: self.rng.clone(),
         }))
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase2a".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase2a", &self.other_parties, current_msg_set)
     }
 
     fn timeout(&self) -> Option<Duration> {
@@ -901,55 +1197,46 @@ impl State<SigningTraits> for Phase2a {
 /// * Computes $` \sigma_{i} = k_{i}\omega_{i} + \sum_{i \not = j} \mu_{ij} + \sum_{i \not = j} \upsilon_{ij} `$, where $` \mu, \space \upsilon `$ have same meaning as $` \alpha , \space \beta `$ in part A
 struct Phase2b {
     params: SigningParameters,
-    multi_party_shared_info: MultiPartyInfo,
+    multi_party_shared_info: MultiPartyPublicInfo,
+    secret_loader: ASecretShareLoader,
     other_parties: BTreeSet<PartyIndex>,
-    gamma_i: FE,
-    k_i: FE,
-    w_i: FE,
+    gamma_i: Secret<FE>,
+    k_i: Secret<FE>,
+    w_i: Secret<FE>,
     comm_scheme: CommitmentScheme,
     commitments: HashMap<PartyIndex, BigInt>,
     mta_a: MtaAliceOutput,
     mta_inputs: HashMap<PartyIndex, MessageA>,
     delta_i: FE,
-    omega_outputs: HashMap<PartyIndex, FE>,
+    omega_outputs: Secret<HashMap<PartyIndex, FE>>,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 #[trace(pretty, prefix = "Phase2b::")]
 impl State<SigningTraits> for Phase2b {
     fn start(&mut self) -> Option<OutMsgVec> {
         log::debug!("Phase 2b starts");
-        // calculate new lagrange coefficients according to teh list of parties which will sign
-
-        let x_i: FE = self.multi_party_shared_info.own_share();
-        let own_x: FE = ECScalar::from(&BigInt::from(
-            self.multi_party_shared_info.own_point() as u64
-        ));
-
-        let signing_parties_as_vec = self
-            .params
-            .signing_parties
-            .iter()
-            .cloned()
-            .collect::<Vec<_>>();
-
-        let multiplier = self
-            .multi_party_shared_info
-            .party_to_point_map
-            .calculate_lagrange_multiplier(signing_parties_as_vec.as_slice(), own_x);
-        self.w_i = x_i * multiplier;
 
         let mut result = Vec::new();
-        for (party, messageA) in &self.mta_inputs {
+        let mut mta_inputs = self.mta_inputs.iter().collect::<Vec<_>>();
+        mta_inputs.sort_by_key(|(party, _)| **party);
+        for (party, messageA) in mta_inputs {
             if let Some(party_ek) = self.multi_party_shared_info.party_he_keys.get(party) {
                 let alice_zkp_setup = self
                     .multi_party_shared_info
                     .range_proof_setups
                     .as_ref()
                     .map(|s| s.party_setups.get(party).expect("zkp setup not found"));
-                let (message, beta_prime) =
-                    MessageB::new(&self.w_i, party_ek, alice_zkp_setup, messageA, MtAwc);
-                self.omega_outputs.insert(*party, beta_prime);
+                let (message, beta_prime) = MessageB::new_with_rng(
+                    &self.w_i,
+                    party_ek,
+                    alice_zkp_setup,
+                    messageA,
+                    MtAwc,
+                    &mut self.rng,
+                );
+                self.omega_outputs.insert(*party, *beta_prime);
                 result.push(OutMsg {
                     recipient: Address::Peer(*party),
                     body: Message::R2b(message),
@@ -980,15 +1267,35 @@ impl State<SigningTraits> for Phase2b {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
-        let responses = match to_hash_map_gen::<PartyIndex, MessageB>(current_msg_set) {
+        let responses = match to_hash_map_gen::<MessageB, _, _>(&current_msg_set) {
             Ok(map) => map,
             Err(e) => {
-                let error_state = ErrorState::new(vec![e]);
+                let error_state = ErrorState::new(e).with_evidence("phase2b", &current_msg_set);
                 log::error!("Phase 2b returns {:?}", error_state);
                 return Transition::FinalState(Err(error_state));
             }
         };
 
+        if let Err(e) = check_ciphertexts(
+            "MessageB.c",
+            responses
+                .iter()
+                .map(|(party, msg)| (party, &msg.c, Some(&self.multi_party_shared_info.own_ek))),
+        ) {
+            let error_state = ErrorState::new(e).with_evidence("phase2b", &current_msg_set);
+            log::error!("Phase 2b returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
+        let dk =
+            match load_paillier_secret(&self.secret_loader, &self.multi_party_shared_info.own_ek) {
+                Ok(dk) => dk,
+                Err(e) => {
+                    log::error!("Phase 2b can't load the secret: {}", e);
+                    return Transition::FinalState(Err(ErrorState::new(vec![e])));
+                }
+            };
+
         let mut errors = Vec::new();
 
         let mut alpha_vec = Vec::new();
@@ -1012,7 +1319,8 @@ impl State<SigningTraits> for Phase2b {
                 &msg.c,
                 &self.k_i,
                 &mta_a_message.c,
-                &self.multi_party_shared_info.own_he_keys,
+                &self.multi_party_shared_info.own_ek,
+                &dk,
                 my_setup,
             ) {
                 Ok(alpha) => alpha_vec.push(alpha),
@@ -1021,31 +1329,34 @@ impl State<SigningTraits> for Phase2b {
         }
 
         if !errors.is_empty() {
-            let error_state = ErrorState::new(errors);
+            let error_state = ErrorState::new(errors).with_evidence("phase2b", &current_msg_set);
             log::error!("Phase 2b returns {:?}", error_state);
             return Transition::FinalState(Err(error_state));
         }
 
-        let ki_w_i = self.k_i.mul(&self.w_i.get_element());
-        let sigma_i = alpha_vec.iter().fold(FE::zero(), |acc, x| acc + x)
-            + self
-                .omega_outputs
-                .values()
-                .fold(FE::zero(), |acc, x| acc + x)
-            + ki_w_i;
+        let ki_w_i = Secret::new(self.k_i.mul(&self.w_i.get_element()));
+        let sigma_i = Secret::new(
+            alpha_vec.iter().fold(FE::zero(), |acc, x| acc + **x)
+                + self
+                    .omega_outputs
+                    .values()
+                    .fold(FE::zero(), |acc, x| acc + x)
+                + *ki_w_i,
+        );
         // k * w = sum(sigma) across the cluster, check the paper
 
         Transition::NewState(Box::new(Phase3 {
             params: self.params.clone(),
             multi_party_info: self.multi_party_shared_info.clone(),
             other_parties: self.other_parties.clone(),
-            gamma_i: self.gamma_i,
-            k_i: self.k_i,
+            gamma_i: Secret::new(*self.gamma_i),
+            k_i: Secret::new(*self.k_i),
             comm_scheme: self.comm_scheme.clone(),
             commitments: self.commitments.clone(),
             delta_i: self.delta_i,
             sigma_i,
             timeout: self.timeout,
+            rng: self.rng.clone(),
         }))
     }
 
@@ -1053,10 +1364,16 @@ impl State<SigningTraits> for Phase2b {
         self.timeout
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase2b".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase2b", &self.other_parties, current_msg_set)
     }
 }
 /// Third phase of the protocol
@@ -1065,15 +1382,16 @@ impl State<SigningTraits> for Phase2b {
 /// * Reconstructs $` \delta = \sum_{i \in S} \delta_{i} = k \gamma `$, where $`S`$ is the signing quorum    
 struct Phase3 {
     params: SigningParameters,
-    multi_party_info: MultiPartyInfo,
+    multi_party_info: MultiPartyPublicInfo,
     other_parties: BTreeSet<PartyIndex>,
-    gamma_i: FE,
-    k_i: FE,
+    gamma_i: Secret<FE>,
+    k_i: Secret<FE>,
     comm_scheme: CommitmentScheme,
     commitments: HashMap<PartyIndex, BigInt>,
     delta_i: FE,
-    sigma_i: FE,
+    sigma_i: Secret<FE>,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 #[trace(pretty, prefix = "Phase3::")]
@@ -1103,10 +1421,10 @@ impl State<SigningTraits> for Phase3 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
-        let responses = match to_hash_map_gen::<PartyIndex, Phase3data>(current_msg_set) {
+        let responses = match to_hash_map_gen::<Phase3data, _, _>(&current_msg_set) {
             Ok(map) => map,
             Err(e) => {
-                let error_state = ErrorState::new(vec![e]);
+                let error_state = ErrorState::new(e).with_evidence("phase3", &current_msg_set);
                 log::error!("Phase 3 returns {:?}", error_state);
                 return Transition::FinalState(Err(error_state));
             }
@@ -1121,20 +1439,27 @@ impl State<SigningTraits> for Phase3 {
             params: self.params.clone(),
             multi_party_info: self.multi_party_info.clone(),
             other_parties: self.other_parties.clone(),
-            gamma_i: self.gamma_i,
-            k_i: self.k_i,
+            gamma_i: Secret::new(*self.gamma_i),
+            k_i: Secret::new(*self.k_i),
             comm_scheme: self.comm_scheme.clone(),
             commitments: self.commitments.clone(),
             delta_inv,
-            sigma_i: self.sigma_i,
+            sigma_i: Secret::new(*self.sigma_i),
             timeout: self.timeout,
+            rng: self.rng.clone(),
         }))
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase3".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase3", &self.other_parties, current_msg_set)
     }
 
     fn timeout(&self) -> Option<Duration> {
@@ -1149,15 +1474,16 @@ impl State<SigningTraits> for Phase3 {
 /// * Reconstructs $` R = ( \prod_{i \in S} \Gamma_{i})^{\delta^{-1}} = g^{k^{-1}} `$ and $` r = H^{\prime}(R) `$
 struct Phase4 {
     params: SigningParameters,
-    multi_party_info: MultiPartyInfo,
+    multi_party_info: MultiPartyPublicInfo,
     other_parties: BTreeSet<PartyIndex>,
-    gamma_i: FE,
-    k_i: FE,
+    gamma_i: Secret<FE>,
+    k_i: Secret<FE>,
     comm_scheme: CommitmentScheme,
     commitments: HashMap<PartyIndex, BigInt>,
     delta_inv: FE,
-    sigma_i: FE,
+    sigma_i: Secret<FE>,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 #[trace(pretty, prefix = "Phase4::")]
@@ -1165,7 +1491,7 @@ impl State<SigningTraits> for Phase4 {
     fn start(&mut self) -> Option<OutMsgVec> {
         log::debug!("Phase 4 starts");
         let g: GE = ECPoint::generator();
-        let g_gamma_i = g * self.gamma_i;
+        let g_gamma_i = g * *self.gamma_i;
         let output = vec![OutMsg {
             recipient: Address::Broadcast,
             body: Message::R4(SignDecommitPhase4 {
@@ -1191,10 +1517,10 @@ impl State<SigningTraits> for Phase4 {
     }
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
-        let responses = match to_hash_map_gen::<PartyIndex, SignDecommitPhase4>(current_msg_set) {
+        let responses = match to_hash_map_gen::<SignDecommitPhase4, _, _>(&current_msg_set) {
             Ok(map) => map,
             Err(e) => {
-                let error_state = ErrorState::new(vec![e]);
+                let error_state = ErrorState::new(e).with_evidence("phase4", &current_msg_set);
                 log::error!("Phase 4 returns {:?}", error_state);
                 return Transition::FinalState(Err(error_state));
             }
@@ -1226,51 +1552,57 @@ impl State<SigningTraits> for Phase4 {
 
         if verification_errors.is_empty() {
             let g: GE = ECPoint::generator();
-            let g_gamma_i = g * self.gamma_i;
+            let g_gamma_i = g * *self.gamma_i;
 
             let g_gamma_sum = responses
                 .iter()
                 .fold(g_gamma_i, |acc, msg| acc + msg.1.g_gamma_i);
 
             let R = g_gamma_sum * self.delta_inv;
-            let local_sig =
-                LocalSignature::new(&self.params.message_hash, &R, &self.k_i, &self.sigma_i);
-            let (p5_commit, p5_decommit) = local_sig.phase5b_proof();
+            let mut rng = self.rng.clone();
+            let local_sig = Secret::new(LocalSignature::new(
+                &self.params.message_hash,
+                &R,
+                &self.k_i,
+                &self.sigma_i,
+                &mut rng,
+            ));
+            let (p5_commit, p5_decommit) = local_sig.phase5b_proof(&mut rng);
 
             Transition::NewState(Box::new(Phase5ab {
                 params: self.params.clone(),
                 multi_party_info: self.multi_party_info.clone(),
                 other_parties: self.other_parties.clone(),
                 R,
-                sigma_i: self.sigma_i,
                 local_sig,
                 p5_commit,
                 p5_decommit,
                 subphase: SubPhaseAB::A,
                 p5_commitments: HashMap::new(),
                 timeout: self.timeout,
+                rng,
             }))
         } else {
-            let error_state = ErrorState::new(verification_errors);
+            let error_state =
+                ErrorState::new(verification_errors).with_evidence("phase4", &current_msg_set);
             log::error!("Phase 4 returns {:?}", error_state);
             Transition::FinalState(Err(error_state))
         }
     }
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase4".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
     }
 
-    fn timeout(&self) -> Option<Duration> {
-        self.timeout
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase4", &self.other_parties, current_msg_set)
     }
-}
 
-impl Drop for Phase4 {
-    fn drop(&mut self) {
-        self.k_i = FE::zero();
-        self.gamma_i = FE::zero();
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
     }
 }
 
@@ -1292,16 +1624,16 @@ enum SubPhaseAB {
 ///
 struct Phase5ab {
     params: SigningParameters,
-    multi_party_info: MultiPartyInfo,
+    multi_party_info: MultiPartyPublicInfo,
     other_parties: BTreeSet<PartyIndex>,
     R: GE,
-    sigma_i: FE,
-    local_sig: LocalSignature,
+    local_sig: Secret<LocalSignature>,
     p5_commit: Phase5Com1,
     p5_decommit: Phase5Decom1,
     subphase: SubPhaseAB,
     p5_commitments: HashMap<PartyIndex, BigInt>,
     timeout: Option<Duration>,
+    rng: RandomnessSource,
 }
 
 #[trace(pretty, prefix = "Phase5a::")]
@@ -1358,20 +1690,22 @@ impl Phase5ab {
     }
 }
 
-impl Clone for Phase5ab {
-    fn clone(&self) -> Self {
+// not traced, as the state holds secrets
+impl Phase5ab {
+    /// copies the state to the next sub-phase, the secrets are copied into the new state only, since the current one is dropped by the state machine
+    fn next_subphase(&self, subphase: SubPhaseAB) -> Self {
         Phase5ab {
             params: self.params.clone(),
             multi_party_info: self.multi_party_info.clone(),
             other_parties: self.other_parties.clone(),
             R: self.R,
-            sigma_i: self.sigma_i,
-            local_sig: self.local_sig.clone(),
+            local_sig: Secret::new((*self.local_sig).clone()),
             p5_commit: self.p5_commit.clone(),
             p5_decommit: self.p5_decommit.clone(),
-            subphase: self.subphase,
+            subphase,
             p5_commitments: self.p5_commitments.clone(),
             timeout: self.timeout,
+            rng: self.rng.clone(),
         }
     }
 }
@@ -1416,71 +1750,81 @@ impl State<SigningTraits> for Phase5ab {
 
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
         match &self.subphase {
-            SubPhaseAB::A => match to_hash_map_gen::<PartyIndex, Phase5Com1>(current_msg_set) {
+            SubPhaseAB::A => match to_hash_map_gen::<Phase5Com1, _, _>(&current_msg_set) {
                 Ok(comms) => {
-                    let mut new_state = self.clone();
+                    let mut new_state = self.next_subphase(SubPhaseAB::B);
                     new_state
                         .p5_commitments
                         .extend(comms.iter().map(|(party, msg)| (*party, msg.com.clone())));
-                    new_state.subphase = SubPhaseAB::B;
                     Transition::NewState(Box::new(new_state))
                 }
                 Err(e) => {
-                    let error_state = ErrorState::new(vec![e]);
+                    let error_state = ErrorState::new(e).with_evidence("phase5a", &current_msg_set);
                     log::error!("Phase 5a returns {:?}", error_state);
                     Transition::FinalState(Err(error_state))
                 }
             },
-            SubPhaseAB::B => {
-                match to_hash_map_gen::<PartyIndex, Phase5Decom1>(current_msg_set) {
-                    Ok(decomms) => {
-                        let mut errors = decomms
+            SubPhaseAB::B => match to_hash_map_gen::<Phase5Decom1, _, _>(&current_msg_set) {
+                Ok(decomms) => {
+                    let mut errors = decomms
+                        .iter()
+                        .filter_map(|(party, msg)| self.check_comms_A(party, msg).err())
+                        .collect::<Vec<_>>();
+                    errors.extend(
+                        decomms
                             .iter()
-                            .filter_map(|(party, msg)| self.check_comms_A(party, msg).err())
-                            .collect::<Vec<_>>();
-                        errors.extend(decomms.iter().filter_map(|(party, msg)| {
-                            self.check_el_gamal_proof(party, msg).err()
-                        }));
-
-                        let (V, A) = self.compute_va(&decomms);
-                        let (p5commit2, p5decommit2) = self.local_sig.phase5d_proof(V, A);
-
-                        if errors.is_empty() {
-                            Transition::NewState(Box::new(Phase5cde {
-                                params: self.params.clone(),
-                                shared_keys: self.multi_party_info.clone(),
-                                other_parties: self.other_parties.clone(),
-                                R: self.R,
-                                sigma_i: self.sigma_i,
-                                local_sig: self.local_sig.clone(),
-                                p5_decommit: self.p5_decommit.clone(),
-                                p5_decommitments: decomms,
-                                p5_commit2: p5commit2,
-                                p5_decommit2: p5decommit2,
-                                p5_commitments2: HashMap::new(),
-                                subphase: SubPhaseCDE::C,
-                                timeout: self.timeout,
-                            }))
-                        } else {
-                            let error_state = ErrorState::new(errors);
-                            log::error!("Phase 5a returns {:?}", error_state);
-                            Transition::FinalState(Err(error_state))
-                        }
-                    }
-                    Err(e) => {
-                        let error_state = ErrorState::new(vec![e]);
+                            .filter_map(|(party, msg)| self.check_el_gamal_proof(party, msg).err()),
+                    );
+
+                    let (V, A) = self.compute_va(&decomms);
+                    let (p5commit2, p5decommit2) =
+                        self.local_sig.phase5d_proof(V, A, &mut self.rng.clone());
+
+                    if errors.is_empty() {
+                        Transition::NewState(Box::new(Phase5cde {
+                            params: self.params.clone(),
+                            shared_keys: self.multi_party_info.clone(),
+                            other_parties: self.other_parties.clone(),
+                            R: self.R,
+                            local_sig: Secret::new((*self.local_sig).clone()),
+                            p5_decommit: self.p5_decommit.clone(),
+                            p5_decommitments: decomms,
+                            p5_commit2: p5commit2,
+                            p5_decommit2: p5decommit2,
+                            p5_commitments2: HashMap::new(),
+                            subphase: SubPhaseCDE::C,
+                            timeout: self.timeout,
+                        }))
+                    } else {
+                        let error_state =
+                            ErrorState::new(errors).with_evidence("phase5b", &current_msg_set);
                         log::error!("Phase 5a returns {:?}", error_state);
                         Transition::FinalState(Err(error_state))
                     }
                 }
-            }
+                Err(e) => {
+                    let error_state = ErrorState::new(e).with_evidence("phase5b", &current_msg_set);
+                    log::error!("Phase 5a returns {:?}", error_state);
+                    Transition::FinalState(Err(error_state))
+                }
+            },
         }
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase5a".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        let phase = match self.subphase {
+            SubPhaseAB::A => "phase5a",
+            SubPhaseAB::B => "phase5b",
+        };
+        PhaseStatus::new(phase, &self.other_parties, current_msg_set)
     }
 
     fn timeout(&self) -> Option<Duration> {
@@ -1510,11 +1854,10 @@ enum SubPhaseCDE {
 /// * Reconstructs full signature and verifies it using standard method
 struct Phase5cde {
     params: SigningParameters,
-    shared_keys: MultiPartyInfo,
+    shared_keys: MultiPartyPublicInfo,
     other_parties: BTreeSet<PartyIndex>,
     R: GE,
-    sigma_i: FE,
-    local_sig: LocalSignature,
+    local_sig: Secret<LocalSignature>,
     p5_decommit: Phase5Decom1,
     p5_decommitments: HashMap<PartyIndex, Phase5Decom1>,
     p5_commit2: Phase5Com2,
@@ -1541,21 +1884,22 @@ impl Phase5cde {
     }
 }
 
-impl Clone for Phase5cde {
-    fn clone(&self) -> Self {
+// not traced, as the state holds secrets
+impl Phase5cde {
+    /// copies the state to the next sub-phase, the secrets are copied into the new state only, since the current one is dropped by the state machine
+    fn next_subphase(&self, subphase: SubPhaseCDE) -> Self {
         Phase5cde {
             params: self.params.clone(),
             shared_keys: self.shared_keys.clone(),
             other_parties: self.other_parties.clone(),
             R: self.R,
-            sigma_i: self.sigma_i,
-            local_sig: self.local_sig.clone(),
+            local_sig: Secret::new((*self.local_sig).clone()),
             p5_commit2: self.p5_commit2.clone(),
             p5_decommit2: self.p5_decommit2.clone(),
             p5_commitments2: self.p5_commitments2.clone(),
             p5_decommit: self.p5_decommit.clone(),
             p5_decommitments: self.p5_decommitments.clone(),
-            subphase: self.subphase,
+            subphase,
             timeout: self.timeout,
         }
     }
@@ -1613,27 +1957,28 @@ impl State<SigningTraits> for Phase5cde {
     fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<SigningTraits> {
         match self.subphase {
             SubPhaseCDE::C => {
-                let comms = match to_hash_map_gen::<PartyIndex, Phase5Com2>(current_msg_set) {
+                let comms = match to_hash_map_gen::<Phase5Com2, _, _>(&current_msg_set) {
                     Ok(map) => map,
                     Err(e) => {
-                        let error_state = ErrorState::new(vec![e]);
+                        let error_state =
+                            ErrorState::new(e).with_evidence("phase5c", &current_msg_set);
                         log::error!("Phase 5 returns {:?}", error_state);
                         return Transition::FinalState(Err(error_state));
                     }
                 };
 
-                let mut new_state = self.clone();
+                let mut new_state = self.next_subphase(SubPhaseCDE::D);
                 new_state
                     .p5_commitments2
                     .extend(comms.iter().map(|(party, msg)| (*party, msg.com.clone())));
-                new_state.subphase = SubPhaseCDE::D;
                 Transition::NewState(Box::new(new_state))
             }
             SubPhaseCDE::D => {
-                let decomms = match to_hash_map_gen::<PartyIndex, Phase5Decom2>(current_msg_set) {
+                let decomms = match to_hash_map_gen::<Phase5Decom2, _, _>(&current_msg_set) {
                     Ok(map) => map,
                     Err(e) => {
-                        let error_state = ErrorState::new(vec![e]);
+                        let error_state =
+                            ErrorState::new(e).with_evidence("phase5d", &current_msg_set);
                         log::error!("Phase 5 returns {:?}", error_state);
                         return Transition::FinalState(Err(error_state));
                     }
@@ -1656,25 +2001,25 @@ impl State<SigningTraits> for Phase5cde {
                 }
 
                 if errors.is_empty() {
-                    let mut new_state = self.clone();
-                    new_state.subphase = SubPhaseCDE::E;
-                    Transition::NewState(Box::new(new_state))
+                    Transition::NewState(Box::new(self.next_subphase(SubPhaseCDE::E)))
                 } else {
-                    let error_state = ErrorState::new(errors);
+                    let error_state =
+                        ErrorState::new(errors).with_evidence("phase5d", &current_msg_set);
                     log::error!("Phase 5 returns {:?}", error_state);
                     Transition::FinalState(Err(error_state))
                 }
             }
             SubPhaseCDE::E => {
-                let local_signatures =
-                    match to_hash_map_gen::<PartyIndex, Phase5Edata>(current_msg_set) {
-                        Ok(map) => map,
-                        Err(e) => {
-                            let error_state = ErrorState::new(vec![e]);
-                            log::error!("Phase 5 returns {:?}", error_state);
-                            return Transition::FinalState(Err(error_state));
-                        }
-                    };
+                let local_signatures = match to_hash_map_gen::<Phase5Edata, _, _>(&current_msg_set)
+                {
+                    Ok(map) => map,
+                    Err(e) => {
+                        let error_state =
+                            ErrorState::new(e).with_evidence("phase5e", &current_msg_set);
+                        log::error!("Phase 5 returns {:?}", error_state);
+                        return Transition::FinalState(Err(error_state));
+                    }
+                };
                 {
                     let sig_vec = local_signatures
                         .into_iter()
@@ -1702,10 +2047,21 @@ impl State<SigningTraits> for Phase5cde {
         }
     }
 
-    fn timeout_outcome(&self, _current_msg_set: Vec<InMsg>) -> MachineResult {
-        Err(ErrorState::new(vec![SigningError::Timeout {
-            phase: "phase5c".to_string(),
-        }]))
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        let phase = match self.subphase {
+            SubPhaseCDE::C => "phase5c",
+            SubPhaseCDE::D => "phase5d",
+            SubPhaseCDE::E => "phase5e",
+        };
+        PhaseStatus::new(phase, &self.other_parties, current_msg_set)
     }
 
     fn timeout(&self) -> Option<Duration> {
@@ -1716,17 +2072,34 @@ impl State<SigningTraits> for Phase5cde {
 #[cfg(test)]
 mod tests {
 
-    use crate::ecdsa::signature::{InMsg, OutMsg, Phase1, SigningTraits};
+    use crate::ecdsa::signature::{
+        InMsg, MachineResult, Message, OutMsg, Phase1, Phase2a, Phase2b, Phase3, Phase4, Phase5ab,
+        Phase5cde, SigningError, SigningTraits,
+    };
 
-    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::algorithms::random::RandomnessSource;
+    use crate::algorithms::validation::ValidationError;
+    use crate::ecdsa::blame::BlameKind;
+    use crate::ecdsa::keygen::{
+        ASecretShareLoader, AsyncSecretShareLoader, BlockingSecretShareLoader, DecryptionKey,
+        MultiPartyInfo, SecretKeyLoaderError, SecretShareHolder, SecretShareLoader,
+    };
+    use crate::ecdsa::PaillierKeys;
     use crate::protocol::{Address, InputMessage, PartyIndex};
+    use crate::secret::AmbiguousIfClone;
     use crate::state_machine::sync_channels::StateMachine;
+    use crate::state_machine::BoxedState;
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::{Action, Adversary, NetworkConditions, Simulation, SimulationReport};
     use anyhow::bail;
     use crossbeam_channel::{Receiver, Sender};
     use curv::elliptic::curves::traits::ECScalar;
-    use curv::BigInt;
+    use curv::{BigInt, FE};
+    use futures::future::{BoxFuture, FutureExt};
     use sha2::{Digest, Sha256};
     use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
     use std::{fs, thread};
 
     struct Node {
@@ -1891,4 +2264,311 @@ mod tests {
 
         Ok(())
     }
+
+    /// signs with parties 0 and 2 holding keys with range proof setups, party 2 is driven by the adversary
+    fn simulated_signing(
+        adversary: impl Fn(&[PartyIndex]) -> Adversary<Message>,
+    ) -> anyhow::Result<SimulationReport<MachineResult>> {
+        let mut hasher = Sha256::new();
+        hasher.input("MPC TS signing tests");
+        let msg_hash: FE = ECScalar::from(&BigInt::from(hasher.result().as_slice()));
+
+        let signing_parties = vec![PartyIndex::from(0), PartyIndex::from(2)];
+        let adversary = adversary(&signing_parties);
+        let mut simulation = Simulation::new(0x7369676e, NetworkConditions::default());
+        for (i, party) in [0, 2].iter().zip(signing_parties.iter()) {
+            let path = format!("tests/data/zkrp-keys.{}.json", i);
+            let multi_party_shared_info: MultiPartyInfo =
+                serde_json::from_str(&fs::read_to_string(Path::new(&path))?)?;
+            let start_state: BoxedState<SigningTraits> = Box::new(Phase1::new_with_rng(
+                msg_hash,
+                multi_party_shared_info,
+                &signing_parties,
+                None,
+                RandomnessSource::seeded(*i),
+            )?);
+            let start_state = if *party == signing_parties[1] {
+                adversary.wrap(start_state)
+            } else {
+                start_state
+            };
+            simulation.add_node(format!("party {}", i), *party, start_state, |outcome| {
+                outcome
+            });
+        }
+        Ok(simulation.run())
+    }
+
+    fn honest_party_errors(
+        report: &SimulationReport<MachineResult>,
+    ) -> anyhow::Result<&[SigningError]> {
+        match &report.outcomes[0].outcome {
+            Some(Err(error_state)) => Ok(error_state.errors()),
+            outcome => bail!("error expected, got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn secret_phases_are_not_clone() {
+        <Phase1 as AmbiguousIfClone<_>>::check();
+        <Phase2a as AmbiguousIfClone<_>>::check();
+        <Phase2b as AmbiguousIfClone<_>>::check();
+        <Phase3 as AmbiguousIfClone<_>>::check();
+        <Phase4 as AmbiguousIfClone<_>>::check();
+        <Phase5ab as AmbiguousIfClone<_>>::check();
+        <Phase5cde as AmbiguousIfClone<_>>::check();
+    }
+
+    #[test]
+    fn seeded_signing_is_reproducible() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let signature = || -> anyhow::Result<(BigInt, BigInt)> {
+            // the adversary without actions follows the protocol honestly
+            let report = simulated_signing(|parties| Adversary::new(parties[1], parties))?;
+            match report.into_outcomes()?.first() {
+                Some(Ok(signed)) => Ok((signed.r.to_big_int(), signed.s.to_big_int())),
+                outcome => bail!("signature expected, got {:?}", outcome),
+            }
+        };
+        assert_eq!(signature()?, signature()?);
+        Ok(())
+    }
+
+    #[test]
+    fn signing_detects_forged_alice_proof() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let report = simulated_signing(|parties| {
+            Adversary::new(parties[1], parties).in_phase(
+                "phase1",
+                Action::Corrupt(|msg: &mut Message| {
+                    if let Message::R1(broadcast) = msg {
+                        scenarios::shift_by_one(&mut broadcast.mta_a.c)
+                    }
+                }),
+            )
+        })?;
+        match honest_party_errors(&report)? {
+            [SigningError::AliceProofFailed { party, .. }] => {
+                assert_eq!(*party, PartyIndex::from(2))
+            }
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn signing_detects_bad_phase5_decommitment() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let report = simulated_signing(|parties| {
+            Adversary::new(parties[1], parties).in_phase(
+                "phase5b",
+                Action::Corrupt(|msg: &mut Message| {
+                    if let Message::R6(decomm) = msg {
+                        scenarios::shift_by_one(&mut decomm.blind_factor)
+                    }
+                }),
+            )
+        })?;
+        match honest_party_errors(&report)? {
+            [SigningError::InvalidDecommitment { party }] => {
+                assert_eq!(*party, PartyIndex::from(2))
+            }
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn signing_blames_invalid_message() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let report = simulated_signing(|parties| {
+            Adversary::new(parties[1], parties).in_phase(
+                "phase5b",
+                Action::Corrupt(|msg: &mut Message| {
+                    if let Message::R6(decomm) = msg {
+                        scenarios::oversize(&mut decomm.blind_factor)
+                    }
+                }),
+            )
+        })?;
+        match honest_party_errors(&report)? {
+            [SigningError::InvalidMessage { party, error }] => {
+                assert_eq!(*party, PartyIndex::from(2));
+                assert!(matches!(error, ValidationError::TooLarge { .. }));
+            }
+            errors => bail!("unexpected errors {:?}", errors),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn signing_rejects_stale_key_share() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let msg_hash: FE = ECScalar::from(&BigInt::from(42));
+        let signing_parties = vec![PartyIndex::from(0), PartyIndex::from(2)];
+        let mut simulation = Simulation::new(0x65706f6368, NetworkConditions::default());
+        for (i, party) in [0, 2].iter().zip(signing_parties.iter()) {
+            let path = format!("tests/data/keys.{}.json", i);
+            let mut keys: MultiPartyInfo = serde_json::from_str(&fs::read_to_string(path)?)?;
+            if *i == 0 {
+                // party 0 holds the share of the next epoch, while party 2 missed the resharing
+                let committee = keys.metadata.committee().cloned().unwrap_or_default();
+                keys.metadata = keys.metadata.next_epoch(committee);
+            }
+            let start_state: BoxedState<SigningTraits> =
+                Box::new(Phase1::new(msg_hash, keys, &signing_parties, None)?);
+            simulation.add_node(format!("party {}", i), *party, start_state, |outcome| {
+                outcome
+            });
+        }
+        match simulation.run().into_outcomes()?.first() {
+            Some(Err(error_state)) => {
+                match error_state.errors() {
+                    [SigningError::EpochMismatch {
+                        party,
+                        epoch: 0,
+                        own_epoch: 1,
+                    }] => assert_eq!(*party, PartyIndex::from(2)),
+                    errors => bail!("unexpected errors {:?}", errors),
+                }
+                assert!(error_state
+                    .blame()
+                    .iter()
+                    .all(|b| b.party == PartyIndex::from(2) && b.kind == BlameKind::StaleKeyShare));
+            }
+            outcome => bail!("error expected, got {:?}", outcome),
+        }
+        Ok(())
+    }
+
+    /// stand-in for a remote vault, counts how many times secrets are fetched
+    ///
+    /// The vault responds after a timer, so that its futures need the tokio runtime to make progress.
+    #[derive(Debug)]
+    struct Vault {
+        keys: MultiPartyInfo,
+        loads: Arc<AtomicUsize>,
+    }
+
+    impl AsyncSecretShareLoader for Vault {
+        fn get_secret_share(&self) -> BoxFuture<'_, Result<Box<FE>, SecretKeyLoaderError>> {
+            async move {
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                self.loads.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(self.keys.own_share()))
+            }
+            .boxed()
+        }
+
+        fn get_paillier_secret(
+            &self,
+        ) -> BoxFuture<'_, Result<Box<DecryptionKey>, SecretKeyLoaderError>> {
+            async move {
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+                self.loads.fetch_add(1, Ordering::SeqCst);
+                Ok(Box::new(self.keys.own_he_keys.dk.clone()))
+            }
+            .boxed()
+        }
+    }
+
+    #[test]
+    fn signing_with_secret_loader() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let msg_hash: FE = ECScalar::from(&BigInt::from(42));
+        let signing_parties = vec![PartyIndex::from(0), PartyIndex::from(2)];
+        let mut simulation = Simulation::new(0x7661756c, NetworkConditions::default());
+        let mut counters = Vec::new();
+        for (i, party) in [0, 2].iter().zip(signing_parties.iter()) {
+            let path = format!("tests/data/keys.{}.json", i);
+            let keys: MultiPartyInfo = serde_json::from_str(&fs::read_to_string(path)?)?;
+            let loads = Arc::new(AtomicUsize::new(0));
+            counters.push(loads.clone());
+            let public_info = keys.public_info();
+            let loader: ASecretShareLoader =
+                Arc::new(Box::new(BlockingSecretShareLoader::new(Vault {
+                    keys,
+                    loads,
+                })));
+            let start_state: BoxedState<SigningTraits> = Box::new(Phase1::new_with_loader(
+                msg_hash,
+                public_info,
+                loader,
+                &signing_parties,
+                None,
+                RandomnessSource::os(),
+            )?);
+            simulation.add_node(format!("party {}", i), *party, start_state, |outcome| {
+                outcome
+            });
+        }
+        for outcome in simulation.run().into_outcomes()? {
+            if let Err(e) = outcome {
+                bail!("signature expected, got {:?}", e);
+            }
+        }
+        // the secret share once, the Paillier key in both parts of phase 2
+        assert!(counters
+            .iter()
+            .all(|loads| loads.load(Ordering::SeqCst) == 3));
+        Ok(())
+    }
+
+    fn load_from_vault() -> anyhow::Result<()> {
+        let keys: MultiPartyInfo =
+            serde_json::from_str(&fs::read_to_string("tests/data/keys.0.json")?)?;
+        let (share, p) = (keys.own_share(), keys.own_he_keys.dk.p.clone());
+        let loads = Arc::new(AtomicUsize::new(0));
+        let loader = BlockingSecretShareLoader::new(Vault {
+            keys,
+            loads: loads.clone(),
+        });
+        let loaded_share = loader
+            .get_secret_share()
+            .map_err(|e| anyhow::anyhow!(e.0))?;
+        let loaded_dk = loader
+            .get_paillier_secret()
+            .map_err(|e| anyhow::anyhow!(e.0))?;
+        assert_eq!(*loaded_share, share);
+        assert_eq!(loaded_dk.p, p);
+        assert_eq!(loads.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn secret_loader_outside_of_runtime() -> anyhow::Result<()> {
+        load_from_vault()
+    }
+
+    #[tokio::test]
+    async fn secret_loader_on_blocking_thread_of_current_thread_runtime() -> anyhow::Result<()> {
+        tokio::task::spawn_blocking(load_from_vault).await?
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn secret_loader_within_multi_thread_runtime() -> anyhow::Result<()> {
+        load_from_vault()
+    }
+
+    #[test]
+    fn signing_holds_only_secrets_of_key_info() -> anyhow::Result<()> {
+        let keys: MultiPartyInfo =
+            serde_json::from_str(&fs::read_to_string("tests/data/keys.0.json")?)?;
+        let (point, share) = (keys.own_point(), keys.own_share());
+        let (public_info, holder) = SecretShareHolder::split(keys);
+        assert_eq!(public_info.own_point, point);
+        assert_eq!(
+            *holder
+                .get_secret_share()
+                .map_err(|e| anyhow::anyhow!(e.0))?,
+            share
+        );
+        assert!(PaillierKeys::is_valid(
+            &public_info.own_ek,
+            &*holder
+                .get_paillier_secret()
+                .map_err(|e| anyhow::anyhow!(e.0))?
+        ));
+        Ok(())
+    }
 }
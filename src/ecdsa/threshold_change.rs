@@ -0,0 +1,880 @@
+//!  Threshold change
+//!
+//! Changes the threshold of the key held by a stable committee, e.g. from 2-of-3 to 3-of-3, while the public key stays the same.
+//! The protocol is the Lagrange-weighted resharing of [`old_member::Phase1`] run by all members at once: every party `i` multiplies its share
+//! by its Lagrange coefficient over the committee, $` w_{i} = \lambda_{i} x_{i} `$, shares $` w_{i} `$ with a random polynomial $` f_{i} `$ of the new degree `t'`
+//! and broadcasts Feldman's commitments $` A_{ik} = g^{a_{ik}} `$ to its coefficients. Each party checks that the free term of every sharing matches
+//! the public share of the dealer, $` A_{i0} = X_{i}^{\lambda_{i}} `$, and that the free terms sum up to the public key.
+//! Party `j` sums up values $` f_{i}(x_{j}) `$ received from all parties into its new share, and public shares of all parties are updated from commitments the same way.
+//!
+//! Unlike resharing, parties keep their x-coordinates, Paillier keys and range proof setups, and only the threshold of [`Parameters`] is updated,
+//! while the share count stays the same. The key starts the next epoch with the same committee, as old shares do not combine with new ones.
+//! Parties finish when all of them have confirmed their new shares along with the digest of the public info with the new threshold,
+//! and keep their old shares if the protocol fails, see [`refresh`](../refresh/index.html) for the caveat on timeouts in the last phase.
+//!
+//! [`old_member::Phase1`]: ../resharing/old_member/struct.Phase1.html
+//! [`Parameters`]: ../struct.Parameters.html
+use crate::algorithms::random::{self, RandomnessSource};
+use crate::algorithms::validation::ValidationError;
+use crate::ecdsa::blame::{Accusation, BlameKind};
+use crate::ecdsa::keygen::{
+    ASecretShareLoader, MultiPartyInfo, MultiPartyPublicInfo, SecretShareLoader,
+};
+use crate::ecdsa::messages::threshold_change::{DealtShare, Phase1Broadcast, ShareVerified};
+use crate::ecdsa::messages::SecretShare;
+use crate::ecdsa::refresh::{evaluate, evaluate_commitments, public_digest, to_fe};
+use crate::ecdsa::{
+    is_broadcast_input_complete, to_hash_map_gen, InputDigest, PaillierKeys, Parameters,
+    PhaseTimeout, ProtocolError,
+};
+use crate::protocol::{Address, PartyIndex};
+use crate::secret::Secret;
+use crate::state_machine::{PhaseStatus, State, StateMachineTraits, Transition};
+use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+use curv::{BigInt, FE, GE};
+use std::collections::{BTreeSet, HashMap};
+use std::iter::FromIterator;
+use std::time::Duration;
+use thiserror::Error;
+use trace::trace;
+
+pub use super::messages::threshold_change::{InMsg, Message, OutMsg};
+
+/// Enumerates errors which can be reported by threshold change protocol
+#[derive(Debug, Error)]
+#[allow(clippy::large_enum_variant)]
+pub enum ThresholdChangeError {
+    #[error("threshold change: {0}")]
+    Timeout(#[from] PhaseTimeout),
+    #[error("unexpected message {message_type:?}, party {party}")]
+    UnknownMessageType {
+        message_type: Message,
+        party: PartyIndex,
+    },
+    #[error("party {party} is configured with different {field}")]
+    InputMismatch { party: PartyIndex, field: String },
+    #[error("public shares of the key are unknown, the key has to be reshared first")]
+    PublicSharesMissing,
+    #[error("party {party} has sent {count} VSS commitments, {expected} expected")]
+    InvalidVssCommitments {
+        party: PartyIndex,
+        count: usize,
+        expected: usize,
+    },
+    #[error("party {party} has shared a value which does not match its weighted public share")]
+    InvalidWeightedShare { party: PartyIndex },
+    #[error("shared values do not sum up to the public key")]
+    PublicKeyMismatch,
+    #[error("share dealt by party {party} does not match its commitments")]
+    InvalidDealtShare { party: PartyIndex },
+    #[error("new share does not match the public share")]
+    ShareVerificationFailed,
+    #[error("party {party} has changed the threshold differently")]
+    InconsistentChange { party: PartyIndex },
+    #[error("protocol setup error: {0}")]
+    ProtocolSetupError(String),
+    #[error("invalid message from party {party}: {error}")]
+    InvalidMessage {
+        party: PartyIndex,
+        error: ValidationError,
+    },
+    #[error("{0}")]
+    GeneralError(String),
+}
+
+impl Accusation for ThresholdChangeError {
+    fn accusation(&self) -> Option<(PartyIndex, BlameKind)> {
+        match self {
+            ThresholdChangeError::UnknownMessageType { party, .. } => {
+                Some((*party, BlameKind::UnexpectedMessage))
+            }
+            ThresholdChangeError::InvalidMessage { party, .. } => {
+                Some((*party, BlameKind::InvalidMessage))
+            }
+            ThresholdChangeError::InputMismatch { party, .. } => {
+                Some((*party, BlameKind::InputMismatch))
+            }
+            ThresholdChangeError::InvalidVssCommitments { party, .. }
+            | ThresholdChangeError::InvalidWeightedShare { party }
+            | ThresholdChangeError::InvalidDealtShare { party } => {
+                Some((*party, BlameKind::InvalidSecretSharing))
+            }
+            _ => None,
+        }
+    }
+
+    fn accusations(&self) -> Vec<(PartyIndex, BlameKind)> {
+        match self {
+            ThresholdChangeError::Timeout(timeout) => timeout.accusations(),
+            _ => self.accusation().into_iter().collect(),
+        }
+    }
+}
+
+/// Contains a vector of possible threshold change errors and blame records derived from these errors
+pub type ErrorState = super::ErrorState<ThresholdChangeError, Message>;
+
+/// Result of threshold change protocol
+#[derive(Clone, Debug)]
+pub struct FinalState {
+    pub info: MultiPartyInfo,
+}
+
+type OutMsgVec = Vec<OutMsg>;
+pub type MachineResult = Result<FinalState, ErrorState>;
+
+/// Type definitions
+#[derive(Debug)]
+pub struct ThresholdChangeTraits;
+
+impl StateMachineTraits for ThresholdChangeTraits {
+    type InMsg = InMsg;
+    type OutMsg = OutMsg;
+    type FinalState = FinalState;
+    type ErrorState = ErrorState;
+}
+
+impl ProtocolError<Message> for ThresholdChangeError {
+    fn unknown_message_type(party: PartyIndex, message_type: Message) -> Self {
+        ThresholdChangeError::UnknownMessageType {
+            message_type,
+            party,
+        }
+    }
+
+    fn invalid_message(party: PartyIndex, error: ValidationError) -> Self {
+        ThresholdChangeError::InvalidMessage { party, error }
+    }
+}
+
+/// Digest of threshold change inputs which all parties have to agree on
+fn input_digest(
+    params: &Parameters,
+    new_params: &Parameters,
+    parties: &[PartyIndex],
+) -> InputDigest {
+    InputDigest::new()
+        .with_parameters("parameters", params)
+        .with_parameters("new parameters", new_params)
+        .with_parties("parties", parties)
+}
+
+/// Value of the sharing polynomial at `x`, given its coefficients starting from the free term
+fn evaluate_sharing(coefficients: &[FE], x: usize) -> FE {
+    match coefficients.split_first() {
+        Some((free_term, rest)) => *free_term + evaluate(rest, x),
+        None => FE::zero(),
+    }
+}
+
+/// Commitment to the value of the sharing polynomial at `x`, given commitments to its coefficients starting from the free term
+///
+/// Returns `None` for the empty list of commitments.
+fn evaluate_sharing_commitments(commitments: &[GE], x: usize) -> Option<GE> {
+    let (free_term, rest) = commitments.split_first()?;
+    Some(match evaluate_commitments(rest, x) {
+        Some(c) => *free_term + c,
+        None => *free_term,
+    })
+}
+
+/// Initial phase of the protocol
+///
+/// * shares own Lagrange-weighted share with the polynomial of the new degree
+/// * broadcasts Feldman's commitments to coefficients of the polynomial
+/// * collects commitments of other parties and checks them against public shares and the public key
+#[derive(Debug)]
+pub struct Phase1 {
+    info: MultiPartyPublicInfo,
+    new_params: Parameters,
+    parties: Vec<PartyIndex>,
+    other_parties: BTreeSet<PartyIndex>,
+    coefficients: Secret<Vec<FE>>,
+    vss_commitments: Vec<GE>,
+    paillier_keys: Secret<PaillierKeys>,
+    inputs: InputDigest,
+    timeout: Option<Duration>,
+}
+
+#[trace(pretty, prefix = "Phase1::")]
+impl Phase1 {
+    /// Initializes the change of the threshold of the key held by `parties` to the one of `new_params`
+    ///
+    /// `parties` have to be the committee of the current epoch, and `new_params` have to keep the share count of the key.
+    pub fn new(
+        multi_party_info: &MultiPartyInfo,
+        parties: &[PartyIndex],
+        new_params: &Parameters,
+        timeout: Option<Duration>,
+    ) -> Result<Self, ThresholdChangeError> {
+        Self::new_with_rng(
+            multi_party_info,
+            parties,
+            new_params,
+            timeout,
+            RandomnessSource::os(),
+        )
+    }
+
+    /// same as `new`, but the coefficients of the new sharing are sampled from `rng`
+    pub fn new_with_rng(
+        multi_party_info: &MultiPartyInfo,
+        parties: &[PartyIndex],
+        new_params: &Parameters,
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    ) -> Result<Self, ThresholdChangeError> {
+        Self::init(
+            &multi_party_info.public_info(),
+            multi_party_info,
+            parties,
+            new_params,
+            timeout,
+            rng,
+        )
+    }
+
+    /// Initializes the protocol which loads the secret share and the Paillier key through `secret_loader` and samples the coefficients of the new sharing from `rng`
+    ///
+    /// The secret share is erased as soon as it is shared, while the Paillier key is kept for the new key info.
+    pub fn new_with_loader(
+        multi_party_info: &MultiPartyPublicInfo,
+        secret_loader: ASecretShareLoader,
+        parties: &[PartyIndex],
+        new_params: &Parameters,
+        timeout: Option<Duration>,
+        rng: RandomnessSource,
+    ) -> Result<Self, ThresholdChangeError> {
+        Self::init(
+            multi_party_info,
+            &**secret_loader,
+            parties,
+            new_params,
+            timeout,
+            rng,
+        )
+    }
+
+    #[trace(disable(secret_loader, rng))]
+    fn init(
+        multi_party_info: &MultiPartyPublicInfo,
+        secret_loader: &dyn SecretShareLoader,
+        parties: &[PartyIndex],
+        new_params: &Parameters,
+        timeout: Option<Duration>,
+        mut rng: RandomnessSource,
+    ) -> Result<Self, ThresholdChangeError> {
+        let params = &multi_party_info.key_params;
+        if new_params.share_count() != params.share_count() {
+            return Err(ThresholdChangeError::ProtocolSetupError(format!(
+                "share count {} differs from the share count of the key {}, the key has to be reshared",
+                new_params.share_count(),
+                params.share_count()
+            )));
+        }
+        let parties_as_set = BTreeSet::from_iter(parties.iter().cloned());
+        if parties_as_set.len() != parties.len() {
+            return Err(ThresholdChangeError::ProtocolSetupError(
+                "duplicate entries in parties' list".to_string(),
+            ));
+        }
+        if parties.len() <= params.threshold() || parties.len() <= new_params.threshold() {
+            return Err(ThresholdChangeError::ProtocolSetupError(
+                "committee too small".to_string(),
+            ));
+        }
+        let metadata = &multi_party_info.metadata;
+        match metadata.committee() {
+            Some(committee) if *committee == parties_as_set => {}
+            _ => {
+                return Err(ThresholdChangeError::ProtocolSetupError(format!(
+                    "parties differ from the committee of epoch {}",
+                    metadata.epoch
+                )))
+            }
+        }
+        let own_party_index = multi_party_info.own_party_index;
+        if !parties_as_set.contains(&own_party_index) {
+            return Err(ThresholdChangeError::ProtocolSetupError(
+                "own party index not in parties' list".to_string(),
+            ));
+        }
+        if parties
+            .iter()
+            .any(|p| !multi_party_info.party_to_point_map.points.contains_key(p))
+        {
+            return Err(ThresholdChangeError::ProtocolSetupError(
+                "x-coordinate of some party is unknown".to_string(),
+            ));
+        }
+        if parties
+            .iter()
+            .any(|p| !multi_party_info.public_shares.contains_key(p))
+        {
+            return Err(ThresholdChangeError::PublicSharesMissing);
+        }
+
+        let dk = secret_loader
+            .get_paillier_secret()
+            .map_err(|e| ThresholdChangeError::ProtocolSetupError(e.0))?;
+        if !PaillierKeys::is_valid(&multi_party_info.own_ek, &dk) {
+            return Err(ThresholdChangeError::ProtocolSetupError(
+                "loaded Paillier key does not match the key info".to_string(),
+            ));
+        }
+        let paillier_keys = Secret::new(PaillierKeys {
+            ek: multi_party_info.own_ek.clone(),
+            dk: (*dk).clone(),
+        });
+
+        let multiplier = multi_party_info
+            .party_to_point_map
+            .calculate_lagrange_multiplier(parties, to_fe(multi_party_info.own_point));
+        let coefficients = {
+            let x_i = secret_loader
+                .get_secret_share()
+                .map(Secret::from_box)
+                .map_err(|e| ThresholdChangeError::ProtocolSetupError(e.0))?;
+            let mut coefficients = vec![*x_i * multiplier];
+            coefficients
+                .extend((0..new_params.threshold()).map(|_| random::sample_scalar(&mut rng)));
+            Secret::new(coefficients)
+            // x_i is erased here
+        };
+        let g: GE = ECPoint::generator();
+        let vss_commitments = coefficients.iter().map(|a| g * a).collect();
+
+        let mut other_parties = parties_as_set;
+        other_parties.remove(&own_party_index);
+
+        Ok(Phase1 {
+            info: multi_party_info.clone(),
+            new_params: *new_params,
+            parties: parties.to_vec(),
+            other_parties,
+            coefficients,
+            vss_commitments,
+            paillier_keys,
+            inputs: input_digest(params, new_params, parties),
+            timeout,
+        })
+    }
+
+    /// checks inputs of other parties and their commitments against weighted public shares
+    #[trace(disable(input))]
+    fn verify_broadcasts(
+        &self,
+        input: &HashMap<PartyIndex, Phase1Broadcast>,
+    ) -> Vec<ThresholdChangeError> {
+        let mut errors = self
+            .inputs
+            .mismatches(input.iter().map(|(p, m)| (p, &m.inputs)))
+            .into_iter()
+            .map(|(party, field)| ThresholdChangeError::InputMismatch { party, field })
+            .collect::<Vec<_>>();
+        let points = &self.info.party_to_point_map;
+        for (party, msg) in input.iter() {
+            let party = *party;
+            if msg.metadata != self.info.metadata {
+                errors.push(ThresholdChangeError::InputMismatch {
+                    party,
+                    field: "metadata".to_string(),
+                });
+            }
+            if msg.public_key != self.info.public_key {
+                errors.push(ThresholdChangeError::InputMismatch {
+                    party,
+                    field: "public_key".to_string(),
+                });
+            }
+            if msg.vss_commitments.len() != self.vss_commitments.len() {
+                errors.push(ThresholdChangeError::InvalidVssCommitments {
+                    party,
+                    count: msg.vss_commitments.len(),
+                    expected: self.vss_commitments.len(),
+                });
+                continue;
+            }
+            let multiplier =
+                points.calculate_lagrange_multiplier(&self.parties, to_fe(points.points[&party]));
+            let weighted_public_share = self.info.public_shares[&party] * &multiplier;
+            if msg.vss_commitments[0].get_element() != weighted_public_share.get_element() {
+                errors.push(ThresholdChangeError::InvalidWeightedShare { party });
+            }
+        }
+        errors
+    }
+}
+
+#[trace(pretty, prefix = "Phase1::")]
+impl State<ThresholdChangeTraits> for Phase1 {
+    fn start(&mut self) -> Option<OutMsgVec> {
+        log::info!("Phase1 starts");
+        let broadcast = Phase1Broadcast {
+            inputs: self.inputs.clone(),
+            public_key: self.info.public_key,
+            metadata: self.info.metadata.clone(),
+            vss_commitments: self.vss_commitments.clone(),
+        };
+        Some(
+            self.other_parties
+                .iter()
+                .map(|p| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R1(broadcast.clone()),
+                })
+                .collect(),
+        )
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+        matches!(msg.body, Message::R1(_))
+            && self.other_parties.contains(&msg.sender)
+            && !msg.is_duplicate(current_msg_set)
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+        is_broadcast_input_complete(current_msg_set, &self.other_parties)
+    }
+
+    fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ThresholdChangeTraits> {
+        let fail = |errors: Vec<ThresholdChangeError>| {
+            let error_state = ErrorState::new(errors).with_evidence("phase1", &current_msg_set);
+            log::error!("Phase1 returns {:?}", error_state);
+            Transition::FinalState(Err(error_state))
+        };
+        let input = match to_hash_map_gen::<Phase1Broadcast, _, _>(&current_msg_set) {
+            Ok(input) => input,
+            Err(errors) => return fail(errors),
+        };
+        let errors = self.verify_broadcasts(&input);
+        if !errors.is_empty() {
+            return fail(errors);
+        }
+
+        let mut vss_commitments = input
+            .into_iter()
+            .map(|(party, msg)| (party, msg.vss_commitments))
+            .collect::<HashMap<_, _>>();
+        vss_commitments.insert(self.info.own_party_index, self.vss_commitments.clone());
+
+        let public_key = vss_commitments
+            .values()
+            .map(|commitments| commitments[0])
+            .fold(None, |acc: Option<GE>, c| match acc {
+                None => Some(c),
+                Some(acc) => Some(acc + c),
+            });
+        let public_key_matches = public_key
+            .map(|y| y.get_element() == self.info.public_key.get_element())
+            .unwrap_or(false);
+        if !public_key_matches {
+            return fail(vec![ThresholdChangeError::PublicKeyMismatch]);
+        }
+
+        Transition::NewState(Box::new(Phase2 {
+            info: self.info.clone(),
+            new_params: self.new_params,
+            other_parties: self.other_parties.clone(),
+            coefficients: Secret::new((*self.coefficients).clone()),
+            vss_commitments,
+            paillier_keys: Secret::new((*self.paillier_keys).clone()),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase1", &self.other_parties, current_msg_set)
+    }
+}
+
+/// Second phase of the protocol
+///
+/// * sends values of own sharing polynomial to other parties
+/// * collects values sent to us and verifies them against commitments
+/// * computes the new share and new public shares of all parties
+struct Phase2 {
+    info: MultiPartyPublicInfo,
+    new_params: Parameters,
+    other_parties: BTreeSet<PartyIndex>,
+    coefficients: Secret<Vec<FE>>,
+    vss_commitments: HashMap<PartyIndex, Vec<GE>>,
+    paillier_keys: Secret<PaillierKeys>,
+    timeout: Option<Duration>,
+}
+
+impl Phase2 {
+    /// public shares of all parties computed from commitments, returns `None` if some of the shares can't be computed
+    fn new_public_shares(&self) -> Option<HashMap<PartyIndex, GE>> {
+        let points = &self.info.party_to_point_map.points;
+        self.vss_commitments
+            .keys()
+            .map(|party| {
+                let x = *points.get(party)?;
+                self.vss_commitments
+                    .values()
+                    .try_fold(None, |acc: Option<GE>, commitments| {
+                        let c = evaluate_sharing_commitments(commitments, x)?;
+                        Some(Some(match acc {
+                            None => c,
+                            Some(acc) => acc + c,
+                        }))
+                    })?
+                    .map(|share| (*party, share))
+            })
+            .collect()
+    }
+}
+
+#[trace(pretty, prefix = "Phase2::")]
+impl State<ThresholdChangeTraits> for Phase2 {
+    fn start(&mut self) -> Option<OutMsgVec> {
+        log::debug!("Phase2 starts");
+        let points = &self.info.party_to_point_map.points;
+        Some(
+            self.other_parties
+                .iter()
+                .map(|p| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R2(DealtShare {
+                        share: evaluate_sharing(&self.coefficients, points[p]),
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+        matches!(msg.body, Message::R2(_))
+            && self.other_parties.contains(&msg.sender)
+            && !msg.is_duplicate(current_msg_set)
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+        is_broadcast_input_complete(current_msg_set, &self.other_parties)
+    }
+
+    fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ThresholdChangeTraits> {
+        let fail = |errors: Vec<ThresholdChangeError>| {
+            let error_state = ErrorState::new(errors).with_evidence("phase2", &current_msg_set);
+            log::error!("Phase2 returns {:?}", error_state);
+            Transition::FinalState(Err(error_state))
+        };
+        let input = match to_hash_map_gen::<DealtShare, _, _>(&current_msg_set) {
+            Ok(input) => Secret::new(
+                input
+                    .into_iter()
+                    .map(|(p, msg)| (p, msg.share))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            Err(errors) => return fail(errors),
+        };
+
+        let own_x = self.info.own_point;
+        let g: GE = ECPoint::generator();
+        let errors = input
+            .iter()
+            .filter(|(party, share)| {
+                evaluate_sharing_commitments(&self.vss_commitments[*party], own_x)
+                    .map(|expected| (g * *share).get_element() != expected.get_element())
+                    .unwrap_or(true)
+            })
+            .map(|(party, _)| ThresholdChangeError::InvalidDealtShare { party: *party })
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return fail(errors);
+        }
+
+        let public_shares = match self.new_public_shares() {
+            Some(public_shares) => public_shares,
+            None => return fail(vec![ThresholdChangeError::PublicSharesMissing]),
+        };
+        let share = Secret::new((
+            own_x,
+            input.values().fold(
+                evaluate_sharing(&self.coefficients, own_x),
+                |acc, dealt_share| acc + *dealt_share,
+            ),
+        ));
+        let public_share_matches = public_shares
+            .get(&self.info.own_party_index)
+            .map(|public_share| public_share.get_element() == (g * share.1).get_element())
+            .unwrap_or(false);
+        if !public_share_matches {
+            return fail(vec![ThresholdChangeError::ShareVerificationFailed]);
+        }
+
+        let committee = self
+            .vss_commitments
+            .keys()
+            .cloned()
+            .collect::<BTreeSet<_>>();
+        let info = MultiPartyPublicInfo {
+            key_params: self.new_params,
+            public_shares,
+            metadata: self.info.metadata.next_epoch(committee),
+            ..self.info.clone()
+        };
+        log::info!(
+            "Phase2 succeeded, the threshold is changed to {}",
+            self.new_params.threshold()
+        );
+
+        Transition::NewState(Box::new(Phase3 {
+            digest: public_digest(&info),
+            info,
+            share,
+            other_parties: self.other_parties.clone(),
+            paillier_keys: Secret::new((*self.paillier_keys).clone()),
+            timeout: self.timeout,
+        }))
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase2", &self.other_parties, current_msg_set)
+    }
+}
+
+/// Last phase of the protocol
+///
+/// * sends the digest of the public info with the new threshold to other parties, confirming that the new share is verified
+/// * collects confirmations of other parties and compares their digests with ours
+struct Phase3 {
+    info: MultiPartyPublicInfo,
+    share: Secret<SecretShare>,
+    other_parties: BTreeSet<PartyIndex>,
+    paillier_keys: Secret<PaillierKeys>,
+    digest: BigInt,
+    timeout: Option<Duration>,
+}
+
+#[trace(pretty, prefix = "Phase3::")]
+impl State<ThresholdChangeTraits> for Phase3 {
+    fn start(&mut self) -> Option<OutMsgVec> {
+        log::debug!("Phase3 starts");
+        Some(
+            self.other_parties
+                .iter()
+                .map(|p| OutMsg {
+                    recipient: Address::Peer(*p),
+                    body: Message::R3(ShareVerified {
+                        digest: self.digest.clone(),
+                    }),
+                })
+                .collect(),
+        )
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_message_expected(&self, msg: &InMsg, current_msg_set: &[InMsg]) -> bool {
+        matches!(msg.body, Message::R3(_))
+            && self.other_parties.contains(&msg.sender)
+            && !msg.is_duplicate(current_msg_set)
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn is_input_complete(&self, current_msg_set: &[InMsg]) -> bool {
+        is_broadcast_input_complete(current_msg_set, &self.other_parties)
+    }
+
+    fn consume(&self, current_msg_set: Vec<InMsg>) -> Transition<ThresholdChangeTraits> {
+        let errors = match to_hash_map_gen::<ShareVerified, _, _>(&current_msg_set) {
+            Ok(input) => input
+                .into_iter()
+                .filter(|(_, msg)| msg.digest != self.digest)
+                .map(|(party, _)| ThresholdChangeError::InconsistentChange { party })
+                .collect::<Vec<_>>(),
+            Err(errors) => errors,
+        };
+        if !errors.is_empty() {
+            let error_state = ErrorState::new(errors).with_evidence("phase3", &current_msg_set);
+            log::error!("Phase3 returns {:?}", error_state);
+            return Transition::FinalState(Err(error_state));
+        }
+
+        log::info!("Phase3 succeeded");
+        Transition::FinalState(Ok(FinalState {
+            info: self
+                .info
+                .clone()
+                .into_multi_party_info(*self.share, (*self.paillier_keys).clone()),
+        }))
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    fn timeout_outcome(&self, current_msg_set: Vec<InMsg>) -> MachineResult {
+        Err(ErrorState::timeout(
+            self.status(&current_msg_set),
+            &current_msg_set,
+        ))
+    }
+
+    #[trace(disable(current_msg_set))]
+    fn status(&self, current_msg_set: &[InMsg]) -> PhaseStatus {
+        PhaseStatus::new("phase3", &self.other_parties, current_msg_set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Phase1, ThresholdChangeError, ThresholdChangeTraits};
+    use crate::algorithms::random::RandomnessSource;
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::ecdsa::Parameters;
+    use crate::state_machine::BoxedState;
+    use crate::testkit::adversary::scenarios;
+    use crate::testkit::fixtures::{load_keys, run_parties};
+    use crate::testkit::{Action, Adversary, NodeOutcome};
+    use anyhow::bail;
+    use curv::cryptographic_primitives::secret_sharing::feldman_vss::VerifiableSS;
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+
+    /// Runs threshold change among holders of `keys`, returns outcomes of nodes in the order of `keys`
+    fn run_threshold_change(
+        keys: &[MultiPartyInfo],
+        new_params: &Parameters,
+        adversary: Option<Adversary<super::Message>>,
+    ) -> anyhow::Result<Vec<NodeOutcome<super::MachineResult>>> {
+        let parties = keys.iter().map(|k| k.own_party_index).collect::<Vec<_>>();
+        let start_state =
+            |key: &MultiPartyInfo| -> anyhow::Result<BoxedState<ThresholdChangeTraits>> {
+                Ok(Box::new(Phase1::new_with_rng(
+                    key,
+                    &parties,
+                    new_params,
+                    None,
+                    RandomnessSource::seeded(0x74687265 + key.own_point() as u64),
+                )?))
+            };
+        Ok(run_parties(0x74687265, keys, start_state, adversary)?.outcomes)
+    }
+
+    fn public_key_of(keys: &[&MultiPartyInfo]) -> GE {
+        let (points, shares): (Vec<FE>, Vec<FE>) = keys
+            .iter()
+            .map(|key| {
+                let x: FE = ECScalar::from(&BigInt::from(key.own_point() as u64));
+                (x, key.own_share())
+            })
+            .unzip();
+        let g: GE = ECPoint::generator();
+        g * VerifiableSS::lagrange_interpolation_at_zero(&points, &shares)
+    }
+
+    #[test]
+    fn threshold_change_keeps_key_and_auxiliary_keys() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("zkrp-keys")?;
+        let new_params = Parameters::new(3, 3)?;
+        let changed = run_threshold_change(&keys, &new_params, None)?
+            .into_iter()
+            .map(|node| match node.outcome {
+                Some(Ok(final_state)) => Ok(final_state.info),
+                Some(Err(error_state)) => bail!("success expected, got {:?}", error_state),
+                None => bail!("{} has not finished", node.name),
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        assert_eq!(changed.len(), keys.len());
+        let public_key = keys[0].public_key.get_element();
+        assert_eq!(
+            public_key_of(&changed.iter().collect::<Vec<_>>()).get_element(),
+            public_key
+        );
+        assert_ne!(
+            public_key_of(&[&changed[0], &changed[1]]).get_element(),
+            public_key
+        );
+
+        let committee = keys[0].metadata.committee().cloned().unwrap_or_default();
+        for (old, new) in keys.iter().zip(changed.iter()) {
+            assert_eq!(new.key_params.threshold(), 2);
+            assert_eq!(new.key_params.share_count(), 3);
+            assert_eq!(new.own_point(), old.own_point());
+            assert_eq!(new.own_he_keys.ek.n, old.own_he_keys.ek.n);
+            assert_eq!(
+                new.range_proof_setups.as_ref().map(|s| &s.my_setup.N_tilde),
+                old.range_proof_setups.as_ref().map(|s| &s.my_setup.N_tilde)
+            );
+            assert_eq!(new.metadata, old.metadata.next_epoch(committee.clone()));
+            assert!(new.validate().is_empty(), "{:?}", new.validate());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn threshold_change_keeps_share_count() -> anyhow::Result<()> {
+        let keys = load_keys("keys")?;
+        let parties = keys.iter().map(|k| k.own_party_index).collect::<Vec<_>>();
+        let new_params = Parameters::new(3, 4)?;
+        match Phase1::new(&keys[0], &parties, &new_params, None) {
+            Err(ThresholdChangeError::ProtocolSetupError(_)) => Ok(()),
+            result => bail!("setup error expected, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn threshold_change_detects_corrupted_share() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let keys = load_keys("keys")?;
+        let parties = keys.iter().map(|k| k.own_party_index).collect::<Vec<_>>();
+        let cheater = parties[parties.len() - 1];
+        let adversary = Adversary::new(cheater, &parties).in_phase(
+            "phase2",
+            Action::Corrupt(|msg: &mut super::Message| {
+                if let super::Message::R2(dealt_share) = msg {
+                    scenarios::shift_by_one(&mut dealt_share.share)
+                }
+            }),
+        );
+        let outcomes = run_threshold_change(&keys, &Parameters::new(3, 3)?, Some(adversary))?;
+
+        assert_eq!(outcomes.len(), parties.len());
+        for node in outcomes.iter().filter(|node| node.party != cheater) {
+            let error_state = match &node.outcome {
+                Some(Err(error_state)) => error_state,
+                Some(Ok(_)) => bail!("{}: error expected", node.name),
+                None => bail!("{} has not finished", node.name),
+            };
+            match error_state.errors.as_slice() {
+                [ThresholdChangeError::InvalidDealtShare { party }] => assert_eq!(*party, cheater),
+                errors => bail!("unexpected errors {:?}", errors),
+            }
+            assert_eq!(error_state.blame.len(), 1);
+            assert_eq!(error_state.blame[0].party, cheater);
+        }
+        Ok(())
+    }
+}
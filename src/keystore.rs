@@ -0,0 +1,471 @@
+//! Encrypted at-rest storage of key shares
+//!
+//! [`MultiPartyInfo`] contains the secret share and the Paillier decryption key of the party, therefore it must not be stored as plain JSON.
+//! [`KeyStore`] seals it with `XSalsa20-Poly1305` (`sodiumoxide::crypto::secretbox`) under the key which is either supplied by the caller
+//! or derived from a passphrase with `Argon2id`. The sealed file is a JSON document:
+//!
+//! ```text
+//! {
+//!   "version": 1,
+//!   "kdf": { "Argon2id13": { "salt": "…", "ops_limit": 2, "mem_limit": 67108864 } },
+//!   "nonce": "…",
+//!   "ciphertext": "…"
+//! }
+//! ```
+//!
+//! The ciphertext is authenticated, so that a tampered file or a wrong passphrase is rejected with [`KeyStoreError::IntegrityCheckFailed`].
+//! Parameters of the key derivation are authenticated implicitly, as changing them changes the key.
+//! The plaintext repeats the schema version, which is compared with the version in the clear.
+//!
+//! When the layout of [`MultiPartyInfo`] changes, [`SCHEMA_VERSION`] is bumped and files of older versions are upgraded on load
+//! by migrations registered with [`KeyStore::with_migration`]. Each migration transforms the JSON representation of keys from one version to the next one.
+//!
+//! ```ignore
+//! let store = KeyStore::new();
+//! store.save("keys.sealed", &multi_party_info, &SealingKey::Passphrase(passphrase))?;
+//! let multi_party_info = store.load("keys.sealed", &SealingKey::Passphrase(passphrase))?;
+//! ```
+//!
+//! [`MultiPartyInfo`]: ../ecdsa/keygen/struct.MultiPartyInfo.html
+//! [`KeyStore`]: struct.KeyStore.html
+//! [`KeyStore::with_migration`]: struct.KeyStore.html#method.with_migration
+//! [`KeyStoreError::IntegrityCheckFailed`]: enum.KeyStoreError.html#variant.IntegrityCheckFailed
+//! [`SCHEMA_VERSION`]: constant.SCHEMA_VERSION.html
+//!
+use crate::ecdsa::keygen::{IntegrityFinding, MultiPartyInfo};
+use serde::{Deserialize, Serialize};
+use sodiumoxide::crypto::pwhash::argon2id13;
+use sodiumoxide::crypto::secretbox;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Version of the schema of sealed keys produced by the crate
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Transforms the JSON representation of keys from one schema version to the next one
+pub type Migration = fn(serde_json::Value) -> Result<serde_json::Value, String>;
+
+/// Enumerates errors reported by the key store
+#[derive(Debug, Error)]
+pub enum KeyStoreError {
+    #[error("keystore: unsupported schema version {found}, at most {supported} supported")]
+    UnsupportedVersion { found: u32, supported: u32 },
+    #[error("keystore: no migration from schema version {0}")]
+    MissingMigration(u32),
+    #[error("keystore: migration from schema version {from} failed: {reason}")]
+    MigrationFailed { from: u32, reason: String },
+    #[error("keystore: integrity check failed, the file is tampered or the key is wrong")]
+    IntegrityCheckFailed,
+    #[error("keystore: keys are inconsistent: {0:?}")]
+    InconsistentKeys(Vec<IntegrityFinding>),
+    #[error("keystore: {0}")]
+    KeyDerivation(String),
+    #[error("keystore: malformed file: {0}")]
+    Malformed(String),
+    #[error("keystore: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("keystore: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Secret the keys are sealed with
+pub enum SealingKey {
+    /// the key is derived from the passphrase with `Argon2id`
+    Passphrase(String),
+    /// the key supplied by the caller is used as is
+    Key(secretbox::Key),
+}
+
+/// Parameters of `Argon2id` key derivation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KdfLimits {
+    pub ops_limit: usize,
+    pub mem_limit: usize,
+}
+
+impl KdfLimits {
+    /// Limits for interactive use, require 64 MiB of memory
+    pub fn interactive() -> Self {
+        KdfLimits {
+            ops_limit: argon2id13::OPSLIMIT_INTERACTIVE.0,
+            mem_limit: argon2id13::MEMLIMIT_INTERACTIVE.0,
+        }
+    }
+
+    /// Limits for highly sensitive data, require 1 GiB of memory
+    pub fn sensitive() -> Self {
+        KdfLimits {
+            ops_limit: argon2id13::OPSLIMIT_SENSITIVE.0,
+            mem_limit: argon2id13::MEMLIMIT_SENSITIVE.0,
+        }
+    }
+
+    /// Limits read from a file are not allowed to exceed `sensitive` ones, so that a forged file can't exhaust resources
+    fn check(&self) -> Result<(), KeyStoreError> {
+        let max = KdfLimits::sensitive();
+        if self.ops_limit <= max.ops_limit && self.mem_limit <= max.mem_limit {
+            Ok(())
+        } else {
+            Err(KeyStoreError::Malformed(format!(
+                "key derivation limits {:?} exceed {:?}",
+                self, max
+            )))
+        }
+    }
+}
+
+/// Key derivation function recorded in the sealed file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Kdf {
+    /// the key is supplied by the caller
+    Raw,
+    Argon2id13 {
+        salt: String,
+        ops_limit: usize,
+        mem_limit: usize,
+    },
+}
+
+/// Sealed keys as stored in the file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SealedKeys {
+    pub version: u32,
+    pub kdf: Kdf,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+#[derive(Serialize)]
+struct Payload<'a, T> {
+    version: u32,
+    keys: &'a T,
+}
+
+#[derive(Deserialize)]
+struct PayloadVersion {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct OwnedPayload<T> {
+    keys: T,
+}
+
+/// Seals and opens key shares, upgrading files of older schema versions
+#[derive(Debug, Clone)]
+pub struct KeyStore {
+    kdf_limits: KdfLimits,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl Default for KeyStore {
+    fn default() -> Self {
+        KeyStore {
+            kdf_limits: KdfLimits::interactive(),
+            migrations: BTreeMap::new(),
+        }
+    }
+}
+
+impl KeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets limits of the key derivation used when keys are sealed with a passphrase
+    pub fn with_kdf_limits(mut self, kdf_limits: KdfLimits) -> Self {
+        self.kdf_limits = kdf_limits;
+        self
+    }
+
+    /// Registers the migration of keys from schema version `from` to `from + 1`, replacing the previously registered one if any
+    pub fn with_migration(mut self, from: u32, migration: Migration) -> Self {
+        self.migrations.insert(from, migration);
+        self
+    }
+
+    /// Seals the keys
+    pub fn seal(
+        &self,
+        keys: &MultiPartyInfo,
+        secret: &SealingKey,
+    ) -> Result<SealedKeys, KeyStoreError> {
+        self.seal_versioned(SCHEMA_VERSION, keys, secret)
+    }
+
+    fn seal_versioned<T: Serialize>(
+        &self,
+        version: u32,
+        keys: &T,
+        secret: &SealingKey,
+    ) -> Result<SealedKeys, KeyStoreError> {
+        init()?;
+        let kdf = match secret {
+            SealingKey::Passphrase(_) => Kdf::Argon2id13 {
+                salt: hex::encode(argon2id13::gen_salt().0),
+                ops_limit: self.kdf_limits.ops_limit,
+                mem_limit: self.kdf_limits.mem_limit,
+            },
+            SealingKey::Key(_) => Kdf::Raw,
+        };
+        let key = derive_key(&kdf, secret)?;
+        let nonce = secretbox::gen_nonce();
+        let plaintext = Zeroizing::new(serde_json::to_vec(&Payload { version, keys })?);
+        let ciphertext = secretbox::seal(&plaintext, &nonce, &key);
+        Ok(SealedKeys {
+            version,
+            kdf,
+            nonce: hex::encode(nonce.0),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Opens sealed keys, verifies their integrity and upgrades them to the current schema version
+    ///
+    /// Keys which fail [`MultiPartyInfo::validate`](../ecdsa/keygen/struct.MultiPartyInfo.html#method.validate) are rejected with `KeyStoreError::InconsistentKeys`.
+    pub fn open(
+        &self,
+        sealed: &SealedKeys,
+        secret: &SealingKey,
+    ) -> Result<MultiPartyInfo, KeyStoreError> {
+        if sealed.version > SCHEMA_VERSION {
+            return Err(KeyStoreError::UnsupportedVersion {
+                found: sealed.version,
+                supported: SCHEMA_VERSION,
+            });
+        }
+        init()?;
+        let key = derive_key(&sealed.kdf, secret)?;
+        let nonce = secretbox::Nonce::from_slice(&decode_hex("nonce", &sealed.nonce)?)
+            .ok_or_else(|| KeyStoreError::Malformed("invalid nonce length".to_string()))?;
+        let ciphertext = decode_hex("ciphertext", &sealed.ciphertext)?;
+        let plaintext = Zeroizing::new(
+            secretbox::open(&ciphertext, &nonce, &key)
+                .map_err(|_| KeyStoreError::IntegrityCheckFailed)?,
+        );
+
+        // the version in the clear is not authenticated, the one inside the ciphertext is
+        let version = serde_json::from_slice::<PayloadVersion>(&plaintext)?.version;
+        if version != sealed.version {
+            return Err(KeyStoreError::IntegrityCheckFailed);
+        }
+        let keys = if version == SCHEMA_VERSION {
+            serde_json::from_slice::<OwnedPayload<MultiPartyInfo>>(&plaintext)?.keys
+        } else {
+            self.migrate(version, &plaintext)?
+        };
+
+        let findings = keys.validate();
+        if findings.is_empty() {
+            Ok(keys)
+        } else {
+            Err(KeyStoreError::InconsistentKeys(findings))
+        }
+    }
+
+    fn migrate(&self, version: u32, plaintext: &[u8]) -> Result<MultiPartyInfo, KeyStoreError> {
+        let mut keys = serde_json::from_slice::<OwnedPayload<serde_json::Value>>(plaintext)?.keys;
+        for from in version..SCHEMA_VERSION {
+            let migration = self
+                .migrations
+                .get(&from)
+                .ok_or(KeyStoreError::MissingMigration(from))?;
+            keys = migration(keys)
+                .map_err(|reason| KeyStoreError::MigrationFailed { from, reason })?;
+            log::info!("keys migrated from schema version {} to {}", from, from + 1);
+        }
+        Ok(serde_json::from_value(keys)?)
+    }
+
+    /// Seals the keys and writes them to the file, which on Unix is readable by the owner only
+    pub fn save<P: AsRef<Path>>(
+        &self,
+        path: P,
+        keys: &MultiPartyInfo,
+        secret: &SealingKey,
+    ) -> Result<(), KeyStoreError> {
+        let sealed = serde_json::to_vec_pretty(&self.seal(keys, secret)?)?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        file.write_all(&sealed)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Reads the file and opens the keys
+    pub fn load<P: AsRef<Path>>(
+        &self,
+        path: P,
+        secret: &SealingKey,
+    ) -> Result<MultiPartyInfo, KeyStoreError> {
+        let sealed = serde_json::from_slice::<SealedKeys>(&fs::read(path)?)?;
+        self.open(&sealed, secret)
+    }
+}
+
+fn init() -> Result<(), KeyStoreError> {
+    sodiumoxide::init()
+        .map_err(|_| KeyStoreError::KeyDerivation("sodiumoxide can't be initialized".to_string()))
+}
+
+fn decode_hex(field: &str, value: &str) -> Result<Vec<u8>, KeyStoreError> {
+    hex::decode(value).map_err(|e| KeyStoreError::Malformed(format!("{}: {}", field, e)))
+}
+
+fn derive_key(kdf: &Kdf, secret: &SealingKey) -> Result<secretbox::Key, KeyStoreError> {
+    match (kdf, secret) {
+        (Kdf::Raw, SealingKey::Key(key)) => Ok(key.clone()),
+        (
+            Kdf::Argon2id13 {
+                salt,
+                ops_limit,
+                mem_limit,
+            },
+            SealingKey::Passphrase(passphrase),
+        ) => {
+            KdfLimits {
+                ops_limit: *ops_limit,
+                mem_limit: *mem_limit,
+            }
+            .check()?;
+            let salt = argon2id13::Salt::from_slice(&decode_hex("salt", salt)?)
+                .ok_or_else(|| KeyStoreError::Malformed("invalid salt length".to_string()))?;
+            let mut key = secretbox::Key([0; secretbox::KEYBYTES]);
+            argon2id13::derive_key(
+                &mut key.0,
+                passphrase.as_bytes(),
+                &salt,
+                argon2id13::OpsLimit(*ops_limit),
+                argon2id13::MemLimit(*mem_limit),
+            )
+            .map_err(|_| KeyStoreError::KeyDerivation("key derivation failed".to_string()))?;
+            Ok(key)
+        }
+        (Kdf::Raw, SealingKey::Passphrase(_)) => Err(KeyStoreError::KeyDerivation(
+            "the keys are sealed with a key, not with a passphrase".to_string(),
+        )),
+        (Kdf::Argon2id13 { .. }, SealingKey::Key(_)) => Err(KeyStoreError::KeyDerivation(
+            "the keys are sealed with a passphrase, not with a key".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyStore, KeyStoreError, SealingKey, SCHEMA_VERSION};
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use sodiumoxide::crypto::secretbox;
+    use std::fs;
+
+    fn keys() -> anyhow::Result<MultiPartyInfo> {
+        Ok(serde_json::from_str(&fs::read_to_string(
+            "tests/data/keys.0.json",
+        )?)?)
+    }
+
+    fn passphrase() -> SealingKey {
+        SealingKey::Passphrase("correct horse battery staple".to_string())
+    }
+
+    #[test]
+    fn roundtrip() -> anyhow::Result<()> {
+        let keys = keys()?;
+        let store = KeyStore::new();
+        for secret in vec![passphrase(), SealingKey::Key(secretbox::gen_key())] {
+            let sealed = store.seal(&keys, &secret)?;
+            assert_eq!(sealed.version, SCHEMA_VERSION);
+            let opened = store.open(&sealed, &secret)?;
+            assert_eq!(opened.own_share(), keys.own_share());
+            assert_eq!(opened.public_key, keys.public_key);
+            assert_eq!(opened.own_he_keys, keys.own_he_keys);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_files_are_rejected() -> anyhow::Result<()> {
+        let keys = keys()?;
+        let store = KeyStore::new();
+        let sealed = store.seal(&keys, &passphrase())?;
+
+        let wrong = SealingKey::Passphrase("wrong".to_string());
+        assert!(matches!(
+            store.open(&sealed, &wrong),
+            Err(KeyStoreError::IntegrityCheckFailed)
+        ));
+
+        let mut tampered = sealed.clone();
+        let flipped = if tampered.ciphertext.ends_with('0') {
+            '1'
+        } else {
+            '0'
+        };
+        tampered.ciphertext.pop();
+        tampered.ciphertext.push(flipped);
+        assert!(matches!(
+            store.open(&tampered, &passphrase()),
+            Err(KeyStoreError::IntegrityCheckFailed)
+        ));
+
+        let mut downgraded = sealed;
+        downgraded.version = 0;
+        assert!(matches!(
+            store.open(&downgraded, &passphrase()),
+            Err(KeyStoreError::IntegrityCheckFailed)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn inconsistent_keys_are_rejected() -> anyhow::Result<()> {
+        let mut keys = keys()?;
+        keys.key_params.share_count += 1;
+        let secret = SealingKey::Key(secretbox::gen_key());
+        let store = KeyStore::new();
+        let sealed = store.seal(&keys, &secret)?;
+        assert!(matches!(
+            store.open(&sealed, &secret),
+            Err(KeyStoreError::InconsistentKeys(findings)) if findings.len() == 1
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn older_versions_are_migrated() -> anyhow::Result<()> {
+        let keys = keys()?;
+        let secret = SealingKey::Key(secretbox::gen_key());
+
+        // pretend that the schema preceding version 1 named the field differently
+        let mut legacy = serde_json::to_value(&keys)?;
+        let object = legacy.as_object_mut().unwrap();
+        let public_key = object.remove("public_key").unwrap();
+        object.insert("y".to_string(), public_key);
+        let sealed = KeyStore::new().seal_versioned(0, &legacy, &secret)?;
+
+        assert!(matches!(
+            KeyStore::new().open(&sealed, &secret),
+            Err(KeyStoreError::MissingMigration(0))
+        ));
+
+        let store = KeyStore::new().with_migration(0, |mut keys| {
+            let object = keys.as_object_mut().ok_or("object expected")?;
+            let public_key = object.remove("y").ok_or("y expected")?;
+            object.insert("public_key".to_string(), public_key);
+            Ok(keys)
+        });
+        let opened = store.open(&sealed, &secret)?;
+        assert_eq!(opened.public_key, keys.public_key);
+        assert_eq!(opened.own_share(), keys.own_share());
+        Ok(())
+    }
+}
@@ -4,14 +4,26 @@
 //!
 //! The scheme comprises
 //!  * key generation performed in the distributed setup with `N` players
+//!  * import of an existing key, which a trusted dealer splits among `N` players
 //!  * message signing carried out by subgroup of `(t+1, N)` players
 //!  * the key resharing performed by `t+1` players resulting in a new group of `M` players holding
 //! new shares of the same signing key.
+//!  * repair of a lost share or enrollment of a new party by `t+1` players, which leaves other shares intact
+//!  * proactive refresh of shares, Paillier keys and range proof setups by the committee of the key, which keeps the public key
+//!  * change of the threshold by the committee of the key, which keeps the public key and auxiliary keys of parties
+//!  * authorized export of the key by `t+1` players to a designated recipient, which every participant records in its audit log
 //! The scheme is based on ECDSA standard with the elliptic curve secp256k1 , which can be substituted by other curves.
 //!
 //! Cryptographic protocols are implemented by [`ecdsa`](./ecdsa/index.html) module.
 //! Additional algorithms can be found in [`algorithms`](./algorithms/index.html) module.
 //! The general purpose state machine is implemented in [`state_machine`](./state_machine/index.html) module.
+//! Deterministic network simulation for testing of protocols is provided by [`testkit`](./testkit/index.html) module,
+//! which is built only with the `testkit` feature enabled.
+//! State machines can be connected to other parties over the network by means of [`transport`](./transport/index.html) module,
+//! which encodes messages in the binary format defined by [`wire`](./wire/index.html) module.
+//! Key shares can be stored encrypted by means of [`keystore`](./keystore/index.html) module,
+//! and backed up to an escrow key verifiably by means of [`backup`](./backup/index.html) module.
+//! Secret values held by protocols are wrapped by [`secret::Secret`](./secret/struct.Secret.html), which erases them on drop.
 #![allow(
     clippy::must_use_candidate,
     clippy::items_after_statements,
@@ -22,9 +34,16 @@
     clippy::used_underscore_binding // if turned on, seems to generate a lot of false positive
 )]
 pub mod algorithms;
+pub mod backup;
 pub mod ecdsa;
+pub mod keystore;
 pub mod protocol;
+pub mod secret;
 pub mod state_machine;
+#[cfg(any(test, feature = "testkit"))]
+pub mod testkit;
+pub mod transport;
+pub mod wire;
 
 #[macro_use]
 extern crate strum_macros;
@@ -0,0 +1,256 @@
+//! Secret values held by protocols
+//!
+//! [`Secret`] wraps a value such as a nonce of signing, a share of the key or a random value of a range proof, so that
+//! * the value is kept on the heap, hence moving the wrapper from one phase to another does not leave copies of the value behind
+//! * `Debug` output is redacted
+//! * the wrapper is not `Clone`, so that the number of copies is under control of the code which owns the value
+//! * the value is erased when the wrapper is dropped
+//!
+//! Optionally, the memory occupied by secret values is locked with `sodium_mlock`, so that it is not swapped to disk, see [`set_memory_locking`].
+//! Note that only the memory of the value itself is locked. Big integers keep their limbs in separate allocations, which are erased but not locked.
+//!
+//! [`Secret`]: struct.Secret.html
+//! [`set_memory_locking`]: fn.set_memory_locking.html
+//!
+use crate::algorithms::zkp::ZkpSetup;
+use crate::ecdsa::keygen::RangeProofSetups;
+use crate::ecdsa::messages::SecretShare;
+use crate::ecdsa::PaillierKeys;
+use curv::arithmetic::traits::ZeroizeBN;
+use curv::{BigInt, FE};
+use paillier::DecryptionKey;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Debug, Formatter};
+use std::hash::Hash;
+use std::mem::{self, ManuallyDrop};
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+use zeroize::Zeroize;
+
+static MEMORY_LOCKING: AtomicBool = AtomicBool::new(false);
+
+/// Error of enabling memory locking
+#[derive(Debug, Error)]
+#[error("secret: {0}")]
+pub struct MemoryLockError(pub String);
+
+/// Enables or disables locking of memory of secret values created afterwards
+///
+/// Locking is disabled by default, as the amount of locked memory is limited by `RLIMIT_MEMLOCK` on Unix systems.
+/// If the value can't be locked, it is used unlocked and the warning is logged.
+pub fn set_memory_locking(enabled: bool) -> Result<(), MemoryLockError> {
+    if enabled {
+        sodiumoxide::init()
+            .map_err(|_| MemoryLockError("sodiumoxide can't be initialized".to_string()))?;
+    }
+    MEMORY_LOCKING.store(enabled, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Values which can be overwritten in place
+///
+/// Unlike `Zeroize`, the trait can be implemented by the crate for foreign types like `BigInt` and `DecryptionKey`.
+pub trait Erase {
+    fn erase(&mut self);
+}
+
+impl Erase for FE {
+    fn erase(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Erase for BigInt {
+    fn erase(&mut self) {
+        self.zeroize_bn();
+    }
+}
+
+impl Erase for DecryptionKey {
+    fn erase(&mut self) {
+        self.p.zeroize_bn();
+        self.q.zeroize_bn();
+    }
+}
+
+impl Erase for PaillierKeys {
+    fn erase(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl Erase for ZkpSetup {
+    fn erase(&mut self) {
+        self.zeroize();
+    }
+}
+
+/// erases the own setup, setups of other parties are public
+impl Erase for RangeProofSetups {
+    fn erase(&mut self) {
+        self.my_setup.zeroize();
+    }
+}
+
+/// erases the value of the share, the x-coordinate is public
+impl Erase for SecretShare {
+    fn erase(&mut self) {
+        self.1.erase();
+    }
+}
+
+impl<T: Erase> Erase for Vec<T> {
+    fn erase(&mut self) {
+        self.drain(..).for_each(|mut x| x.erase());
+    }
+}
+
+impl<K: Eq + Hash, V: Erase> Erase for HashMap<K, V> {
+    fn erase(&mut self) {
+        self.drain().for_each(|(_, mut v)| v.erase());
+    }
+}
+
+/// Owner of the secret value, see [module level documentation](index.html)
+pub struct Secret<T: Erase> {
+    value: Box<ManuallyDrop<T>>,
+    locked: bool,
+}
+
+impl<T: Erase> Secret<T> {
+    /// Moves the value to the heap, locking its memory if enabled by [`set_memory_locking`](fn.set_memory_locking.html)
+    pub fn new(value: T) -> Self {
+        Self::from_box(Box::new(value))
+    }
+
+    /// Takes ownership of the value which is already on the heap, e.g. the one returned by a secret loader, without copying it
+    pub fn from_box(value: Box<T>) -> Self {
+        // ManuallyDrop<T> has the same layout as T
+        let value = unsafe { Box::from_raw(Box::into_raw(value) as *mut ManuallyDrop<T>) };
+        let mut secret = Secret {
+            value,
+            locked: false,
+        };
+        if MEMORY_LOCKING.load(Ordering::SeqCst) && mem::size_of::<T>() > 0 {
+            secret.locked = sodiumoxide::utils::mlock(secret.bytes()).is_ok();
+            if !secret.locked {
+                log::warn!("memory of the secret value can't be locked");
+            }
+        }
+        secret
+    }
+
+    /// whether the memory of the value is locked
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    fn bytes(&mut self) -> &mut [u8] {
+        let ptr = &mut **self.value as *mut T as *mut u8;
+        // the slice covers the memory of the boxed value only, and it is used solely by `sodium_mlock` and `sodium_munlock`
+        unsafe { std::slice::from_raw_parts_mut(ptr, mem::size_of::<T>()) }
+    }
+}
+
+impl<T: Erase> Deref for Secret<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Erase> DerefMut for Secret<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+impl<T: Erase> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.value.erase();
+        // the value is dropped in place, so that its memory remains allocated until it is unlocked
+        unsafe { ManuallyDrop::drop(&mut self.value) };
+        if self.locked {
+            // munlock overwrites the memory with zeros before unlocking
+            let _ = sodiumoxide::utils::munlock(self.bytes());
+        }
+    }
+}
+
+impl<T: Erase> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Secret([***])")
+    }
+}
+
+/// Compile-time check that a type does not implement `Clone`
+///
+/// `<T as AmbiguousIfClone<_>>::check()` compiles only if exactly one of the implementations applies to `T`, that is if `T` is not `Clone`.
+#[cfg(test)]
+pub(crate) trait AmbiguousIfClone<A> {
+    fn check() {}
+}
+
+#[cfg(test)]
+impl<T: ?Sized> AmbiguousIfClone<()> for T {}
+
+#[cfg(test)]
+pub(crate) struct IsClone;
+
+#[cfg(test)]
+impl<T: ?Sized + Clone> AmbiguousIfClone<IsClone> for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::{set_memory_locking, AmbiguousIfClone, Erase, Secret};
+    use curv::elliptic::curves::traits::ECScalar;
+    use curv::{BigInt, FE};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[derive(Debug)]
+    struct Probe {
+        n: BigInt,
+        erased: Rc<Cell<bool>>,
+    }
+
+    impl Erase for Probe {
+        fn erase(&mut self) {
+            self.n.erase();
+            self.erased.set(true);
+        }
+    }
+
+    #[test]
+    fn secret_is_erased_on_drop() {
+        let erased = Rc::new(Cell::new(false));
+        let secret = Secret::new(Probe {
+            n: BigInt::from(42),
+            erased: erased.clone(),
+        });
+        assert_eq!(secret.n, BigInt::from(42));
+        assert_eq!(format!("{:?}", secret), "Secret([***])");
+        drop(secret);
+        assert!(erased.get());
+    }
+
+    #[test]
+    fn locked_secret() {
+        set_memory_locking(true).expect("sodiumoxide initialized");
+        let mut k_i = Secret::new(FE::new_random());
+        let k = *k_i;
+        *k_i = k * k;
+        assert_eq!(*k_i, k * k);
+        set_memory_locking(false).expect("locking disabled");
+        // locking may be forbidden by the environment, the value is usable in either case
+        let _ = k_i.is_locked();
+    }
+
+    #[test]
+    fn secret_is_not_clone() {
+        <Secret<FE> as AmbiguousIfClone<_>>::check();
+    }
+}
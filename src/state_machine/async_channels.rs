@@ -9,7 +9,9 @@ use futures::{SinkExt, StreamExt};
 use tokio::time;
 
 use crate::protocol::Instruction;
-use crate::state_machine::{BoxedState, StateMachineTraits, Transition};
+use crate::state_machine::{
+    BoxedState, MachineStatus, StateMachineTraits, StatusHandle, StatusTracker, Transition,
+};
 use std::collections::VecDeque;
 
 /// Finite state machine
@@ -33,6 +35,7 @@ where
     outqueue: UnboundedSender<T::OutMsg>,
     retained: Vec<T::InMsg>,
     discarded: DiscardedDeck<T::InMsg>,
+    tracker: StatusTracker,
 }
 
 /// container for deferred messaged
@@ -66,6 +69,9 @@ impl<T> DiscardedDeck<T> {
             self.current.push_back(m)
         }
     }
+    pub fn len(&self) -> usize {
+        self.current.len() + self.next_state.len()
+    }
 }
 
 impl<T> StateMachine<T>
@@ -84,13 +90,26 @@ where
             outqueue,
             retained: Vec::new(),
             discarded: DiscardedDeck::new(),
+            tracker: StatusTracker::new(),
         }
     }
 
+    /// Returns the handle which reports the progress of the machine while it executes
+    pub fn status_handle(&self) -> StatusHandle {
+        self.tracker.handle.clone()
+    }
+
+    /// Returns current progress of the machine
+    pub fn status(&self) -> MachineStatus {
+        self.tracker
+            .status(self.state.status(&self.retained), self.discarded.len())
+    }
+
     /// Execute main loop of the machine.
     pub async fn execute(&mut self) -> Option<Result<T::FinalState, T::ErrorState>> {
         log::trace!("starting State Machine");
 
+        self.tracker.machine_started();
         self.state_post_transition().await;
 
         let driving_result = {
@@ -137,15 +156,22 @@ where
                 match transition {
                     Transition::NewState(state) => {
                         let _ = std::mem::replace(&mut self.state, state);
+                        self.tracker.phase_started();
                         self.state_post_transition().await;
                         self.discarded.flip();
                     }
                     Transition::FinalState(outcome) => return Some(outcome),
                 }
             }
+            self.publish_status();
         }
     }
 
+    fn publish_status(&self) {
+        self.tracker
+            .publish(self.state.status(&self.retained), self.discarded.len());
+    }
+
     /// internal function which processes the message according to the state machine algorithm
     fn process_message(&mut self, message: T::InMsg) -> Option<Transition<T>> {
         // Check message is expected.
@@ -179,13 +205,16 @@ where
                 }
             }
         }
+        self.publish_status();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::protocol::{Address, InputMessage, OutputMessage};
+    use crate::protocol::{Address, InputMessage, OutputMessage, PartyIndex};
+    use crate::state_machine::PhaseStatus;
+    use std::collections::BTreeSet;
     use std::time::Duration;
 
     use crate::state_machine::async_channels::tests::MessageType::*;
@@ -287,6 +316,16 @@ mod tests {
         fn timeout_outcome(&self, _current_msg_set: Vec<In>) -> MachineResult {
             unimplemented!()
         }
+
+        fn status(&self, current_msg_set: &[In]) -> PhaseStatus {
+            let phase = match self.sub_phase {
+                P1 => "P1",
+                P2 => "P2",
+                P3 => "P3",
+            };
+            let expected = (1..=3).map(PartyIndex::from).collect::<BTreeSet<_>>();
+            PhaseStatus::new(phase, &expected, current_msg_set)
+        }
     }
 
     #[tokio::test]
@@ -480,4 +519,66 @@ mod tests {
             }
         })
     }
+
+    #[tokio::test]
+    async fn status_query() {
+        let _ = env_logger::try_init();
+
+        let (mut ingress, rx) = futures::channel::mpsc::unbounded();
+        let (tx, _egress) = futures::channel::mpsc::unbounded();
+
+        let start_state = Box::new(MultiPhase::new());
+        let mut machine = StateMachine::<TestTraits>::new(start_state, rx, tx);
+        let handle = machine.status_handle();
+        assert!(handle.status().is_none());
+
+        let (tx_result, rx_result) = futures::channel::oneshot::channel();
+        tokio::spawn(async move {
+            let result = machine.execute().await;
+            tx_result.send(result).unwrap();
+        });
+
+        // P1 completes, P2 receives a message from party 1, P3 message is deferred
+        for (sender, msg_type) in vec![
+            (1, P1_Input),
+            (2, P1_Input),
+            (3, P1_Input),
+            (1, P2_Input),
+            (2, P3_Input),
+        ] {
+            let _ = ingress
+                .send(Instruction::Data(InputMessage {
+                    sender: PartyIndex::from(sender),
+                    body: msg_type,
+                }))
+                .await;
+        }
+
+        // the handle is published after each message, wait until the last one is processed
+        let mut status = None;
+        for _ in 0..100 {
+            status = handle.status().filter(|s| s.deferred == 1);
+            if status.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let status = status.expect("machine did not report its status");
+
+        assert_eq!(status.phase.phase, "P2");
+        assert_eq!(
+            status.phase.received_senders,
+            vec![PartyIndex::from(1)].into_iter().collect()
+        );
+        assert_eq!(
+            status.phase.missing_senders(),
+            vec![PartyIndex::from(2), PartyIndex::from(3)]
+                .into_iter()
+                .collect()
+        );
+        assert!(status.phase_elapsed <= status.total_elapsed);
+
+        let _ = ingress.send(Instruction::Terminate).await;
+        assert!(matches!(rx_result.await, Ok(None)));
+    }
 }
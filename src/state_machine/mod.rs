@@ -28,14 +28,35 @@
 //!  The machine will check then if this duration is expired before  `is_input_complete` returns true.
 //!  In this case the machine stops and returns the value provided by `timeout_outcome` method of the current state object.
 //!
+//! # Progress reporting
+//!
+//! While the machine is running, its progress can be observed through a [`StatusHandle`] obtained from the machine before it starts.
+//! Both the async and the sync machine publish to the handle after each processed message and each transition.
+//! The reported [`MachineStatus`] contains the name of the current phase, the parties the phase expects messages from,
+//! the parties whose messages were already received, the number of deferred messages and the elapsed time.
+//! Phase specific part of the report is provided by the `status` method of the trait [`State`].
+//!
+//! # Step-driven machine
+//!
+//! The [`Stepper`] executes states same way as both machines above, but without channels and clock.
+//! It is driven by the caller message by message, which makes it suitable for deterministic single threaded simulations.
+//!
 //! [`State`]: trait.State.html
+//! [`Stepper`]: stepper/struct.Stepper.html
 //! [`Transition`]: enum.Transition.html
+//! [`StatusHandle`]: struct.StatusHandle.html
+//! [`MachineStatus`]: struct.MachineStatus.html
 //!
 pub mod async_channels;
+pub mod stepper;
 pub mod sync_channels;
 
+use crate::protocol::{InputMessage, PartyIndex};
+use serde::Serialize;
+use std::collections::BTreeSet;
 use std::fmt::{Debug, Error, Formatter};
-use std::time::Duration;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 pub trait StateMachineTraits {
     type InMsg;
@@ -80,6 +101,142 @@ where
         &self,
         current_msg_set: Vec<T::InMsg>,
     ) -> Result<T::FinalState, T::ErrorState>;
+
+    /// Reports the progress of the phase given the messages retained by the machine so far
+    fn status(&self, _current_msg_set: &[T::InMsg]) -> PhaseStatus {
+        PhaseStatus::default()
+    }
+}
+
+/// Progress of a single phase of a protocol
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PhaseStatus {
+    /// name of the phase, empty if the state does not report it
+    pub phase: String,
+    /// parties the phase expects messages from
+    pub expected_senders: BTreeSet<PartyIndex>,
+    /// parties whose messages are already received by the phase
+    pub received_senders: BTreeSet<PartyIndex>,
+}
+
+impl PhaseStatus {
+    pub fn new<B>(
+        phase: &str,
+        expected_senders: &BTreeSet<PartyIndex>,
+        current_msg_set: &[InputMessage<B>],
+    ) -> Self {
+        PhaseStatus {
+            phase: phase.to_owned(),
+            expected_senders: expected_senders.clone(),
+            received_senders: current_msg_set.iter().map(|m| m.sender).collect(),
+        }
+    }
+
+    /// Returns parties which are expected to send a message but have not sent it yet
+    pub fn missing_senders(&self) -> BTreeSet<PartyIndex> {
+        self.expected_senders
+            .difference(&self.received_senders)
+            .cloned()
+            .collect()
+    }
+}
+
+/// Snapshot of the progress of a running machine
+#[derive(Debug, Clone, Serialize)]
+pub struct MachineStatus {
+    /// progress of the current phase
+    pub phase: PhaseStatus,
+    /// number of messages deferred to the next phases
+    pub deferred: usize,
+    /// time elapsed since the current phase has started
+    pub phase_elapsed: Duration,
+    /// time elapsed since the machine has started
+    pub total_elapsed: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct StatusRecord {
+    phase: PhaseStatus,
+    deferred: usize,
+    machine_started: Instant,
+    phase_started: Instant,
+}
+
+impl StatusRecord {
+    fn to_status(&self) -> MachineStatus {
+        MachineStatus {
+            phase: self.phase.clone(),
+            deferred: self.deferred,
+            phase_elapsed: self.phase_started.elapsed(),
+            total_elapsed: self.machine_started.elapsed(),
+        }
+    }
+}
+
+/// Shared handle which lets other threads or tasks observe the progress of a running machine
+///
+/// The handle is updated by the machine each time it processes a message or moves to the next phase.
+/// It returns `None` until the machine is started.
+#[derive(Debug, Clone, Default)]
+pub struct StatusHandle {
+    record: Arc<Mutex<Option<StatusRecord>>>,
+}
+
+impl StatusHandle {
+    pub fn status(&self) -> Option<MachineStatus> {
+        self.record
+            .lock()
+            .ok()
+            .and_then(|record| record.as_ref().map(StatusRecord::to_status))
+    }
+}
+
+/// Keeps track of timing of a machine and publishes its progress to the [`StatusHandle`]
+#[derive(Debug)]
+struct StatusTracker {
+    machine_started: Instant,
+    phase_started: Instant,
+    handle: StatusHandle,
+}
+
+impl StatusTracker {
+    fn new() -> Self {
+        let now = Instant::now();
+        StatusTracker {
+            machine_started: now,
+            phase_started: now,
+            handle: StatusHandle::default(),
+        }
+    }
+
+    /// resets the clocks; called once the machine starts executing
+    fn machine_started(&mut self) {
+        self.machine_started = Instant::now();
+        self.phase_started = self.machine_started;
+    }
+
+    fn phase_started(&mut self) {
+        self.phase_started = Instant::now();
+    }
+
+    fn record(&self, phase: PhaseStatus, deferred: usize) -> StatusRecord {
+        StatusRecord {
+            phase,
+            deferred,
+            machine_started: self.machine_started,
+            phase_started: self.phase_started,
+        }
+    }
+
+    fn status(&self, phase: PhaseStatus, deferred: usize) -> MachineStatus {
+        self.record(phase, deferred).to_status()
+    }
+
+    fn publish(&self, phase: PhaseStatus, deferred: usize) {
+        if let Ok(mut record) = self.handle.record.lock() {
+            *record = Some(self.record(phase, deferred));
+        }
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////
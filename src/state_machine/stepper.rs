@@ -0,0 +1,243 @@
+//! Step-driven state machine
+//!
+//! The machine executes the same sequence of states as the [`Async state machine`] and the [`Sync state machine`], however it owns neither queues nor a clock.
+//! Instead, the caller feeds the machine with input messages one by one, collects the output after each step and decides itself when the current phase has expired.
+//! This makes the machine suitable for single threaded drivers like network simulators, or for composing several protocols within one node.
+//!
+//! [`Async state machine`]: ../async_channels/index.html
+//! [`Sync state machine`]: ../sync_channels/index.html
+//!
+use crate::state_machine::sync_channels::DiscardedDeck;
+use crate::state_machine::{BoxedState, PhaseStatus, StateMachineTraits, Transition};
+use std::time::Duration;
+
+/// Step-driven finite state machine
+///
+/// See [`sync_channels::StateMachine`](../sync_channels/struct.StateMachine.html)
+pub struct Stepper<T>
+where
+    T: StateMachineTraits,
+{
+    state: BoxedState<T>,
+    retained: Vec<T::InMsg>,
+    discarded: DiscardedDeck<T::InMsg>,
+    output: Vec<T::OutMsg>,
+    phase_index: usize,
+}
+
+impl<T: StateMachineTraits> Stepper<T> {
+    pub fn new(start_state: BoxedState<T>) -> Self {
+        Stepper {
+            state: start_state,
+            retained: Vec::new(),
+            discarded: DiscardedDeck::new(),
+            output: Vec::new(),
+            phase_index: 0,
+        }
+    }
+
+    /// Starts the first state and returns its output
+    pub fn start(&mut self) -> Vec<T::OutMsg> {
+        self.state_prepare();
+        self.take_output()
+    }
+
+    /// Feeds the message to the machine
+    ///
+    /// Messages deferred by earlier phases are processed first once the machine moves to the next phase, same way the channel based machines do.
+    /// Returns the outcome of the protocol when the machine reaches its final state. The output produced by the step is available through [`take_output`](#method.take_output).
+    pub fn deliver(&mut self, message: T::InMsg) -> Option<Result<T::FinalState, T::ErrorState>> {
+        let mut next = Some(message);
+        while let Some(message) = next.take().or_else(|| self.discarded.pop()) {
+            if let Some(transition) = self.process_message(message) {
+                match transition {
+                    Transition::NewState(state) => {
+                        self.state = state;
+                        self.phase_index += 1;
+                        self.state_prepare();
+                        self.discarded.flip();
+                    }
+                    Transition::FinalState(outcome) => return Some(outcome),
+                }
+            }
+        }
+        None
+    }
+
+    /// Terminates current phase by its timeout
+    pub fn expire(&mut self) -> Result<T::FinalState, T::ErrorState> {
+        self.state
+            .timeout_outcome(self.retained.drain(..).collect())
+    }
+
+    /// Returns the output accumulated since the last call
+    pub fn take_output(&mut self) -> Vec<T::OutMsg> {
+        self.output.drain(..).collect()
+    }
+
+    /// Timeout of the current phase
+    pub fn timeout(&self) -> Option<Duration> {
+        self.state.timeout()
+    }
+
+    /// Number of transitions made by the machine so far, changes every time the machine enters new phase
+    pub fn phase_index(&self) -> usize {
+        self.phase_index
+    }
+
+    /// Progress of the current phase
+    pub fn status(&self) -> PhaseStatus {
+        self.state.status(&self.retained)
+    }
+
+    /// Number of messages deferred to next phases
+    pub fn deferred(&self) -> usize {
+        self.discarded.len()
+    }
+
+    /// Tells whether the current phase expects the message, given `pending` messages which are accepted on behalf of the machine but not delivered yet
+    pub fn is_message_expected(&self, message: &T::InMsg, pending: &[T::InMsg]) -> bool
+    where
+        T::InMsg: Clone,
+    {
+        self.state
+            .is_message_expected(message, &self.with_retained(pending))
+    }
+
+    /// Tells whether the current phase would be complete once `pending` messages are delivered
+    pub fn is_input_complete(&self, pending: &[T::InMsg]) -> bool
+    where
+        T::InMsg: Clone,
+    {
+        self.state.is_input_complete(&self.with_retained(pending))
+    }
+
+    fn with_retained(&self, pending: &[T::InMsg]) -> Vec<T::InMsg>
+    where
+        T::InMsg: Clone,
+    {
+        self.retained.iter().chain(pending).cloned().collect()
+    }
+
+    fn process_message(&mut self, message: T::InMsg) -> Option<Transition<T>> {
+        if self.state.is_message_expected(&message, &self.retained) {
+            self.retained.push(message);
+        } else {
+            self.discarded.save(message);
+            return None;
+        }
+
+        if self.state.is_input_complete(&self.retained) {
+            Some(self.state.consume(self.retained.drain(..).collect()))
+        } else {
+            None
+        }
+    }
+
+    fn state_prepare(&mut self) {
+        if let Some(output) = self.state.start() {
+            self.output.extend(output);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Stepper;
+    use crate::state_machine::{State, StateMachineTraits, Transition};
+
+    struct Traits;
+
+    impl StateMachineTraits for Traits {
+        type InMsg = u32;
+        type OutMsg = String;
+        type FinalState = Vec<u32>;
+        type ErrorState = ();
+    }
+
+    /// collects `count` messages equal to `expected` and moves to collect the next number
+    struct Collect {
+        expected: u32,
+        count: usize,
+        collected: Vec<u32>,
+    }
+
+    impl State<Traits> for Collect {
+        fn start(&mut self) -> Option<Vec<String>> {
+            Some(vec![format!("collect {}", self.expected)])
+        }
+
+        fn is_message_expected(&self, msg: &u32, _current_msg_set: &[u32]) -> bool {
+            *msg == self.expected
+        }
+
+        fn is_input_complete(&self, current_msg_set: &[u32]) -> bool {
+            current_msg_set.len() == self.count
+        }
+
+        fn consume(&self, current_msg_set: Vec<u32>) -> Transition<Traits> {
+            let mut collected = self.collected.clone();
+            collected.extend(current_msg_set);
+            if self.expected == 3 {
+                Transition::FinalState(Ok(collected))
+            } else {
+                Transition::NewState(Box::new(Collect {
+                    expected: self.expected + 1,
+                    count: self.count,
+                    collected,
+                }))
+            }
+        }
+
+        fn timeout_outcome(&self, _current_msg_set: Vec<u32>) -> Result<Vec<u32>, ()> {
+            Err(())
+        }
+    }
+
+    #[test]
+    fn deferred_messages_are_replayed() {
+        let mut stepper = Stepper::<Traits>::new(Box::new(Collect {
+            expected: 1,
+            count: 2,
+            collected: vec![],
+        }));
+        assert_eq!(stepper.start(), vec!["collect 1".to_string()]);
+
+        assert!(stepper.deliver(3).is_none());
+        assert!(stepper.deliver(2).is_none());
+        assert!(stepper.deliver(1).is_none());
+        assert_eq!(stepper.deferred(), 2);
+        assert!(stepper.take_output().is_empty());
+
+        // the deferred 2 is consumed by the next phase, while 3 is deferred again
+        assert!(stepper.deliver(1).is_none());
+        assert_eq!(stepper.phase_index(), 1);
+        assert_eq!(stepper.deferred(), 1);
+        assert_eq!(stepper.take_output(), vec!["collect 2".to_string()]);
+
+        assert!(stepper.deliver(2).is_none());
+        assert_eq!(stepper.phase_index(), 2);
+        assert_eq!(stepper.deferred(), 0);
+
+        let outcome = stepper.deliver(3);
+        assert_eq!(outcome, Some(Ok(vec![1, 1, 2, 2, 3, 3])));
+    }
+
+    #[test]
+    fn pending_messages_are_counted() {
+        let mut stepper = Stepper::<Traits>::new(Box::new(Collect {
+            expected: 1,
+            count: 2,
+            collected: vec![],
+        }));
+        stepper.start();
+        assert!(stepper.is_message_expected(&1, &[]));
+        assert!(!stepper.is_message_expected(&2, &[1]));
+        assert!(!stepper.is_input_complete(&[1]));
+        assert!(stepper.is_input_complete(&[1, 1]));
+
+        assert!(stepper.deliver(1).is_none());
+        assert!(stepper.is_input_complete(&[1]));
+        assert_eq!(stepper.phase_index(), 0);
+    }
+}
@@ -7,7 +7,9 @@
 //!
 //! [`Async state machine`]: ../async_channels/index.html
 //!
-use crate::state_machine::{BoxedState, StateMachineTraits, Transition};
+use crate::state_machine::{
+    BoxedState, MachineStatus, StateMachineTraits, StatusHandle, StatusTracker, Transition,
+};
 use crossbeam_channel::{after, Receiver, Sender};
 use std::collections::VecDeque;
 use std::time::Instant;
@@ -25,12 +27,13 @@ where
     timeout: Option<Receiver<Instant>>,
     retained: Vec<T::InMsg>,
     discarded: DiscardedDeck<T::InMsg>,
+    tracker: StatusTracker,
 }
 
 /// container for deferred messaged
 ///
 /// See [`async_channels::DiscardedDeck`](../async_channels/struct.DiscardedDeck.html)
-struct DiscardedDeck<T> {
+pub(super) struct DiscardedDeck<T> {
     current: VecDeque<T>,
     next_state: VecDeque<T>,
 }
@@ -53,6 +56,9 @@ impl<T> DiscardedDeck<T> {
             self.current.push_back(m)
         }
     }
+    pub fn len(&self) -> usize {
+        self.current.len() + self.next_state.len()
+    }
 }
 
 impl<'a, T: StateMachineTraits> StateMachine<'a, T> {
@@ -68,12 +74,27 @@ impl<'a, T: StateMachineTraits> StateMachine<'a, T> {
             timeout: None,
             retained: Vec::new(),
             discarded: DiscardedDeck::new(),
+            tracker: StatusTracker::new(),
         }
     }
 
+    /// Returns the handle which reports the progress of the machine while it executes
+    ///
+    /// As `execute` blocks the calling thread, the handle has to be obtained before the machine starts.
+    pub fn status_handle(&self) -> StatusHandle {
+        self.tracker.handle.clone()
+    }
+
+    /// Returns current progress of the machine
+    pub fn status(&self) -> MachineStatus {
+        self.tracker
+            .status(self.state.status(&self.retained), self.discarded.len())
+    }
+
     pub fn execute(&mut self) -> Option<Result<T::FinalState, T::ErrorState>> {
         log::trace!("starting State Machine");
 
+        self.tracker.machine_started();
         self.state_prepare();
 
         loop {
@@ -104,15 +125,22 @@ impl<'a, T: StateMachineTraits> StateMachine<'a, T> {
                 match transition {
                     Transition::NewState(state) => {
                         let _ = std::mem::replace(&mut self.state, state);
+                        self.tracker.phase_started();
                         self.state_prepare();
                         self.discarded.flip();
                     }
                     Transition::FinalState(outcome) => return Some(outcome),
                 }
             }
+            self.publish_status();
         }
     }
 
+    fn publish_status(&self) {
+        self.tracker
+            .publish(self.state.status(&self.retained), self.discarded.len());
+    }
+
     fn process_message(&mut self, message: T::InMsg) -> Option<Transition<T>> {
         // Check message is expected.
         if self.state.is_message_expected(&message, &self.retained) {
@@ -144,6 +172,7 @@ impl<'a, T: StateMachineTraits> StateMachine<'a, T> {
                 }
             }
         }
+        self.publish_status();
     }
 }
 
@@ -0,0 +1,312 @@
+//! Malicious parties
+//!
+//! An [`Adversary`] wraps the states of an honest party and tampers with their output, while the party keeps following the protocol otherwise.
+//! The misbehaviour is defined per phase by an [`Action`]:
+//!  * `Corrupt` alters every message sent in the phase
+//!  * `Equivocate` splits broadcasts into peer messages and alters only those sent to the victims
+//!  * `Withhold` sends nothing in the phase
+//!  * `Replay` sends previously recorded messages, for example from an earlier session, instead of the genuine output
+//!  * `Append` sends given messages along with the genuine output, for example messages of later phases ahead of time
+//!
+//! Phases are identified by the names reported by the `status` method of the protocol states, like `"phase2"` of the key generation.
+//! Ready made alterations of values carried by messages are collected in [`scenarios`].
+//!
+//! ```ignore
+//! let adversary = Adversary::new(party, &parties).in_phase(
+//!     "phase3",
+//!     Action::Corrupt(|msg: &mut Message| {
+//!         if let Message::R3(fvss) = msg {
+//!             scenarios::shift_by_one(&mut fvss.share.1)
+//!         }
+//!     }),
+//! );
+//! simulation.add_node("malicious", party, adversary.wrap(start_state), |outcome| outcome);
+//! ```
+//!
+//! [`Adversary`]: struct.Adversary.html
+//! [`Action`]: enum.Action.html
+//! [`scenarios`]: scenarios/index.html
+//!
+use crate::protocol::{Address, OutputMessage, PartyIndex};
+use crate::state_machine::{BoxedState, PhaseStatus, State, StateMachineTraits, Transition};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Misbehaviour of a party in a single phase
+pub enum Action<M> {
+    /// applies the function to every message sent in the phase
+    Corrupt(fn(&mut M)),
+    /// sends genuine messages to other parties and messages altered by `mutate` to `victims`
+    Equivocate {
+        victims: BTreeSet<PartyIndex>,
+        mutate: fn(&mut M),
+    },
+    /// sends nothing in the phase
+    Withhold,
+    /// sends given messages instead of the genuine output of the phase
+    Replay(Vec<OutputMessage<M>>),
+    /// sends given messages in addition to the genuine output of the phase, e.g. messages of later phases ahead of time
+    Append(Vec<OutputMessage<M>>),
+}
+
+struct Tampering<M> {
+    other_parties: Vec<PartyIndex>,
+    actions: HashMap<String, Action<M>>,
+    transcript: Vec<(String, Vec<OutputMessage<M>>)>,
+}
+
+/// Handle of a malicious party
+///
+/// Clones of the handle share the list of actions and the transcript of genuine messages, so that the transcript can be inspected after the protocol has finished.
+pub struct Adversary<M> {
+    tampering: Arc<Mutex<Tampering<M>>>,
+}
+
+impl<M> Clone for Adversary<M> {
+    fn clone(&self) -> Self {
+        Adversary {
+            tampering: self.tampering.clone(),
+        }
+    }
+}
+
+impl<M> Adversary<M>
+where
+    M: Clone + Send + 'static,
+{
+    /// Creates the adversary acting as `party` among `parties`, which follows the protocol honestly until an action is added
+    pub fn new(party: PartyIndex, parties: &[PartyIndex]) -> Self {
+        Adversary {
+            tampering: Arc::new(Mutex::new(Tampering {
+                other_parties: parties.iter().filter(|p| **p != party).copied().collect(),
+                actions: HashMap::new(),
+                transcript: Vec::new(),
+            })),
+        }
+    }
+
+    /// Adds the action performed in the phase with given name
+    pub fn in_phase(self, phase: &str, action: Action<M>) -> Self {
+        self.lock().actions.insert(phase.to_string(), action);
+        self
+    }
+
+    /// Wraps the start state of the protocol, so that all subsequent states are wrapped too
+    pub fn wrap<T>(&self, start_state: BoxedState<T>) -> BoxedState<T>
+    where
+        T: StateMachineTraits<OutMsg = OutputMessage<M>> + 'static,
+    {
+        Box::new(Malicious {
+            inner: start_state,
+            adversary: self.clone(),
+        })
+    }
+
+    /// Genuine output of each phase the party went through, before tampering
+    pub fn transcript(&self) -> Vec<(String, Vec<OutputMessage<M>>)> {
+        self.lock().transcript.clone()
+    }
+
+    fn tamper(&self, phase: &str, output: Vec<OutputMessage<M>>) -> Vec<OutputMessage<M>> {
+        let mut tampering = self.lock();
+        tampering
+            .transcript
+            .push((phase.to_string(), output.clone()));
+
+        let action = match tampering.actions.get(phase) {
+            Some(action) => action,
+            None => return output,
+        };
+        log::info!("adversary tampers with the output of {}", phase);
+        match action {
+            Action::Corrupt(mutate) => output
+                .into_iter()
+                .map(|mut msg| {
+                    mutate(&mut msg.body);
+                    msg
+                })
+                .collect(),
+            Action::Equivocate { victims, mutate } => output
+                .into_iter()
+                .flat_map(|msg| {
+                    let recipients = match msg.recipient {
+                        Address::Peer(p) => vec![p],
+                        Address::Broadcast => tampering.other_parties.clone(),
+                    };
+                    recipients
+                        .into_iter()
+                        .map(|p| {
+                            let mut body = msg.body.clone();
+                            if victims.contains(&p) {
+                                mutate(&mut body);
+                            }
+                            OutputMessage {
+                                recipient: Address::Peer(p),
+                                body,
+                            }
+                        })
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            Action::Withhold => Vec::new(),
+            Action::Replay(messages) => messages.clone(),
+            Action::Append(messages) => output.into_iter().chain(messages.clone()).collect(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Tampering<M>> {
+        // the lock is never held across a panic, hence it can't be poisoned
+        self.tampering.lock().expect("adversary lock")
+    }
+}
+
+/// State which forwards everything to the honest state except its output
+struct Malicious<T, M>
+where
+    T: StateMachineTraits,
+{
+    inner: BoxedState<T>,
+    adversary: Adversary<M>,
+}
+
+impl<T, M> State<T> for Malicious<T, M>
+where
+    T: StateMachineTraits<OutMsg = OutputMessage<M>> + 'static,
+    M: Clone + Send + 'static,
+{
+    fn start(&mut self) -> Option<Vec<OutputMessage<M>>> {
+        let phase = self.inner.status(&[]).phase;
+        let output = self.inner.start().unwrap_or_default();
+        Some(self.adversary.tamper(&phase, output))
+    }
+
+    fn is_message_expected(&self, msg: &T::InMsg, current_msg_set: &[T::InMsg]) -> bool {
+        self.inner.is_message_expected(msg, current_msg_set)
+    }
+
+    fn is_input_complete(&self, current_msg_set: &[T::InMsg]) -> bool {
+        self.inner.is_input_complete(current_msg_set)
+    }
+
+    fn consume(&self, current_msg_set: Vec<T::InMsg>) -> Transition<T> {
+        match self.inner.consume(current_msg_set) {
+            Transition::NewState(state) => Transition::NewState(self.adversary.wrap(state)),
+            final_state => final_state,
+        }
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.inner.timeout()
+    }
+
+    fn timeout_outcome(
+        &self,
+        current_msg_set: Vec<T::InMsg>,
+    ) -> Result<T::FinalState, T::ErrorState> {
+        self.inner.timeout_outcome(current_msg_set)
+    }
+
+    fn status(&self, current_msg_set: &[T::InMsg]) -> PhaseStatus {
+        self.inner.status(current_msg_set)
+    }
+}
+
+/// Alterations of values carried by protocol messages which honest parties are expected to detect
+///
+/// Tests select the altered field of the message, leaving other messages intact:
+///
+/// ```ignore
+/// Action::Corrupt(|msg: &mut Message| {
+///     if let Message::R3(fvss) = msg {
+///         scenarios::shift_by_one(&mut fvss.share.1)
+///     }
+/// })
+/// ```
+pub mod scenarios {
+    use curv::elliptic::curves::traits::ECScalar;
+    use curv::{BigInt, FE};
+
+    /// Value which can be shifted by one
+    pub trait Shift {
+        fn shift(&mut self);
+    }
+
+    impl Shift for FE {
+        fn shift(&mut self) {
+            let one: FE = ECScalar::from(&BigInt::one());
+            *self = *self + one;
+        }
+    }
+
+    impl Shift for BigInt {
+        fn shift(&mut self) {
+            *self = &*self + &BigInt::one();
+        }
+    }
+
+    /// shifts the value by one, so that it no longer matches its commitment, proof or public counterpart
+    pub fn shift_by_one<T: Shift>(value: &mut T) {
+        value.shift()
+    }
+
+    /// replaces the integer by one too large to pass validation
+    pub fn oversize(value: &mut BigInt) {
+        *value = BigInt::one() << 1024;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Adversary};
+    use crate::protocol::{Address, OutputMessage, PartyIndex};
+    use std::collections::BTreeSet;
+
+    fn negate(x: &mut i32) {
+        *x = -*x;
+    }
+
+    #[test]
+    fn equivocation_splits_broadcast() {
+        let parties = (0..3).map(PartyIndex::from).collect::<Vec<_>>();
+        let victims = std::iter::once(parties[2]).collect::<BTreeSet<_>>();
+        let adversary = Adversary::new(parties[0], &parties)
+            .in_phase(
+                "phase1",
+                Action::Equivocate {
+                    victims,
+                    mutate: negate,
+                },
+            )
+            .in_phase("phase2", Action::Withhold)
+            .in_phase(
+                "phase3",
+                Action::Append(vec![OutputMessage {
+                    recipient: Address::Peer(parties[1]),
+                    body: 2,
+                }]),
+            );
+
+        let broadcast = vec![OutputMessage {
+            recipient: Address::Broadcast,
+            body: 1,
+        }];
+        let output = adversary
+            .tamper("phase1", broadcast.clone())
+            .into_iter()
+            .map(|m| (m.recipient, m.body))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            output,
+            vec![
+                (Address::Peer(parties[1]), 1),
+                (Address::Peer(parties[2]), -1)
+            ]
+        );
+
+        assert!(adversary.tamper("phase2", broadcast.clone()).is_empty());
+        assert_eq!(adversary.tamper("phase3", broadcast.clone()).len(), 2);
+        assert_eq!(adversary.tamper("phase4", broadcast).len(), 1);
+        assert_eq!(adversary.transcript().len(), 4);
+    }
+}
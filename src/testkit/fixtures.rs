@@ -0,0 +1,149 @@
+//! Fixtures shared by tests of protocols
+//!
+//! Keys in `tests/data` are stored as `<prefix>.<i>.json`, where `i` is the index of the party, for parties 0, 1 and 2.
+//! Each set holds 2-of-3 shares of a single key, `zkrp-keys` being the set with range proof setups.
+//! The files predate metadata of keys and public shares of parties. The metadata is derived when the file is deserialized,
+//! and [`load_keys`] computes public shares from shares of the whole set.
+//! Spare range proof setups for parties which join a key or rotate their setups are stored in `rp-setups.json`.
+//!
+//! [`Run`] simulates a protocol over the reliable network, the start state of at most one party being wrapped into an [`Adversary`].
+//! Seeds of simulations are fixed per test module, so that every test of the module replays the same schedule.
+//!
+//! [`load_keys`]: fn.load_keys.html
+//! [`Run`]: struct.Run.html
+//! [`Adversary`]: ../adversary/struct.Adversary.html
+//!
+use crate::algorithms::zkp::ZkpSetup;
+use crate::ecdsa::keygen::MultiPartyInfo;
+use crate::protocol::{InputMessage, OutputMessage, PartyIndex};
+use crate::state_machine::{BoxedState, StateMachineTraits};
+use crate::testkit::{Adversary, NetworkConditions, Simulation, SimulationReport};
+use curv::elliptic::curves::traits::ECPoint;
+use curv::GE;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+
+/// Loads shares of the key set stored under `prefix`, ordered by party, and fills in public shares of parties if they are missing
+pub fn load_keys(prefix: &str) -> anyhow::Result<Vec<MultiPartyInfo>> {
+    let mut keys = (0..3)
+        .map(|i| {
+            let path = format!("tests/data/{}.{}.json", prefix, i);
+            Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        })
+        .collect::<anyhow::Result<Vec<MultiPartyInfo>>>()?;
+    let g: GE = ECPoint::generator();
+    let public_shares = keys
+        .iter()
+        .map(|key| (key.own_party_index, g * &key.own_share()))
+        .collect::<HashMap<_, _>>();
+    for key in keys.iter_mut().filter(|key| key.public_shares.is_empty()) {
+        key.public_shares = public_shares.clone();
+    }
+    Ok(keys)
+}
+
+/// Loads spare range proof setups
+pub fn load_range_proof_setups() -> anyhow::Result<Vec<ZkpSetup>> {
+    Ok(serde_json::from_str(&fs::read_to_string(
+        "tests/data/rp-setups.json",
+    )?)?)
+}
+
+/// Share of `party` among `keys`
+pub fn key_of(keys: &[MultiPartyInfo], party: PartyIndex) -> &MultiPartyInfo {
+    keys.iter()
+        .find(|key| key.own_party_index == party)
+        .expect("key of the party is missing")
+}
+
+/// Simulation over the reliable network in which at most one party is malicious
+pub struct Run<M, O> {
+    simulation: Simulation<M, O>,
+    malicious: Option<(PartyIndex, Adversary<M>)>,
+}
+
+impl<M, O> Run<M, O>
+where
+    M: Clone + Display + Send + 'static,
+    O: 'static,
+{
+    pub fn new(seed: u64) -> Self {
+        Run {
+            simulation: Simulation::new(seed, NetworkConditions::default()),
+            malicious: None,
+        }
+    }
+
+    /// Makes `party` malicious, if `adversary` is given
+    pub fn with_adversary(mut self, party: PartyIndex, adversary: Option<Adversary<M>>) -> Self {
+        self.malicious = adversary.map(|adversary| (party, adversary));
+        self
+    }
+
+    /// Adds the node of `party`, wrapping its start state into the adversary if the party is malicious
+    pub fn add_node<T, F>(
+        &mut self,
+        name: impl Into<String>,
+        party: PartyIndex,
+        start_state: BoxedState<T>,
+        map: F,
+    ) where
+        T: StateMachineTraits<InMsg = InputMessage<M>, OutMsg = OutputMessage<M>> + 'static,
+        F: Fn(Result<T::FinalState, T::ErrorState>) -> O + 'static,
+    {
+        self.add_node_with_filter(name, party, start_state, |_| true, map)
+    }
+
+    /// Same as `add_node`, but the node accepts only messages satisfying `accepts`
+    pub fn add_node_with_filter<T, F>(
+        &mut self,
+        name: impl Into<String>,
+        party: PartyIndex,
+        start_state: BoxedState<T>,
+        accepts: fn(&M) -> bool,
+        map: F,
+    ) where
+        T: StateMachineTraits<InMsg = InputMessage<M>, OutMsg = OutputMessage<M>> + 'static,
+        F: Fn(Result<T::FinalState, T::ErrorState>) -> O + 'static,
+    {
+        let start_state = match &self.malicious {
+            Some((malicious, adversary)) if *malicious == party => adversary.wrap(start_state),
+            _ => start_state,
+        };
+        self.simulation
+            .add_node_with_filter(name, party, start_state, accepts, map);
+    }
+
+    pub fn run(self) -> SimulationReport<O> {
+        self.simulation.run()
+    }
+}
+
+/// Runs the protocol among holders of `keys`, starting each of them from the state made by `start_state`
+///
+/// The holder of the last key is malicious, if `adversary` is given.
+pub fn run_parties<T, M>(
+    seed: u64,
+    keys: &[MultiPartyInfo],
+    start_state: impl Fn(&MultiPartyInfo) -> anyhow::Result<BoxedState<T>>,
+    adversary: Option<Adversary<M>>,
+) -> anyhow::Result<SimulationReport<Result<T::FinalState, T::ErrorState>>>
+where
+    T: StateMachineTraits<InMsg = InputMessage<M>, OutMsg = OutputMessage<M>> + 'static,
+    M: Clone + Display + Send + 'static,
+{
+    let mut run = Run::new(seed);
+    if let Some(key) = keys.last() {
+        run = run.with_adversary(key.own_party_index, adversary);
+    }
+    for key in keys {
+        run.add_node(
+            format!("party {}", key.own_party_index),
+            key.own_party_index,
+            start_state(key)?,
+            |outcome| outcome,
+        );
+    }
+    Ok(run.run())
+}
@@ -0,0 +1,25 @@
+//! Tools for testing of protocols
+//!
+//! The module provides the deterministic in-process network [`Simulation`], which runs several parties of any protocol in a single thread.
+//! Parties are driven by [`Stepper`] machines, while the network between them is simulated with a virtual clock and a seeded random generator.
+//! Delivery delays, reordering, duplication and drops of messages are configured by [`NetworkConditions`].
+//!
+//! Same seed and same conditions result in the same schedule of deliveries, so a seed which made a test fail can be used to replay the failure.
+//! Note that the randomness consumed by cryptographic primitives of protocols is not controlled by the simulation.
+//! Start states created by `new_with_rng` constructors with a seeded [`RandomnessSource`] make the secrets and nonces of parties reproducible as well.
+//!
+//! Misbehaving parties are simulated by wrapping their states into an [`Adversary`], which tampers with messages sent in chosen phases.
+//!
+//! [`Simulation`]: simulator/struct.Simulation.html
+//! [`Adversary`]: adversary/struct.Adversary.html
+//! [`NetworkConditions`]: simulator/struct.NetworkConditions.html
+//! [`Stepper`]: ../state_machine/stepper/struct.Stepper.html
+//! [`RandomnessSource`]: ../algorithms/random/struct.RandomnessSource.html
+//!
+pub mod adversary;
+#[cfg(test)]
+pub mod fixtures;
+pub mod simulator;
+
+pub use adversary::{Action, Adversary};
+pub use simulator::{NetworkConditions, NodeOutcome, Simulation, SimulationReport};
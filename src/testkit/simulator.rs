@@ -0,0 +1,647 @@
+//! Deterministic network simulator
+//!
+//! Each node of the simulation is a [`Stepper`] executing some protocol on behalf of a party.
+//! Messages produced by nodes are routed according to their [`Address`]:
+//!  * `Peer(p)` is delivered to every other node acting as the party `p`
+//!  * `Broadcast` is delivered to every node acting as a party different from the sender
+//!
+//! A node can additionally filter messages it accepts, which allows to run protocols like resharing where the same party takes part in two committees.
+//!
+//! Every message is scheduled for delivery at some point of virtual time. Timeouts of phases are scheduled on the same clock,
+//! so that an expired phase terminates its node exactly the same way the channel based machines do.
+//! The simulation stops when every node has finished or when nothing is scheduled anymore, in which case the remaining nodes are considered stalled.
+//!
+//! [`Stepper`]: ../../state_machine/stepper/struct.Stepper.html
+//! [`Address`]: ../../protocol/enum.Address.html
+//!
+use crate::protocol::{Address, InputMessage, OutputMessage, PartyIndex};
+use crate::state_machine::stepper::Stepper;
+use crate::state_machine::{BoxedState, StateMachineTraits};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Display;
+use std::time::Duration;
+
+/// Properties of the simulated network
+///
+/// The default value describes reliable network which delivers messages immediately in the order they were sent.
+#[derive(Debug, Clone, Default)]
+pub struct NetworkConditions {
+    /// lower bound of a message delay
+    pub min_delay: Duration,
+    /// upper bound of a message delay
+    pub max_delay: Duration,
+    /// allows messages sent over the same link to overtake each other
+    pub reorder: bool,
+    /// probability of a message to be delivered twice
+    pub duplicate_probability: f64,
+    /// probability of a message to be lost
+    pub drop_probability: f64,
+}
+
+impl NetworkConditions {
+    /// Network which delays messages randomly and reorders them, but delivers each message exactly once
+    pub fn jittery(max_delay: Duration) -> Self {
+        NetworkConditions {
+            max_delay,
+            reorder: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// Final outcome of a node
+#[derive(Debug)]
+pub struct NodeOutcome<O> {
+    pub name: String,
+    pub party: PartyIndex,
+    /// outcome of the protocol, `None` if the node stalled
+    pub outcome: Option<O>,
+    /// virtual time when the node has finished
+    pub finished_at: Option<Duration>,
+    /// names of phases the node went through, with virtual time when each phase started
+    pub phases: Vec<(String, Duration)>,
+}
+
+/// Result of the simulation
+#[derive(Debug)]
+pub struct SimulationReport<O> {
+    /// seed of the simulation, which is sufficient to replay it under same conditions
+    pub seed: u64,
+    /// outcomes of nodes in the order of their addition to the simulation
+    pub outcomes: Vec<NodeOutcome<O>>,
+    /// virtual time elapsed until the simulation has stopped
+    pub elapsed: Duration,
+    pub delivered: usize,
+    pub dropped: usize,
+    pub duplicated: usize,
+    /// deliveries in the order they happened
+    pub trace: Vec<String>,
+}
+
+impl<O> SimulationReport<O> {
+    /// Names of nodes which did not finish the protocol
+    pub fn stalled(&self) -> Vec<&str> {
+        self.outcomes
+            .iter()
+            .filter(|n| n.outcome.is_none())
+            .map(|n| n.name.as_str())
+            .collect()
+    }
+
+    /// Outcomes of nodes, or error naming the stalled nodes and the seed
+    pub fn into_outcomes(self) -> anyhow::Result<Vec<O>> {
+        if !self.stalled().is_empty() {
+            anyhow::bail!(
+                "nodes {:?} stalled in simulation with seed {}",
+                self.stalled(),
+                self.seed
+            );
+        }
+        Ok(self
+            .outcomes
+            .into_iter()
+            .filter_map(|n| n.outcome)
+            .collect())
+    }
+}
+
+/// Type erased node of the simulation
+trait Driver<B, O> {
+    fn start(&mut self) -> Vec<OutputMessage<B>>;
+    fn deliver(&mut self, message: InputMessage<B>) -> Option<O>;
+    fn take_output(&mut self) -> Vec<OutputMessage<B>>;
+    fn expire(&mut self) -> O;
+    fn timeout(&self) -> Option<Duration>;
+    fn phase_index(&self) -> usize;
+    fn phase(&self) -> String;
+}
+
+struct StepperDriver<T, O>
+where
+    T: StateMachineTraits,
+{
+    stepper: Stepper<T>,
+    map: Box<dyn Fn(Result<T::FinalState, T::ErrorState>) -> O>,
+}
+
+impl<T, B, O> Driver<B, O> for StepperDriver<T, O>
+where
+    T: StateMachineTraits<InMsg = InputMessage<B>, OutMsg = OutputMessage<B>>,
+{
+    fn start(&mut self) -> Vec<OutputMessage<B>> {
+        self.stepper.start()
+    }
+
+    fn deliver(&mut self, message: InputMessage<B>) -> Option<O> {
+        self.stepper.deliver(message).map(|r| (self.map)(r))
+    }
+
+    fn take_output(&mut self) -> Vec<OutputMessage<B>> {
+        self.stepper.take_output()
+    }
+
+    fn expire(&mut self) -> O {
+        (self.map)(self.stepper.expire())
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.stepper.timeout()
+    }
+
+    fn phase_index(&self) -> usize {
+        self.stepper.phase_index()
+    }
+
+    fn phase(&self) -> String {
+        self.stepper.status().phase
+    }
+}
+
+struct Node<B, O> {
+    name: String,
+    party: PartyIndex,
+    accepts: fn(&B) -> bool,
+    driver: Box<dyn Driver<B, O>>,
+    outcome: Option<O>,
+    finished_at: Option<Duration>,
+    phases: Vec<(String, Duration)>,
+}
+
+enum Event<B> {
+    Deliver {
+        from: usize,
+        to: usize,
+        message: InputMessage<B>,
+    },
+    Timeout {
+        node: usize,
+        phase_index: usize,
+    },
+}
+
+/// Event with its position in the schedule
+///
+/// Events are ordered by time, then by random tiebreak if reordering is allowed, then by the order of scheduling.
+struct Scheduled<B> {
+    at: Duration,
+    tiebreak: u64,
+    seq: u64,
+    event: Event<B>,
+}
+
+impl<B> Scheduled<B> {
+    fn key(&self) -> (Duration, u64, u64) {
+        (self.at, self.tiebreak, self.seq)
+    }
+}
+
+impl<B> PartialEq for Scheduled<B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<B> Eq for Scheduled<B> {}
+
+impl<B> PartialOrd for Scheduled<B> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<B> Ord for Scheduled<B> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// Single threaded simulation of a protocol run by several nodes
+///
+/// `B` is the type of message bodies of the protocol, `O` is the type the outcomes of all nodes are mapped to.
+///
+/// ```ignore
+/// let mut simulation = Simulation::new(seed, NetworkConditions::jittery(Duration::from_millis(10)));
+/// for (party, start_state) in start_states {
+///     simulation.add_node(format!("party {}", party), party, start_state, |outcome| outcome);
+/// }
+/// let outcomes = simulation.run().into_outcomes()?;
+/// ```
+pub struct Simulation<B, O> {
+    seed: u64,
+    conditions: NetworkConditions,
+    rng: StdRng,
+    now: Duration,
+    seq: u64,
+    queue: BinaryHeap<Reverse<Scheduled<B>>>,
+    links: HashMap<(usize, usize), Duration>,
+    nodes: Vec<Node<B, O>>,
+    delivered: usize,
+    dropped: usize,
+    duplicated: usize,
+    trace: Vec<String>,
+}
+
+impl<B, O> Simulation<B, O>
+where
+    B: Clone + Display + 'static,
+    O: 'static,
+{
+    pub fn new(seed: u64, conditions: NetworkConditions) -> Self {
+        Simulation {
+            seed,
+            conditions,
+            rng: StdRng::seed_from_u64(seed),
+            now: Duration::default(),
+            seq: 0,
+            queue: BinaryHeap::new(),
+            links: HashMap::new(),
+            nodes: Vec::new(),
+            delivered: 0,
+            dropped: 0,
+            duplicated: 0,
+            trace: Vec::new(),
+        }
+    }
+
+    /// Adds a node which executes the protocol starting from `start_state` on behalf of `party`
+    ///
+    /// `map` converts the result of the protocol to the common outcome type of the simulation.
+    pub fn add_node<T, F>(
+        &mut self,
+        name: impl Into<String>,
+        party: PartyIndex,
+        start_state: BoxedState<T>,
+        map: F,
+    ) where
+        T: StateMachineTraits<InMsg = InputMessage<B>, OutMsg = OutputMessage<B>> + 'static,
+        F: Fn(Result<T::FinalState, T::ErrorState>) -> O + 'static,
+    {
+        self.add_node_with_filter(name, party, start_state, |_| true, map)
+    }
+
+    /// Adds a node which accepts only messages satisfying `accepts`
+    pub fn add_node_with_filter<T, F>(
+        &mut self,
+        name: impl Into<String>,
+        party: PartyIndex,
+        start_state: BoxedState<T>,
+        accepts: fn(&B) -> bool,
+        map: F,
+    ) where
+        T: StateMachineTraits<InMsg = InputMessage<B>, OutMsg = OutputMessage<B>> + 'static,
+        F: Fn(Result<T::FinalState, T::ErrorState>) -> O + 'static,
+    {
+        self.nodes.push(Node {
+            name: name.into(),
+            party,
+            accepts,
+            driver: Box::new(StepperDriver {
+                stepper: Stepper::new(start_state),
+                map: Box::new(map),
+            }),
+            outcome: None,
+            finished_at: None,
+            phases: Vec::new(),
+        });
+    }
+
+    /// Runs all nodes until they finish or until the network has nothing more to deliver
+    pub fn run(mut self) -> SimulationReport<O> {
+        log::info!(
+            "starting simulation of {} nodes with seed {}",
+            self.nodes.len(),
+            self.seed
+        );
+        for index in 0..self.nodes.len() {
+            let output = self.nodes[index].driver.start();
+            self.enter_phase(index);
+            self.send(index, output);
+        }
+
+        while !self.is_finished() {
+            let scheduled = match self.queue.pop() {
+                Some(Reverse(scheduled)) => scheduled,
+                None => break,
+            };
+            self.now = scheduled.at;
+            match scheduled.event {
+                Event::Deliver { from, to, message } => self.deliver(from, to, message),
+                Event::Timeout { node, phase_index } => {
+                    if self.nodes[node].outcome.is_none()
+                        && self.nodes[node].driver.phase_index() == phase_index
+                    {
+                        self.trace.push(format!(
+                            "{:?} timeout of {}",
+                            self.now, self.nodes[node].name
+                        ));
+                        let outcome = self.nodes[node].driver.expire();
+                        self.finish(node, outcome);
+                    }
+                }
+            }
+        }
+
+        let report = SimulationReport {
+            seed: self.seed,
+            outcomes: self
+                .nodes
+                .into_iter()
+                .map(|n| NodeOutcome {
+                    name: n.name,
+                    party: n.party,
+                    outcome: n.outcome,
+                    finished_at: n.finished_at,
+                    phases: n.phases,
+                })
+                .collect(),
+            elapsed: self.now,
+            delivered: self.delivered,
+            dropped: self.dropped,
+            duplicated: self.duplicated,
+            trace: self.trace,
+        };
+        if !report.stalled().is_empty() {
+            log::info!(
+                "simulation with seed {} stalled, nodes {:?} did not finish",
+                report.seed,
+                report.stalled()
+            );
+        }
+        report
+    }
+
+    fn is_finished(&self) -> bool {
+        self.nodes.iter().all(|n| n.outcome.is_some())
+    }
+
+    fn deliver(&mut self, from: usize, to: usize, message: InputMessage<B>) {
+        if self.nodes[to].outcome.is_some() {
+            return;
+        }
+        self.delivered += 1;
+        self.trace.push(format!(
+            "{:?} {} -> {}: {}",
+            self.now, self.nodes[from].name, self.nodes[to].name, message.body
+        ));
+
+        let phase_index = self.nodes[to].driver.phase_index();
+        let outcome = self.nodes[to].driver.deliver(message);
+        let output = self.nodes[to].driver.take_output();
+        if self.nodes[to].driver.phase_index() != phase_index && outcome.is_none() {
+            self.enter_phase(to);
+        }
+        self.send(to, output);
+        if let Some(outcome) = outcome {
+            self.finish(to, outcome);
+        }
+    }
+
+    fn enter_phase(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        node.phases.push((node.driver.phase(), self.now));
+        if let Some(timeout) = node.driver.timeout() {
+            let event = Event::Timeout {
+                node: index,
+                phase_index: node.driver.phase_index(),
+            };
+            self.schedule(self.now + timeout, 0, event);
+        }
+    }
+
+    fn finish(&mut self, index: usize, outcome: O) {
+        let node = &mut self.nodes[index];
+        node.outcome = Some(outcome);
+        node.finished_at = Some(self.now);
+    }
+
+    fn send(&mut self, from: usize, output: Vec<OutputMessage<B>>) {
+        let sender = self.nodes[from].party;
+        for message in output {
+            let recipients = self
+                .nodes
+                .iter()
+                .enumerate()
+                .filter(|(index, node)| {
+                    let addressed = match message.recipient {
+                        Address::Peer(peer) => node.party == peer && *index != from,
+                        Address::Broadcast => node.party != sender,
+                    };
+                    addressed && (node.accepts)(&message.body)
+                })
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            for to in recipients {
+                self.transmit(
+                    from,
+                    to,
+                    InputMessage {
+                        sender,
+                        body: message.body.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Schedules delivery of the message according to network conditions
+    ///
+    /// Same number of random values is drawn for every message, so that the schedule depends only on the seed and on the sequence of sent messages.
+    fn transmit(&mut self, from: usize, to: usize, message: InputMessage<B>) {
+        let drop: f64 = self.rng.gen();
+        let duplicate: f64 = self.rng.gen();
+        let delays = [self.delay(), self.delay()];
+        let tiebreaks: [u64; 2] = [self.rng.gen(), self.rng.gen()];
+
+        if drop < self.conditions.drop_probability {
+            self.dropped += 1;
+            return;
+        }
+        let copies = if duplicate < self.conditions.duplicate_probability {
+            self.duplicated += 1;
+            2
+        } else {
+            1
+        };
+
+        for (delay, tiebreak) in delays.iter().zip(tiebreaks.iter()).take(copies) {
+            let mut at = self.now + *delay;
+            let tiebreak = if self.conditions.reorder {
+                *tiebreak
+            } else {
+                // keep the order of messages sent over the same link
+                let last = self.links.entry((from, to)).or_default();
+                at = at.max(*last);
+                *last = at;
+                0
+            };
+            let event = Event::Deliver {
+                from,
+                to,
+                message: message.clone(),
+            };
+            self.schedule(at, tiebreak, event);
+        }
+    }
+
+    fn delay(&mut self) -> Duration {
+        let min = self.conditions.min_delay.as_micros() as u64;
+        let max = self.conditions.max_delay.as_micros() as u64;
+        Duration::from_micros(self.rng.gen_range(min, max.max(min) + 1))
+    }
+
+    fn schedule(&mut self, at: Duration, tiebreak: u64, event: Event<B>) {
+        self.seq += 1;
+        self.queue.push(Reverse(Scheduled {
+            at,
+            tiebreak,
+            seq: self.seq,
+            event,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NetworkConditions, Simulation};
+    use crate::protocol::{Address, InputMessage, OutputMessage, PartyIndex};
+    use crate::state_machine::{State, StateMachineTraits, Transition};
+    use std::time::Duration;
+
+    struct EchoTraits;
+
+    impl StateMachineTraits for EchoTraits {
+        type InMsg = InputMessage<u32>;
+        type OutMsg = OutputMessage<u32>;
+        type FinalState = Vec<PartyIndex>;
+        type ErrorState = usize;
+    }
+
+    /// broadcasts `round` and waits for same round from all other parties, for given number of rounds
+    struct Round {
+        round: u32,
+        rounds: u32,
+        others: usize,
+        timeout: Option<Duration>,
+    }
+
+    impl State<EchoTraits> for Round {
+        fn start(&mut self) -> Option<Vec<OutputMessage<u32>>> {
+            Some(vec![OutputMessage {
+                recipient: Address::Broadcast,
+                body: self.round,
+            }])
+        }
+
+        fn is_message_expected(
+            &self,
+            msg: &InputMessage<u32>,
+            current_msg_set: &[InputMessage<u32>],
+        ) -> bool {
+            msg.body == self.round && current_msg_set.iter().all(|m| m.sender != msg.sender)
+        }
+
+        fn is_input_complete(&self, current_msg_set: &[InputMessage<u32>]) -> bool {
+            current_msg_set.len() == self.others
+        }
+
+        fn consume(&self, current_msg_set: Vec<InputMessage<u32>>) -> Transition<EchoTraits> {
+            if self.round + 1 == self.rounds {
+                Transition::FinalState(Ok(current_msg_set.iter().map(|m| m.sender).collect()))
+            } else {
+                Transition::NewState(Box::new(Round {
+                    round: self.round + 1,
+                    ..*self
+                }))
+            }
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            self.timeout
+        }
+
+        fn timeout_outcome(
+            &self,
+            current_msg_set: Vec<InputMessage<u32>>,
+        ) -> Result<Vec<PartyIndex>, usize> {
+            Err(current_msg_set.len())
+        }
+    }
+
+    fn simulation(
+        seed: u64,
+        conditions: NetworkConditions,
+        timeout: Option<Duration>,
+    ) -> Simulation<u32, Result<Vec<PartyIndex>, usize>> {
+        let mut simulation = Simulation::new(seed, conditions);
+        for i in 0..4 {
+            simulation.add_node(
+                format!("party {}", i),
+                PartyIndex::from(i),
+                Box::new(Round {
+                    round: 0,
+                    rounds: 3,
+                    others: 3,
+                    timeout,
+                }),
+                |outcome| outcome,
+            );
+        }
+        simulation
+    }
+
+    #[test]
+    fn reliable_network_completes() -> anyhow::Result<()> {
+        let report = simulation(1, NetworkConditions::default(), None).run();
+        assert_eq!(report.delivered, 4 * 3 * 3);
+        assert!(report.outcomes.iter().all(|n| n.phases.len() == 3));
+        for outcome in report.into_outcomes()? {
+            assert_eq!(outcome.map(|senders| senders.len()), Ok(3));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn same_seed_replays_same_schedule() {
+        let conditions = NetworkConditions {
+            duplicate_probability: 0.3,
+            ..NetworkConditions::jittery(Duration::from_millis(50))
+        };
+        let first = simulation(7, conditions.clone(), None).run();
+        let second = simulation(7, conditions.clone(), None).run();
+        let other = simulation(8, conditions, None).run();
+
+        assert!(first.stalled().is_empty());
+        assert_eq!(first.trace, second.trace);
+        assert_eq!(first.elapsed, second.elapsed);
+        assert_ne!(first.trace, other.trace);
+    }
+
+    #[test]
+    fn dropped_messages_expire_phase() {
+        let conditions = NetworkConditions {
+            drop_probability: 1.0,
+            ..NetworkConditions::default()
+        };
+        let report = simulation(3, conditions, Some(Duration::from_secs(1))).run();
+        assert_eq!(report.dropped, 4 * 3);
+        assert_eq!(report.elapsed, Duration::from_secs(1));
+        assert!(report
+            .outcomes
+            .iter()
+            .all(|n| matches!(n.outcome, Some(Err(0)))));
+    }
+
+    #[test]
+    fn lost_messages_without_timeout_stall() {
+        let conditions = NetworkConditions {
+            drop_probability: 1.0,
+            ..NetworkConditions::default()
+        };
+        let report = simulation(3, conditions, None).run();
+        assert_eq!(report.stalled().len(), 4);
+        assert!(report.into_outcomes().is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! In-memory transport
+//!
+//! Connects parties running within one process through unbounded channels. Intended for tests.
+use crate::protocol::PartyIndex;
+use crate::transport::{Transport, TransportError};
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::future::BoxFuture;
+use futures::lock::Mutex as AsyncMutex;
+use futures::{FutureExt, StreamExt};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+type Frame = (PartyIndex, Vec<u8>);
+
+/// Hub connecting all endpoints created from it
+#[derive(Debug, Clone, Default)]
+pub struct MemoryNetwork {
+    endpoints: Arc<Mutex<HashMap<PartyIndex, UnboundedSender<Frame>>>>,
+}
+
+impl MemoryNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates the endpoint of the party, replacing the previous endpoint of same party
+    pub fn endpoint(&self, party: PartyIndex) -> MemoryTransport {
+        let (sender, receiver) = mpsc::unbounded();
+        self.endpoints
+            .lock()
+            .expect("memory network lock is poisoned")
+            .insert(party, sender);
+        MemoryTransport {
+            party,
+            network: self.clone(),
+            inbox: AsyncMutex::new(receiver),
+        }
+    }
+}
+
+/// Endpoint of a party in [`MemoryNetwork`](struct.MemoryNetwork.html)
+#[derive(Debug)]
+pub struct MemoryTransport {
+    party: PartyIndex,
+    network: MemoryNetwork,
+    inbox: AsyncMutex<UnboundedReceiver<Frame>>,
+}
+
+impl Transport for MemoryTransport {
+    fn own_party(&self) -> PartyIndex {
+        self.party
+    }
+
+    fn peers(&self) -> Vec<PartyIndex> {
+        let mut peers = self
+            .network
+            .endpoints
+            .lock()
+            .expect("memory network lock is poisoned")
+            .keys()
+            .filter(|party| **party != self.party)
+            .cloned()
+            .collect::<Vec<_>>();
+        peers.sort();
+        peers
+    }
+
+    fn send(&self, to: PartyIndex, frame: Vec<u8>) -> Result<(), TransportError> {
+        let endpoints = self
+            .network
+            .endpoints
+            .lock()
+            .expect("memory network lock is poisoned");
+        endpoints
+            .get(&to)
+            .ok_or(TransportError::UnknownParty(to))?
+            .unbounded_send((self.party, frame))
+            .map_err(|_| TransportError::Disconnected(to))
+    }
+
+    fn receive(&self) -> BoxFuture<'_, Result<(PartyIndex, Vec<u8>), TransportError>> {
+        async move {
+            let mut inbox = self.inbox.lock().await;
+            inbox.next().await.ok_or(TransportError::Closed)
+        }
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryNetwork;
+    use crate::protocol::PartyIndex;
+    use crate::transport::{Transport, TransportError};
+
+    #[tokio::test]
+    async fn frames_are_delivered_in_order() -> anyhow::Result<()> {
+        let network = MemoryNetwork::new();
+        let first = network.endpoint(PartyIndex::from(0));
+        let second = network.endpoint(PartyIndex::from(1));
+
+        assert_eq!(first.peers(), vec![PartyIndex::from(1)]);
+        first.send(PartyIndex::from(1), vec![1])?;
+        first.send(PartyIndex::from(1), vec![2])?;
+        assert_eq!(second.receive().await?, (PartyIndex::from(0), vec![1]));
+        assert_eq!(second.receive().await?, (PartyIndex::from(0), vec![2]));
+
+        assert!(matches!(
+            first.send(PartyIndex::from(2), vec![]),
+            Err(TransportError::UnknownParty(_))
+        ));
+        Ok(())
+    }
+}
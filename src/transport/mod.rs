@@ -0,0 +1,194 @@
+//! Networking layer for state machines
+//!
+//! The protocols of the crate produce [`OutputMessage`]s addressed either to a single party or to all of them,
+//! and consume [`InputMessage`]s tagged with their sender. A [`Transport`] maps [`PartyIndex`] to a connection and moves opaque frames between parties,
+//! while the function [`execute`] encodes the messages in the [`wire`] format, routes them by their [`Address`] and feeds received ones to the async [`StateMachine`]:
+//!
+//! ```ignore
+//! let transport = SocketTransport::bind(own_party, own_endpoint, peer_endpoints, ReconnectPolicy::default()).await?;
+//! let outcome = transport::execute(start_state, &transport).await?;
+//! ```
+//!
+//! The module provides following backends:
+//!  * [`MemoryNetwork`], which connects parties within one process, for tests
+//!  * [`SocketTransport`] over TCP or Unix sockets, which reconnects to a peer when the connection is lost
+//!
+//! Transports neither authenticate parties nor encrypt the traffic, which has to be provided by the network the parties run in.
+//!
+//! [`OutputMessage`]: ../protocol/struct.OutputMessage.html
+//! [`InputMessage`]: ../protocol/struct.InputMessage.html
+//! [`PartyIndex`]: ../protocol/struct.PartyIndex.html
+//! [`Address`]: ../protocol/enum.Address.html
+//! [`Transport`]: trait.Transport.html
+//! [`execute`]: fn.execute.html
+//! [`wire`]: ../wire/index.html
+//! [`StateMachine`]: ../state_machine/async_channels/struct.StateMachine.html
+//! [`MemoryNetwork`]: memory/struct.MemoryNetwork.html
+//! [`SocketTransport`]: socket/struct.SocketTransport.html
+//!
+pub mod memory;
+pub mod socket;
+
+pub use memory::{MemoryNetwork, MemoryTransport};
+pub use socket::{Endpoint, ReconnectPolicy, SocketTransport};
+
+use crate::protocol::{Address, InputMessage, Instruction, OutputMessage, PartyIndex};
+use crate::state_machine::async_channels::StateMachine;
+use crate::state_machine::{BoxedState, StateMachineTraits};
+use crate::wire::{self, WireError, WireMessage};
+use futures::channel::mpsc;
+use futures::future::BoxFuture;
+use futures::{pin_mut, select, FutureExt, StreamExt};
+use thiserror::Error;
+
+/// Enumerates errors reported by transports
+#[derive(Debug, Error)]
+pub enum TransportError {
+    #[error("transport: unknown party {0}")]
+    UnknownParty(PartyIndex),
+    #[error("transport: connection to {0} is closed")]
+    Disconnected(PartyIndex),
+    #[error("transport: closed")]
+    Closed,
+    #[error("{0}")]
+    Wire(#[from] WireError),
+    #[error("transport: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Delivers frames between parties
+///
+/// `send` queues the frame for delivery and returns immediately, so that a slow or temporarily unreachable peer does not block the others.
+/// The order of frames sent to the same party is preserved.
+pub trait Transport: Send + Sync {
+    /// Index of the party the transport acts for
+    fn own_party(&self) -> PartyIndex;
+    /// Parties the transport can deliver frames to, not including the own party
+    fn peers(&self) -> Vec<PartyIndex>;
+    /// Queues the frame for delivery to the party
+    fn send(&self, to: PartyIndex, frame: Vec<u8>) -> Result<(), TransportError>;
+    /// Waits for the next frame from any party
+    fn receive(&self) -> BoxFuture<'_, Result<(PartyIndex, Vec<u8>), TransportError>>;
+}
+
+/// Sends the message to the parties it is addressed to
+pub fn route<B, C>(transport: &C, message: &OutputMessage<B>) -> Result<(), TransportError>
+where
+    B: WireMessage,
+    C: Transport + ?Sized,
+{
+    let frame = wire::encode(&message.body)?;
+    match &message.recipient {
+        Address::Peer(party) => transport.send(*party, frame),
+        Address::Broadcast => transport
+            .peers()
+            .into_iter()
+            .try_for_each(|party| transport.send(party, frame.clone())),
+    }
+}
+
+/// Executes the protocol starting from `start_state`, exchanging messages with other parties through `transport`
+///
+/// Returns the outcome of the state machine, or the error of the transport which prevents the protocol from continuing.
+/// Frames which can't be decoded, including messages of incompatible versions of the wire format, are logged and dropped, as they are not a reason to abandon the protocol.
+pub async fn execute<T, B, C>(
+    start_state: BoxedState<T>,
+    transport: &C,
+) -> Result<Option<Result<T::FinalState, T::ErrorState>>, TransportError>
+where
+    T: StateMachineTraits<InMsg = InputMessage<B>, OutMsg = OutputMessage<B>>,
+    B: WireMessage,
+    C: Transport + ?Sized,
+{
+    let (ingress, inqueue) = mpsc::unbounded();
+    let (outqueue, mut egress) = mpsc::unbounded();
+    let mut machine = StateMachine::<T>::new(start_state, inqueue, outqueue);
+
+    let execution = machine.execute().fuse();
+    pin_mut!(execution);
+    loop {
+        let receive = transport.receive().fuse();
+        pin_mut!(receive);
+        select! {
+            outcome = execution => {
+                // deliver the output of the last phase
+                while let Ok(Some(message)) = egress.try_next() {
+                    route(transport, &message)?;
+                }
+                return Ok(outcome);
+            },
+            message = egress.next() => {
+                if let Some(message) = message {
+                    route(transport, &message)?;
+                }
+            },
+            frame = receive => {
+                let (sender, frame) = frame?;
+                match wire::decode(&frame) {
+                    Ok(body) => {
+                        if ingress
+                            .unbounded_send(Instruction::Data(InputMessage { sender, body }))
+                            .is_err()
+                        {
+                            log::debug!("state machine has stopped, frame from {} is dropped", sender);
+                        }
+                    }
+                    Err(e) => log::warn!("invalid frame from {}: {}", sender, e),
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ecdsa::keygen::MultiPartyInfo;
+    use crate::ecdsa::signature::{Phase1, SigningTraits};
+    use crate::protocol::PartyIndex;
+    use crate::state_machine::BoxedState;
+    use crate::transport::{execute, MemoryNetwork};
+    use crate::Signature;
+    use curv::elliptic::curves::traits::ECScalar;
+    use curv::{BigInt, FE};
+    use std::fs;
+
+    #[tokio::test]
+    async fn signing_over_memory_network() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let network = MemoryNetwork::new();
+        let signers = (0..2).map(PartyIndex::from).collect::<Vec<_>>();
+        let message_hash: FE = ECScalar::from(&BigInt::from(42));
+
+        // all endpoints have to exist before the first broadcast
+        let endpoints = signers
+            .iter()
+            .map(|party| network.endpoint(*party))
+            .collect::<Vec<_>>();
+        let mut handles = Vec::new();
+        for (i, transport) in endpoints.into_iter().enumerate() {
+            let multi_party_info: MultiPartyInfo =
+                serde_json::from_str(&fs::read_to_string(format!("tests/data/keys.{}.json", i))?)?;
+            let start_state: BoxedState<SigningTraits> =
+                Box::new(Phase1::new(message_hash, multi_party_info, &signers, None)?);
+            handles.push(tokio::spawn(async move {
+                execute(start_state, &transport).await
+            }));
+        }
+
+        let public_key =
+            serde_json::from_str::<MultiPartyInfo>(&fs::read_to_string("tests/data/keys.0.json")?)?
+                .public_key;
+        for handle in handles {
+            let signed_message = match handle.await?? {
+                Some(Ok(signed_message)) => signed_message,
+                outcome => anyhow::bail!("signature expected, got {:?}", outcome),
+            };
+            let signature = Signature {
+                r: signed_message.r,
+                s: signed_message.s,
+            };
+            assert!(signature.verify(&public_key, &message_hash));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,420 @@
+//! Transport over TCP and Unix sockets
+//!
+//! Every party listens on its own endpoint and opens one outgoing connection to every peer, so that each connection carries frames in one direction only.
+//! The connecting party introduces itself with the handshake frame containing its [`PartyIndex`]. Frames are delimited by their length encoded as big endian `u32`.
+//!
+//! Outgoing connections are established lazily by a background task per peer. When the connection can't be established or breaks,
+//! the task reconnects according to [`ReconnectPolicy`] and resends the frame which has not been written.
+//! Frames queued for a peer are discarded once the policy gives up, after which sending to the peer fails with `TransportError::Disconnected`.
+//!
+//! The transport spawns its tasks on the tokio runtime, hence it has to be created within the runtime context.
+//! Dropping the transport closes the listener and the outgoing connections.
+//!
+//! [`PartyIndex`]: ../../protocol/struct.PartyIndex.html
+//! [`ReconnectPolicy`]: struct.ReconnectPolicy.html
+//!
+use crate::protocol::PartyIndex;
+use crate::transport::{Transport, TransportError};
+use bytes::Bytes;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use futures::channel::oneshot;
+use futures::future::BoxFuture;
+use futures::lock::Mutex as AsyncMutex;
+use futures::{FutureExt, SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::time;
+use tokio_util::codec::{FramedRead, FramedWrite, LengthDelimitedCodec};
+
+/// Upper bound of a frame size, protects the receiver from allocating arbitrary amounts of memory
+pub const MAX_FRAME_LENGTH: usize = 64 * 1024 * 1024;
+
+type Frame = (PartyIndex, Vec<u8>);
+type Reader = Box<dyn AsyncRead + Send + Unpin>;
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Address a party listens on
+#[derive(Debug, Clone, PartialEq)]
+pub enum Endpoint {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Tcp(address) => write!(f, "{}", address),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+/// Defines how a lost connection is re-established
+///
+/// The delay between attempts starts from `initial_delay` and doubles after each failure up to `max_delay`.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    /// number of consecutive failed attempts after which the peer is considered gone, `None` for unlimited
+    pub max_attempts: Option<usize>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        ReconnectPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            max_attempts: Some(60),
+        }
+    }
+}
+
+fn codec() -> LengthDelimitedCodec {
+    LengthDelimitedCodec::builder()
+        .length_field_length(4)
+        .max_frame_length(MAX_FRAME_LENGTH)
+        .new_codec()
+}
+
+enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    async fn bind(endpoint: &Endpoint) -> std::io::Result<Self> {
+        match endpoint {
+            Endpoint::Tcp(address) => Ok(Listener::Tcp(TcpListener::bind(*address).await?)),
+            #[cfg(unix)]
+            Endpoint::Unix(path) => {
+                // the socket file left by previous run prevents binding
+                let _ = std::fs::remove_file(path);
+                Ok(Listener::Unix(UnixListener::bind(path)?))
+            }
+        }
+    }
+
+    fn local_endpoint(&self) -> std::io::Result<Endpoint> {
+        match self {
+            Listener::Tcp(listener) => Ok(Endpoint::Tcp(listener.local_addr()?)),
+            #[cfg(unix)]
+            Listener::Unix(listener) => listener
+                .local_addr()?
+                .as_pathname()
+                .map(|path| Endpoint::Unix(path.to_path_buf()))
+                .ok_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::Other, "unnamed unix socket")
+                }),
+        }
+    }
+
+    async fn accept(&mut self) -> std::io::Result<Reader> {
+        match self {
+            Listener::Tcp(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let (stream, _) = listener.accept().await?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+async fn connect(endpoint: &Endpoint) -> std::io::Result<Writer> {
+    match endpoint {
+        Endpoint::Tcp(address) => {
+            let stream = TcpStream::connect(*address).await?;
+            stream.set_nodelay(true)?;
+            Ok(Box::new(stream))
+        }
+        #[cfg(unix)]
+        Endpoint::Unix(path) => Ok(Box::new(UnixStream::connect(path).await?)),
+    }
+}
+
+/// Transport over TCP or Unix sockets
+#[derive(Debug)]
+pub struct SocketTransport {
+    party: PartyIndex,
+    local_endpoint: Endpoint,
+    outboxes: HashMap<PartyIndex, UnboundedSender<Vec<u8>>>,
+    inbox: AsyncMutex<UnboundedReceiver<Frame>>,
+    /// stops the listener once dropped together with the transport
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl SocketTransport {
+    /// Starts listening on `endpoint` and spawns tasks delivering frames to `peers`
+    pub async fn bind(
+        party: PartyIndex,
+        endpoint: Endpoint,
+        peers: HashMap<PartyIndex, Endpoint>,
+        policy: ReconnectPolicy,
+    ) -> Result<Self, TransportError> {
+        let mut listener = Listener::bind(&endpoint).await?;
+        let local_endpoint = listener.local_endpoint()?;
+        log::info!("party {} listens on {}", party, local_endpoint);
+
+        let (inbox_sender, inbox) = mpsc::unbounded();
+        let (shutdown, mut shutdown_receiver) = oneshot::channel::<()>();
+        let known_parties = peers.keys().cloned().collect::<Vec<_>>();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    // the transport has been dropped
+                    _ = &mut shutdown_receiver => {
+                        log::debug!("party {} stops listening", party);
+                        return;
+                    }
+                    accepted = listener.accept() => match accepted {
+                        Ok(reader) => {
+                            tokio::spawn(receive(reader, known_parties.clone(), inbox_sender.clone()));
+                        }
+                        Err(e) => log::warn!("accept failed: {}", e),
+                    }
+                }
+            }
+        });
+
+        let outboxes = peers
+            .into_iter()
+            .filter(|(peer, _)| *peer != party)
+            .map(|(peer, endpoint)| {
+                let (sender, receiver) = mpsc::unbounded();
+                tokio::spawn(deliver(party, peer, endpoint, receiver, policy.clone()));
+                (peer, sender)
+            })
+            .collect();
+
+        Ok(SocketTransport {
+            party,
+            local_endpoint,
+            outboxes,
+            inbox: AsyncMutex::new(inbox),
+            _shutdown: shutdown,
+        })
+    }
+
+    /// Endpoint the transport listens on, which differs from the requested one if the port was chosen by the system
+    pub fn local_endpoint(&self) -> &Endpoint {
+        &self.local_endpoint
+    }
+}
+
+impl Transport for SocketTransport {
+    fn own_party(&self) -> PartyIndex {
+        self.party
+    }
+
+    fn peers(&self) -> Vec<PartyIndex> {
+        let mut peers = self.outboxes.keys().cloned().collect::<Vec<_>>();
+        peers.sort();
+        peers
+    }
+
+    fn send(&self, to: PartyIndex, frame: Vec<u8>) -> Result<(), TransportError> {
+        self.outboxes
+            .get(&to)
+            .ok_or(TransportError::UnknownParty(to))?
+            .unbounded_send(frame)
+            .map_err(|_| TransportError::Disconnected(to))
+    }
+
+    fn receive(&self) -> BoxFuture<'_, Result<(PartyIndex, Vec<u8>), TransportError>> {
+        async move {
+            let mut inbox = self.inbox.lock().await;
+            inbox.next().await.ok_or(TransportError::Closed)
+        }
+        .boxed()
+    }
+}
+
+/// Reads frames of an incoming connection and forwards them to the inbox
+async fn receive(reader: Reader, known_parties: Vec<PartyIndex>, inbox: UnboundedSender<Frame>) {
+    let mut frames = FramedRead::new(reader, codec());
+    let sender = match frames.next().await {
+        Some(Ok(handshake)) => match PartyIndex::from_slice(&handshake) {
+            Ok(party) if known_parties.contains(&party) => party,
+            Ok(party) => {
+                log::warn!("connection from unknown party {} is rejected", party);
+                return;
+            }
+            Err(e) => {
+                log::warn!("invalid handshake: {}", e);
+                return;
+            }
+        },
+        Some(Err(e)) => {
+            log::warn!("handshake failed: {}", e);
+            return;
+        }
+        None => return,
+    };
+    log::debug!("party {} has connected", sender);
+
+    while let Some(frame) = frames.next().await {
+        match frame {
+            Ok(frame) => {
+                if inbox.unbounded_send((sender, frame.to_vec())).is_err() {
+                    return;
+                }
+            }
+            Err(e) => {
+                log::warn!("connection from {} failed: {}", sender, e);
+                return;
+            }
+        }
+    }
+    log::debug!("party {} has disconnected", sender);
+}
+
+/// Writes frames queued for the peer, reconnecting when necessary
+async fn deliver(
+    party: PartyIndex,
+    peer: PartyIndex,
+    endpoint: Endpoint,
+    mut outbox: UnboundedReceiver<Vec<u8>>,
+    policy: ReconnectPolicy,
+) {
+    let mut connection = None;
+    while let Some(frame) = outbox.next().await {
+        let mut attempts = 0;
+        let mut delay = policy.initial_delay;
+        loop {
+            if connection.is_none() {
+                connection = match connect(&endpoint).await {
+                    Ok(writer) => {
+                        let mut writer = FramedWrite::new(writer, codec());
+                        match writer.send(Bytes::from(party.0.to_vec())).await {
+                            Ok(()) => Some(writer),
+                            Err(e) => {
+                                log::debug!("handshake with {} failed: {}", peer, e);
+                                None
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        log::debug!("connection to {} at {} failed: {}", peer, endpoint, e);
+                        None
+                    }
+                };
+            }
+            if let Some(writer) = connection.as_mut() {
+                match writer.send(Bytes::from(frame.clone())).await {
+                    Ok(()) => break,
+                    Err(e) => {
+                        log::debug!("connection to {} is lost: {}", peer, e);
+                        connection = None;
+                    }
+                }
+            }
+
+            attempts += 1;
+            if policy.max_attempts.map_or(false, |max| attempts >= max) {
+                log::error!(
+                    "party {} is unreachable at {} after {} attempts",
+                    peer,
+                    endpoint,
+                    attempts
+                );
+                // closing the outbox makes further sends to the peer fail
+                return;
+            }
+            time::sleep(delay).await;
+            delay = std::cmp::min(delay * 2, policy.max_delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Endpoint, ReconnectPolicy, SocketTransport};
+    use crate::protocol::PartyIndex;
+    use crate::transport::Transport;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn free_tcp_endpoint() -> anyhow::Result<Endpoint> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        Ok(Endpoint::Tcp(listener.local_addr()?))
+    }
+
+    async fn exchange(first: Endpoint, second: Endpoint) -> anyhow::Result<()> {
+        let (a, b) = (PartyIndex::from(0), PartyIndex::from(1));
+        let peers = vec![(a, first.clone()), (b, second.clone())]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+
+        let first =
+            SocketTransport::bind(a, first, peers.clone(), ReconnectPolicy::default()).await?;
+        // the frame waits until the second party starts listening
+        first.send(b, b"early".to_vec())?;
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let second = SocketTransport::bind(b, second, peers, ReconnectPolicy::default()).await?;
+
+        assert_eq!(second.receive().await?, (a, b"early".to_vec()));
+        second.send(a, vec![0u8; 100_000])?;
+        assert_eq!(first.receive().await?, (b, vec![0u8; 100_000]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listener_is_closed_with_transport() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let party = PartyIndex::from(0);
+        let endpoint = free_tcp_endpoint()?;
+        let address = match &endpoint {
+            Endpoint::Tcp(address) => *address,
+            #[cfg(unix)]
+            Endpoint::Unix(_) => unreachable!(),
+        };
+        let peers = vec![(party, endpoint.clone())].into_iter().collect();
+        let transport =
+            SocketTransport::bind(party, endpoint, peers, ReconnectPolicy::default()).await?;
+        assert!(tokio::net::TcpStream::connect(address).await.is_ok());
+
+        // nobody connects, still the listener has to stop
+        drop(transport);
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(address).await.is_err() {
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        anyhow::bail!("listener is still open after the transport is dropped")
+    }
+
+    #[tokio::test]
+    async fn tcp_exchange_with_reconnect() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        exchange(free_tcp_endpoint()?, free_tcp_endpoint()?).await
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn unix_exchange_with_reconnect() -> anyhow::Result<()> {
+        let _ = env_logger::builder().is_test(true).try_init();
+        let dir = std::env::temp_dir();
+        let id = std::process::id();
+        exchange(
+            Endpoint::Unix(dir.join(format!("ecdsa-mpc-{}.0.sock", id))),
+            Endpoint::Unix(dir.join(format!("ecdsa-mpc-{}.1.sock", id))),
+        )
+        .await
+    }
+}
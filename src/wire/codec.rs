@@ -0,0 +1,1046 @@
+//! Compact binary encoding of the message body
+//!
+//! The encoding is not self-describing, fields of structures are written in the order of their declaration and enum variants are tagged by their position.
+//!
+//! * unsigned integers are LEB128 variable length integers, signed ones are zigzag encoded first, `u8` and `i8` are written as a single byte
+//! * lengths of sequences, strings and byte arrays are variable length integers
+//! * maps are written as sequences of entries sorted by the encoding of the key
+//! * points of the curve are 33 bytes in the compressed SEC1 form
+//! * strings are tagged with their representation:
+//!   a decimal integer, which is how `curv` and `paillier` serialize big integers, is written as the sign and the length followed by the big endian magnitude;
+//!   a hex string, which is how `curv` serializes scalars and coordinates, is written as the number of digits followed by the packed digits;
+//!   any other string is written as UTF-8
+//!
+//! Every value has exactly one encoding, the decoder rejects overlong integers, unsorted maps and strings written in a representation
+//! the encoder would not choose.
+//!
+use curv::arithmetic::traits::Converter;
+use curv::{BigInt, PK};
+use serde::de::value::MapDeserializer;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use thiserror::Error;
+
+/// Name under which `curv` serializes points of secp256k1
+const POINT_STRUCT: &str = "Secp256k1Point";
+const POINT_LENGTH: usize = 33;
+const COORDINATE_LENGTH: usize = 32;
+
+const TEXT: u8 = 0;
+const DECIMAL: u8 = 1;
+const LOWER_HEX: u8 = 2;
+const UPPER_HEX: u8 = 3;
+
+/// Error of the encoder or the decoder
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct CodecError(String);
+
+impl ser::Error for CodecError {
+    fn custom<T: Display>(msg: T) -> Self {
+        CodecError(msg.to_string())
+    }
+}
+
+impl de::Error for CodecError {
+    fn custom<T: Display>(msg: T) -> Self {
+        CodecError(msg.to_string())
+    }
+}
+
+fn error<T>(msg: &str) -> Result<T, CodecError> {
+    Err(CodecError(msg.to_string()))
+}
+
+/// Encodes the value
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CodecError> {
+    let mut serializer = Serializer::default();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.output)
+}
+
+/// Decodes the value, which has to span the whole input
+pub fn from_bytes<'de, T: de::Deserialize<'de>>(input: &'de [u8]) -> Result<T, CodecError> {
+    let mut deserializer = Deserializer { input };
+    let value = T::deserialize(&mut deserializer)?;
+    if !deserializer.input.is_empty() {
+        return error("trailing bytes after the value");
+    }
+    Ok(value)
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn is_decimal(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+        && (digits == "0" && digits.len() == s.len() || !digits.starts_with('0'))
+}
+
+/// Chooses the representation of the string
+fn string_tag(s: &str) -> u8 {
+    if is_decimal(s) {
+        DECIMAL
+    } else if !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+    {
+        LOWER_HEX
+    } else if !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_digit() || (b'A'..=b'F').contains(&b))
+    {
+        UPPER_HEX
+    } else {
+        TEXT
+    }
+}
+
+fn write_str(output: &mut Vec<u8>, s: &str) {
+    let tag = string_tag(s);
+    output.push(tag);
+    match tag {
+        DECIMAL => {
+            let negative = s.starts_with('-');
+            let magnitude = BigInt::from_str_radix(s.trim_start_matches('-'), 10)
+                .expect("decimal string is checked");
+            let bytes = if magnitude == BigInt::zero() {
+                vec![]
+            } else {
+                BigInt::to_vec(&magnitude)
+            };
+            write_varint(output, ((bytes.len() as u64) << 1) | negative as u64);
+            output.extend_from_slice(&bytes);
+        }
+        LOWER_HEX | UPPER_HEX => {
+            write_varint(output, s.len() as u64);
+            let padded = if s.len() % 2 == 1 {
+                format!("0{}", s)
+            } else {
+                s.to_string()
+            };
+            output.extend(hex::decode(padded).expect("hex string is checked"));
+        }
+        _ => {
+            write_varint(output, s.len() as u64);
+            output.extend_from_slice(s.as_bytes());
+        }
+    }
+}
+
+fn parse_coordinate(s: &str) -> Result<[u8; COORDINATE_LENGTH], CodecError> {
+    if s.len() > 2 * COORDINATE_LENGTH {
+        return error("coordinate of the point is too long");
+    }
+    let padded = format!("{:0>width$}", s, width = 2 * COORDINATE_LENGTH);
+    let bytes = hex::decode(padded).map_err(|e| CodecError(e.to_string()))?;
+    let mut coordinate = [0u8; COORDINATE_LENGTH];
+    coordinate.copy_from_slice(&bytes);
+    Ok(coordinate)
+}
+
+/// Encoder of the format
+#[derive(Default)]
+pub struct Serializer {
+    output: Vec<u8>,
+}
+
+impl<'a> ser::Serializer for &'a mut Serializer {
+    type Ok = ();
+    type Error = CodecError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = StructSerializer<'a>;
+    type SerializeStructVariant = Self;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bool(self, v: bool) -> Result<(), CodecError> {
+        self.output.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), CodecError> {
+        self.output.push(v as u8);
+        Ok(())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), CodecError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), CodecError> {
+        self.serialize_i64(v.into())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), CodecError> {
+        write_varint(&mut self.output, zigzag(v));
+        Ok(())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), CodecError> {
+        self.output.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), CodecError> {
+        self.output.push(v);
+        Ok(())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), CodecError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), CodecError> {
+        self.serialize_u64(v.into())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), CodecError> {
+        write_varint(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), CodecError> {
+        self.output.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), CodecError> {
+        self.output.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), CodecError> {
+        self.output.extend_from_slice(&v.to_le_bytes());
+        Ok(())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), CodecError> {
+        self.serialize_str(v.encode_utf8(&mut [0u8; 4]))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), CodecError> {
+        write_str(&mut self.output, v);
+        Ok(())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), CodecError> {
+        write_varint(&mut self.output, v.len() as u64);
+        self.output.extend_from_slice(v);
+        Ok(())
+    }
+
+    fn serialize_none(self) -> Result<(), CodecError> {
+        self.output.push(0);
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), CodecError> {
+        self.output.push(1);
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), CodecError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), CodecError> {
+        self.serialize_u32(variant_index)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), CodecError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), CodecError> {
+        write_varint(&mut self.output, variant_index.into());
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, CodecError> {
+        match len {
+            Some(len) => {
+                write_varint(&mut self.output, len as u64);
+                Ok(self)
+            }
+            None => error("length of the sequence is unknown"),
+        }
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, CodecError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, CodecError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, CodecError> {
+        write_varint(&mut self.output, variant_index.into());
+        Ok(self)
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, CodecError> {
+        Ok(MapSerializer {
+            serializer: self,
+            entries: Vec::new(),
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<StructSerializer<'a>, CodecError> {
+        if name == POINT_STRUCT {
+            Ok(StructSerializer::Point {
+                serializer: self,
+                x: None,
+                y: None,
+            })
+        } else {
+            Ok(StructSerializer::Plain(self))
+        }
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, CodecError> {
+        write_varint(&mut self.output, variant_index.into());
+        Ok(self)
+    }
+}
+
+impl<'a> ser::SerializeSeq for &'a mut Serializer {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTuple for &'a mut Serializer {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+}
+
+impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        _key: &'static str,
+        value: &T,
+    ) -> Result<(), CodecError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+}
+
+/// Collects entries of the map to write them sorted
+pub struct MapSerializer<'a> {
+    serializer: &'a mut Serializer,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    key: Option<Vec<u8>>,
+}
+
+impl<'a> ser::SerializeMap for MapSerializer<'a> {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), CodecError> {
+        self.key = Some(to_bytes(key)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), CodecError> {
+        match self.key.take() {
+            Some(key) => {
+                self.entries.push((key, to_bytes(value)?));
+                Ok(())
+            }
+            None => error("value of the map is serialized before its key"),
+        }
+    }
+
+    fn end(mut self) -> Result<(), CodecError> {
+        self.entries.sort();
+        write_varint(&mut self.serializer.output, self.entries.len() as u64);
+        for (key, value) in self.entries {
+            self.serializer.output.extend(key);
+            self.serializer.output.extend(value);
+        }
+        Ok(())
+    }
+}
+
+/// Writes fields of structures in order, or the compressed form of a point
+pub enum StructSerializer<'a> {
+    Plain(&'a mut Serializer),
+    Point {
+        serializer: &'a mut Serializer,
+        x: Option<String>,
+        y: Option<String>,
+    },
+}
+
+impl<'a> ser::SerializeStruct for StructSerializer<'a> {
+    type Ok = ();
+    type Error = CodecError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), CodecError> {
+        match self {
+            StructSerializer::Plain(serializer) => value.serialize(&mut **serializer),
+            StructSerializer::Point { x, y, .. } => {
+                let coordinate: String = from_bytes(&to_bytes(value)?)?;
+                match key {
+                    "x" => *x = Some(coordinate),
+                    "y" => *y = Some(coordinate),
+                    _ => return error("unexpected field of the point"),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn end(self) -> Result<(), CodecError> {
+        match self {
+            StructSerializer::Plain(_) => Ok(()),
+            StructSerializer::Point {
+                serializer,
+                x: Some(x),
+                y: Some(y),
+            } => {
+                let x = parse_coordinate(&x)?;
+                let y = parse_coordinate(&y)?;
+                serializer
+                    .output
+                    .push(0x02 | (y[COORDINATE_LENGTH - 1] & 1));
+                serializer.output.extend_from_slice(&x);
+                Ok(())
+            }
+            StructSerializer::Point { .. } => error("coordinate of the point is missing"),
+        }
+    }
+}
+
+/// Decoder of the format
+pub struct Deserializer<'de> {
+    input: &'de [u8],
+}
+
+impl<'de> Deserializer<'de> {
+    fn take(&mut self, len: usize) -> Result<&'de [u8], CodecError> {
+        if self.input.len() < len {
+            return error("unexpected end of input");
+        }
+        let (head, tail) = self.input.split_at(len);
+        self.input = tail;
+        Ok(head)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn read_array<A: Default + AsMut<[u8]>>(&mut self) -> Result<A, CodecError> {
+        let mut array = A::default();
+        let len = array.as_mut().len();
+        array.as_mut().copy_from_slice(self.take(len)?);
+        Ok(array)
+    }
+
+    fn read_varint(&mut self) -> Result<u64, CodecError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_u8()?;
+            let bits = u64::from(byte & 0x7f);
+            if shift == 63 && bits > 1 {
+                return error("variable length integer overflows");
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                if byte == 0 && shift > 0 {
+                    return error("overlong variable length integer");
+                }
+                return Ok(value);
+            }
+        }
+        error("variable length integer overflows")
+    }
+
+    fn read_len(&mut self) -> Result<usize, CodecError> {
+        let len = self.read_varint()?;
+        if len > self.input.len() as u64 {
+            return error("length exceeds the input");
+        }
+        Ok(len as usize)
+    }
+
+    fn read_bool(&mut self) -> Result<bool, CodecError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => error("invalid boolean"),
+        }
+    }
+
+    fn read_string(&mut self) -> Result<String, CodecError> {
+        let tag = self.read_u8()?;
+        let s = match tag {
+            DECIMAL => {
+                let header = self.read_varint()?;
+                let negative = header & 1 == 1;
+                if header >> 1 > self.input.len() as u64 {
+                    return error("length exceeds the input");
+                }
+                let bytes = self.take((header >> 1) as usize)?;
+                if bytes.first() == Some(&0) || (negative && bytes.is_empty()) {
+                    return error("non-canonical integer");
+                }
+                let magnitude = BigInt::from(bytes).to_str_radix(10);
+                if negative {
+                    format!("-{}", magnitude)
+                } else {
+                    magnitude
+                }
+            }
+            LOWER_HEX | UPPER_HEX => {
+                let digits = self.read_varint()?;
+                if digits > 2 * self.input.len() as u64 {
+                    return error("length exceeds the input");
+                }
+                let digits = digits as usize;
+                let bytes = self.take((digits + 1) / 2)?;
+                let s = if tag == LOWER_HEX {
+                    hex::encode(bytes)
+                } else {
+                    hex::encode_upper(bytes)
+                };
+                if digits % 2 == 1 {
+                    match s.strip_prefix('0') {
+                        Some(s) => s.to_string(),
+                        None => return error("non-canonical hex string"),
+                    }
+                } else {
+                    s
+                }
+            }
+            TEXT => {
+                let len = self.read_len()?;
+                String::from_utf8(self.take(len)?.to_vec())
+                    .map_err(|e| CodecError(e.to_string()))?
+            }
+            _ => return error("unknown representation of the string"),
+        };
+        if string_tag(&s) != tag {
+            return error("non-canonical string");
+        }
+        Ok(s)
+    }
+
+    fn read_point(&mut self) -> Result<(String, String), CodecError> {
+        let compressed = self.take(POINT_LENGTH)?;
+        let point =
+            PK::from_slice(compressed).map_err(|_| CodecError("invalid point".to_string()))?;
+        let uncompressed = point.serialize_uncompressed();
+        Ok((
+            hex::encode(&uncompressed[1..1 + COORDINATE_LENGTH]),
+            hex::encode(&uncompressed[1 + COORDINATE_LENGTH..]),
+        ))
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for &'a mut Deserializer<'de> {
+    type Error = CodecError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, CodecError> {
+        error("the format is not self-describing")
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_bool(self.read_bool()?)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_i8(self.read_u8()? as i8)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let value = unzigzag(self.read_varint()?);
+        visitor.visit_i16(
+            value
+                .try_into()
+                .map_err(|_| CodecError("i16 overflows".to_string()))?,
+        )
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let value = unzigzag(self.read_varint()?);
+        visitor.visit_i32(
+            value
+                .try_into()
+                .map_err(|_| CodecError("i32 overflows".to_string()))?,
+        )
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_i64(unzigzag(self.read_varint()?))
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_i128(i128::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_u8(self.read_u8()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let value = self.read_varint()?;
+        visitor.visit_u16(
+            value
+                .try_into()
+                .map_err(|_| CodecError("u16 overflows".to_string()))?,
+        )
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let value = self.read_varint()?;
+        visitor.visit_u32(
+            value
+                .try_into()
+                .map_err(|_| CodecError("u32 overflows".to_string()))?,
+        )
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_u64(self.read_varint()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_u128(u128::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_f32(f32::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_f64(f64::from_le_bytes(self.read_array()?))
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let s = self.read_string()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => error("expected a single character"),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_string(self.read_string()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_string(self.read_string()?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let len = self.read_len()?;
+        visitor.visit_borrowed_bytes(self.take(len)?)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        if self.read_bool()? {
+            visitor.visit_some(self)
+        } else {
+            visitor.visit_none()
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let len = self.read_len()?;
+        visitor.visit_seq(SeqAccess {
+            deserializer: self,
+            remaining: len,
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        visitor.visit_seq(SeqAccess {
+            deserializer: self,
+            remaining: len,
+        })
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CodecError> {
+        let len = self.read_len()?;
+        visitor.visit_map(MapAccess {
+            deserializer: self,
+            remaining: len,
+            previous_key: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        if name == POINT_STRUCT {
+            let (x, y) = self.read_point()?;
+            let mut coordinates =
+                MapDeserializer::<_, CodecError>::new(vec![("x", x), ("y", y)].into_iter());
+            let value = visitor.visit_map(&mut coordinates)?;
+            coordinates.end()?;
+            Ok(value)
+        } else {
+            self.deserialize_tuple(fields.len(), visitor)
+        }
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        visitor.visit_enum(self)
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, CodecError> {
+        error("identifiers are not encoded")
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, CodecError> {
+        error("the format is not self-describing")
+    }
+}
+
+struct SeqAccess<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    remaining: usize,
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for SeqAccess<'a, 'de> {
+    type Error = CodecError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, CodecError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+struct MapAccess<'a, 'de> {
+    deserializer: &'a mut Deserializer<'de>,
+    remaining: usize,
+    previous_key: Option<&'de [u8]>,
+}
+
+impl<'a, 'de> de::MapAccess<'de> for MapAccess<'a, 'de> {
+    type Error = CodecError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, CodecError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+        self.remaining -= 1;
+        let start = self.deserializer.input;
+        let key = seed.deserialize(&mut *self.deserializer)?;
+        let encoded = &start[..start.len() - self.deserializer.input.len()];
+        if matches!(self.previous_key, Some(previous) if encoded <= previous) {
+            return error("keys of the map are not sorted");
+        }
+        self.previous_key = Some(encoded);
+        Ok(Some(key))
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, CodecError> {
+        seed.deserialize(&mut *self.deserializer)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for &'a mut Deserializer<'de> {
+    type Error = CodecError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), CodecError> {
+        let index = self.read_varint()?;
+        let index = u32::try_from(index).map_err(|_| CodecError("u32 overflows".to_string()))?;
+        let variant: de::value::U32Deserializer<CodecError> = index.into_deserializer();
+        let value = seed.deserialize(variant)?;
+        Ok((value, self))
+    }
+}
+
+impl<'a, 'de> de::VariantAccess<'de> for &'a mut Deserializer<'de> {
+    type Error = CodecError;
+
+    fn unit_variant(self) -> Result<(), CodecError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(
+        self,
+        seed: T,
+    ) -> Result<T::Value, CodecError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CodecError> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_bytes, to_bytes, CodecError};
+    use curv::BigInt;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn big_integers_are_length_prefixed() -> Result<(), CodecError> {
+        let cases = vec![
+            (BigInt::zero(), vec![1, 0]),
+            (BigInt::from(0x1234), vec![1, 4, 0x12, 0x34]),
+            (BigInt::from(-1), vec![1, 3, 1]),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(to_bytes(&value)?, expected);
+            assert_eq!(from_bytes::<BigInt>(&expected)?, value);
+        }
+
+        let large = (BigInt::from(1) << 256) - BigInt::from(1);
+        let bytes = to_bytes(&large)?;
+        assert_eq!(bytes.len(), 1 + 1 + 32);
+        assert_eq!(from_bytes::<BigInt>(&bytes)?, large);
+        Ok(())
+    }
+
+    #[test]
+    fn strings_roundtrip() -> Result<(), CodecError> {
+        let cases = vec![
+            ("abc", vec![2, 3, 0x0a, 0xbc]),
+            ("0a", vec![2, 2, 0x0a]),
+            ("0A", vec![3, 2, 0x0a]),
+            ("007", vec![2, 3, 0x00, 0x07]),
+            ("x", vec![0, 1, b'x']),
+            ("", vec![0, 0]),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(to_bytes(value)?, expected);
+            assert_eq!(from_bytes::<String>(&expected)?, value);
+        }
+
+        let mut map = BTreeMap::new();
+        map.insert("y".to_string(), 1u32);
+        map.insert("x".to_string(), 300u32);
+        let bytes = to_bytes(&map)?;
+        assert_eq!(bytes, vec![2, 0, 1, b'x', 0xac, 0x02, 0, 1, b'y', 1]);
+        assert_eq!(from_bytes::<BTreeMap<String, u32>>(&bytes)?, map);
+        Ok(())
+    }
+
+    #[test]
+    fn non_canonical_input_is_rejected() {
+        // leading zero byte of the magnitude
+        assert!(from_bytes::<BigInt>(&[1, 4, 0, 1]).is_err());
+        // negative zero
+        assert!(from_bytes::<BigInt>(&[1, 1]).is_err());
+        // odd number of digits with non-zero padding
+        assert!(from_bytes::<String>(&[2, 3, 0x1a, 0xbc]).is_err());
+        // decimal integer written as text
+        assert!(from_bytes::<String>(&[0, 1, b'7']).is_err());
+        // overlong integer
+        assert!(from_bytes::<u32>(&[0x81, 0x00]).is_err());
+        // unsorted keys
+        assert!(from_bytes::<BTreeMap<u8, u8>>(&[2, 2, 0, 1, 0]).is_err());
+        // trailing bytes
+        assert!(from_bytes::<u8>(&[1, 2]).is_err());
+        // length beyond the input
+        assert!(from_bytes::<Vec<u8>>(&[0xff, 0xff, 0x03]).is_err());
+    }
+}
@@ -0,0 +1,409 @@
+//! Binary wire format of protocol messages
+//!
+//! Every encoded message starts with the fixed size header followed by the body:
+//!
+//! | offset | size | field                                         |
+//! |--------|------|-----------------------------------------------|
+//! | 0      | 2    | magic bytes `b"TS"`                           |
+//! | 2      | 2    | version of the wire format, big endian        |
+//! | 4      | 1    | kind of the message, see [`MessageKind`]      |
+//! | 5      | 1    | reserved, zero                                |
+//! | 6      | 4    | length of the body in bytes, big endian       |
+//!
+//! The body is the compact encoding of the message, which writes fields of structures in the order of their declaration
+//! and tags enum variants by their position. Therefore adding, removing or reordering fields or variants of any message type changes the format
+//! and requires [`WIRE_FORMAT_VERSION`] to be bumped.
+//!
+//! Values of the curve and big integers are written in binary:
+//!
+//! | value            | encoding                                                                 |
+//! |------------------|--------------------------------------------------------------------------|
+//! | point            | 33 bytes, compressed SEC1 form                                           |
+//! | scalar           | 32 bytes, big endian, for fields marked with [`scalar`](scalar/index.html) |
+//! | big integer      | tag, variable length sign and length, big endian magnitude               |
+//! | integer, length  | LEB128 variable length integer                                           |
+//!
+//! Scalars nested in types of `curv`, like proofs, are written as packed hex digits, since `curv` serializes them as hex strings.
+//! The encoding of every value is unique, and maps are sorted by their keys.
+//!
+//! The decoder checks the header before touching the body, so that a message of an incompatible version or of another protocol is rejected with [`WireError`]
+//! instead of failing deep inside a phase. The size of the body is bounded, and lengths inside the body are checked against the remaining input before anything is allocated.
+//!
+//! After decoding, the message is checked with [`Validate`], so that points off the curve and oversized big integers
+//! are rejected before any phase of the protocol sees them.
+//!
+//! [`MessageKind`]: enum.MessageKind.html
+//! [`WIRE_FORMAT_VERSION`]: constant.WIRE_FORMAT_VERSION.html
+//! [`WireError`]: enum.WireError.html
+//! [`Validate`]: ../algorithms/validation/trait.Validate.html
+//!
+use crate::algorithms::validation::{Validate, ValidationError};
+use crate::ecdsa::messages::{
+    export, import, keygen, refresh, repair, resharing, signing, threshold_change,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+mod codec;
+pub mod scalar;
+
+/// Version of the wire format produced by the crate
+pub const WIRE_FORMAT_VERSION: u16 = 4;
+/// Size of the header in bytes
+pub const HEADER_LENGTH: usize = 10;
+/// Default upper bound of the body size accepted by the decoder
+pub const DEFAULT_SIZE_LIMIT: usize = 16 * 1024 * 1024;
+
+const MAGIC: [u8; 2] = *b"TS";
+
+/// Enumerates errors reported by the decoder and the encoder
+#[derive(Debug, Error, PartialEq)]
+pub enum WireError {
+    #[error("wire: message is truncated, {expected} bytes expected, {found} found")]
+    Truncated { expected: usize, found: usize },
+    #[error("wire: invalid magic bytes {0:?}")]
+    InvalidMagic([u8; 2]),
+    #[error("wire: unsupported version {found}, expected {expected}")]
+    UnsupportedVersion { found: u16, expected: u16 },
+    #[error("wire: unknown message kind {0}")]
+    UnknownKind(u8),
+    #[error("wire: unexpected message kind {found:?}, expected {expected:?}")]
+    UnexpectedKind {
+        found: MessageKind,
+        expected: MessageKind,
+    },
+    #[error("wire: body of {size} bytes exceeds the limit of {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+    #[error("wire: {0} trailing bytes after the body")]
+    TrailingBytes(usize),
+    #[error("wire: malformed body: {0}")]
+    Malformed(String),
+    #[error("wire: {0}")]
+    Invalid(#[from] ValidationError),
+}
+
+/// Identifies the protocol a message belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Keygen = 1,
+    Signing = 2,
+    Resharing = 3,
+    Repair = 4,
+    Refresh = 5,
+    ThresholdChange = 6,
+    Import = 7,
+    Export = 8,
+}
+
+impl TryFrom<u8> for MessageKind {
+    type Error = WireError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(MessageKind::Keygen),
+            2 => Ok(MessageKind::Signing),
+            3 => Ok(MessageKind::Resharing),
+            4 => Ok(MessageKind::Repair),
+            5 => Ok(MessageKind::Refresh),
+            6 => Ok(MessageKind::ThresholdChange),
+            7 => Ok(MessageKind::Import),
+            8 => Ok(MessageKind::Export),
+            _ => Err(WireError::UnknownKind(value)),
+        }
+    }
+}
+
+/// Message which can be sent over the wire
+pub trait WireMessage: Serialize + DeserializeOwned + Validate {
+    const KIND: MessageKind;
+}
+
+impl WireMessage for keygen::Message {
+    const KIND: MessageKind = MessageKind::Keygen;
+}
+
+impl WireMessage for signing::Message {
+    const KIND: MessageKind = MessageKind::Signing;
+}
+
+impl WireMessage for resharing::Message {
+    const KIND: MessageKind = MessageKind::Resharing;
+}
+
+impl WireMessage for repair::Message {
+    const KIND: MessageKind = MessageKind::Repair;
+}
+
+impl WireMessage for refresh::Message {
+    const KIND: MessageKind = MessageKind::Refresh;
+}
+
+impl WireMessage for threshold_change::Message {
+    const KIND: MessageKind = MessageKind::ThresholdChange;
+}
+
+impl WireMessage for import::Message {
+    const KIND: MessageKind = MessageKind::Import;
+}
+
+impl WireMessage for export::Message {
+    const KIND: MessageKind = MessageKind::Export;
+}
+
+/// Header of an encoded message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Header {
+    pub version: u16,
+    pub kind: MessageKind,
+    pub length: usize,
+}
+
+impl Header {
+    /// Parses the header, checking the magic bytes, the version and the kind of the message
+    pub fn parse(bytes: &[u8]) -> Result<Self, WireError> {
+        if bytes.len() < HEADER_LENGTH {
+            return Err(WireError::Truncated {
+                expected: HEADER_LENGTH,
+                found: bytes.len(),
+            });
+        }
+        let magic = [bytes[0], bytes[1]];
+        if magic != MAGIC {
+            return Err(WireError::InvalidMagic(magic));
+        }
+        let version = u16::from_be_bytes([bytes[2], bytes[3]]);
+        if version != WIRE_FORMAT_VERSION {
+            return Err(WireError::UnsupportedVersion {
+                found: version,
+                expected: WIRE_FORMAT_VERSION,
+            });
+        }
+        let kind = MessageKind::try_from(bytes[4])?;
+        let length = u32::from_be_bytes([bytes[6], bytes[7], bytes[8], bytes[9]]) as usize;
+        Ok(Header {
+            version,
+            kind,
+            length,
+        })
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&self.version.to_be_bytes());
+        bytes.push(self.kind as u8);
+        bytes.push(0);
+        bytes.extend_from_slice(&(self.length as u32).to_be_bytes());
+    }
+}
+
+/// Encodes the message
+pub fn encode<M: WireMessage>(message: &M) -> Result<Vec<u8>, WireError> {
+    let body = codec::to_bytes(message).map_err(|e| WireError::Malformed(e.to_string()))?;
+    if body.len() > u32::MAX as usize {
+        return Err(WireError::TooLarge {
+            size: body.len(),
+            limit: u32::MAX as usize,
+        });
+    }
+    let mut bytes = Vec::with_capacity(HEADER_LENGTH + body.len());
+    Header {
+        version: WIRE_FORMAT_VERSION,
+        kind: M::KIND,
+        length: body.len(),
+    }
+    .write(&mut bytes);
+    bytes.extend_from_slice(&body);
+    Ok(bytes)
+}
+
+/// Decodes and validates the message whose body does not exceed [`DEFAULT_SIZE_LIMIT`](constant.DEFAULT_SIZE_LIMIT.html)
+pub fn decode<M: WireMessage>(bytes: &[u8]) -> Result<M, WireError> {
+    decode_with_limit(bytes, DEFAULT_SIZE_LIMIT)
+}
+
+/// Decodes and validates the message whose body does not exceed `limit` bytes
+pub fn decode_with_limit<M: WireMessage>(bytes: &[u8], limit: usize) -> Result<M, WireError> {
+    let header = Header::parse(bytes)?;
+    if header.kind != M::KIND {
+        return Err(WireError::UnexpectedKind {
+            found: header.kind,
+            expected: M::KIND,
+        });
+    }
+    if header.length > limit {
+        return Err(WireError::TooLarge {
+            size: header.length,
+            limit,
+        });
+    }
+    let body = &bytes[HEADER_LENGTH..];
+    if body.len() < header.length {
+        return Err(WireError::Truncated {
+            expected: HEADER_LENGTH + header.length,
+            found: bytes.len(),
+        });
+    }
+    if body.len() > header.length {
+        return Err(WireError::TrailingBytes(body.len() - header.length));
+    }
+    let message: M = codec::from_bytes(body).map_err(|e| WireError::Malformed(e.to_string()))?;
+    message.validate()?;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_with_limit, encode, Header, MessageKind, WireError, HEADER_LENGTH};
+    use crate::algorithms::validation::ValidationError;
+    use crate::ecdsa::messages::keygen::{DecommitPublicKey, Message as KeygenMessage};
+    use crate::ecdsa::messages::resharing::Message as ResharingMessage;
+    use crate::ecdsa::messages::signing::{Message as SigningMessage, Phase3data};
+    use curv::elliptic::curves::traits::{ECPoint, ECScalar};
+    use curv::{BigInt, FE, GE};
+
+    fn decommitment() -> KeygenMessage {
+        let u_i: FE = ECScalar::from(&BigInt::from(7));
+        KeygenMessage::R2(DecommitPublicKey {
+            blind_factor: BigInt::from(0x1234_5678),
+            y_i: GE::generator() * u_i,
+        })
+    }
+
+    #[test]
+    fn roundtrip() -> Result<(), WireError> {
+        let bytes = encode(&decommitment())?;
+        let header = Header::parse(&bytes)?;
+        assert_eq!(header.kind, MessageKind::Keygen);
+        assert_eq!(header.length, bytes.len() - HEADER_LENGTH);
+        match (decode::<KeygenMessage>(&bytes)?, decommitment()) {
+            (KeygenMessage::R2(decoded), KeygenMessage::R2(expected)) => {
+                assert_eq!(decoded, expected)
+            }
+            (decoded, _) => panic!("unexpected message {:?}", decoded),
+        }
+
+        let delta_i: FE = ECScalar::from(&BigInt::from(42));
+        let bytes = encode(&SigningMessage::R3(Phase3data { delta_i }))?;
+        match decode::<SigningMessage>(&bytes)? {
+            SigningMessage::R3(decoded) => assert_eq!(decoded.delta_i, delta_i),
+            decoded => panic!("unexpected message {:?}", decoded),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn values_are_compact() -> Result<(), WireError> {
+        // variant, big integer of 4 bytes with its tag and length, compressed point
+        let bytes = encode(&decommitment())?;
+        assert_eq!(bytes.len(), HEADER_LENGTH + 1 + (1 + 1 + 4) + 33);
+
+        // variant, scalar
+        let delta_i: FE = ECScalar::from(&BigInt::from(42));
+        let bytes = encode(&SigningMessage::R3(Phase3data { delta_i }))?;
+        assert_eq!(bytes.len(), HEADER_LENGTH + 1 + 32);
+        assert_eq!(bytes[HEADER_LENGTH + 32], 42);
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_points_and_scalars_are_rejected() -> Result<(), WireError> {
+        let mut bytes = encode(&decommitment())?;
+        // prefix of the compressed point
+        bytes[HEADER_LENGTH + 7] = 0x05;
+        assert!(matches!(
+            decode::<KeygenMessage>(&bytes),
+            Err(WireError::Malformed(_))
+        ));
+
+        let delta_i: FE = ECScalar::from(&BigInt::from(42));
+        let mut bytes = encode(&SigningMessage::R3(Phase3data { delta_i }))?;
+        for byte in bytes[HEADER_LENGTH + 1..].iter_mut() {
+            *byte = 0xff;
+        }
+        assert!(matches!(
+            decode::<SigningMessage>(&bytes),
+            Err(WireError::Malformed(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn smaller_than_json() -> Result<(), WireError> {
+        let binary = encode(&decommitment())?;
+        let json = serde_json::to_vec(&decommitment()).unwrap();
+        assert!(binary.len() < json.len());
+        Ok(())
+    }
+
+    #[test]
+    fn incompatible_messages_are_rejected() -> Result<(), WireError> {
+        let bytes = encode(&ResharingMessage::Ack)?;
+        assert!(matches!(
+            decode::<SigningMessage>(&bytes),
+            Err(WireError::UnexpectedKind {
+                found: MessageKind::Resharing,
+                expected: MessageKind::Signing
+            })
+        ));
+
+        let mut newer = bytes.clone();
+        newer[3] += 1;
+        assert!(matches!(
+            decode::<ResharingMessage>(&newer),
+            Err(WireError::UnsupportedVersion { .. })
+        ));
+
+        let mut garbage = bytes.clone();
+        garbage[0] = b'{';
+        assert!(matches!(
+            decode::<ResharingMessage>(&garbage),
+            Err(WireError::InvalidMagic(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_messages_are_rejected() -> Result<(), WireError> {
+        let oversized = KeygenMessage::R2(DecommitPublicKey {
+            blind_factor: BigInt::from(1) << 100_000,
+            y_i: GE::generator(),
+        });
+        assert!(matches!(
+            decode::<KeygenMessage>(&encode(&oversized)?),
+            Err(WireError::Invalid(ValidationError::TooLarge { .. }))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn size_is_checked() -> Result<(), WireError> {
+        let bytes = encode(&decommitment())?;
+        assert!(matches!(
+            decode::<KeygenMessage>(&bytes[..bytes.len() - 1]),
+            Err(WireError::Truncated { .. })
+        ));
+
+        let mut longer = bytes.clone();
+        longer.push(0);
+        assert_eq!(
+            decode::<KeygenMessage>(&longer).err(),
+            Some(WireError::TrailingBytes(1))
+        );
+
+        assert!(matches!(
+            decode_with_limit::<KeygenMessage>(&bytes, 8),
+            Err(WireError::TooLarge { .. })
+        ));
+
+        // the header claims the body larger than the limit, nothing is allocated
+        let mut forged = bytes[..HEADER_LENGTH].to_vec();
+        forged[6..10].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(
+            decode::<KeygenMessage>(&forged),
+            Err(WireError::TooLarge { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+//! Serialization of scalars as 32 bytes big endian in the wire format
+//!
+//! Human readable formats, like JSON, keep the hex string produced by `curv`.
+//! Use it as `#[serde(with = "crate::wire::scalar")]` on fields of messages holding a scalar,
+//! and `#[serde(with = "crate::wire::scalar::share")]` on fields holding a [`SecretShare`](../../ecdsa/messages/type.SecretShare.html).
+//!
+use curv::arithmetic::traits::Converter;
+use curv::elliptic::curves::traits::ECScalar;
+use curv::{BigInt, FE};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use zeroize::Zeroizing;
+
+const SCALAR_LENGTH: usize = 32;
+
+pub fn serialize<S: Serializer>(scalar: &FE, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        return scalar.serialize(serializer);
+    }
+    let value = Zeroizing::new(BigInt::to_vec(&scalar.to_big_int()));
+    let mut bytes = Zeroizing::new([0u8; SCALAR_LENGTH]);
+    bytes[SCALAR_LENGTH - value.len()..].copy_from_slice(&value);
+    bytes.serialize(serializer)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<FE, D::Error> {
+    if deserializer.is_human_readable() {
+        return FE::deserialize(deserializer);
+    }
+    let bytes = Zeroizing::new(<[u8; SCALAR_LENGTH]>::deserialize(deserializer)?);
+    let value = BigInt::from(&bytes[..]);
+    if value >= FE::q() {
+        return Err(D::Error::custom(
+            "scalar is not reduced modulo the group order",
+        ));
+    }
+    Ok(ECScalar::from(&value))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Scalar(#[serde(with = "crate::wire::scalar")] FE);
+
+/// Serialization of secret shares, which pair the x-coordinate with the scalar
+pub mod share {
+    use super::Scalar;
+    use crate::ecdsa::messages::SecretShare;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(share: &SecretShare, serializer: S) -> Result<S::Ok, S::Error> {
+        (share.0, Scalar(share.1)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SecretShare, D::Error> {
+        let (x, Scalar(y)) = Deserialize::deserialize(deserializer)?;
+        Ok((x, y))
+    }
+}